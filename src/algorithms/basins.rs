@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::entities::EntityIndex;
+use crate::typed_map::fields::IdRef;
+use crate::world_map::WorldMapTransaction;
+use crate::world_map::fields::Neighbor;
+use crate::world_map::tile_layer::TileForBasins;
+use crate::world_map::tile_layer::TileSchema;
+
+pub(crate) fn generate_water_basins<Progress: ProgressObserver>(target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let mut tiles = target.edit_tile_layer()?;
+
+    let tile_map = tiles.read_features().into_entities_index::<_,TileForBasins>(progress)?;
+
+    let basins = find_basins(&tile_map, progress)?;
+
+    for (fid,basin_id) in basins.into_iter().watch(progress,"Writing basins.","Basins written.") {
+        let mut feature = tiles.try_feature_by_id(&fid)?;
+        feature.set_basin_id(&basin_id)?;
+        tiles.update_feature(feature)?;
+    }
+
+    Ok(())
+}
+
+/// Follows the flow from every land tile to the tile where it ends up. Water stops when it reaches the ocean, flows off the map, or reaches the bottom of a lake with no outlet. Water in a lake with an outlet continues on from the outlet. Where the flow from a tile is split, it follows the largest share, which `distribute_flow` always lists first.
+fn find_basins<Progress: ProgressObserver>(tile_map: &EntityIndex<TileSchema,TileForBasins>, progress: &mut Progress) -> Result<IndexMap<IdRef,Option<IdRef>>,CommandError> {
+
+    let mut lake_outlets = IndexMap::new();
+    for (fid,tile) in tile_map.iter() {
+        if let Some(Neighbor::Tile(from) | Neighbor::CrossMap(from,_)) = tile.outlet_from() {
+            if let Some(lake_id) = tile_map.try_get(from)?.lake_id() {
+                _ = lake_outlets.entry(lake_id.clone()).or_insert_with(|| fid.clone());
+            }
+        }
+    }
+
+    let next_tile = |tile: &TileForBasins| -> Option<IdRef> {
+        if tile.grouping().is_ocean() {
+            None
+        } else if let Some(flow_to) = tile.flow_to().first() { // the largest share of the flow
+            match flow_to {
+                Neighbor::Tile(next) | Neighbor::CrossMap(next,_) => Some(next.clone()),
+                Neighbor::OffMap(_) => None,
+            }
+        } else {
+            // the bottom of a lake, which might drain out through an outlet.
+            tile.lake_id().as_ref().and_then(|lake_id| lake_outlets.get(lake_id)).cloned()
+        }
+    };
+
+    let mut basins: IndexMap<IdRef,IdRef> = IndexMap::new();
+    let mut result = IndexMap::new();
+
+    for (fid,tile) in tile_map.iter().watch(progress,"Finding basins.","Basins found.") {
+        if tile.grouping().is_ocean() {
+            _ = result.insert(fid.clone(), None);
+            continue;
+        }
+
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current = fid.clone();
+        let basin_id = loop {
+            if let Some(basin_id) = basins.get(&current) {
+                break basin_id.clone();
+            }
+            if !visited.insert(current.clone()) {
+                // water is flowing in a circle across flat land, so it ends here.
+                break current;
+            }
+            path.push(current.clone());
+            match next_tile(tile_map.try_get(&current)?) {
+                Some(next) => current = next,
+                None => break current,
+            }
+        };
+
+        for tile_id in path {
+            _ = basins.insert(tile_id, basin_id.clone());
+        }
+        _ = result.insert(fid.clone(), Some(basin_id));
+
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_basins;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::utils::edge::Edge;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::tile_layer::TileForBasins;
+    use crate::world_map::tile_layer::TileSchema;
+
+    #[test]
+    fn test_basins() {
+        let tile = |id| Neighbor::Tile(IdRef::new(id));
+        let tile_map: EntityIndex<TileSchema,TileForBasins> = [
+            // a river flowing into the ocean at tile 3
+            (1,TileForBasins::new(Grouping::Continent, vec![tile(2)], None, None)),
+            (2,TileForBasins::new(Grouping::Continent, vec![tile(3)], None, None)),
+            (3,TileForBasins::new(Grouping::Ocean, Vec::new(), None, None)),
+            // a river flowing into a lake with no outlet at tile 5
+            (4,TileForBasins::new(Grouping::Continent, vec![tile(5)], None, None)),
+            (5,TileForBasins::new(Grouping::Lake, Vec::new(), Some(IdRef::new(1)), None)),
+            // a lake which drains through tile 7 into the river
+            (6,TileForBasins::new(Grouping::Lake, Vec::new(), Some(IdRef::new(2)), None)),
+            (7,TileForBasins::new(Grouping::Continent, vec![tile(2)], None, Some(tile(6)))),
+            // a tile flowing off the map
+            (8,TileForBasins::new(Grouping::Continent, vec![Neighbor::OffMap(Edge::East)], None, None)),
+            // two flat tiles flowing into each other
+            (9,TileForBasins::new(Grouping::Continent, vec![tile(10)], None, None)),
+            (10,TileForBasins::new(Grouping::Continent, vec![tile(9)], None, None)),
+        ].into_iter().map(|(id,tile)| (IdRef::new(id),tile)).collect();
+
+        let basins = find_basins(&tile_map, &mut ()).unwrap();
+
+        let basin = |id| basins[&IdRef::new(id)].clone();
+        assert_eq!(basin(1),Some(IdRef::new(3)));
+        assert_eq!(basin(2),Some(IdRef::new(3)));
+        assert_eq!(basin(3),None);
+        assert_eq!(basin(4),Some(IdRef::new(5)));
+        assert_eq!(basin(5),Some(IdRef::new(5)));
+        assert_eq!(basin(6),Some(IdRef::new(3)));
+        assert_eq!(basin(7),Some(IdRef::new(3)));
+        assert_eq!(basin(8),Some(IdRef::new(8)));
+        assert_eq!(basin(9),basin(10));
+    }
+}
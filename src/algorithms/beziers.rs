@@ -225,6 +225,25 @@ pub(crate) fn bezierify_points(line: &[Coordinates], scale: f64) -> Result<Vec<C
 }
 
 
+/// Inserts the specified number of evenly spaced points between each pair of vertices, giving the curve more control points to follow the original shape.
+pub(crate) fn densify_points(line: &[Coordinates], count: usize) -> Vec<Coordinates> {
+    let mut result = Vec::with_capacity(line.len() + (line.len().saturating_sub(1) * count));
+    for pair in line.windows(2) {
+        if let [start,end] = pair {
+            result.push(start.clone());
+            let step = end.subtract(start).multiply(1.0/(count as f64 + 1.0));
+            for i in 1..=count {
+                result.push(start.add(&step.multiply(i as f64)));
+            }
+        }
+    }
+    if let Some(last) = line.last() {
+        result.push(last.clone());
+    }
+    result
+}
+
+
 pub(crate) fn find_curve_making_point(start_point: &Coordinates, end_point: &Coordinates) -> Coordinates {
     // This function creates a phantom point which can be used to give an otherwise straight ending segment a bit of a curve.
     let parallel = start_point.subtract(end_point);
@@ -247,6 +266,9 @@ pub(crate) fn bezierify_points_with_phantoms(before: Option<&Coordinates>, line:
 #[cfg(test)]
 mod test {
     use super::PolyBezier;
+    use super::bezierify_points;
+    use super::densify_points;
+    use crate::utils::coordinates::Coordinates;
 
     #[test]
     fn test_bezier() {
@@ -335,4 +357,36 @@ mod test {
     }
     
     
+
+    #[test]
+    fn test_densify_points() {
+
+        let square: Vec<Coordinates> = vec![
+            (0.0, 0.0).try_into().unwrap(),
+            (0.0, 4.0).try_into().unwrap(),
+            (4.0, 4.0).try_into().unwrap(),
+            (4.0, 0.0).try_into().unwrap(),
+            (0.0, 0.0).try_into().unwrap(),
+        ];
+
+        assert_eq!(densify_points(&square, 0),square);
+
+        let dense = densify_points(&square, 3);
+        assert_eq!(dense.len(),17);
+        assert_eq!(dense[1],(0.0, 1.0).try_into().unwrap());
+        assert_eq!(dense.last(),square.last());
+
+        let smooth = bezierify_points(&square, 100.0).unwrap();
+        let dense_smooth = bezierify_points(&dense, 100.0).unwrap();
+        assert!(dense_smooth.len() > smooth.len());
+
+        // the curve follows the densified points closely, so it should not bulge far from the sides of the original square.
+        let distance_from_square = |point: &Coordinates| {
+            let (x,y) = point.to_tuple();
+            x.abs().min((x - 4.0).abs()).min(y.abs()).min((y - 4.0).abs())
+        };
+        let max_distance = dense_smooth.iter().map(distance_from_square).fold(0.0, f64::max);
+        assert!(max_distance < 0.5,"densified curve strayed {max_distance} from the original shape");
+
+    }
 }
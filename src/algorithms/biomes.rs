@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
 use crate::entity;
 use crate::typed_map::features::TypedFeature;
 use crate::world_map::biome_layer::BiomeMatrix;
@@ -13,6 +16,7 @@ use crate::world_map::tile_layer::TileSchema;
 use crate::world_map::tile_layer::TileFeature;
 use crate::commands::OverwriteBiomesArg;
 use crate::commands::OverrideBiomeCriteriaArg;
+use crate::commands::IceCapArg;
 use crate::typed_map::fields::IdRef;
 
 pub(crate) fn fill_biome_defaults<Progress: ProgressObserver>(target: &mut WorldMapTransaction, override_criteria: &OverrideBiomeCriteriaArg, overwrite_layer: &OverwriteBiomesArg, progress: &mut Progress) -> Result<(),CommandError> {
@@ -33,73 +37,257 @@ pub(crate) fn fill_biome_defaults<Progress: ProgressObserver>(target: &mut World
     Ok(())
 }
 
-pub(crate) fn apply_biomes<Progress: ProgressObserver>(target: &mut WorldMapTransaction, biomes: &BiomeMatrix, progress: &mut Progress) -> Result<(), CommandError> {
+entity!(BiomeSource: Tile {
+    #[get=false] fid: IdRef,
+    #[get=false] temperature: f64,
+    #[get=false] elevation: f64,
+    #[get=false] depth: Option<f64>,
+    #[get=false] water_flow: f64,
+    #[get=false] precipitation: f64,
+    #[get=false] lake_id: Option<IdRef>,
+    #[get=false] grouping: Grouping
+});
+
+pub(crate) fn apply_biomes<Progress: ProgressObserver>(target: &mut WorldMapTransaction, biomes: &BiomeMatrix, ice_cap: &IceCapArg, progress: &mut Progress) -> Result<(), CommandError> {
 
-    // we need a lake information map
+    // we only need to know which lakes are marshes, so that is looked up once instead of for every tile.
     let mut lakes_layer = target.edit_lakes_layer()?;
 
     let lake_map = lakes_layer.read_features().into_entities_index::<_,LakeForBiomes>(progress)?;
 
-    let mut tiles_layer = target.edit_tile_layer()?; 
+    let marsh_lakes: HashSet<IdRef> = lake_map.iter().filter(|(_,lake)| matches!(lake.type_(),LakeType::Marsh)).map(|(fid,_)| fid.clone()).collect();
 
-    entity!(BiomeSource: Tile {
-        #[get=false] fid: IdRef,
-        #[get=false] temperature: f64,
-        #[get=false] water_flow: f64,
-        #[get=false] precipitation: f64,
-        #[get=false] lake_id: Option<IdRef>,
-        #[get=false] grouping: Grouping
-    });
+    let mut tiles_layer = target.edit_tile_layer()?; 
 
     let tiles = tiles_layer.read_features().into_entities_vec::<_,BiomeSource>(progress)?;
 
-    for tile in tiles.iter().watch(progress,"Applying biomes.","Biomes applied.") {
+    // a tile which refers to a lake that isn't there can't be a marsh, but that means the lakes are out of date.
+    let missing_lakes = tiles.iter().filter(|tile| tile.lake_id.as_ref().is_some_and(|id| lake_map.maybe_get(id).is_none())).count();
+    if missing_lakes > 0 {
+        progress.warning(|| format!("{missing_lakes} tiles refer to lakes which could not be found, so they were not checked for marshes. The lakes may need to be regenerated."));
+    }
+
+    let assignments = assign_biomes(biomes, tiles.iter().watch(progress,"Applying biomes.","Biomes applied."), &marsh_lakes, ice_cap.ice_cap_temperature);
+
+    tiles_layer.update_features_by_id(assignments, progress, TileFeature::set_biome)
+
+}
+
+fn assign_biomes<'biomes, 'tiles, Tiles: Iterator<Item = &'tiles BiomeSource>>(biomes: &'biomes BiomeMatrix, tiles: Tiles, marsh_lakes: &HashSet<IdRef>, ice_cap_temperature: Option<f64>) -> BTreeMap<IdRef,&'biomes String> {
+    tiles.map(|tile| (tile.fid.clone(),select_biome(biomes, tile, marsh_lakes, ice_cap_temperature))).collect()
+}
 
-        let biome = if tile.grouping.is_ocean() {
-            biomes.ocean()
-        } else if tile.temperature < biomes.glacier().1 {
-            &biomes.glacier().0
+/// Chooses the biome for a tile. The names are borrowed from the matrix, so nothing has to be looked up or cloned.
+fn select_biome<'biomes>(biomes: &'biomes BiomeMatrix, tile: &BiomeSource, marsh_lakes: &HashSet<IdRef>, ice_cap_temperature: Option<f64>) -> &'biomes String {
+    if ice_cap_temperature.is_some_and(|ice_cap| tile.temperature <= ice_cap) {
+        // the ice cap covers land and sea alike.
+        &biomes.glacier().0
+    } else if tile.grouping.is_ocean() {
+        // files generated before depth was recorded will only have the elevation.
+        let depth = -tile.depth.unwrap_or_else(|| tile.elevation.min(0.0));
+        biomes.ocean_zones().iter().find(|(_,max_depth,min_temperature,max_temperature)| {
+            (&depth <= max_depth) && (&tile.temperature >= min_temperature) && (&tile.temperature <= max_temperature)
+        }).map_or_else(|| biomes.ocean(), |(zone,_,_,_)| zone)
+    } else if tile.temperature < biomes.glacier().1 {
+        &biomes.glacier().0
+    } else if (tile.water_flow > biomes.wetland().1) || tile.lake_id.as_ref().is_some_and(|id| marsh_lakes.contains(id)) {
+        // it's a wetland
+        &biomes.wetland().0
+    } else if let Some((barren,_,_)) = biomes.barren().filter(|(_,elevation,temperature)| (&tile.elevation >= elevation) && (&tile.temperature <= temperature)) {
+        barren
+    } else {
+        // The original calculation favored deserts too much
+        //let moisture_band = ((tile.precipitation/5.0).floor() as usize).min(4); // 0-4
+        // FUTURE: A better climate modelling system, with less ambiguous precipitation units and seasonal values
+        // would allow me to use the Koppen Climate system here, which would also change how biomes are defined.
+        let moisture_band = if tile.precipitation < 1.0 {
+            0
+        } else if tile.precipitation < 2.0 {
+            1
         } else {
-            // is it a wetland?
-            if (tile.water_flow > biomes.wetland().1) || 
-               matches!(tile.lake_id.as_ref().map(|id| lake_map.try_get(id).map(LakeForBiomes::type_)).transpose()?, Some(LakeType::Marsh)) {
-                &biomes.wetland().0
+            let level = (tile.precipitation/20.0).floor() as usize;
+            if level <= 2 {
+                2
+            } else if level <= 3 {
+                3
             } else {
-                // The original calculation favored deserts too much
-                //let moisture_band = ((tile.precipitation/5.0).floor() as usize).min(4); // 0-4
-                // FUTURE: A better climate modelling system, with less ambiguous precipitation units and seasonal values
-                // would allow me to use the Koppen Climate system here, which would also change how biomes are defined.
-                let moisture_band = if tile.precipitation < 1.0 {
-                    0
-                } else if tile.precipitation < 2.0 {
-                    1
-                } else {
-                    let level = (tile.precipitation/20.0).floor() as usize;
-                    if level <= 2 {
-                        2
-                    } else if level <= 3 {
-                        3
-                    } else {
-                        4
-                    }
-                };
-
-                let temperature_band = ((20.0 - tile.temperature).max(0.0).floor() as usize).min(25);
-                &biomes.matrix()[moisture_band][temperature_band]
+                4
             }
+        };
+
+        let temperature_band = ((20.0 - tile.temperature).max(0.0).floor() as usize).min(25);
+        &biomes.matrix()[moisture_band][temperature_band]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use rand::Rng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::BiomeSource;
+    use super::select_biome;
+    use super::assign_biomes;
+    use crate::commands::OverrideBiomeCriteriaArg;
+    use crate::typed_map::fields::IdRef;
+    use prisma::Rgb;
+
+    use crate::world_map::biome_layer::BiomeMatrix;
+    use crate::world_map::biome_layer::NewBiome;
+    use crate::world_map::fields::BiomeCriteria;
+    use crate::world_map::biome_layer::BiomeSchema;
+    use crate::world_map::fields::Grouping;
+
+    fn default_biomes(barren_biome: bool) -> BiomeMatrix {
+        BiomeSchema::build_matrix_from_biomes(&BiomeSchema::get_default_biomes(&OverrideBiomeCriteriaArg { 
+            max_glacier_temp: None, 
+            min_wetland_flow: None,
+            barren_biome,
+            min_barren_elevation: None,
+            max_barren_temp: None
+        })).unwrap()
+    }
+
+    const fn tile(temperature: f64, water_flow: f64, precipitation: f64, lake_id: Option<IdRef>, grouping: Grouping) -> BiomeSource {
+        BiomeSource {
+            fid: IdRef::new(1),
+            temperature,
+            elevation: 100.0,
+            depth: None,
+            water_flow,
+            precipitation,
+            lake_id,
+            grouping
+        }
+    }
+
+    #[test]
+    fn test_select_biome() {
+        let biomes = default_biomes(false);
+        let marsh_lakes = HashSet::from([IdRef::new(7)]);
+
+        let biome = |tile: BiomeSource| select_biome(&biomes, &tile, &marsh_lakes, None).clone();
+
+        assert_eq!(biome(tile(25.0, 0.0, 0.0, None, Grouping::Ocean)),BiomeSchema::OCEAN);
+        assert_eq!(biome(tile(-10.0, 0.0, 50.0, None, Grouping::Continent)),BiomeSchema::GLACIER);
+        assert_eq!(biome(tile(15.0, 500.0, 50.0, None, Grouping::Continent)),BiomeSchema::WETLAND);
+        assert_eq!(biome(tile(15.0, 0.0, 50.0, Some(IdRef::new(7)), Grouping::Continent)),BiomeSchema::WETLAND);
+        assert_eq!(biome(tile(15.0, 0.0, 50.0, Some(IdRef::new(8)), Grouping::Continent)),BiomeSchema::TEMPERATE_DECIDUOUS_FOREST);
+        assert_eq!(biome(tile(25.0, 0.0, 0.5, None, Grouping::Continent)),BiomeSchema::HOT_DESERT);
+        assert_eq!(biome(tile(25.0, 0.0, 100.0, None, Grouping::Continent)),BiomeSchema::TROPICAL_RAINFOREST);
+        assert_eq!(biome(tile(-4.5, 0.0, 1.5, None, Grouping::Island)),BiomeSchema::TUNDRA);
+    }
+
+    #[test]
+    fn test_barren_biome() {
+        let marsh_lakes = HashSet::new();
+        let mut high_cold = tile(-4.5, 0.0, 1.5, None, Grouping::Continent);
+        high_cold.elevation = 4000.0;
+        let mut low_cold = tile(-4.5, 0.0, 1.5, None, Grouping::Continent);
+        low_cold.elevation = 500.0;
+
+        let without = default_biomes(false);
+        assert_eq!(select_biome(&without, &high_cold, &marsh_lakes, None),BiomeSchema::TUNDRA);
+
+        let with = default_biomes(true);
+        assert_eq!(select_biome(&with, &high_cold, &marsh_lakes, None),BiomeSchema::BARREN);
+        assert_eq!(select_biome(&with, &low_cold, &marsh_lakes, None),BiomeSchema::TUNDRA);
+    }
 
-    
+    #[test]
+    fn test_ocean_zones() {
+        let marsh_lakes = HashSet::new();
+        let ocean_tile = |temperature: f64, depth: f64| {
+            let mut result = tile(temperature, 0.0, 0.0, None, Grouping::Ocean);
+            result.elevation = -depth;
+            result.depth = Some(-depth);
+            result
         };
+        let warm_shallow = ocean_tile(25.0, 50.0);
+        let cold_deep = ocean_tile(-2.0, 4000.0);
+        let temperate_deep = ocean_tile(10.0, 4000.0);
 
-        let mut tile = tiles_layer.try_feature_by_id(&tile.fid)?;
-        
-        tile.set_biome(biome)?;
+        // without any zones, it's all ocean
+        let plain = default_biomes(false);
+        assert_eq!(select_biome(&plain, &warm_shallow, &marsh_lakes, None),BiomeSchema::OCEAN);
+        assert_eq!(select_biome(&plain, &cold_deep, &marsh_lakes, None),BiomeSchema::OCEAN);
 
-        tiles_layer.update_feature(tile)?;
+        let zone = |name: &str, criteria: BiomeCriteria| NewBiome {
+            name: name.to_owned(),
+            habitability: 0,
+            criteria,
+            movement_cost: 10,
+            supports_nomadic: false,
+            supports_hunting: false,
+            color: Rgb::new(0,0,0)
+        };
+        let mut biomes = BiomeSchema::get_default_biomes(&OverrideBiomeCriteriaArg { 
+            max_glacier_temp: None, 
+            min_wetland_flow: None,
+            barren_biome: false,
+            min_barren_elevation: None,
+            max_barren_temp: None
+        });
+        biomes.push(zone("Reef", BiomeCriteria::OceanZone(200.0, 20.0, 40.0)));
+        biomes.push(zone("Polar sea", BiomeCriteria::OceanZone(11000.0, -10.0, 0.0)));
+        let zoned = BiomeSchema::build_matrix_from_biomes(&biomes).unwrap();
 
+        assert_eq!(select_biome(&zoned, &warm_shallow, &marsh_lakes, None),"Reef");
+        assert_eq!(select_biome(&zoned, &cold_deep, &marsh_lakes, None),"Polar sea");
+        assert_eq!(select_biome(&zoned, &temperate_deep, &marsh_lakes, None),BiomeSchema::OCEAN);
     }
 
+    #[test]
+    fn test_assign_biomes_order() {
+        let biomes = default_biomes(false);
+        let marsh_lakes = HashSet::new();
 
-    Ok(())
+        let mut random = StdRng::seed_from_u64(0);
+        let mut tiles: Vec<BiomeSource> = (1..=100).map(|fid| BiomeSource {
+            fid: IdRef::new(fid),
+            temperature: random.gen_range(-20.0..30.0),
+            elevation: 100.0,
+            depth: None,
+            water_flow: 0.0,
+            precipitation: random.gen_range(0.0..120.0),
+            lake_id: None,
+            grouping: Grouping::Continent
+        }).collect();
+
+        let forward = assign_biomes(&biomes, tiles.iter(), &marsh_lakes, None);
+        tiles.reverse();
+        let backward = assign_biomes(&biomes, tiles.iter(), &marsh_lakes, None);
+        tiles.shuffle(&mut random);
+        let shuffled = assign_biomes(&biomes, tiles.iter(), &marsh_lakes, None);
+
+        assert_eq!(forward,backward);
+        assert_eq!(forward,shuffled);
+        // and they come out in fid order, which is the order they will be written in.
+        assert!(forward.keys().zip(forward.keys().skip(1)).all(|(a,b)| a < b));
+        assert_eq!(forward.len(),100);
+    }
+
+    #[test]
+    fn test_ice_cap() {
+        let biomes = default_biomes(false);
+        let marsh_lakes = HashSet::new();
+
+        let frozen_sea = tile(-12.0, 0.0, 0.0, None, Grouping::Ocean);
+        let frozen_land = tile(-4.5, 0.0, 1.5, None, Grouping::Continent);
+        let cool_land = tile(5.0, 0.0, 1.5, None, Grouping::Continent);
+        let warm_sea = tile(5.0, 0.0, 0.0, None, Grouping::Ocean);
 
+        // without the ice cap, only the glacier criteria applies, and never at sea.
+        assert_eq!(select_biome(&biomes, &frozen_sea, &marsh_lakes, None),BiomeSchema::OCEAN);
+        assert_eq!(select_biome(&biomes, &frozen_land, &marsh_lakes, None),BiomeSchema::TUNDRA);
+
+        assert_eq!(select_biome(&biomes, &frozen_sea, &marsh_lakes, Some(-4.0)),BiomeSchema::GLACIER);
+        assert_eq!(select_biome(&biomes, &frozen_land, &marsh_lakes, Some(-4.0)),BiomeSchema::GLACIER);
+        assert_ne!(select_biome(&biomes, &cool_land, &marsh_lakes, Some(-4.0)),BiomeSchema::GLACIER);
+        assert_eq!(select_biome(&biomes, &warm_sea, &marsh_lakes, Some(-4.0)),BiomeSchema::OCEAN);
+    }
 }
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
 use std::collections::HashSet;
 
 use angular_units::Deg;
 use angular_units::Angle;
+use ordered_float::OrderedFloat;
+use rangemap::RangeMap;
+use rayon::prelude::*;
 
 use crate::entity;
 use crate::world_map::tile_layer::TileFeature;
@@ -16,10 +20,15 @@ use crate::world_map::tile_layer::TileSchema;
 use crate::commands::TemperatureRangeArg;
 use crate::commands::WindsArg;
 use crate::commands::PrecipitationArg;
-use crate::progress::WatchableQueue;
 use crate::world_map::fields::NeighborAndDirection;
 use crate::world_map::fields::Neighbor;
 use crate::typed_map::fields::IdRef;
+use crate::typed_map::entities::EntityIndex;
+use crate::utils::coordinates::Coordinates;
+use crate::utils::tile_filter::TileFilter;
+use crate::raster::RasterMap;
+use crate::raster::RasterSampler;
+use crate::raster::ResampleMethod;
 
 pub(crate) fn generate_temperatures<Progress: ProgressObserver>(target: &mut WorldMapTransaction, temperatures: &TemperatureRangeArg, progress: &mut Progress) -> Result<(),CommandError> {
 
@@ -151,19 +160,133 @@ pub(crate) fn generate_temperatures<Progress: ProgressObserver>(target: &mut Wor
     Ok(())
 }
 
+entity!(TileForOceanCurrents: Tile {
+    #[get=false] grouping: Grouping,
+    #[get=false] wind: Deg<f64>,
+    #[get=false] neighbors: Vec<NeighborAndDirection>,
+    #[get=false] temperature: f64
+});
+
+/// Adjusts temperatures by carrying them downwind over the ocean, as a rough stand-in for ocean currents. Requires winds.
+pub(crate) fn apply_ocean_currents<Progress: ProgressObserver>(target: &mut WorldMapTransaction, current_strength: f64, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let mut layer = target.edit_tile_layer()?;
+
+    let mut tile_map = layer.read_features().into_entities_index::<_,TileForOceanCurrents>(progress)?;
+
+    trace_ocean_currents(&mut tile_map, current_strength, progress)?;
+
+    for (fid,tile) in tile_map.iter().watch(progress,"Writing temperatures.","Temperatures written.") {
+        let mut working_feature = layer.try_feature_by_id(fid)?;
+
+        working_feature.set_temperature(&((tile.temperature*100.0).round()/100.0))?;
+
+        layer.update_feature(working_feature)?;
+
+    }
+
+    Ok(())
+}
+
+/// Follows the wind from every ocean tile for a limited number of tiles, carrying its temperature with it. The carried temperature drifts towards the temperature of each ocean tile it crosses, and stops at the first land tile. Each tile reached is then pulled towards the average of the temperatures carried to it, so coasts downwind of warmer water are warmed and those downwind of colder water are cooled.
+fn trace_ocean_currents<Progress: ProgressObserver>(tile_map: &mut EntityIndex<TileSchema,TileForOceanCurrents>, current_strength: f64, progress: &mut Progress) -> Result<(),CommandError> {
+
+    // How many tiles the carried temperature travels before it's forgotten.
+    const CURRENT_REACH: usize = 10;
+    // How much of the difference from each ocean tile crossed the carried temperature takes on.
+    const CURRENT_MIXING: f64 = 0.2;
+
+    let mut carried: HashMap<IdRef,(f64,usize)> = HashMap::new();
+
+    let mut starts: Vec<IdRef> = tile_map.iter().filter(|(_,tile)| tile.grouping.is_ocean()).map(|(fid,_)| fid.clone()).collect();
+    // sorted to keep the results reproducible, as with precipitation.
+    starts.sort();
+
+    for start_id in starts.into_iter().watch(progress,"Tracing ocean currents.","Ocean currents traced.") {
+        let mut visited = HashSet::from([start_id.clone()]);
+        let mut queue = vec![(start_id.clone(),tile_map.try_get(&start_id)?.temperature,0)];
+
+        while let Some((tile_id,temperature,steps)) = queue.pop() {
+            if steps >= CURRENT_REACH {
+                continue;
+            }
+            let tile = tile_map.try_get(&tile_id)?;
+            for next_id in downwind_neighbors(&tile.neighbors, tile.wind) {
+                let (Neighbor::Tile(next_id) | Neighbor::CrossMap(next_id,_)) = next_id else {
+                    // the current carries the temperature off of the map
+                    continue;
+                };
+                if !visited.insert(next_id.clone()) {
+                    continue;
+                }
+                let next = tile_map.try_get(next_id)?;
+                let entry = carried.entry(next_id.clone()).or_insert((0.0,0));
+                entry.0 += temperature;
+                entry.1 += 1;
+                if next.grouping.is_ocean() {
+                    queue.push((next_id.clone(),(next.temperature - temperature).mul_add(CURRENT_MIXING, temperature),steps + 1));
+                }
+            }
+        }
+    }
+
+    for (fid,tile) in tile_map.iter_mut() {
+        if let Some((total,count)) = carried.get(fid) {
+            let carried_temperature = total / *count as f64;
+            tile.temperature = (carried_temperature - tile.temperature).mul_add(current_strength, tile.temperature);
+        }
+    }
+
+    Ok(())
+}
+
+/// Wind directions by bands of latitude.
+pub(crate) struct WindBands {
+    bands: RangeMap<OrderedFloat<f64>,u16>
+}
+
+impl WindBands {
+
+    pub(crate) const fn new(bands: RangeMap<OrderedFloat<f64>,u16>) -> Self {
+        Self {
+            bands
+        }
+    }
+
+    pub(crate) fn get(&self, latitude: f64) -> Option<u16> {
+        let latitude = OrderedFloat(latitude);
+        self.bands.get(&latitude).copied().or_else(|| {
+            // The ranges don't include their ends, so a tile exactly at the north pole gets the wind of the band which ends there.
+            self.bands.iter().last().filter(|(range,_)| (range.end == latitude) && (latitude == OrderedFloat(90.0))).map(|(_,wind)| *wind)
+        })
+    }
+}
+
 pub(crate) fn generate_winds<Progress: ProgressObserver>(target: &mut WorldMapTransaction, winds: &WindsArg, progress: &mut Progress) -> Result<(),CommandError> {
 
     let mut layer = target.edit_tile_layer()?;
 
     // Algorithm borrowed from AFMG with some modifications
 
-    let winds = winds.to_range_map();
+    let raster = if let Some(wind_raster) = &winds.wind_raster {
+        progress.start_unknown_endpoint(|| "Reading wind raster");
+
+        let raster = RasterMap::open(wind_raster)?.read_sampler(1)?;
+
+        progress.finish(|| "Raster read.");
+
+        Some(raster)
+    } else {
+        None
+    };
+
+    let winds = winds.to_wind_bands();
 
     let features = layer.read_features().into_entities_vec::<_,TileForWinds>(progress)?;
 
     for feature in features.iter().watch(progress,"Generating winds.","Winds generated.") {
 
-        let wind_dir = Deg(winds.get(&ordered_float::OrderedFloat(*feature.site_y())).copied().unwrap_or(90) as f64);
+        let wind_dir = wind_direction(&winds, raster.as_ref(), feature.site());
  
         let mut working_feature = layer.try_feature_by_id(feature.fid())?;
         
@@ -179,6 +302,15 @@ pub(crate) fn generate_winds<Progress: ProgressObserver>(target: &mut WorldMapTr
 
 
 
+/// Chooses the wind direction for a tile site from the raster, if there is one with data under the site, or from the latitude bands.
+fn wind_direction(bands: &WindBands, raster: Option<&RasterSampler>, site: &Coordinates) -> Deg<f64> {
+    let (site_x,site_y) = site.to_tuple();
+    raster.and_then(|raster| raster.sample_data(site_x, site_y, ResampleMethod::Nearest)).map_or_else(
+        || Deg(bands.get(site_y).unwrap_or(90) as f64), 
+        |direction| Deg(direction.rem_euclid(360.0))
+    )
+}
+
 #[derive(Clone)]
 pub(crate) struct PrecipitationFactors {
     lat_modifier: f64,
@@ -219,26 +351,155 @@ entity!(TileDataForPrecipitation: Tile {
 
 pub(crate) fn generate_precipitation<Progress: ProgressObserver>(target: &mut WorldMapTransaction, precipitation_arg: &PrecipitationArg, progress: &mut Progress) -> Result<(),CommandError> {
 
-    let mut layer = target.edit_tile_layer()?;
+    let precipitation_modifier = precipitation_arg.validated_factor()?;
 
-    let precipitation_modifier = precipitation_arg.precipitation_factor;
+    let mut layer = target.edit_tile_layer()?;
 
         
     // I need to trace the data across the map, so I can't just do quick read and writes to the database.
     let mut tile_map = layer.read_features().into_entities_index::<_,TileDataForPrecipitation>(progress)?;
 
-    let mut visited = HashSet::new();
+    trace_precipitation(&mut tile_map, precipitation_modifier, precipitation_arg.parallel, progress)?;
+
+    for (fid,tile) in tile_map.iter().watch(progress,"Writing precipitation.","Precipitation written.") {
+        let mut working_feature = layer.try_feature_by_id(fid)?; 
+        
+        working_feature.set_precipitation(&tile.precipitation)?;
+
+        layer.update_feature(working_feature)?;
+
+
+    }
+
+    Ok(())
+}
+
+entity!(TileForPrecipitationAdjustment: Tile {
+    site: Coordinates,
+    #[get=false] precipitation: f64
+});
+
+pub(crate) fn adjust_precipitation<Progress: ProgressObserver>(target: &mut WorldMapTransaction, multiplier: f64, filter: &TileFilter, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let mut layer = target.edit_tile_layer()?;
+
+    let mut tile_map = layer.read_features().into_entities_index::<_,TileForPrecipitationAdjustment>(progress)?;
+
+    multiply_precipitation(&mut tile_map, multiplier, filter);
+
+    for (fid,tile) in tile_map.iter().watch(progress,"Writing precipitation.","Precipitation written.") {
+        let mut working_feature = layer.try_feature_by_id(fid)?; 
+        
+        working_feature.set_precipitation(&tile.precipitation)?;
+
+        layer.update_feature(working_feature)?;
+
+    }
+
+    Ok(())
+}
+
+/// Multiplies the precipitation of the tiles which pass the filter, leaving the rest alone.
+fn multiply_precipitation(tile_map: &mut EntityIndex<TileSchema,TileForPrecipitationAdjustment>, multiplier: f64, filter: &TileFilter) {
+    for (_,tile) in tile_map.iter_mut() {
+        if filter.includes(tile.site()) {
+            tile.precipitation *= multiplier;
+        }
+    }
+}
+
+/// Lists the neighbors which lie within 45 degrees of the wind direction.
+fn downwind_neighbors(neighbors: &[NeighborAndDirection], wind: Deg<f64>) -> impl Iterator<Item = &Neighbor> {
+    neighbors.iter().filter_map(move |NeighborAndDirection(fid,direction)| {
+        // calculate angle difference
+        let angle_diff = Deg((direction.scalar() - wind.scalar()).abs());
+        // if the difference is greater than half a turn, it's actually reflected
+        let angle_diff = if angle_diff > Deg::half_turn() {
+            angle_diff.reflect_x()
+        } else {
+            angle_diff
+        };
+
+        // if the angle difference is greater than 45, it's not going the right way, so don't even bother with this one.
+        (angle_diff < Deg(45.0)).then_some(fid)
+    })
+}
+
+/// When tracing in parallel, precipitation is traced from this many starting tiles at a time. The chunks don't depend on the number of threads, so neither do the results.
+const PRECIPITATION_CHUNK_SIZE: usize = 1024;
+
+/// Carries humidity along the winds from every tile, dropping it as precipitation along the way. Winds which blow off the edge of the map take their remaining humidity with them.
+/// 
+/// In parallel, each chunk of starting tiles is traced on its own, and the precipitation from all chunks is then added up in order. Since the chunks don't see each other's precipitation, tiles can pass the maximum precipitation for their latitude, so the sums are limited to that maximum afterwards. This gives results which are close to, but not the same as, tracing all of the tiles in order.
+fn trace_precipitation<Progress: ProgressObserver>(tile_map: &mut EntityIndex<TileSchema,TileDataForPrecipitation>, precipitation_modifier: f64, parallel: bool, progress: &mut Progress) -> Result<(),CommandError> {
 
-    // I can't work on the tiles map while also iterating it, so I have to copy the keys
-    let mut working_queue: Vec<(IdRef,Option<f64>,IdRef)> = tile_map.keys().map(|id| (id.clone(),None,id.clone())).collect();
     // The order of the tiles changes the results, so make sure they are always in the same order to 
     // keep the results reproducible. I know this seems OCD, but it's important if anyone wants
     // to test things.
-    working_queue.sort_by_cached_key(|(id,_,_)| id.clone());
-    let mut working_queue = working_queue.watch_queue(progress,"Tracing winds.","Winds traced.");
+    let mut starts: Vec<IdRef> = tile_map.keys().cloned().collect();
+    starts.sort();
+
+    let tiles = &*tile_map;
 
-    while let Some((tile_id,humidity,start_id)) = working_queue.pop() {
-        let mut tile = tile_map.try_get(&tile_id)?.clone(); // I'm cloning so I can make some changes without messing with the original.
+    let precipitation = if parallel {
+        progress.start_known_endpoint(|| ("Tracing winds.",starts.len()));
+        let sync = progress.sync_progress();
+
+        // rayon keeps the chunks in order when collecting.
+        let chunks = starts.par_chunks(PRECIPITATION_CHUNK_SIZE).map(|chunk| {
+            let mut chunk_precipitation = HashMap::new();
+            for start_id in chunk.iter().rev() {
+                trace_precipitation_from(tiles, start_id, precipitation_modifier, &mut chunk_precipitation)?;
+                sync.increment();
+            }
+            Ok(chunk_precipitation)
+        }).collect::<Result<Vec<_>,CommandError>>()?;
+
+        progress.finish(|| "Winds traced.");
+
+        // the sums for each tile are added in the order of the chunks, so they don't depend on which thread finished first.
+        let mut merged = HashMap::new();
+        for chunk in chunks {
+            let mut chunk: Vec<(IdRef,f64)> = chunk.into_iter().collect();
+            chunk.sort_by(|(a,_),(b,_)| a.cmp(b));
+            for (fid,amount) in chunk {
+                *merged.entry(fid).or_insert(0.0) += amount;
+            }
+        }
+        merged
+
+    } else {
+        let mut traced_precipitation = HashMap::new();
+        // the tiles are traced from the end of the list, which is how they were traced when this was a single queue.
+        for start_id in starts.iter().rev().watch(progress,"Tracing winds.","Winds traced.") {
+            trace_precipitation_from(tiles, start_id, precipitation_modifier, &mut traced_precipitation)?;
+        }
+        traced_precipitation
+    };
+
+    for (fid,tile) in tile_map.iter_mut() {
+        if let Some(amount) = precipitation.get(fid) {
+            tile.precipitation = if parallel {
+                amount.min(tile.factors.max_precipitation)
+            } else {
+                *amount
+            };
+        }
+    }
+
+    Ok(())
+}
+
+/// Carries humidity along the winds from a single tile, adding the precipitation it drops to the amounts already recorded.
+fn trace_precipitation_from(tile_map: &EntityIndex<TileSchema,TileDataForPrecipitation>, start_id: &IdRef, precipitation_modifier: f64, precipitation: &mut HashMap<IdRef,f64>) -> Result<(),CommandError> {
+
+    // I don't want to go in circles.
+    let mut visited = HashSet::new();
+
+    let mut working_queue: Vec<(IdRef,Option<f64>)> = vec![(start_id.clone(),None)];
+
+    while let Some((tile_id,humidity)) = working_queue.pop() {
+        let tile = tile_map.try_get(&tile_id)?;
         let humidity = if let Some(humidity) = humidity {
             humidity
         } else if tile.grouping.is_ocean() {
@@ -254,64 +515,44 @@ pub(crate) fn generate_precipitation<Progress: ProgressObserver>(target: &mut Wo
             // push humidity onto the neighbor tiles and then process them.
 
             // find neighbor closest to wind direction
-            let mut best_neighbors = Vec::new();
-            for NeighborAndDirection(fid,direction) in &tile.neighbors {
-                // calculate angle difference
-                let angle_diff = Deg((direction.scalar() - tile.wind.scalar()).abs());
-                // if the difference is greater than half a turn, it's actually reflected
-                let angle_diff = if angle_diff > Deg::half_turn() {
-                    angle_diff.reflect_x()
-                } else {
-                    angle_diff
-                };
-            
-                // if the angle difference is greater than 45, it's not going the right way, so don't even bother with this one.
-                if angle_diff < Deg(45.0) {
-                    best_neighbors.push(fid.clone())
-                }
-
-            }
+            let best_neighbors: Vec<Neighbor> = downwind_neighbors(&tile.neighbors, tile.wind).cloned().collect();
 
             if best_neighbors.is_empty() {
                 // otherwise there were no other neighbors in the wind direction, so drop the remaining humidity here.
                 // (I don't know why this would happen on a global world)
-                tile.precipitation = (tile.precipitation + humidity).min(tile.factors.max_precipitation);
-
-                let real_current = tile_map.try_get_mut(&tile_id)?; 
-                real_current.precipitation = tile.precipitation;
+                let tile_precipitation = precipitation.entry(tile_id.clone()).or_insert(0.0);
+                *tile_precipitation = (*tile_precipitation + humidity).min(tile.factors.max_precipitation);
 
             } else {
                 // spread the humidity amongst them... FUTURE: Should I wait it for the more direct tiles?
                 let humidity = humidity/best_neighbors.len() as f64;
 
                 for next_fid in best_neighbors {
-                    if !visited.insert((start_id.clone(),next_fid.clone())) {
+                    if !visited.insert(next_fid.clone()) {
                         continue;
                         // set already contained the value, so we've reached one we've already visited, I don't want to go in circles.
                     } 
-    
+
+                    let mut tile_precipitation = precipitation.get(&tile_id).copied().unwrap_or_default();
 
                     match next_fid {
                         Neighbor::Tile(next_fid) | Neighbor::CrossMap(next_fid,_) => {
    
-                            let mut next = tile_map.try_get(&next_fid)?.clone(); // I'm cloning so I can make some changes without messing with the original.
+                            let next = tile_map.try_get(&next_fid)?;
+                            let mut next_precipitation = precipitation.get(&next_fid).copied().unwrap_or_default();
 
-                            let humidity = precipitate(&mut tile, Some(&mut next), humidity);
+                            let humidity = precipitate(tile, &mut tile_precipitation, Some((next,&mut next_precipitation)), humidity);
         
-                            let real_current = tile_map.try_get_mut(&tile_id)?;
-                            real_current.precipitation = tile.precipitation;
-                
-                            let real_next = tile_map.try_get_mut(&next_fid)?;
-                            real_next.precipitation = next.precipitation;
+                            _ = precipitation.insert(tile_id.clone(), tile_precipitation);
+                            _ = precipitation.insert(next_fid.clone(), next_precipitation);
                 
-                            working_queue.push((next_fid,Some(humidity),start_id.clone()));                        
+                            working_queue.push((next_fid,Some(humidity)));                        
                         }
                         Neighbor::OffMap(_) => {
                             // the humidity spreads off of the map
-                            _ = precipitate(&mut tile, None, humidity);
+                            _ = precipitate(tile, &mut tile_precipitation, None, humidity);
 
-                            let real_current = tile_map.try_get_mut(&tile_id)?;
-                            real_current.precipitation = tile.precipitation;
+                            _ = precipitation.insert(tile_id.clone(), tile_precipitation);
 
                         }
                     }
@@ -328,20 +569,10 @@ pub(crate) fn generate_precipitation<Progress: ProgressObserver>(target: &mut Wo
 
     }
 
-    for (fid,tile) in tile_map.iter().watch(progress,"Writing precipitation.","Precipitation written.") {
-        let mut working_feature = layer.try_feature_by_id(fid)?; 
-        
-        working_feature.set_precipitation(&tile.precipitation)?;
-
-        layer.update_feature(working_feature)?;
-
-
-    }
-
     Ok(())
 }
 
-fn precipitate(tile: &mut TileDataForPrecipitation, next: Option<&mut TileDataForPrecipitation>, humidity: f64) -> f64 {
+fn precipitate(tile: &TileDataForPrecipitation, accumulated: &mut f64, next: Option<(&TileDataForPrecipitation,&mut f64)>, humidity: f64) -> f64 {
 
     // Many of these calculations were taken from AFMG and I don't know where they got that.
     // FUTURE: I would love if someone could give me some better calculations, as I feel there are some things missing here compared to what I learned in school.
@@ -353,7 +584,7 @@ fn precipitate(tile: &mut TileDataForPrecipitation, next: Option<&mut TileDataFo
 
     let (tile_precipitation,humidity) = if tile.temperature >= -5.0 { 
         if tile.grouping.is_ocean() {
-            if let Some(next) = next {
+            if let Some((next,next_precipitation)) = next {
                 if next.grouping.is_ocean() {
                     (
                         // precipitation over water cells, not that it's going to change our climates at all...
@@ -366,7 +597,7 @@ fn precipitate(tile: &mut TileDataForPrecipitation, next: Option<&mut TileDataFo
                     // we don't subtract this from regular humidity
                     // NOTE: The AFMG code uses a random number between 10 and 20 instead of 15. I didn't feel like this was
                     // necessary considering it's the only randomness I would use, and nothing else is randomized.
-                    next.precipitation += (humidity / 15.0).max(1.0);
+                    *next_precipitation += (humidity / 15.0).max(1.0);
     
                     (
                         // no precipitation on this cell
@@ -383,7 +614,7 @@ fn precipitate(tile: &mut TileDataForPrecipitation, next: Option<&mut TileDataFo
         } else {
             // precipitation under normal conditions
             let normal_loss = humidity / (10.0 * tile.factors.lat_modifier);
-            let (diff,elev_modifier) = if let Some(next) = next {
+            let (diff,elev_modifier) = if let Some((next,_)) = next {
                 (
                     // difference in height
                     (next.elevation - tile.elevation).max(0.0)/100.0,
@@ -415,13 +646,275 @@ fn precipitate(tile: &mut TileDataForPrecipitation, next: Option<&mut TileDataFo
         (0.0,humidity)
     };
 
-    tile.precipitation += tile_precipitation;
-    if tile.precipitation > tile.factors.max_precipitation {
-        let extra = (tile.precipitation - tile.factors.max_precipitation).min(tile_precipitation);
-        tile.precipitation -= extra;
+    *accumulated += tile_precipitation;
+    if *accumulated > tile.factors.max_precipitation {
+        let extra = (*accumulated - tile.factors.max_precipitation).min(tile_precipitation);
+        *accumulated -= extra;
         humidity + extra
     } else {
         humidity
     }
 }
 
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+
+    use super::trace_precipitation;
+    use super::trace_ocean_currents;
+    use super::wind_direction;
+    use super::TileForOceanCurrents;
+    use super::multiply_precipitation;
+    use super::TileForPrecipitationAdjustment;
+    use super::PrecipitationFactors;
+    use super::TileDataForPrecipitation;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::utils::edge::Edge;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileSchema;
+    use crate::commands::TileFilterArg;
+    use crate::commands::WindsArg;
+    use crate::commands::PrecipitationArg;
+    use crate::raster::RasterSampler;
+    use crate::utils::coordinates::Coordinates;
+    use ordered_float::OrderedFloat;
+
+    fn tile(wind: f64, neighbors: Vec<NeighborAndDirection>) -> TileDataForPrecipitation {
+        TileDataForPrecipitation {
+            elevation: 100.0,
+            wind: Deg(wind),
+            grouping: Grouping::Continent,
+            neighbors,
+            temperature: 20.0,
+            precipitation: 0.0,
+            factors: PrecipitationFactors {
+                lat_modifier: 1.0,
+                max_precipitation: 120.0
+            }
+        }
+    }
+
+    #[test]
+    fn test_wind_off_top_edge() {
+        // tile 1 sits on the north edge of the map, with the wind blowing north off of it.
+        // tile 2 is below it, with the wind blowing south, where there is nothing, so it drops all of its humidity.
+        let mut tile_map: EntityIndex<TileSchema,TileDataForPrecipitation> = [
+            (IdRef::new(1),tile(0.0, vec![
+                NeighborAndDirection(Neighbor::OffMap(Edge::North),Deg(0.0)),
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(180.0))
+            ])),
+            (IdRef::new(2),tile(180.0, vec![
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(1)),Deg(0.0))
+            ])),
+        ].into_iter().collect();
+
+        trace_precipitation(&mut tile_map, 1.0, false, &mut ()).unwrap();
+
+        // tile 1 only gets the precipitation it would normally lose, the rest of the humidity is blown off the map.
+        let top = tile_map.try_get(&IdRef::new(1)).unwrap().precipitation;
+        assert!(top > 0.0 && top < 0.2, "top precipitation was {top}");
+        // and none of it blew back onto tile 2.
+        let below = tile_map.try_get(&IdRef::new(2)).unwrap().precipitation;
+        assert_eq!(below.to_bits(),1.0f64.to_bits());
+    }
+
+    #[test]
+    fn test_ocean_currents() {
+        // a row of tiles from west to east: a coast, three tiles of warmer ocean, and another coast, with the wind blowing east.
+        let row_tile = |id: u64, grouping: Grouping, temperature: f64| {
+            let mut neighbors = Vec::new();
+            if id > 1 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+            }
+            if id < 5 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+            }
+            (IdRef::new(id),TileForOceanCurrents {
+                grouping,
+                wind: Deg(90.0),
+                neighbors,
+                temperature
+            })
+        };
+        let tile_map = || -> EntityIndex<TileSchema,TileForOceanCurrents> {
+            [
+                row_tile(1, Grouping::Continent, 10.0),
+                row_tile(2, Grouping::Ocean, 20.0),
+                row_tile(3, Grouping::Ocean, 20.0),
+                row_tile(4, Grouping::Ocean, 20.0),
+                row_tile(5, Grouping::Continent, 10.0),
+            ].into_iter().collect()
+        };
+        let temperature = |tiles: &EntityIndex<TileSchema,TileForOceanCurrents>, id: u64| tiles.try_get(&IdRef::new(id)).unwrap().temperature;
+
+        // without the currents, both coasts stay the same.
+        let mut still = tile_map();
+        trace_ocean_currents(&mut still, 0.0, &mut ()).unwrap();
+        assert_eq!(temperature(&still,1).to_bits(),temperature(&still,5).to_bits());
+
+        let mut currents = tile_map();
+        trace_ocean_currents(&mut currents, 0.5, &mut ()).unwrap();
+        // the downwind coast is warmed half way to the ocean temperature, the upwind coast gets nothing.
+        assert_eq!(temperature(&currents,5).to_bits(),15.0f64.to_bits());
+        assert_eq!(temperature(&currents,1).to_bits(),10.0f64.to_bits());
+        // the ocean tiles all had the same temperature, so they don't change.
+        assert_eq!(temperature(&currents,3).to_bits(),20.0f64.to_bits());
+    }
+
+    #[test]
+    fn test_parallel_precipitation_is_deterministic() {
+        // a grid of tiles larger than one chunk, with an ocean along the west and the wind blowing east across it.
+        const SIZE: u64 = 60;
+        let tile_map = || -> EntityIndex<TileSchema,TileDataForPrecipitation> {
+            (0..SIZE).flat_map(|row| (0..SIZE).map(move |column| (row,column))).map(|(row,column)| {
+                let index = row * SIZE + column;
+                let mut neighbors = Vec::new();
+                if row > 0 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(index - SIZE + 1)),Deg(180.0)));
+                }
+                if row < SIZE - 1 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(index + SIZE + 1)),Deg(0.0)));
+                }
+                if column > 0 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(index)),Deg(270.0)));
+                }
+                if column < SIZE - 1 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(index + 2)),Deg(90.0)));
+                } else {
+                    neighbors.push(NeighborAndDirection(Neighbor::OffMap(Edge::East),Deg(90.0)));
+                }
+                let mut tile = tile(((row % 3) as f64).mul_add(-10.0, 90.0), neighbors);
+                if column < 10 {
+                    tile.grouping = Grouping::Ocean;
+                }
+                tile.elevation = (column * 50) as f64;
+                (IdRef::new(index + 1),tile)
+            }).collect()
+        };
+
+        let trace_with_threads = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let mut tiles = tile_map();
+            pool.install(|| trace_precipitation(&mut tiles, 1.0, true, &mut ())).unwrap();
+            tiles.iter().map(|(fid,tile)| (fid.clone(),tile.precipitation.to_bits())).collect::<Vec<_>>()
+        };
+
+        let single = trace_with_threads(1);
+        assert!(single.iter().any(|(_,precipitation)| f64::from_bits(*precipitation) > 0.0));
+        assert_eq!(single,trace_with_threads(4));
+        assert_eq!(single,trace_with_threads(7));
+    }
+
+    #[test]
+    fn test_filtered_precipitation_boost() {
+        // a column of tiles from the south pole to the north pole, every 15 degrees.
+        let mut tile_map: EntityIndex<TileSchema,TileForPrecipitationAdjustment> = (0..13).map(|i| {
+            (IdRef::new(i + 1),TileForPrecipitationAdjustment {
+                site: (0.0,(i as f64).mul_add(15.0, -90.0)).try_into().unwrap(),
+                precipitation: 10.0
+            })
+        }).collect();
+
+        let filter = TileFilterArg {
+            latitudes: Some("-23.5..=23.5".parse().unwrap()),
+            region: None
+        }.filter().unwrap();
+        multiply_precipitation(&mut tile_map, 2.0, &filter);
+
+        for (fid,tile) in tile_map.iter() {
+            let expected = if tile.site.y().abs() <= 23.5 {
+                20.0f64
+            } else {
+                10.0
+            };
+            assert_eq!(tile.precipitation.to_bits(),expected.to_bits(),"tile {fid}");
+        }
+    }
+
+    #[test]
+    fn test_wind_at_poles() {
+        let winds = WindsArg {
+            north_polar_wind: 225,
+            north_middle_wind: 45,
+            north_tropical_wind: 225,
+            south_tropical_wind: 315,
+            south_middle_wind: 135,
+            south_polar_wind: 315,
+            wind_range: Vec::new(),
+            wind_raster: None
+        }.to_wind_bands();
+
+        assert_eq!(winds.get(90.0),Some(225));
+        assert_eq!(winds.get(89.9),Some(225));
+        assert_eq!(winds.get(-90.0),Some(315));
+        assert_eq!(winds.get(60.0),Some(225));
+        assert_eq!(winds.get(59.9),Some(45));
+        assert_eq!(winds.get(90.1),None);
+
+        // a custom band reaching the pole also covers it.
+        let custom_winds = WindsArg {
+            north_polar_wind: 225,
+            north_middle_wind: 45,
+            north_tropical_wind: 225,
+            south_tropical_wind: 315,
+            south_middle_wind: 135,
+            south_polar_wind: 315,
+            wind_range: vec![(OrderedFloat(80.0)..OrderedFloat(90.0),0)],
+            wind_raster: None
+        }.to_wind_bands();
+        assert_eq!(custom_winds.get(90.0),Some(0));
+    }
+
+    #[test]
+    fn test_wind_raster() {
+        let bands = WindsArg {
+            north_polar_wind: 225,
+            north_middle_wind: 45,
+            north_tropical_wind: 225,
+            south_tropical_wind: 315,
+            south_middle_wind: 135,
+            south_polar_wind: 315,
+            wind_range: Vec::new(),
+            wind_raster: None
+        }.to_wind_bands();
+        // three pixels along the equator, the middle one without data.
+        let raster = RasterSampler::new(0.0, 0.0, 1.0, 3, 1, vec![10.0, -9999.0, -90.0], Some(-9999.0));
+
+        let wind = |lon: f64, lat: f64| {
+            let site: Coordinates = (lon,lat).try_into().unwrap();
+            wind_direction(&bands, Some(&raster), &site).0
+        };
+
+        assert_eq!(wind(0.5,0.5).to_bits(),10.0f64.to_bits());
+        // directions are kept within a full turn
+        assert_eq!(wind(2.5,0.5).to_bits(),270.0f64.to_bits());
+        // no data and off the raster fall back to the bands
+        assert_eq!(wind(1.5,0.5).to_bits(),225.0f64.to_bits());
+        assert_eq!(wind(1.5,-0.5).to_bits(),315.0f64.to_bits());
+        // and without a raster, the bands are used everywhere
+        let site: Coordinates = (0.5,0.5).try_into().unwrap();
+        assert_eq!(wind_direction(&bands, None, &site).0.to_bits(),225.0f64.to_bits());
+    }
+
+    #[test]
+    fn test_precipitation_factor() {
+        let precipitation = |precipitation_factor: f64, allow_extreme_precipitation: bool| PrecipitationArg {
+            precipitation_factor,
+            allow_extreme_precipitation,
+            parallel: false
+        }.validated_factor();
+
+        assert_eq!(precipitation(0.0,false).unwrap().to_bits(),0.0_f64.to_bits());
+        assert_eq!(precipitation(5.0,false).unwrap().to_bits(),5.0_f64.to_bits());
+        _ = precipitation(5.5,false).unwrap_err();
+        _ = precipitation(-1.0,false).unwrap_err();
+        _ = precipitation(f64::NAN,false).unwrap_err();
+        // the override allows larger amounts, but still not negative ones.
+        assert_eq!(precipitation(20.0,true).unwrap().to_bits(),20.0_f64.to_bits());
+        _ = precipitation(-1.0,true).unwrap_err();
+    }
+}
@@ -1,9 +1,20 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::path::Path;
+
 use rand::Rng;
 use prisma::Rgb;
 use prisma::Hsv;
 use prisma::FromColor;
 use angular_units::Deg;
 use angular_units::Angle;
+use serde::Deserialize;
+use schemars::JsonSchema;
+use serde_json::from_reader as from_json_reader;
+
+use crate::errors::CommandError;
+use crate::world_map::fields::ColorConversion;
 
 
 
@@ -574,3 +585,90 @@ impl RandomColorGenerator {
 }
 
 
+#[derive(Deserialize,JsonSchema)]
+pub(crate) struct ColorRampStopSource {
+    /// The value at which the color is reached exactly
+    value: f64,
+    /// The color as a hex string, such as '#1F78B4'
+    color: String
+}
+
+/// Maps continuous values, such as elevation or temperature, to colors by interpolating between a list of stops.
+pub(crate) struct ColorRamp {
+    stops: Vec<(f64,Rgb<u8>)> // sorted by value, never empty
+}
+
+impl ColorRamp {
+
+    pub(crate) fn new(mut stops: Vec<(f64,Rgb<u8>)>) -> Result<Self,CommandError> {
+        if stops.is_empty() {
+            return Err(CommandError::ColorRampRead("At least one stop is required.".to_owned()))
+        }
+        // a stable sort, so two stops with the same value can make a sharp edge, such as at a coastline.
+        stops.sort_by(|(a,_),(b,_)| a.total_cmp(b));
+        Ok(Self {
+            stops
+        })
+    }
+
+    pub(crate) fn from_json<Reader: Read>(source: BufReader<Reader>) -> Result<Self,CommandError> {
+        let data = from_json_reader::<_,Vec<ColorRampStopSource>>(source).map_err(|e| CommandError::ColorRampRead(format!("{e}")))?;
+        let stops = data.into_iter().map(|stop| Ok((stop.value,Rgb::try_from_hex_str(&stop.color)?))).collect::<Result<_,CommandError>>()?;
+        Self::new(stops)
+    }
+
+    pub(crate) fn from_file<AsPath: AsRef<Path>>(file: AsPath) -> Result<Self,CommandError> {
+        let ramp_source = File::open(file).map_err(|e| CommandError::ColorRampRead(format!("{e}")))?;
+        Self::from_json(BufReader::new(ramp_source))
+    }
+
+    /// Returns the color at the value, interpolated between the stops on either side. Values beyond the first or last stop get that stop's color.
+    pub(crate) fn sample(&self, t: f64) -> Rgb<u8> {
+        let mix = |a: u8, b: u8, amount: f64| f64::from(a).mul_add(1.0 - amount, f64::from(b) * amount).round() as u8;
+        match self.stops.iter().position(|(value,_)| t < *value) {
+            Some(0) => self.stops[0].1,
+            Some(index) => {
+                let (low,low_color) = self.stops[index - 1];
+                let (high,high_color) = self.stops[index];
+                let amount = (t - low)/(high - low);
+                Rgb::new(mix(low_color.red(),high_color.red(),amount),mix(low_color.green(),high_color.green(),amount),mix(low_color.blue(),high_color.blue(),amount))
+            },
+            None => self.stops[self.stops.len() - 1].1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use prisma::Rgb;
+
+    use super::ColorRamp;
+
+    #[test]
+    fn test_color_ramp() {
+        let ramp = ColorRamp::from_json(BufReader::new(r##"[
+            { "value": 10.0, "color": "#C86400" },
+            { "value": 0.0, "color": "#000000" }
+        ]"##.as_bytes())).expect("ramp should parse");
+
+        // endpoints, and beyond them
+        assert_eq!(ramp.sample(0.0),Rgb::new(0,0,0));
+        assert_eq!(ramp.sample(10.0),Rgb::new(200,100,0));
+        assert_eq!(ramp.sample(-5.0),Rgb::new(0,0,0));
+        assert_eq!(ramp.sample(20.0),Rgb::new(200,100,0));
+        // midpoint
+        assert_eq!(ramp.sample(5.0),Rgb::new(100,50,0));
+    }
+
+    #[test]
+    fn test_color_ramp_sharp_edge() {
+        let ramp = ColorRamp::new(vec![(-1.0,Rgb::new(0,0,100)),(0.0,Rgb::new(0,0,200)),(0.0,Rgb::new(0,200,0)),(1.0,Rgb::new(0,100,0))]).expect("ramp should be valid");
+        assert_eq!(ramp.sample(-0.5),Rgb::new(0,0,150));
+        assert_eq!(ramp.sample(0.0),Rgb::new(0,200,0));
+        assert_eq!(ramp.sample(0.5),Rgb::new(0,150,0));
+
+        assert!(ColorRamp::new(Vec::new()).is_err(),"empty ramp should fail");
+    }
+}
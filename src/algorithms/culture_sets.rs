@@ -21,7 +21,9 @@ use crate::world_map::tile_layer::TileForCulturePrefSorting;
 use core::slice::Iter;
 use core::ops::Index;
 use std::io::Read;
+use std::io::BufRead;
 use crate::typed_map::entities::NamedEntity;
+use crate::typed_map::fields::IdRef;
 
 #[derive(Clone,Serialize,Deserialize,JsonSchema)]
 pub(crate) enum TilePreference {
@@ -167,6 +169,16 @@ impl CultureSetItem {
 
     }
 
+    #[cfg(test)]
+    pub(crate) const fn new(name: String, namer: String, probability: f64) -> Self {
+        Self {
+            name,
+            namer,
+            probability,
+            preferences: TilePreference::Habitability
+        }
+    }
+
     pub(crate) fn name(&self) -> &str {
         &self.name
     }
@@ -175,6 +187,10 @@ impl CultureSetItem {
         &self.namer
     }
 
+    pub(crate) const fn probability(&self) -> f64 {
+        self.probability
+    }
+
     pub(crate) const fn preferences(&self) -> &TilePreference {
         &self.preferences
     }
@@ -197,6 +213,13 @@ impl CultureSet {
         }
     }
 
+    #[cfg(test)]
+    pub(crate) const fn from_items(source: Vec<CultureSetItem>) -> Self {
+        Self {
+            source
+        }
+    }
+
     pub(crate) fn from_files<Random: Rng>(files: &Vec<PathBuf>, rng: &mut Random, namers: &mut NamerSet) -> Result<Self,CommandError> {
         let mut result = Self::empty();
 
@@ -303,3 +326,109 @@ impl<'data_life> IntoIterator for &'data_life CultureSet {
         self.source.iter()
     }
 }
+
+#[derive(Deserialize)]
+struct CultureAssignmentProperties {
+    fid: u64,
+    culture: String
+}
+
+#[derive(Deserialize)]
+struct CultureAssignmentFeature {
+    properties: CultureAssignmentProperties
+}
+
+#[derive(Deserialize)]
+struct CultureAssignmentCollection {
+    features: Vec<CultureAssignmentFeature>
+}
+
+/// Cultures assigned to tiles by hand, read from a CSV or GeoJSON file, for use instead of generating them.
+pub(crate) struct CultureAssignments {
+    tiles: Vec<(IdRef,String)>
+}
+
+impl CultureAssignments {
+
+    /// Reads lines of `fid,culture`. A header line is skipped if its first value isn't a number.
+    pub(crate) fn from_csv<Reader: Read>(source: BufReader<Reader>) -> Result<Self,CommandError> {
+        let mut tiles = Vec::new();
+        for (index,line) in source.lines().enumerate() {
+            let line = line.map_err(|e| CommandError::CultureImportRead(format!("{e}")))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let unquote = |value: &str| value.trim().trim_matches('"').to_owned();
+            let (fid,culture) = line.split_once(',').ok_or_else(|| CommandError::CultureImportRead(format!("Line {} should have a tile fid and a culture name separated by a comma.",index + 1)))?;
+            match unquote(fid).parse::<u64>() {
+                Ok(fid) => tiles.push((IdRef::new(fid),unquote(culture))),
+                Err(_) if index == 0 => (), // a header
+                Err(e) => return Err(CommandError::CultureImportRead(format!("Line {} has an invalid tile fid: {e}",index + 1)))
+            }
+        }
+        Ok(Self { tiles })
+    }
+
+    /// Reads a GeoJSON feature collection, whose features have `fid` and `culture` properties. Geometries are ignored.
+    pub(crate) fn from_geojson<Reader: Read>(source: BufReader<Reader>) -> Result<Self,CommandError> {
+        let data = from_json_reader::<_,CultureAssignmentCollection>(source).map_err(|e| CommandError::CultureImportRead(format!("{e}")))?;
+        Ok(Self {
+            tiles: data.features.into_iter().map(|feature| (IdRef::new(feature.properties.fid),feature.properties.culture)).collect()
+        })
+    }
+
+    pub(crate) fn from_file<AsPath: AsRef<Path>>(file: AsPath) -> Result<Self,CommandError> {
+
+        enum Format {
+            Csv,
+            GeoJSON
+        }
+
+        let format = match file.as_ref().extension().and_then(OsStr::to_str) {
+            Some("csv") => Format::Csv,
+            Some("geojson" | "json") => Format::GeoJSON,
+            _ => return Err(CommandError::CultureImportRead(format!("'{}' should have one of the supported extensions: csv, geojson or json.",file.as_ref().display())))
+        };
+
+        let assignment_source = File::open(file).map_err(|e| CommandError::CultureImportRead(format!("{e}")))?;
+        let reader = BufReader::new(assignment_source);
+
+        match format {
+            Format::Csv => Self::from_csv(reader),
+            Format::GeoJSON => Self::from_geojson(reader),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Iter<'_,(IdRef,String)> {
+        self.tiles.iter()
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use super::CultureAssignments;
+    use crate::errors::CommandError;
+    use crate::typed_map::fields::IdRef;
+
+    #[test]
+    fn test_read_culture_assignments() {
+        let csv = "fid,culture\n1,Elves\n\n2, \"High Elves\"\n";
+        let assignments = CultureAssignments::from_csv(BufReader::new(csv.as_bytes())).unwrap();
+        assert_eq!(assignments.iter().cloned().collect::<Vec<_>>(),vec![(IdRef::new(1),"Elves".to_owned()),(IdRef::new(2),"High Elves".to_owned())]);
+
+        assert!(CultureAssignments::from_csv(BufReader::new("1,Elves\nx,Dwarves".as_bytes())).is_err());
+
+        let geojson = r#"{"type":"FeatureCollection","features":[
+            {"type":"Feature","geometry":null,"properties":{"fid":3,"culture":"Dwarves"}}
+        ]}"#;
+        let features = CultureAssignments::from_geojson(BufReader::new(geojson.as_bytes())).unwrap();
+        assert_eq!(features.iter().cloned().collect::<Vec<_>>(),vec![(IdRef::new(3),"Dwarves".to_owned())]);
+
+        // the format comes from the extension, so files without a known one aren't guessed at.
+        assert!(matches!(CultureAssignments::from_file("cultures.txt"),Err(CommandError::CultureImportRead(_))));
+        assert!(matches!(CultureAssignments::from_file("cultures"),Err(CommandError::CultureImportRead(_))));
+    }
+}
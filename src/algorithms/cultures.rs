@@ -3,8 +3,11 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 
 use rand::Rng;
+use rand_distr::Distribution;
+use rand_distr::WeightedIndex;
 use priority_queue::PriorityQueue;
 use ordered_float::OrderedFloat;
+use indexmap::IndexMap;
 
 use crate::progress::ProgressObserver;
 use crate::progress::WatchableIterator;
@@ -12,6 +15,8 @@ use crate::progress::WatchablePriorityQueue;
 use crate::world_map::WorldMapTransaction;
 use crate::errors::CommandError;
 use crate::algorithms::culture_sets::CultureSet;
+use crate::algorithms::culture_sets::CultureSetItem;
+use crate::algorithms::culture_sets::CultureAssignments;
 use crate::algorithms::naming::NamerSet;
 use crate::world_map::water_layers::LakeForCultureGen;
 use crate::world_map::biome_layer::BiomeForCultureGen;
@@ -19,6 +24,9 @@ use crate::world_map::biome_layer::BiomeForCultureExpand;
 use crate::world_map::tile_layer::TileForCultureGen;
 use crate::world_map::tile_layer::TileForCulturePrefSorting;
 use crate::world_map::tile_layer::TileForCultureExpand;
+use crate::world_map::tile_layer::TileForCultureImport;
+use crate::world_map::tile_layer::TileSchema;
+use crate::typed_map::fields::IdRef;
 use crate::utils::random::RandomIndex;
 use crate::utils::coordinates::Coordinates;
 use crate::utils::ToRoman;
@@ -38,6 +46,8 @@ use crate::commands::OverwriteCulturesArg;
 use crate::commands::SizeVarianceArg;
 use crate::commands::RiverThresholdArg;
 use crate::commands::ExpansionFactorArg;
+use crate::commands::CultureWaterCostArg;
+use crate::commands::MaxExpansionCostArg;
 use crate::world_map::fields::NeighborAndDirection;
 use crate::world_map::fields::Neighbor;
 use crate::typed_map::entities::NamedEntity;
@@ -60,19 +70,12 @@ impl CultureType {
 
 
 
-pub(crate) fn generate_cultures<Random: Rng, Progress: ProgressObserver>(target: &mut WorldMapTransaction, rng: &mut Random, culture_set: &CultureSet, namers: &NamerSet, culture_count: usize, size_variance: &SizeVarianceArg, river_threshold: &RiverThresholdArg, overwrite_layer: &OverwriteCulturesArg, progress: &mut Progress) -> Result<(),CommandError> {
+pub(crate) fn generate_cultures<Random: Rng, Progress: ProgressObserver>(target: &mut WorldMapTransaction, rng: &mut Random, culture_set: &CultureSet, namers: &NamerSet, culture_count: usize, diversify_namers: bool, size_variance: &SizeVarianceArg, river_threshold: &RiverThresholdArg, overwrite_layer: &OverwriteCulturesArg, progress: &mut Progress) -> Result<(),CommandError> {
 
     const MAX_ATTEMPTS: usize = 100;
 
     // Algorithm copied from AFMG
 
-    let culture_count = if culture_count > culture_set.len() {
-        progress.warning(|| format!("The provided culture set is not large enough to produce the requested number of cultures. The count will be limited to {}.",culture_set.len()));
-        culture_set.len()
-    } else {
-        culture_count
-    };
-
     let world_shape = target.edit_properties_layer()?.get_world_shape()?;
 
     let biomes = target.edit_biomes_layer()?.read_features().into_named_entities_index(progress)?;
@@ -81,12 +84,12 @@ pub(crate) fn generate_cultures<Random: Rng, Progress: ProgressObserver>(target:
 
     let mut tile_layer = target.edit_tile_layer()?;
 
-    let (max_habitability, mut populated) = get_culturable_tiles(&mut tile_layer, &biomes, &lake_map, progress)?;
+    let (max_habitability, mut populated, has_ocean) = get_culturable_tiles(&mut tile_layer, &biomes, &lake_map, progress)?;
 
     let culture_count = if populated.len() < (culture_count * 25) {
         let fixed_culture_count = populated.len().div_euclid(25);
         if fixed_culture_count == 0 {
-            progress.warning(|| "There aren't enough habitable tiles to support urban societies. Only the 'wildlands' culture will be created.")
+            progress.warning(|| "There aren't enough habitable tiles to support urban societies. No cultures will be created.")
         } else {
             progress.warning(|| format!("There aren't enough habitiable tiles to support the requested number of cultures. The count will be limited to {fixed_culture_count}."))
         }
@@ -96,7 +99,7 @@ pub(crate) fn generate_cultures<Random: Rng, Progress: ProgressObserver>(target:
         culture_count
     };
 
-    let culture_sources = culture_set.select(rng,culture_count);
+    let culture_sources = select_culture_sources(culture_set, rng, culture_count, progress);
 
     let mut placed_centers = Vec::new();
     let mut cultures = Vec::new();
@@ -162,7 +165,7 @@ pub(crate) fn generate_cultures<Random: Rng, Progress: ProgressObserver>(target:
         let name = culture_source.name().to_owned();
 
         // define the culture type
-        let culture_type = get_culture_type(&center, river_threshold.river_threshold, rng);
+        let culture_type = get_culture_type(&center, river_threshold.river_threshold, has_ocean, rng);
         
         let expansionism = culture_type.generate_expansionism(rng,size_variance.size_variance);
 
@@ -204,6 +207,10 @@ pub(crate) fn generate_cultures<Random: Rng, Progress: ProgressObserver>(target:
 
     }
 
+    if diversify_namers {
+        diversify_culture_namers(&mut cultures, namers);
+    }
+
     // NOTE: AFMG Had a Wildlands culture that was automatically placed wherever there were no cultures.
     // However, that culture did not behave like other cultures. The option is to do this, have a
     // special culture that doesn't have a culture center, and doesn't behave like a culture, or to 
@@ -228,14 +235,134 @@ pub(crate) fn generate_cultures<Random: Rng, Progress: ProgressObserver>(target:
     Ok(())
 }
 
-fn get_culturable_tiles<'biome_life, Progress: ProgressObserver>(tile_layer: &mut TileLayer, biomes: &'biome_life EntityLookup<BiomeSchema, BiomeForCultureGen>, lake_map: &EntityIndex<LakeSchema, LakeForCultureGen>, progress: &mut Progress) -> Result<(f64, Vec<TileForCulturePrefSorting<'biome_life>>), CommandError> {
+fn diversify_culture_namers(cultures: &mut [NewCulture], namers: &NamerSet) {
+    let culture_namers: Vec<String> = cultures.iter().map(|culture| culture.namer.clone()).collect();
+    for (culture,namer) in cultures.iter_mut().zip(namers.diversify(&culture_namers)) {
+        culture.namer = namer;
+    }
+}
+
+/// Sets the culture of each assigned tile, which must be land, and returns the cultures in the order they first appear along with their most habitable tile, which becomes the culture center.
+fn apply_culture_assignments(tile_map: &mut EntityIndex<TileSchema,TileForCultureImport>, assignments: &CultureAssignments) -> Result<Vec<(String,IdRef)>,CommandError> {
+
+    let mut centers: IndexMap<String,(IdRef,f64)> = IndexMap::new();
+
+    for (fid,culture) in assignments.iter() {
+        let tile = tile_map.try_get_mut(fid)?;
+        if tile.grouping().is_water() {
+            return Err(CommandError::CultureAssignedToWater(culture.clone(),fid.clone()))
+        }
+        tile.set_culture(Some(culture.clone()));
+        let habitability = *tile.habitability();
+        match centers.get_mut(culture) {
+            Some(center) => if habitability > center.1 {
+                *center = (fid.clone(),habitability);
+            },
+            None => {
+                _ = centers.insert(culture.clone(), (fid.clone(),habitability));
+            }
+        }
+    }
+
+    Ok(centers.into_iter().map(|(culture,(center,_))| (culture,center)).collect())
+}
+
+pub(crate) fn import_cultures<Random: Rng, Progress: ProgressObserver>(target: &mut WorldMapTransaction, rng: &mut Random, culture_set: &CultureSet, namers: &NamerSet, assignments: &CultureAssignments, diversify_namers: bool, overwrite_layer: &OverwriteCulturesArg, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let mut tiles = target.edit_tile_layer()?;
+
+    let mut tile_map = tiles.read_features().into_entities_index::<_,TileForCultureImport>(progress)?;
+
+    let centers = apply_culture_assignments(&mut tile_map, assignments)?;
+
+    let mut colors = RandomColorGenerator::new(None,Some(Luminosity::Light)).generate_colors(centers.len(), rng).into_iter();
+
+    let mut cultures = Vec::new();
+
+    for (name,center_tile_id) in centers {
+
+        let namer = if let Some(source) = culture_set.into_iter().find(|source| source.name() == name) {
+            source.namer_name().to_owned()
+        } else {
+            progress.warning(|| format!("Culture '{name}' is not in the culture set, so it will be created with the default namer."));
+            namers.default_namer().to_owned()
+        };
+
+        namers.check_exists(&namer)?;
+
+        // imported cultures don't expand, so the type and expansionism are only used by later steps such as nations.
+        cultures.push(NewCulture {
+            name,
+            namer,
+            type_: CultureType::Generic,
+            expansionism: 1.0,
+            center_tile_id,
+            color: colors.next().expect("There should have been just as many colors generated as cultures.")
+        });
+    }
+
+    if diversify_namers {
+        diversify_culture_namers(&mut cultures, namers);
+    }
+
+    for (fid,tile) in tile_map.iter().watch(progress,"Writing cultures.","Cultures written.") {
+
+        let mut feature = tiles.try_feature_by_id(fid)?;
+
+        feature.set_culture(tile.culture())?;
+
+        feature.set_secondary_culture(&None)?;
+
+        feature.set_culture_blend(&0.0)?;
+
+        tiles.update_feature(feature)?;
+
+    }
+
+    let mut cultures_layer = target.create_cultures_layer(overwrite_layer)?;
+
+    for culture in cultures.iter().watch(progress,"Writing cultures.","Cultures written.") {
+
+        _ = cultures_layer.add_culture(culture)?;
+
+    }
+
+    Ok(())
+}
+
+/// Chooses the cultures to place from the set. If the set doesn't have that many cultures, every culture is chosen once, and the rest reuse cultures chosen by their probability. The reused cultures get numbered names along with any other duplicates.
+fn select_culture_sources<Random: Rng, Progress: ProgressObserver>(culture_set: &CultureSet, rng: &mut Random, culture_count: usize, progress: &Progress) -> Vec<CultureSetItem> {
+
+    let mut selected = culture_set.select(rng,culture_count);
+
+    let reused_count = culture_count.saturating_sub(selected.len());
+    if (reused_count > 0) && (culture_set.len() > 0) {
+        progress.warning(|| format!("The provided culture set only has {} cultures, so {reused_count} of the requested cultures will reuse them.",culture_set.len()));
+
+        // if no culture has a positive probability, they are all equally likely instead.
+        let weights = WeightedIndex::new(culture_set.into_iter().map(|culture| culture.probability().max(0.0))).ok();
+        for _ in 0..reused_count {
+            let index = match &weights {
+                Some(weights) => weights.sample(rng),
+                None => rng.gen_range(0..culture_set.len())
+            };
+            selected.push(culture_set[index].clone());
+        }
+    }
+
+    selected
+}
+
+fn get_culturable_tiles<'biome_life, Progress: ProgressObserver>(tile_layer: &mut TileLayer, biomes: &'biome_life EntityLookup<BiomeSchema, BiomeForCultureGen>, lake_map: &EntityIndex<LakeSchema, LakeForCultureGen>, progress: &mut Progress) -> Result<(f64, Vec<TileForCulturePrefSorting<'biome_life>>, bool), CommandError> {
 
     let mut max_habitability: f64 = 0.0;
+    let mut has_ocean = false;
     
     let mut populated = Vec::new();
     
     for tile in tile_layer.read_features().into_entities::<TileForCultureGen>().watch(progress,"Reading tiles.","Tiles read.") {
         let (_,tile) = tile?;
+        has_ocean |= tile.grouping().is_ocean();
         if tile.population() > &0 {
             max_habitability = max_habitability.max(*tile.habitability());
             populated.push(tile);
@@ -249,11 +376,11 @@ fn get_culturable_tiles<'biome_life, Progress: ProgressObserver>(tile_layer: &mu
         sortable_populated.push(TileForCulturePrefSorting::from(tile, &*tile_layer, biomes, lake_map)?);
     }
 
-    Ok((max_habitability, sortable_populated))
+    Ok((max_habitability, sortable_populated, has_ocean))
 }
 
 
-fn get_culture_type<Random: Rng>(center: &TileForCulturePrefSorting, river_threshold: f64, rng: &mut Random) -> CultureType {
+fn get_culture_type<Random: Rng>(center: &TileForCulturePrefSorting, river_threshold: f64, has_ocean: bool, rng: &mut Random) -> CultureType {
     if center.elevation_scaled() < 70 && *center.biome().supports_nomadic() {
         return CultureType::Nomadic 
     } else if center.elevation_scaled() > 50 {
@@ -267,9 +394,10 @@ fn get_culture_type<Random: Rng>(center: &TileForCulturePrefSorting, river_thres
             }
         }
 
-        if (center.neighboring_lake_size().is_none() && rng.gen_bool(0.1)) || // on the ocean cost (on water cost and not on a lake)
+        // a world with no ocean has nowhere to sail, so cultures on small lakes are left to the other types.
+        if has_ocean && ((center.neighboring_lake_size().is_none() && rng.gen_bool(0.1)) || // on the ocean cost (on water cost and not on a lake)
            ((water_count == 1) && rng.gen_bool(0.6)) || // on exactly one water (makes a good harbor)
-           (matches!(center.grouping(),Grouping::Islet) && rng.gen_bool(0.4)) { // on a small island
+           (matches!(center.grouping(),Grouping::Islet) && rng.gen_bool(0.4))) { // on a small island
             return CultureType::Naval
         }
     }
@@ -297,7 +425,7 @@ fn too_close(point_vec: &Vec<Coordinates>, new_point: &Coordinates, spacing: f64
 }
 
 
-pub(crate) fn expand_cultures<Progress: ProgressObserver>(target: &mut WorldMapTransaction, river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, progress: &mut Progress) -> Result<(),CommandError> {
+pub(crate) fn expand_cultures<Progress: ProgressObserver>(target: &mut WorldMapTransaction, river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, max_cost: &MaxExpansionCostArg, water_cost: &CultureWaterCostArg, progress: &mut Progress) -> Result<(),CommandError> {
 
     let world_shape = target.edit_properties_layer()?.get_world_shape()?;
 
@@ -310,6 +438,45 @@ pub(crate) fn expand_cultures<Progress: ProgressObserver>(target: &mut WorldMapT
     // we're working with a tile map, and completely overwriting whatever is there.
     let mut tile_map = tiles.read_features().into_entities_index::<_,TileForCultureExpand>(progress)?;
 
+    let tile_size = tiles.estimate_average_tile_area(&world_shape)?;
+
+    let max_expansion_cost = expansion_cost_limit(tile_size, limit_factor, max_cost);
+
+    spread_cultures(&mut tile_map, cultures, &biome_map, river_threshold.river_threshold, water_cost.culture_water_cost, max_expansion_cost, progress)?;
+
+    let (unclaimed_count,unclaimed_area) = tally_unclaimed_tiles(tile_map.iter().map(|(_,tile)| (*tile.habitability(),*tile.area(),tile.culture().is_some())));
+    progress.message(|| format!("{unclaimed_count} habitable tiles, covering {unclaimed_area:.1} square degrees, were not reached by any culture. A higher expansion factor will let cultures expand further."));
+
+    for (fid,tile) in tile_map.iter().watch(progress,"Writing cultures.","Cultures written.") {
+
+        let mut feature = tiles.try_feature_by_id(fid)?;
+
+        feature.set_culture(tile.culture())?;
+
+        feature.set_secondary_culture(tile.secondary_culture())?;
+
+        feature.set_culture_blend(tile.culture_blend())?;
+
+        tiles.update_feature(feature)?;
+
+    }
+
+
+    Ok(())
+}
+
+fn expansion_cost_limit(tile_size: f64, limit_factor: &ExpansionFactorArg, max_cost: &MaxExpansionCostArg) -> OrderedFloat<f64> {
+    // This is how far the cultures will be able to spread.
+    // This is a arbitrary number, it basically limits the size of the culture to about 10,000 "square degrees". Although once
+    // I get sherical directions and areas, I'll want to revisit this.
+    let factor_limit = 10000.0/tile_size * limit_factor.expansion_factor;
+    // The hard cap is applied to the same cumulative cost, so a tile beyond it is never claimed, and neither is anything that
+    // can only be reached through it.
+    OrderedFloat::from(factor_limit.min(max_cost.max_expansion_cost))
+}
+
+fn spread_cultures<Progress: ProgressObserver>(tile_map: &mut EntityIndex<TileSchema,TileForCultureExpand>, cultures: Vec<CultureForPlacement>, biome_map: &EntityLookup<BiomeSchema,BiomeForCultureExpand>, river_threshold: f64, water_cost: f64, max_expansion_cost: OrderedFloat<f64>, progress: &mut Progress) -> Result<(),CommandError> {
+
     // priority queue keeps tasks sorted by priority
     // Since I need to go for the least priorities first, I need the double queue to get pop_min
     let mut queue = PriorityQueue::new();
@@ -317,12 +484,8 @@ pub(crate) fn expand_cultures<Progress: ProgressObserver>(target: &mut WorldMapT
     // empty hashmap of tile ids
     let mut costs = HashMap::new();
 
-    let tile_size = tiles.estimate_average_tile_area(&world_shape)?;
-
-    // This is how far the cultures will be able to spread.
-    // This is a arbitrary number, it basically limits the size of the culture to about 10,000 "square degrees". Although once
-    // I get sherical directions and areas, I'll want to revisit this.
-    let max_expansion_cost = OrderedFloat::from(10000.0/tile_size * limit_factor.expansion_factor);
+    // the cheapest cost at which each culture reached each tile, including the ones which lost the tile, so the runner-up can be found for blending.
+    let mut reached = HashMap::new();
 
     let mut culture_centers = HashSet::new();
     
@@ -368,9 +531,9 @@ pub(crate) fn expand_cultures<Progress: ProgressObserver>(target: &mut WorldMapT
                     // from my view, this will always return 0.
                     // let biome_change_cost = if neighbor_biome == biome_map.get(&neighbor.biome) { 0 } else { 20 };
 
-                    let height_cost = get_height_cost(neighbor, culture.type_());
+                    let height_cost = get_water_cost(neighbor, culture.type_(), water_cost).unwrap_or_else(|| get_height_cost(neighbor, culture.type_()));
 
-                    let river_cost = get_river_cost(neighbor, river_threshold.river_threshold, culture.type_());
+                    let river_cost = get_river_cost(neighbor, river_threshold, culture.type_());
 
                     let type_cost = get_shore_cost(neighbor, culture.type_());
 
@@ -380,6 +543,10 @@ pub(crate) fn expand_cultures<Progress: ProgressObserver>(target: &mut WorldMapT
 
                     if total_cost <= max_expansion_cost {
 
+                        let reached_costs = reached.entry(neighbor_id.clone()).or_insert_with(HashMap::new);
+                        let reached_cost = reached_costs.entry(culture.name().clone()).or_insert(total_cost);
+                        *reached_cost = total_cost.min(*reached_cost);
+
                         // if no previous cost has been assigned for this tile, or if the total_cost is less than the previously assigned cost,
                         // then I can place or replace the culture with this one. This will remove cultures that were previously
                         // placed, and in theory could even wipe a culture off the map. (Although the previous culture placement
@@ -435,18 +602,38 @@ pub(crate) fn expand_cultures<Progress: ProgressObserver>(target: &mut WorldMapT
 
     }
 
-    for (fid,tile) in tile_map.iter().watch(progress,"Writing cultures.","Cultures written.") {
+    blend_cultures(tile_map, &costs, &reached);
 
-        let mut feature = tiles.try_feature_by_id(fid)?;
+    Ok(())
 
-        feature.set_culture(tile.culture())?;
+}
 
-        tiles.update_feature(feature)?;
+/// Counts the habitable tiles which no culture or nation reached, and adds up their area, so users can tell how much the expansion factor is limiting them. Each tile is given as its habitability, its area, and whether it was claimed.
+pub(crate) fn tally_unclaimed_tiles<Tiles: IntoIterator<Item = (f64,f64,bool)>>(tiles: Tiles) -> (usize,f64) {
+    tiles.into_iter().filter(|(habitability,_,claimed)| (*habitability > 0.0) && !claimed).fold((0,0.0), |(count,total_area),(_,area,_)| (count + 1,total_area + area))
+}
 
-    }
+/// Where another culture also reached a tile, the cheapest of those is recorded as the secondary culture, with a blend weight that
+/// approaches 0.5 as its cost approaches the cost of the culture that won the tile. Cultures only spread from tiles they won, so
+/// this only happens along the borders.
+fn blend_cultures(tile_map: &mut EntityIndex<TileSchema,TileForCultureExpand>, costs: &HashMap<IdRef,OrderedFloat<f64>>, reached: &HashMap<IdRef,HashMap<String,OrderedFloat<f64>>>) {
 
+    for (tile_id,tile) in tile_map.iter_mut() {
+        let (Some(cost),Some(reached_costs)) = (costs.get(tile_id),reached.get(tile_id)) else {
+            continue;
+        };
+        let runner_up = reached_costs.iter().filter(|(name,_)| Some(*name) != tile.culture().as_ref()).min_by(|(a_name,a_cost),(b_name,b_cost)| a_cost.cmp(b_cost).then_with(|| a_name.cmp(b_name)));
+        if let Some((name,runner_up_cost)) = runner_up {
+            let total = cost.into_inner() + runner_up_cost.into_inner();
+            tile.set_culture_blend(if total > 0.0 {
+                cost.into_inner() / total
+            } else {
+                0.5
+            });
+            tile.set_secondary_culture(Some(name.clone()));
+        }
+    }
 
-    Ok(())
 }
 
 const fn get_shore_cost(neighbor: &TileForCultureExpand, culture_type: &CultureType) -> f64 {
@@ -509,50 +696,43 @@ fn get_river_cost(neighbor: &TileForCultureExpand, river_threshold: f64, culture
     }
 }
 
+// Nomadic cultures pay this many times the water cost, which comes to 50 per unit of area at the default.
+const NOMADIC_WATER_COST_FACTOR: f64 = 50.0 / 6.0;
+
+/// Returns the cost of crossing the neighbor if it's water, or None if it's land. The water cost is per unit of the tile's area.
+fn get_water_cost(neighbor: &TileForCultureExpand, culture_type: &CultureType, water_cost: f64) -> Option<f64> {
+    if !neighbor.grouping().is_water() {
+        return None;
+    }
+    Some(match culture_type {
+        // low water crossing penalty
+        CultureType::Naval => neighbor.area() * 2.0,
+        // low lake crossing penalty for lake cultures
+        CultureType::Lake if neighbor.lake_id().is_some() => 10.0,
+        // nomads avoid water more than most
+        CultureType::Nomadic => scale_water_cost(neighbor, water_cost * NOMADIC_WATER_COST_FACTOR),
+        // general sea/lake crossing penalty
+        CultureType::Lake |
+        CultureType::Highland |
+        CultureType::Generic |
+        CultureType::River |
+        CultureType::Hunting => scale_water_cost(neighbor, water_cost)
+    })
+}
+
+fn scale_water_cost(neighbor: &TileForCultureExpand, water_cost: f64) -> f64 {
+    if water_cost.is_infinite() {
+        // avoid a NaN if the area is zero
+        water_cost
+    } else {
+        neighbor.area() * water_cost
+    }
+}
+
+/// Returns the cost of crossing the neighbor based on its elevation. Water is handled by [`get_water_cost`].
 fn get_height_cost(neighbor: &TileForCultureExpand, culture_type: &CultureType) -> f64 {
     match culture_type {
-        CultureType::Lake => if neighbor.lake_id().is_some() {
-            // low lake crossing penalty for lake cultures
-            10.0
-        } else if neighbor.grouping().is_water() {
-            // general sea/lake crossing penalty
-            neighbor.area() * 6.0
-        } else if neighbor.elevation_scaled() >= &67 {
-            // mountain crossing penalty
-            200.0 
-        } else if neighbor.elevation_scaled() > &44 {
-            // hill crossing penalt
-            30.0
-        } else {
-            0.0
-        },
-        CultureType::Naval => if neighbor.grouping().is_water() {
-            // low water crossing penalty
-            neighbor.area() * 2.0
-        } else if neighbor.elevation_scaled() >= &67 {
-            // mountain crossing penalty
-            200.0 
-        } else if neighbor.elevation_scaled() > &44 {
-            // hill crossing penalt
-            30.0
-        } else {
-            0.0
-        },
-        CultureType::Nomadic => if neighbor.grouping().is_water() {
-            neighbor.area() * 50.0
-        } else if neighbor.elevation_scaled() >= &67 {
-            // mountain crossing penalty
-            200.0 
-        } else if neighbor.elevation_scaled() > &44 {
-            // hill crossing penalt
-            30.0
-        } else {
-            0.0
-        },
-        CultureType::Highland => if neighbor.grouping().is_water() {
-            // general sea/lake corssing penalty
-            neighbor.area() * 6.0
-        } else if neighbor.elevation_scaled() < &44 {
+        CultureType::Highland => if neighbor.elevation_scaled() < &44 {
             // big penalty for highlanders in lowlands
             3000.0
         } else if neighbor.elevation_scaled() < &62 {
@@ -562,12 +742,12 @@ fn get_height_cost(neighbor: &TileForCultureExpand, culture_type: &CultureType)
             // no penalty in highlands
             0.0
         },
+        CultureType::Lake |
+        CultureType::Naval |
+        CultureType::Nomadic |
         CultureType::Generic |
         CultureType::River |
-        CultureType::Hunting => if neighbor.grouping().is_water() {
-            // general sea/lake corssing penalty
-            neighbor.area() * 6.0
-        } else if neighbor.elevation_scaled() >= &67 {
+        CultureType::Hunting => if neighbor.elevation_scaled() >= &67 {
             // mountain crossing penalty
             200.0 
         } else if neighbor.elevation_scaled() > &44 {
@@ -605,4 +785,239 @@ fn get_biome_cost(culture_biome: &String, neighbor_biome: &BiomeForCultureExpand
     
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use angular_units::Deg;
+    use ordered_float::OrderedFloat;
+
+    use std::io::BufReader;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::apply_culture_assignments;
+    use super::select_culture_sources;
+    use crate::algorithms::culture_sets::CultureSet;
+    use crate::algorithms::culture_sets::CultureSetItem;
+    use super::spread_cultures;
+    use super::tally_unclaimed_tiles;
+    use super::expansion_cost_limit;
+    use crate::commands::ExpansionFactorArg;
+    use crate::commands::MaxExpansionCostArg;
+    use crate::algorithms::culture_sets::CultureAssignments;
+    use crate::errors::CommandError;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::entities::EntityLookup;
+    use crate::typed_map::fields::IdRef;
+    use crate::world_map::biome_layer::BiomeForCultureExpand;
+    use crate::world_map::biome_layer::BiomeSchema;
+    use crate::world_map::culture_layer::CultureForPlacement;
+    use crate::world_map::fields::CultureType;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileForCultureExpand;
+    use crate::world_map::tile_layer::TileForCultureImport;
+    use crate::world_map::tile_layer::TileSchema;
+
+    #[test]
+    fn test_secondary_culture() {
+        // a row of four tiles, with a culture at each end.
+        let mut tile_map: EntityIndex<TileSchema,TileForCultureExpand> = (1..=4).map(|id| {
+            let mut neighbors = Vec::new();
+            if id > 1 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+            }
+            if id < 4 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+            }
+            (IdRef::new(id),TileForCultureExpand::new(BiomeSchema::GRASSLAND.to_owned(), Grouping::Continent, neighbors))
+        }).collect();
+
+        let biome_map = EntityLookup::<BiomeSchema,_>::from(HashMap::from([
+            (BiomeSchema::GRASSLAND.to_owned(),BiomeForCultureExpand::new(BiomeSchema::GRASSLAND.to_owned(), 50))
+        ]));
+
+        let cultures = vec![
+            CultureForPlacement::new("West".to_owned(), IdRef::new(1), CultureType::Generic, OrderedFloat::from(1.0)),
+            CultureForPlacement::new("East".to_owned(), IdRef::new(4), CultureType::Generic, OrderedFloat::from(1.0)),
+        ];
+
+        spread_cultures(&mut tile_map, cultures, &biome_map, 10.0, 6.0, OrderedFloat::from(1000.0), &mut ()).unwrap();
+
+        let culture_at = |id: u64| {
+            let tile = tile_map.try_get(&IdRef::new(id)).unwrap();
+            (tile.culture().clone(),tile.secondary_culture().clone(),*tile.culture_blend())
+        };
+
+        // the centers are never contested
+        assert_eq!(culture_at(1),(Some("West".to_owned()),None,0.0));
+        assert_eq!(culture_at(4),(Some("East".to_owned()),None,0.0));
+        // the tiles between them are claimed by the nearest culture, and the other one reaches them at twice the cost.
+        assert_eq!(culture_at(2),(Some("West".to_owned()),Some("East".to_owned()),1.0/3.0));
+        assert_eq!(culture_at(3),(Some("East".to_owned()),Some("West".to_owned()),1.0/3.0));
+    }
+
+    #[test]
+    fn test_naval_crosses_strait() {
+        // a row of five tiles, with a one-tile strait in the middle.
+        let spread_from_west = |culture_type: CultureType| {
+            let mut tile_map: EntityIndex<TileSchema,TileForCultureExpand> = (1..=5).map(|id| {
+                let mut neighbors = Vec::new();
+                if id > 1 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+                }
+                if id < 5 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+                }
+                let tile = if id == 3 {
+                    TileForCultureExpand::new(BiomeSchema::OCEAN.to_owned(), Grouping::Ocean, neighbors)
+                } else {
+                    TileForCultureExpand::new(BiomeSchema::GRASSLAND.to_owned(), Grouping::Continent, neighbors)
+                };
+                (IdRef::new(id),tile)
+            }).collect();
+
+            let biome_map = EntityLookup::<BiomeSchema,_>::from(HashMap::from([
+                (BiomeSchema::GRASSLAND.to_owned(),BiomeForCultureExpand::new(BiomeSchema::GRASSLAND.to_owned(), 50)),
+                (BiomeSchema::OCEAN.to_owned(),BiomeForCultureExpand::new(BiomeSchema::OCEAN.to_owned(), 10))
+            ]));
+
+            let cultures = vec![CultureForPlacement::new("West".to_owned(), IdRef::new(1), culture_type, OrderedFloat::from(1.0))];
+
+            spread_cultures(&mut tile_map, cultures, &biome_map, 10.0, 1000.0, OrderedFloat::from(1000.0), &mut ()).unwrap();
+
+            tile_map.iter().map(|(_,tile)| tile.culture().is_some()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(spread_from_west(CultureType::Naval),vec![true,true,true,true,true]);
+        assert_eq!(spread_from_west(CultureType::Generic),vec![true,true,false,false,false]);
+    }
+
+    #[test]
+    fn test_unclaimed_tiles() {
+        // a row of ten tiles, with a culture at one end.
+        let unclaimed_with_factor = |expansion_factor: f64| {
+            let mut tile_map: EntityIndex<TileSchema,TileForCultureExpand> = (1..=10).map(|id| {
+                let mut neighbors = Vec::new();
+                if id > 1 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+                }
+                if id < 10 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+                }
+                (IdRef::new(id),TileForCultureExpand::new(BiomeSchema::GRASSLAND.to_owned(), Grouping::Continent, neighbors))
+            }).collect();
+
+            let biome_map = EntityLookup::<BiomeSchema,_>::from(HashMap::from([
+                (BiomeSchema::GRASSLAND.to_owned(),BiomeForCultureExpand::new(BiomeSchema::GRASSLAND.to_owned(), 50))
+            ]));
+
+            let cultures = vec![CultureForPlacement::new("West".to_owned(), IdRef::new(1), CultureType::Generic, OrderedFloat::from(1.0))];
+
+            spread_cultures(&mut tile_map, cultures, &biome_map, 10.0, 6.0, OrderedFloat::from(60.0 * expansion_factor), &mut ()).unwrap();
+
+            tally_unclaimed_tiles(tile_map.iter().map(|(_,tile)| (*tile.habitability(),*tile.area(),tile.culture().is_some())))
+        };
+
+        // each tile costs 10 to reach
+        assert_eq!(unclaimed_with_factor(1.0),(3,3.0));
+        assert_eq!(unclaimed_with_factor(0.5),(6,6.0));
+        assert_eq!(unclaimed_with_factor(2.0),(0,0.0));
+    }
+
+    #[test]
+    fn test_max_expansion_cost() {
+        // a row of eight tiles, with a glacier just west of the middle, and a culture at each end.
+        let spread_with_limit = |expansion_factor: f64, max_expansion_cost: f64| {
+            let mut tile_map: EntityIndex<TileSchema,TileForCultureExpand> = (1..=8).map(|id| {
+                let mut neighbors = Vec::new();
+                if id > 1 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+                }
+                if id < 8 {
+                    neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+                }
+                let biome = if id == 4 { BiomeSchema::GLACIER } else { BiomeSchema::GRASSLAND };
+                (IdRef::new(id),TileForCultureExpand::new(biome.to_owned(), Grouping::Continent, neighbors))
+            }).collect();
+
+            let biome_map = EntityLookup::<BiomeSchema,_>::from(HashMap::from([
+                (BiomeSchema::GRASSLAND.to_owned(),BiomeForCultureExpand::new(BiomeSchema::GRASSLAND.to_owned(), 50)),
+                (BiomeSchema::GLACIER.to_owned(),BiomeForCultureExpand::new(BiomeSchema::GLACIER.to_owned(), 5000))
+            ]));
+
+            let cultures = vec![
+                CultureForPlacement::new("West".to_owned(), IdRef::new(1), CultureType::Generic, OrderedFloat::from(1.0)),
+                CultureForPlacement::new("East".to_owned(), IdRef::new(8), CultureType::Generic, OrderedFloat::from(1.0)),
+            ];
+
+            let limit_factor = ExpansionFactorArg {
+                expansion_factor
+            };
+            let limit = expansion_cost_limit(1.0, &limit_factor, &MaxExpansionCostArg { max_expansion_cost });
+
+            spread_cultures(&mut tile_map, cultures, &biome_map, 10.0, 6.0, limit, &mut ()).unwrap();
+
+            tile_map.iter().map(|(_,tile)| tile.culture().clone()).collect::<Vec<_>>()
+        };
+
+        let west = Some("West".to_owned());
+        let east = Some("East".to_owned());
+
+        // by default, the cap doesn't change anything, and the expansion factor lets the cultures cross the glacier, which costs 10000 to enter. The west is closer, so it gets the glacier.
+        assert_eq!(spread_with_limit(2.0, f64::INFINITY),vec![west.clone(),west.clone(),west.clone(),west.clone(),east.clone(),east.clone(),east.clone(),east.clone()]);
+        // with the cap, neither culture can claim the glacier, but each still claims everything on its own side of it.
+        assert_eq!(spread_with_limit(2.0, 100.0),vec![west.clone(),west.clone(),west,None,east.clone(),east.clone(),east.clone(),east]);
+    }
+
+    #[test]
+    fn test_import_cultures() {
+        let mut tile_map: EntityIndex<TileSchema,TileForCultureImport> = [(1,0.5,Grouping::Continent),(2,0.9,Grouping::Continent),(3,0.2,Grouping::Ocean),(4,0.7,Grouping::Island)].into_iter().map(|(id,habitability,grouping)| {
+            (IdRef::new(id),TileForCultureImport::new(habitability,grouping))
+        }).collect();
+
+        let assignments = CultureAssignments::from_csv(BufReader::new("fid,culture\n1,Elves\n2,Elves\n4,Dwarves\n".as_bytes())).unwrap();
+
+        let centers = apply_culture_assignments(&mut tile_map, &assignments).unwrap();
+
+        // the centers are the most habitable tile for each culture
+        assert_eq!(centers,vec![("Elves".to_owned(),IdRef::new(2)),("Dwarves".to_owned(),IdRef::new(4))]);
+
+        let cultures: Vec<Option<String>> = tile_map.iter().map(|(_,tile)| tile.culture().clone()).collect();
+        assert_eq!(cultures,vec![Some("Elves".to_owned()),Some("Elves".to_owned()),None,Some("Dwarves".to_owned())]);
+
+        // tiles which aren't in the map can't be assigned
+        let missing = CultureAssignments::from_csv(BufReader::new("5,Elves".as_bytes())).unwrap();
+        _ = apply_culture_assignments(&mut tile_map, &missing).unwrap_err();
+
+        // nor can water
+        let water = CultureAssignments::from_csv(BufReader::new("3,Elves".as_bytes())).unwrap();
+        assert!(matches!(apply_culture_assignments(&mut tile_map, &water),Err(CommandError::CultureAssignedToWater(..))));
+    }
+
+    #[test]
+    fn test_select_more_cultures_than_set() {
+        let culture_set = CultureSet::from_items(vec![
+            CultureSetItem::new("Elves".to_owned(),"elvish".to_owned(),1.0),
+            CultureSetItem::new("Dwarves".to_owned(),"dwarvish".to_owned(),0.0),
+        ]);
+
+        let mut random = StdRng::seed_from_u64(42);
+        let selected = select_culture_sources(&culture_set, &mut random, 6, &());
+        let names: Vec<&str> = selected.iter().map(CultureSetItem::name).collect();
+
+        // every culture is placed once, and the rest reuse cultures by probability, so they can't be the dwarves
+        assert_eq!(names.len(),6);
+        assert_eq!(names.iter().filter(|name| **name == "Dwarves").count(),1);
+        assert_eq!(names.iter().filter(|name| **name == "Elves").count(),5);
+
+        // asking for fewer than the set has doesn't reuse any
+        let fewer = select_culture_sources(&culture_set, &mut random, 1, &());
+        assert_eq!(fewer.len(),1);
+    }
+}
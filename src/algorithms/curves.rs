@@ -42,6 +42,9 @@ pub(crate) fn curvify_layer_by_theme<Progress: ProgressObserver, ThemeType: Them
 
     let layer = ThemeType::edit_theme_layer(target)?;
 
+    // progress is borrowed by the watcher, so these are collected and reported after the loop.
+    let mut fixed_polygons = Vec::new();
+
     for multipolygon in polygon_segments.map.iter().watch(progress, "Writing reshaped polygons.", "Reshaped polygons written.") {
         let mut polygons = Vec::new();
         let (fid,multipolygon) = multipolygon;
@@ -67,7 +70,7 @@ pub(crate) fn curvify_layer_by_theme<Progress: ProgressObserver, ThemeType: Them
             let polygon_geometry = if polygon_geometry.is_valid() {
                 polygon_geometry.into()
             } else {
-                eprintln!("Fixing invalid polygon.");
+                fixed_polygons.push(fid.clone());
                 polygon_geometry.make_valid_structure()?
             };
             let polygon_geometry = polygon_geometry.intersection(&extent_polygon)?;
@@ -80,6 +83,9 @@ pub(crate) fn curvify_layer_by_theme<Progress: ProgressObserver, ThemeType: Them
 
     }
 
+    if !fixed_polygons.is_empty() {
+        progress.warning(|| format!("{} invalid polygon(s) were fixed after curving (ids: {}).",fixed_polygons.len(),fixed_polygons.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")))
+    }
 
     Ok(())
 }
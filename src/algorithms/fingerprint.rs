@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::features::TypedFeature;
+use crate::typed_map::fields::IdRef;
+use crate::typed_map::schema::Schema;
+use crate::world_map::tile_layer::TileLayer;
+use crate::world_map::tile_layer::TileSchema;
+
+/// The tile fields which go into the fingerprint. These cover the output of every generation step, so any change in output should change the fingerprint.
+const FINGERPRINT_FIELDS: [&str; 17] = [
+    TileSchema::FIELD_SITE_X,
+    TileSchema::FIELD_SITE_Y,
+    TileSchema::FIELD_AREA,
+    TileSchema::FIELD_ELEVATION,
+    TileSchema::FIELD_GROUPING,
+    TileSchema::FIELD_TEMPERATURE,
+    TileSchema::FIELD_WIND,
+    TileSchema::FIELD_PRECIPITATION,
+    TileSchema::FIELD_WATER_FLOW,
+    TileSchema::FIELD_LAKE_ID,
+    TileSchema::FIELD_BIOME,
+    TileSchema::FIELD_HABITABILITY,
+    TileSchema::FIELD_POPULATION,
+    TileSchema::FIELD_CULTURE,
+    TileSchema::FIELD_TOWN_ID,
+    TileSchema::FIELD_NATION_ID,
+    TileSchema::FIELD_SUBNATION_ID,
+];
+
+/// A 64-bit FNV-1a hash. The hashers in the standard library aren't guaranteed to give the same results between versions of Rust, which would defeat the purpose of a fingerprint.
+pub(crate) struct Fingerprinter {
+    hash: u64
+}
+
+impl Fingerprinter {
+
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub(crate) const fn new() -> Self {
+        Self {
+            hash: Self::OFFSET_BASIS
+        }
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= u64::from(*byte);
+            self.hash = self.hash.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Writes a field value, marked so that a null value is different from an empty one, and so that values can't run together.
+    pub(crate) fn write_field(&mut self, value: Option<&str>) {
+        if let Some(value) = value {
+            self.write(&[1]);
+            self.write(&(value.len() as u64).to_le_bytes());
+            self.write(value.as_bytes());
+        } else {
+            self.write(&[0]);
+        }
+    }
+
+    pub(crate) const fn finish(&self) -> u64 {
+        self.hash
+    }
+
+}
+
+/// Calculates a fingerprint from the key fields of every tile, in fid order. Fields which haven't been generated yet are hashed as nulls.
+pub(crate) fn fingerprint_tiles<Progress: ProgressObserver>(tiles: &mut TileLayer, progress: &mut Progress) -> Result<String,CommandError> {
+
+    // the fields are read as the strings GDAL stores, so there's no need to worry about how each type would be hashed.
+    let mut values = BTreeMap::new();
+    for tile in tiles.read_features().watch(progress,format!("Reading {}.",TileSchema::LAYER_NAME),"Tiles read.") {
+        let fid: IdRef = tile.fid()?;
+        let feature = tile.into_feature();
+        let fields = FINGERPRINT_FIELDS.iter().map(|field| feature.field_as_string_by_name(field)).collect::<Result<Vec<_>,_>>()?;
+        _ = values.insert(fid,fields);
+    }
+
+    let mut fingerprinter = Fingerprinter::new();
+    for (fid,fields) in values.iter().watch(progress,"Calculating fingerprint.","Fingerprint calculated.") {
+        fingerprinter.write(&fid.to_inner().to_le_bytes());
+        for field in fields {
+            fingerprinter.write_field(field.as_deref());
+        }
+    }
+
+    Ok(format!("{:016x}",fingerprinter.finish()))
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fingerprinter;
+
+    #[test]
+    fn test_fingerprinter() {
+        // known FNV-1a values
+        assert_eq!(Fingerprinter::new().finish(),0xcbf2_9ce4_8422_2325);
+        let mut single = Fingerprinter::new();
+        single.write(b"a");
+        assert_eq!(single.finish(),0xaf63_dc4c_8601_ec8c);
+
+        let hash_fields = |fields: &[Option<&str>]| {
+            let mut fingerprinter = Fingerprinter::new();
+            for field in fields {
+                fingerprinter.write_field(*field);
+            }
+            fingerprinter.finish()
+        };
+
+        assert_eq!(hash_fields(&[Some("12.5"),None]),hash_fields(&[Some("12.5"),None]));
+        assert_ne!(hash_fields(&[None]),hash_fields(&[Some("")]));
+        assert_ne!(hash_fields(&[Some("ab"),Some("c")]),hash_fields(&[Some("a"),Some("bc")]));
+    }
+}
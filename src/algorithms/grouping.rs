@@ -9,6 +9,10 @@ use crate::errors::CommandError;
 use crate::world_map::fields::NeighborAndDirection;
 use crate::world_map::fields::Neighbor;
 use crate::typed_map::fields::IdRef;
+use crate::typed_map::entities::EntityIndex;
+use crate::world_map::tile_layer::TileForShelf;
+use crate::world_map::tile_layer::TileSchema;
+use crate::commands::ShelfDepthArg;
 
 pub(crate) fn calculate_grouping<Progress: ProgressObserver>(target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(),CommandError> {
 
@@ -136,4 +140,197 @@ pub(crate) fn calculate_grouping<Progress: ProgressObserver>(target: &mut WorldM
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Turns isolated bodies of ocean back into land, so that the water flow will fill them as lakes. This must be done before the coastline is created.
+pub(crate) fn release_isolated_basins<Progress: ProgressObserver>(target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let mut tiles = target.edit_tile_layer()?;
+
+    let tile_map = tiles.read_features().into_entities_index::<_,TileForGroupingCalc>(progress)?;
+
+    let basins = find_isolated_basins(&tile_map)?;
+
+    if !basins.is_empty() {
+        progress.message(|| format!("Turning {} isolated ocean tiles into land.",basins.len()));
+    }
+
+    for fid in basins.iter().watch(progress,"Releasing isolated basins.","Isolated basins released.") {
+        let mut feature = tiles.try_feature_by_id(fid)?;
+        // the grouping for land will be calculated later.
+        feature.set_grouping(&Grouping::Continent)?;
+        feature.set_depth(&None)?;
+        tiles.update_feature(feature)?;
+    }
+
+    Ok(())
+
+}
+
+/// Finds the tiles in bodies of ocean which don't touch the edge of the map. The largest body is always treated as the real ocean, even if it doesn't reach the edge.
+fn find_isolated_basins(tile_map: &EntityIndex<TileSchema,TileForGroupingCalc>) -> Result<HashSet<IdRef>,CommandError> {
+
+    let mut visited = HashSet::new();
+    let mut bodies = Vec::new();
+
+    for (fid,tile) in tile_map.iter() {
+        if !tile.grouping().is_ocean() || visited.contains(fid) {
+            continue;
+        }
+
+        _ = visited.insert(fid.clone());
+        let mut body = vec![fid.clone()];
+        let mut touches_edge = false;
+        let mut queue = vec![fid.clone()];
+
+        while let Some(current) = queue.pop() {
+            let current = tile_map.try_get(&current)?;
+            if current.edge().is_some() {
+                touches_edge = true;
+            }
+            for NeighborAndDirection(neighbor,_) in current.neighbors() {
+                match neighbor {
+                    Neighbor::Tile(neighbor_id) | Neighbor::CrossMap(neighbor_id,_) => {
+                        if tile_map.try_get(neighbor_id)?.grouping().is_ocean() && visited.insert(neighbor_id.clone()) {
+                            body.push(neighbor_id.clone());
+                            queue.push(neighbor_id.clone());
+                        }
+                    },
+                    // the water would flow off of the map here.
+                    Neighbor::OffMap(_) => touches_edge = true
+                }
+            }
+        }
+
+        bodies.push((body,touches_edge));
+    }
+
+    // ties go to the first body found
+    let largest = bodies.iter().enumerate().fold(None, |largest: Option<(usize,usize)>,(index,(body,_))| match largest {
+        Some((_,size)) if size >= body.len() => largest,
+        _ => Some((index,body.len()))
+    }).map(|(index,_)| index);
+
+    Ok(bodies.into_iter().enumerate().filter(|(index,(_,touches_edge))| !touches_edge && (Some(*index) != largest)).flat_map(|(_,(body,_))| body).collect())
+
+}
+
+pub(crate) fn calculate_shelf<Progress: ProgressObserver>(target: &mut WorldMapTransaction, shelf_depth: &ShelfDepthArg, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let mut tiles = target.edit_tile_layer()?;
+
+    let tile_map = tiles.read_features().into_entities_index::<_,TileForShelf>(progress)?;
+
+    let shelf = find_shelf_tiles(&tile_map, shelf_depth.shelf_depth)?;
+
+    for (fid,_) in tile_map.iter().watch(progress,"Writing continental shelf.","Continental shelf written.") {
+        let mut feature = tiles.try_feature_by_id(fid)?;
+        feature.set_shelf(&shelf.contains(fid))?;
+        tiles.update_feature(feature)?;
+    }
+
+    Ok(())
+}
+
+/// Finds shallow ocean tiles next to land, and then spreads out from those through other shallow ocean tiles.
+fn find_shelf_tiles(tile_map: &EntityIndex<TileSchema,TileForShelf>, shelf_depth: f64) -> Result<HashSet<IdRef>,CommandError> {
+    let mut shelf = HashSet::new();
+    let mut queue = Vec::new();
+
+    for (fid,tile) in tile_map.iter() {
+        if tile.is_shallow_ocean(shelf_depth) {
+            for NeighborAndDirection(neighbor,_) in tile.neighbors() {
+                if let Neighbor::Tile(neighbor) | Neighbor::CrossMap(neighbor,_) = neighbor {
+                    if !tile_map.try_get(neighbor)?.grouping().is_ocean() {
+                        queue.push(fid.clone());
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    while let Some(fid) = queue.pop() {
+        if !shelf.insert(fid.clone()) {
+            continue;
+        }
+        for NeighborAndDirection(neighbor,_) in tile_map.try_get(&fid)?.neighbors() {
+            if let Neighbor::Tile(neighbor) | Neighbor::CrossMap(neighbor,_) = neighbor {
+                if !shelf.contains(neighbor) && tile_map.try_get(neighbor)?.is_shallow_ocean(shelf_depth) {
+                    queue.push(neighbor.clone());
+                }
+            }
+        }
+    }
+
+    Ok(shelf)
+}
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+
+    use super::find_shelf_tiles;
+    use super::find_isolated_basins;
+    use crate::world_map::tile_layer::TileForGroupingCalc;
+    use crate::utils::edge::Edge;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileForShelf;
+    use crate::world_map::tile_layer::TileSchema;
+
+    #[test]
+    fn test_shelf() {
+        // a row of tiles from land out to the deep ocean, followed by a shallow seamount that isn't connected to land.
+        let depths = [None,Some(-50.0),Some(-150.0),Some(-3000.0),Some(-100.0),Some(-3000.0)];
+        let tile_map: EntityIndex<TileSchema,TileForShelf> = depths.iter().enumerate().map(|(i,depth)| {
+            let id = i as u64 + 1;
+            let mut neighbors = Vec::new();
+            if id > 1 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+            }
+            if i < (depths.len() - 1) {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+            }
+            let (grouping,elevation) = match depth {
+                Some(depth) => (Grouping::Ocean,*depth),
+                None => (Grouping::Continent,100.0)
+            };
+            (IdRef::new(id),TileForShelf::new(grouping, elevation, *depth, neighbors))
+        }).collect();
+
+        let mut shelf: Vec<_> = find_shelf_tiles(&tile_map, 200.0).unwrap().into_iter().collect();
+        shelf.sort();
+        assert_eq!(shelf,vec![IdRef::new(2),IdRef::new(3)]);
+
+        let narrow_shelf: Vec<_> = find_shelf_tiles(&tile_map, 100.0).unwrap().into_iter().collect();
+        assert_eq!(narrow_shelf,vec![IdRef::new(2)]);
+    }
+
+    #[test]
+    fn test_isolated_basins() {
+        // a row of tiles: an ocean running off the west edge of the map, a ridge, a basin below sea level which was flooded as ocean, and more land.
+        let tiles = [Some(Edge::West),None,None,None,None,None];
+        let ocean = [true,true,false,true,true,false];
+        let tile_map: EntityIndex<TileSchema,TileForGroupingCalc> = tiles.iter().enumerate().map(|(i,edge)| {
+            let id = i as u64 + 1;
+            let mut neighbors = Vec::new();
+            if id > 1 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+            }
+            if i < (tiles.len() - 1) {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+            }
+            let grouping = if ocean[i] { Grouping::Ocean } else { Grouping::Continent };
+            (IdRef::new(id),TileForGroupingCalc::new(grouping, edge.clone(), neighbors))
+        }).collect();
+
+        let mut basins: Vec<_> = find_isolated_basins(&tile_map).unwrap().into_iter().collect();
+        basins.sort();
+        // the basin is as large as the ocean, but only the ocean reaches the edge.
+        assert_eq!(basins,vec![IdRef::new(4),IdRef::new(5)]);
+    }
+}
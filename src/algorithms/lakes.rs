@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::algorithms::naming::NamerSet;
+use crate::commands::MinNamedLakeSizeArg;
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::entities::EntityLookup;
+use crate::typed_map::entities::NamedEntity;
+use crate::typed_map::fields::IdRef;
+use crate::world_map::WorldMapTransaction;
+use crate::world_map::culture_layer::CultureSchema;
+use crate::world_map::culture_layer::CultureWithNamer;
+use crate::world_map::fields::Neighbor;
+use crate::world_map::fields::NeighborAndDirection;
+use crate::world_map::tile_layer::TileForLakeNames;
+use crate::world_map::water_layers::LakeForNames;
+
+/// Names each lake which covers at least the minimum number of tiles, using the namer for the culture of the largest tile in the lake or along its shore. Lakes without a culture nearby use the default namer, and smaller lakes have their names removed.
+pub(crate) fn name_lakes<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer>(target: &mut WorldMapTransaction, rng: &mut Random, culture_lookup: &EntityLookup<CultureSchema,Culture>, namers: &mut NamerSet, min_size: &MinNamedLakeSizeArg, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let tile_map = target.edit_tile_layer()?.read_features().into_entities_index::<_,TileForLakeNames>(progress)?;
+
+    // the area and culture of the largest tile with a culture in or along each lake
+    let mut lake_cultures: HashMap<IdRef,(f64,String)> = HashMap::new();
+
+    for (_,tile) in tile_map.iter().watch(progress,"Finding cultures around lakes.","Cultures around lakes found.") {
+        let Some(lake_id) = tile.lake_id() else {
+            continue;
+        };
+
+        let mut candidates = vec![tile];
+        for NeighborAndDirection(neighbor,_) in tile.neighbors() {
+            match neighbor {
+                Neighbor::Tile(neighbor_id) | Neighbor::CrossMap(neighbor_id,_) => {
+                    let neighbor = tile_map.try_get(neighbor_id)?;
+                    if neighbor.lake_id().is_none() && !neighbor.grouping().is_water() {
+                        candidates.push(neighbor);
+                    }
+                },
+                Neighbor::OffMap(_) => ()
+            }
+        }
+
+        for candidate in candidates {
+            if let Some(culture) = candidate.culture() {
+                if lake_cultures.get(lake_id).is_none_or(|(area,_)| candidate.area() > area) {
+                    _ = lake_cultures.insert(lake_id.clone(), (*candidate.area(),culture.clone()));
+                }
+            }
+        }
+    }
+
+    let mut lakes = target.edit_lakes_layer()?;
+
+    let mut names = BTreeMap::new();
+
+    for lake in lakes.read_features().into_entities::<LakeForNames>().watch(progress,"Naming lakes.","Lakes named.") {
+        let (fid,lake) = lake?;
+        let name = if lake.size() >= &min_size.min_named_lake_size {
+            let culture = lake_cultures.get(&fid).map(|(_,culture)| culture_lookup.try_get(culture)).transpose()?;
+            let namer = Culture::get_namer(culture, namers)?;
+            Some(namer.make_lake_name(rng, lake.type_()))
+        } else {
+            None
+        };
+        _ = names.insert(fid, name);
+    }
+
+    namers.report_fallbacks(progress);
+
+    lakes.update_features_by_id(names, progress, |feature,name| feature.set_name(&name))
+
+}
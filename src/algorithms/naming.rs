@@ -10,6 +10,7 @@ use std::fs::File;
 use rand::Rng;
 use rand_distr::Normal;
 use rand_distr::Distribution;
+use rand_distr::WeightedIndex;
 use serde::Serialize;
 use serde::Deserialize;
 use serde_json::Serializer as JSONSerializer;
@@ -28,6 +29,8 @@ use crate::utils::random::RandomIndex;
 use crate::errors::CommandError;
 use crate::progress::ProgressObserver;
 use crate::commands::NamerArg;
+use crate::algorithms::culture_sets::CultureSet;
+use crate::world_map::fields::LakeType;
 
 struct NamerLoadObserver<'data,Progress: ProgressObserver> {
     name: &'data str,
@@ -182,10 +185,96 @@ impl StateSuffixBehavior {
     }
 }
 
+#[derive(Clone,Default,PartialEq,Serialize,Deserialize,JsonSchema)]
+enum LakeNameBehavior {
+    #[default]
+    Default, // use the default for the type of lake
+    NoAffix, // use the name alone
+    Prefix(String), // put the specified word before the name, as in "Lake X"
+    Suffix(String), // put the specified word after the name, as in "X Salt Flat"
+}
+
+impl LakeNameBehavior {
+
+    fn default_for(lake_type: &LakeType) -> Self {
+        match lake_type {
+            LakeType::Fresh | LakeType::Frozen => Self::Prefix("Lake".to_owned()),
+            LakeType::Salt => Self::Suffix("Salt Lake".to_owned()),
+            LakeType::Pluvial => Self::Suffix("Salt Flat".to_owned()),
+            LakeType::Dry => Self::Suffix("Dry Lake".to_owned()),
+            LakeType::Marsh => Self::Suffix("Marsh".to_owned()),
+        }
+    }
+
+    fn apply(&self, lake_type: &LakeType, name: String) -> String {
+        match self {
+            Self::Default => Self::default_for(lake_type).apply(lake_type, name),
+            Self::NoAffix => name,
+            Self::Prefix(prefix) => format!("{prefix} {name}"),
+            Self::Suffix(suffix) => format!("{name} {suffix}"),
+        }
+    }
+}
+
+/// How the names of each type of lake are formed. Types which aren't specified use the default, which is "Lake X" for fresh and frozen lakes, "X Salt Lake", "X Salt Flat" for pluvial lakes, "X Dry Lake" and "X Marsh".
+#[derive(Clone,Default,PartialEq,Serialize,Deserialize,JsonSchema)]
+struct LakeNameSource {
+    #[serde(default)]
+    fresh: LakeNameBehavior,
+    #[serde(default)]
+    salt: LakeNameBehavior,
+    #[serde(default)]
+    frozen: LakeNameBehavior,
+    #[serde(default)]
+    pluvial: LakeNameBehavior,
+    #[serde(default)]
+    dry: LakeNameBehavior,
+    #[serde(default)]
+    marsh: LakeNameBehavior,
+}
+
+impl LakeNameSource {
+
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    const fn for_type(&self, lake_type: &LakeType) -> &LakeNameBehavior {
+        match lake_type {
+            LakeType::Fresh => &self.fresh,
+            LakeType::Salt => &self.salt,
+            LakeType::Frozen => &self.frozen,
+            LakeType::Pluvial => &self.pluvial,
+            LakeType::Dry => &self.dry,
+            LakeType::Marsh => &self.marsh,
+        }
+    }
+}
+
 #[derive(Serialize,Deserialize,JsonSchema)]
 struct MarkovSource {
     duplicatable_letters: Vec<char>,
     seed_words: Vec<String>,
+    /// The number of letters before a syllable used to choose it. Higher orders follow the seed words more closely, but need more of them.
+    #[serde(default = "MarkovSource::default_order")]
+    order: usize,
+}
+
+impl MarkovSource {
+
+    const fn default_order() -> usize {
+        1
+    }
+}
+
+#[derive(Serialize,Deserialize,JsonSchema)]
+struct GenderedSource {
+    #[serde(default)]
+    masculine: Vec<String>,
+    #[serde(default)]
+    feminine: Vec<String>,
+    #[serde(default)]
+    neutral: Vec<String>,
 }
 
 // FUTURE: all this to get rid of a few warnings that I can't get rid of in the derive macro output
@@ -197,7 +286,8 @@ mod namer_method_source {
     #[serde(tag="method")]
     pub(super) enum NamerMethodSource {
         Markov(MarkovSource),
-        ListPicker(Vec<String>)
+        ListPicker(Vec<String>),
+        Gendered(GenderedSource)
     }
     
 }
@@ -214,19 +304,27 @@ pub(crate) struct NamerSource {
     method: NamerMethodSource,
     state_name: Vec<StateNameBehavior>,
     state_suffix: StateSuffixBehavior,
+    #[serde(default,skip_serializing_if="LakeNameSource::is_default")]
+    lake_name: LakeNameSource,
 }
 
 struct MarkovGenerator {
-    chain: HashMap<Option<char>, Vec<String>>,
+    /// Syllables keyed by the letters before them. The syllables which start a word have an empty key.
+    chain: HashMap<String, Vec<String>>,
+    order: usize,
     length_distribution: Normal<f64>,
     minimum_length: usize,
     duplicatable_letters: Vec<char>,
     seed_words: Vec<String>,
+    /// The number of words made since the fallbacks were last reported
+    words_made: usize,
+    /// The number of those words which were replaced with a seed word because a valid one couldn't be built
+    seed_word_fallbacks: usize,
 
 }
 
 struct Chain {
-    string_map: HashMap<Option<char>, Vec<String>>,
+    string_map: HashMap<String, Vec<String>>,
     length_distribution: Normal<f64>,
     minimum_length: usize,
 }
@@ -234,7 +332,7 @@ struct Chain {
 impl MarkovGenerator {
 
     // calculate Markov chain for a namesbase
-    fn calculate_chain<Progress: ProgressObserver>(name: &str, array: &[String], progress: &mut NamerLoadObserver<Progress>) -> Result<Chain,CommandError> {
+    fn calculate_chain<Progress: ProgressObserver>(name: &str, array: &[String], order: usize, progress: &mut NamerLoadObserver<Progress>) -> Result<Chain,CommandError> {
         if array.is_empty() {
             Err(CommandError::EmptyNamerInput(name.to_owned()))
         } else {
@@ -260,11 +358,7 @@ impl MarkovGenerator {
                 let mut syllable = String::new();
                 let mut i = 0; 
                 while i < word.len() {
-                    let prev_char = if i == 0 { 
-                        None 
-                    } else { 
-                        word.get(i-1).copied() 
-                    }; // pre-onset letter
+                    let prev_chars: String = word[i.saturating_sub(order)..i].iter().collect(); // pre-onset letters
                     let mut vowel_found = false; 
     
                     for c in i..word.len() {
@@ -320,9 +414,9 @@ impl MarkovGenerator {
                     }
     
                     i += syllable.len().min(1); 
-                    match chain.get_mut(&prev_char) {
+                    match chain.get_mut(&prev_chars) {
                         None => {
-                            _ = chain.insert(prev_char,vec![syllable]);
+                            _ = chain.insert(prev_chars,vec![syllable]);
                         },
                         Some(entry) => entry.push(syllable),
                     }
@@ -354,24 +448,42 @@ impl MarkovGenerator {
 
 
     fn new<Progress: ProgressObserver>(name: &str, base: MarkovSource, progress: &mut NamerLoadObserver<Progress>) -> Result<Self,CommandError> {
-        let Chain{string_map: chain,length_distribution,minimum_length} = Self::calculate_chain(name,&base.seed_words,progress)?;
+        let Chain{string_map: chain,length_distribution,minimum_length} = Self::calculate_chain(name,&base.seed_words,base.order,progress)?;
         
 
         Ok(Self {
             chain,
+            order: base.order,
             length_distribution,
             minimum_length,
             duplicatable_letters: base.duplicatable_letters,
-            seed_words: base.seed_words
+            seed_words: base.seed_words,
+            words_made: 0,
+            seed_word_fallbacks: 0
         })
     }
 
-    pub(crate) fn make_word<Random: Rng>(&self, rng: &mut Random) -> String {
+    fn start_choices(&self) -> &Vec<String> {
+        self.chain.get("").expect("How would we get an empty chain?") // As long as the input wasn't empty, this shouldn't panic
+    }
+
+    /// Finds the syllables which can follow the word once the current syllable is added to it. If no syllables follow all of the last letters, fewer letters are tried, and the word starts over if none of them are found.
+    fn next_choices(&self, word: &str, syllable: &str) -> &Vec<String> {
+        let preceding: Vec<char> = word.chars().chain(syllable.chars()).collect();
+        (1..=self.order.min(preceding.len())).rev().find_map(|length| {
+            let key: String = preceding[preceding.len() - length..].iter().collect();
+            self.chain.get(&key)
+        }).unwrap_or_else(|| self.start_choices())
+    }
+
+    pub(crate) fn make_word<Random: Rng>(&mut self, rng: &mut Random) -> String {
+
+        self.words_made += 1;
 
         let min_len = self.minimum_length;
         let cutoff_len = self.length_distribution.sample(rng).ceil() as usize;
 
-        let mut choices = self.chain.get(&None).expect("How would we get an empty chain?"); // As long as the input wasn't empty, this shouldn't panic
+        let mut choices = self.start_choices();
         let mut cur = choices.choose(rng).clone();
         let mut word = String::new();
         for _ in 0..20 {
@@ -381,7 +493,7 @@ impl MarkovGenerator {
                 if word.len() < min_len {
                     cur = String::new();
                     word = String::new();
-                    choices = self.chain.get(&None).expect("How would we get an empty chain?"); // As long as the input wasn't empty, this shouldn't panic.
+                    choices = self.start_choices();
                 } else {
                     break
                 }
@@ -397,7 +509,7 @@ impl MarkovGenerator {
                 }
                 break;
             } else {
-                choices = self.chain.get(&cur.chars().last()).unwrap_or_else(|| self.chain.get(&None).expect("How would we get an empty chain?")) // As long as the original input wasn't empty, this shouldn't panic
+                choices = self.next_choices(&word, &cur)
             }
 
             word.push_str(&cur);
@@ -448,6 +560,7 @@ impl MarkovGenerator {
         }
 
         if name.len() < 2 {
+            self.seed_word_fallbacks += 1;
             name.clone_from(self.seed_words.choose(rng));
         }
 
@@ -458,6 +571,7 @@ impl MarkovGenerator {
 }
 
 struct ListPicker {
+    list: Vec<String>,
     available: Vec<String>,
     picked: Vec<String>
 }
@@ -469,12 +583,18 @@ impl ListPicker {
             Err(CommandError::EmptyNamerInput(name.to_owned()))
         } else {
             Ok(Self {
-                available: list,
+                available: list.clone(),
+                list,
                 picked: Vec::new()
             })    
         }
     }
 
+    fn reset(&mut self) {
+        self.available.clone_from(&self.list);
+        self.picked.clear();
+    }
+
     fn pick_word<Random: Rng>(&mut self, rng: &mut Random) -> String {
         if self.available.is_empty() {
             self.available = mem::replace(&mut self.picked, Vec::new())
@@ -486,9 +606,57 @@ impl ListPicker {
     }
 }
 
+/// The kind of given name to make, for namers which keep separate lists of them.
+#[derive(Clone,Copy)]
+pub(crate) enum NameGender {
+    Masculine,
+    Feminine,
+    Neutral
+}
+
+struct GenderedPicker {
+    combined: ListPicker,
+    masculine: Option<ListPicker>,
+    feminine: Option<ListPicker>,
+    neutral: Option<ListPicker>
+}
+
+impl GenderedPicker {
+
+    fn new(name: &str, source: GenderedSource) -> Result<Self,CommandError> {
+        let combined = source.masculine.iter().chain(&source.feminine).chain(&source.neutral).cloned().collect();
+        // the only error is an empty list, and names for that category come from the combined list instead.
+        let category = |list: Vec<String>| ListPicker::new(name, list).ok();
+        Ok(Self {
+            combined: ListPicker::new(name, combined)?,
+            masculine: category(source.masculine),
+            feminine: category(source.feminine),
+            neutral: category(source.neutral)
+        })
+    }
+
+    fn pick_word<Random: Rng>(&mut self, rng: &mut Random, gender: Option<NameGender>) -> String {
+        let category = match gender {
+            Some(NameGender::Masculine) => self.masculine.as_mut(),
+            Some(NameGender::Feminine) => self.feminine.as_mut(),
+            Some(NameGender::Neutral) => self.neutral.as_mut(),
+            None => None
+        };
+        category.unwrap_or(&mut self.combined).pick_word(rng)
+    }
+
+    fn reset(&mut self) {
+        self.combined.reset();
+        for category in [&mut self.masculine, &mut self.feminine, &mut self.neutral].into_iter().flatten() {
+            category.reset()
+        }
+    }
+}
+
 enum NamerMethod {
     Markov(MarkovGenerator),
-    ListPicker(ListPicker)
+    ListPicker(ListPicker),
+    Gendered(GenderedPicker)
 }
 
 impl NamerMethod {
@@ -496,14 +664,40 @@ impl NamerMethod {
     pub(crate) fn make_word<Random: Rng>(&mut self, rng: &mut Random) -> String {
         match self {
             Self::Markov(markov) => markov.make_word(rng),
-            Self::ListPicker(picker) => picker.pick_word(rng)
+            Self::ListPicker(picker) => picker.pick_word(rng),
+            Self::Gendered(picker) => picker.pick_word(rng, None)
+        }
+    }
+
+    fn make_gendered_word<Random: Rng>(&mut self, rng: &mut Random, gender: NameGender) -> String {
+        match self {
+            Self::Gendered(picker) => picker.pick_word(rng, Some(gender)),
+            Self::Markov(_) | Self::ListPicker(_) => self.make_word(rng)
+        }
+    }
+
+    /// Puts all of the words back into the lists, so the names picked don't depend on what was picked before.
+    fn reset(&mut self) {
+        match self {
+            Self::Markov(_) => (),
+            Self::ListPicker(picker) => picker.reset(),
+            Self::Gendered(picker) => picker.reset()
+        }
+    }
+
+    /// Returns the number of seed word fallbacks and words made since the last call, then starts counting again. List pickers always use their words, so have nothing to count.
+    fn take_fallback_counts(&mut self) -> Option<(usize,usize)> {
+        match self {
+            Self::Markov(markov) => Some((mem::take(&mut markov.seed_word_fallbacks),mem::take(&mut markov.words_made))),
+            Self::ListPicker(_) | Self::Gendered(_) => None
         }
     }
 
     fn new<Progress: ProgressObserver>(name: &str, method: NamerMethodSource, progress: &mut NamerLoadObserver<Progress>) -> Result<Self,CommandError> {
         Ok(match method {
             NamerMethodSource::Markov(markov) => Self::Markov(MarkovGenerator::new(name,markov,progress)?),
-            NamerMethodSource::ListPicker(list) => Self::ListPicker(ListPicker::new(name,list)?)
+            NamerMethodSource::ListPicker(list) => Self::ListPicker(ListPicker::new(name,list)?),
+            NamerMethodSource::Gendered(lists) => Self::Gendered(GenderedPicker::new(name,lists)?)
         })
     }
 
@@ -512,7 +706,8 @@ impl NamerMethod {
 pub(crate) struct Namer {
     method: NamerMethod,
     state_name: Vec<StateNameBehavior>,
-    state_suffix: StateSuffixBehavior
+    state_suffix: StateSuffixBehavior,
+    lake_name: LakeNameSource
 }
 
 impl Namer {
@@ -536,7 +731,8 @@ impl Namer {
         Ok(Self {
             method,
             state_name,
-            state_suffix: base.state_suffix
+            state_suffix: base.state_suffix,
+            lake_name: base.lake_name
         })
     }
 
@@ -545,10 +741,29 @@ impl Namer {
         self.method.make_word(rng)
     }
 
+    fn take_fallback_counts(&mut self) -> Option<(usize,usize)> {
+        self.method.take_fallback_counts()
+    }
+
     pub(crate) fn make_name<Random: Rng>(&mut self, rng: &mut Random) -> String {
         self.make_word(rng)//.to_title_case()
     }
 
+    /// Makes a given name of the specified gender. Namers without separate lists for each gender make the same names as `make_name`.
+    pub(crate) fn make_gendered_name<Random: Rng>(&mut self, rng: &mut Random, gender: NameGender) -> String {
+        self.method.make_gendered_word(rng, gender)
+    }
+
+    /// Makes a name for a lake, with the word for its type of lake added as specified for the namer.
+    pub(crate) fn make_lake_name<Random: Rng>(&mut self, rng: &mut Random, lake_type: &LakeType) -> String {
+        let name = self.make_name(rng);
+        self.lake_name.for_type(lake_type).apply(lake_type, name)
+    }
+
+    pub(crate) const fn is_gendered(&self) -> bool {
+        matches!(self.method,NamerMethod::Gendered(_))
+    }
+
     pub(crate) fn make_state_name<Random: Rng>(&mut self, rng: &mut Random) -> String {
         let mut name = self.make_word(rng);
 
@@ -650,8 +865,47 @@ impl Namer {
 
 }
 
+/// Namers for towns in tiles without a culture, weighted by the probabilities of the cultures which use them.
+pub(crate) struct FallbackNamers {
+    namers: Vec<String>,
+    weights: WeightedIndex<f64>
+}
+
+impl FallbackNamers {
+
+    /// Probabilities for the same namer are added together. Returns None if no namer has a positive weight.
+    pub(crate) fn from_weights<'name, Weights: IntoIterator<Item = (&'name str, f64)>>(weights: Weights) -> Option<Self> {
+        let mut namers: Vec<String> = Vec::new();
+        let mut totals: Vec<f64> = Vec::new();
+        for (namer,probability) in weights {
+            if probability <= 0.0 {
+                continue;
+            }
+            if let Some(index) = namers.iter().position(|name| name == namer) {
+                totals[index] += probability;
+            } else {
+                namers.push(namer.to_owned());
+                totals.push(probability);
+            }
+        }
+
+        let index = WeightedIndex::new(totals).ok()?;
+        Some(Self {
+            namers,
+            weights: index
+        })
+    }
+
+    pub(crate) fn choose<Random: Rng>(&self, rng: &mut Random) -> &str {
+        &self.namers[self.weights.sample(rng)]
+    }
+}
+
 pub(crate) struct NamerSet {
     default_namer: String,
+    weight_default_namer: bool,
+    report_name_fallbacks: bool,
+    fallback_namers: Option<FallbackNamers>,
     map: HashMap<String,Namer>
 }
 
@@ -663,10 +917,77 @@ impl NamerSet {
         self.map.get_mut(name).ok_or_else(|| CommandError::UnknownNamer(name.to_owned()))
     }
 
+    pub(crate) fn default_namer(&self) -> &str {
+        &self.default_namer
+    }
+
+    pub(crate) const fn fallback_namers(&self) -> Option<&FallbackNamers> {
+        self.fallback_namers.as_ref()
+    }
+
+    pub(crate) const fn wants_fallback_namers(&self) -> bool {
+        self.weight_default_namer && self.fallback_namers.is_none()
+    }
+
+    /// If a weighted default namer was requested, this sets up the namers to choose from based on the probabilities in the culture set.
+    pub(crate) fn weight_fallback_by<Progress: ProgressObserver>(&mut self, cultures: &CultureSet, progress: &Progress) {
+        if self.weight_default_namer {
+            self.fallback_namers = FallbackNamers::from_weights(cultures.into_iter().map(|culture| (culture.namer_name(),culture.probability())));
+            if self.fallback_namers.is_none() {
+                progress.warning(|| format!("No cultures have a positive probability, using default namer '{}' for tiles without a culture.",self.default_namer));
+            }
+        }
+    }
+
+    /// If requested, reports how often each markov namer had to fall back to a seed word since the last report, which shows which seed data needs improving.
+    pub(crate) fn report_fallbacks<Progress: ProgressObserver>(&mut self, progress: &Progress) {
+        if self.report_name_fallbacks {
+            let mut names = self.list_names();
+            names.sort(); // so the report is in the same order every time
+            for name in names {
+                if let Some((fallbacks,words)) = self.map.get_mut(&name).and_then(Namer::take_fallback_counts) {
+                    if fallbacks > 0 {
+                        progress.message(|| format!("{:.0}% of names fell back to seed words for namer '{name}' ({fallbacks} of {words}).",(fallbacks as f64 * 100.0) / words as f64));
+                    }
+                }
+            }
+        }
+    }
+
     pub(crate) fn list_names(&self) -> Vec<String> {
         self.map.keys().cloned().collect()
     }
 
+    /// Replaces the namers for a list of cultures so that, as far as the loaded namers allow, no two cultures share one. The first culture to use a namer keeps it, and the rest are given the unused namers in alphabetical order, starting over once every namer is in use.
+    pub(crate) fn diversify(&self, culture_namers: &[String]) -> Vec<String> {
+        let mut names = self.list_names();
+        names.sort(); // so the same cultures get the same namers every time
+
+        let mut used = HashSet::new();
+        let mut result: Vec<Option<String>> = culture_namers.iter().map(|namer| used.insert(namer.clone()).then(|| namer.clone())).collect();
+
+        let mut available = names.iter().cycle();
+        for namer in result.iter_mut().filter(|namer| namer.is_none()) {
+            if used.len() >= names.len() {
+                // every namer is already taken, so they have to be shared again.
+                used.clear();
+            }
+            let next = available.find(|name| !used.contains(*name)).expect("There should be an unused namer, since they were cleared if they were all in use.");
+            _ = used.insert(next.clone());
+            *namer = Some(next.clone());
+        }
+
+        result.into_iter().flatten().collect()
+    }
+
+    /// Returns the namers to the state they were loaded in. `big-bang` calls this before each stage which names things, so it names them the same as running the stage's command separately with a freshly loaded set.
+    pub(crate) fn reset(&mut self) {
+        #[allow(clippy::iter_over_hash_type)] // the order doesn't matter, every namer is reset
+        for namer in self.map.values_mut() {
+            namer.method.reset()
+        }
+    }
+
     pub(crate) fn check_exists(&self, namer: &str) -> Result<(),CommandError> {
         if self.map.contains_key(namer) {
             Ok(())
@@ -686,13 +1007,19 @@ impl NamerSet {
             _ = map.insert(name, namer);
         }
         
+        let weight_default_namer = args.weight_default_namer && args.default_namer.is_none();
+
         let default_namer = if let Some(default_namer) = args.default_namer {
+            if args.weight_default_namer {
+                progress.warning(|| "A default namer was specified, so the default namer will not be weighted by culture.")
+            }
             if !map.contains_key(&default_namer) {
                 return Err(CommandError::UnknownNamer(default_namer))
             }
             default_namer    
         } else {
-            let keys: Vec<&String> = map.keys().collect();
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort(); // the order of a hashmap is different every time, which would choose a different namer for the same seed
             let result = keys.choose(rng).to_owned().clone();
             progress.message(|| format!("Using default namer '{result}'"));
             result
@@ -701,6 +1028,9 @@ impl NamerSet {
         
         Ok(Self {
             default_namer,
+            weight_default_namer,
+            report_name_fallbacks: args.report_name_fallbacks,
+            fallback_namers: None,
             map
         })
     }
@@ -781,9 +1111,11 @@ impl NamerSetSource {
                 method: NamerMethodSource::Markov(MarkovSource {
                     duplicatable_letters: duplicate_chars.into_iter().collect(),
                     seed_words: list,
+                    order: MarkovSource::default_order(),
                 }),
                 state_name: Vec::new(),
                 state_suffix: StateSuffixBehavior::NoSuffix,
+                lake_name: LakeNameSource::default(),
             });
     
         } else {
@@ -792,6 +1124,7 @@ impl NamerSetSource {
                 method: NamerMethodSource::ListPicker(list),
                 state_name: Vec::new(),
                 state_suffix: StateSuffixBehavior::Default,
+                lake_name: LakeNameSource::default(),
             });
     
         }
@@ -801,6 +1134,44 @@ impl NamerSetSource {
         
     }
 
+    /**
+    Loads a namer which picks given names from separate lists for each gender, from the text files in a directory. The namer is named after the directory.
+
+    The lists are read from `male.txt` or `masculine.txt`, `female.txt` or `feminine.txt`, and `neutral.txt`, one name per line. Any of them may be missing, and names for that gender will be picked from all of the lists.
+    */
+    pub(crate) fn extend_from_directory<AsPath: AsRef<Path>>(&mut self, directory: AsPath) -> Result<(),CommandError> {
+        let directory = directory.as_ref();
+        let name = directory.file_name().and_then(OsStr::to_str).unwrap_or("").to_owned();
+
+        let read_list = |file_names: &[&str]| -> Result<Vec<String>,CommandError> {
+            let mut list = Vec::new();
+            for file_name in file_names {
+                let file = directory.join(file_name);
+                if file.is_file() {
+                    let source = File::open(file).map_err(|e| CommandError::NamerSourceRead(format!("{e}")))?;
+                    for line in BufReader::new(source).lines() {
+                        list.push(line.map_err(|e| CommandError::NamerSourceRead(format!("{e}")))?)
+                    }
+                }
+            }
+            Ok(list)
+        };
+
+        self.add_namer(NamerSource {
+            name,
+            method: NamerMethodSource::Gendered(GenderedSource {
+                masculine: read_list(&["male.txt","masculine.txt"])?,
+                feminine: read_list(&["female.txt","feminine.txt"])?,
+                neutral: read_list(&["neutral.txt"])?
+            }),
+            state_name: Vec::new(),
+            state_suffix: StateSuffixBehavior::Default,
+            lake_name: LakeNameSource::default(),
+        });
+
+        Ok(())
+    }
+
     pub(crate) fn extend_from_file<AsPath: AsRef<Path>>(&mut self, file: AsPath, text_is_markov: bool) -> Result<(),CommandError> {
 
         enum Format {
@@ -808,6 +1179,10 @@ impl NamerSetSource {
             TextList(String)
         }
 
+        if file.as_ref().is_dir() {
+            return self.extend_from_directory(file)
+        }
+
         let format = match file.as_ref().extension().and_then(OsStr::to_str) {
             Some("txt") => Format::TextList(file.as_ref().file_stem().and_then(OsStr::to_str).unwrap_or("").to_owned()),
             Some("json" | _) | None => Format::JSON, // this is the default, although perhaps the 'txt' should be the default?
@@ -826,3 +1201,206 @@ impl NamerSetSource {
     }
 
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::Namer;
+    use super::NamerLoadObserver;
+    use super::NamerSetSource;
+    use super::NamerMethodSource;
+    use super::NameGender;
+    use super::LakeNameBehavior;
+    use super::LakeNameSource;
+    use super::NamerSet;
+    use super::FallbackNamers;
+    use crate::commands::NamerArg;
+    use crate::world_map::fields::LakeType;
+
+    const SEED_WORDS: &str = "Ashford\nBramley\nCarlton\nDunmore\nEastwood\nFairley\nGranton\nHartwell\nKingsley\nLangford\nMarston\nNorthwick\nOakley\nPemberton\nRosewell\nStanford\nThornbury\nWaverley\nWestbrook\nWinslow\n";
+
+    #[test]
+    fn test_seed_word_fallbacks() {
+        // a single letter seed word can never build a name of two letters, so every word falls back
+        let mut source = NamerSetSource::empty();
+        source.extend_from_text("pathological".to_owned(), true, BufReader::new("a\n".as_bytes())).expect("Namer source should load.");
+        let namer_source = source.source.remove("pathological").expect("Namer should have been added.");
+        let mut namer = Namer::new(namer_source, &mut NamerLoadObserver::new("pathological", &mut ())).expect("Namer should be created.");
+
+        let mut random = StdRng::seed_from_u64(42);
+        for _ in 0..10 {
+            _ = namer.make_name(&mut random);
+        }
+
+        assert_eq!(namer.take_fallback_counts(), Some((10,10)));
+        // the counts start over after they are taken
+        assert_eq!(namer.take_fallback_counts(), Some((0,0)));
+    }
+
+    fn make_markov_names(order: usize) -> Vec<String> {
+        let mut source = NamerSetSource::empty();
+        source.extend_from_text("towns".to_owned(), true, BufReader::new(SEED_WORDS.as_bytes())).expect("Namer source should load.");
+        let mut namer_source = source.source.remove("towns").expect("Namer should have been added.");
+        let NamerMethodSource::Markov(markov) = &mut namer_source.method else {
+            panic!("Namer should be a markov chain.")
+        };
+        markov.order = order;
+        let mut namer = Namer::new(namer_source, &mut NamerLoadObserver::new("towns", &mut ())).expect("Namer should be created.");
+
+        let mut random = StdRng::seed_from_u64(42);
+        (0..20).map(|_| namer.make_name(&mut random)).collect()
+    }
+
+    #[test]
+    fn test_markov_order() {
+        let first_order = make_markov_names(1);
+        let second_order = make_markov_names(2);
+        assert!(second_order.iter().all(|name| name.len() >= 2));
+        assert_ne!(first_order,second_order);
+        // the same seed always makes the same names.
+        assert_eq!(second_order,make_markov_names(2));
+    }
+
+    #[test]
+    fn test_markov_order_source() {
+        let mut source = NamerSetSource::empty();
+        source.extend_from_json(BufReader::new(r#"[
+            {"name": "first", "method": "Markov", "duplicatable_letters": [], "seed_words": ["Ashford"], "state_name": [], "state_suffix": "Default"},
+            {"name": "second", "method": "Markov", "duplicatable_letters": [], "seed_words": ["Ashford"], "order": 2, "state_name": [], "state_suffix": "Default"}
+        ]"#.as_bytes())).expect("Namer source should load.");
+
+        // files written before the order was added still load as first order chains.
+        for (name,expected) in [("first",1),("second",2)] {
+            let NamerMethodSource::Markov(markov) = &source.source[name].method else {
+                panic!("Namer should be a markov chain.")
+            };
+            assert_eq!(markov.order,expected);
+        }
+    }
+
+    #[test]
+    fn test_gendered_names() {
+        let mut source = NamerSetSource::empty();
+        source.extend_from_json(BufReader::new(r#"[
+            {"name": "given", "method": "Gendered", "masculine": ["Arthur", "Bran"], "feminine": ["Cara"], "state_name": [], "state_suffix": "Default"}
+        ]"#.as_bytes())).expect("Namer source should load.");
+        let namer_source = source.source.remove("given").expect("Namer should have been added.");
+        let mut namer = Namer::new(namer_source, &mut NamerLoadObserver::new("given", &mut ())).expect("Namer should be created.");
+        assert!(namer.is_gendered());
+
+        let mut random = StdRng::seed_from_u64(42);
+        for _ in 0..10 {
+            assert!(["Arthur","Bran"].contains(&namer.make_gendered_name(&mut random, NameGender::Masculine).as_str()));
+            assert_eq!(namer.make_gendered_name(&mut random, NameGender::Feminine),"Cara");
+            // there are no neutral names, so they come from all of the lists.
+            assert!(["Arthur","Bran","Cara"].contains(&namer.make_gendered_name(&mut random, NameGender::Neutral).as_str()));
+            assert!(["Arthur","Bran","Cara"].contains(&namer.make_name(&mut random).as_str()));
+        }
+
+        // other namers make the same kind of names whatever the gender.
+        source.extend_from_text("places".to_owned(), false, BufReader::new("Ashford\n".as_bytes())).expect("Namer source should load.");
+        let namer_source = source.source.remove("places").expect("Namer should have been added.");
+        let mut namer = Namer::new(namer_source, &mut NamerLoadObserver::new("places", &mut ())).expect("Namer should be created.");
+        assert!(!namer.is_gendered());
+        assert_eq!(namer.make_gendered_name(&mut random, NameGender::Feminine),"Ashford");
+    }
+
+    #[test]
+    fn test_gendered_directory() {
+        use std::fs;
+        use std::path::PathBuf;
+
+        let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("tmp").join("test_gendered_directory");
+        fs::create_dir_all(&directory).expect("directory should be created");
+        fs::write(directory.join("male.txt"), "Arthur\nBran\n").expect("names should be written");
+        fs::write(directory.join("female.txt"), "Cara\n").expect("names should be written");
+
+        let mut source = NamerSetSource::empty();
+        source.extend_from_file(&directory, false).expect("Namer source should load.");
+        let NamerMethodSource::Gendered(lists) = &source.source["test_gendered_directory"].method else {
+            panic!("Namer should pick gendered names.")
+        };
+        assert_eq!(lists.masculine,vec!["Arthur","Bran"]);
+        assert_eq!(lists.feminine,vec!["Cara"]);
+        assert!(lists.neutral.is_empty());
+    }
+
+    #[test]
+    fn test_lake_names() {
+        let mut source: LakeNameSource = serde_json::from_str(r#"{"salt": {"Prefix": "Salt Lake"}, "marsh": "NoAffix"}"#).expect("Lake names should parse.");
+        assert_eq!(source.for_type(&LakeType::Fresh).apply(&LakeType::Fresh, "Ashford".to_owned()),"Lake Ashford");
+        assert_eq!(source.for_type(&LakeType::Dry).apply(&LakeType::Dry, "Ashford".to_owned()),"Ashford Dry Lake");
+        assert_eq!(source.for_type(&LakeType::Salt).apply(&LakeType::Salt, "Ashford".to_owned()),"Salt Lake Ashford");
+        assert_eq!(source.for_type(&LakeType::Marsh).apply(&LakeType::Marsh, "Ashford".to_owned()),"Ashford");
+
+        source.pluvial = LakeNameBehavior::Suffix("Playa".to_owned());
+        assert_eq!(source.for_type(&LakeType::Pluvial).apply(&LakeType::Pluvial, "Ashford".to_owned()),"Ashford Playa");
+        assert!(!source.is_default());
+        assert!(LakeNameSource::default().is_default());
+    }
+
+    #[test]
+    fn test_diversify_namers() {
+        let load = || {
+            let args = NamerArg {
+                namers: vec!["share/namers/afmg_namers.json".into()],
+                default_namer: Some("English".to_owned()),
+                weight_default_namer: false,
+                report_name_fallbacks: false
+            };
+            NamerSet::load_from(args, &mut StdRng::seed_from_u64(42), &mut ()).expect("Namers should load.")
+        };
+
+        let namers = load();
+        let count = namers.list_names().len();
+
+        // five cultures all using the same namer, and one using its own.
+        let cultures: Vec<String> = ["English","English","German","English","English","English"].into_iter().map(str::to_owned).collect();
+        let diversified = namers.diversify(&cultures);
+
+        assert_eq!(diversified.len(),cultures.len());
+        // the first cultures to use a namer keep it
+        assert_eq!(diversified[0],"English");
+        assert_eq!(diversified[2],"German");
+        let mut distinct = diversified.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(),cultures.len(),"every culture should have its own namer");
+
+        // the namers are loaded into a hash map, so loading again checks that the order doesn't depend on it.
+        assert_eq!(load().diversify(&cultures),diversified);
+
+        // with more cultures than namers, they have to share, but every namer gets used.
+        let crowd = vec!["English".to_owned(); count + 1];
+        let mut shared = namers.diversify(&crowd);
+        shared.sort();
+        shared.dedup();
+        assert_eq!(shared.len(),count);
+    }
+
+    #[test]
+    fn test_fallback_namer_weights() {
+        // "english" is split across two cultures to make sure the probabilities are added together.
+        let fallback = FallbackNamers::from_weights([("english",4.5),("elvish",1.0),("english",4.5),("dwarvish",0.0)]).expect("Weights should be valid.");
+
+        let mut random = StdRng::seed_from_u64(0);
+        let mut english = 0;
+        let mut elvish = 0;
+        for _ in 0..10000 {
+            match fallback.choose(&mut random) {
+                "english" => english += 1,
+                "elvish" => elvish += 1,
+                other => panic!("Namer '{other}' should not have been chosen.")
+            }
+        }
+
+        assert!((8800..=9200).contains(&english),"english was chosen {english} times");
+        assert!((800..=1200).contains(&elvish),"elvish was chosen {elvish} times");
+
+        assert!(FallbackNamers::from_weights([("dwarvish",0.0)]).is_none());
+    }
+}
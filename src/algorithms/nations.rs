@@ -32,40 +32,58 @@ use crate::commands::OverwriteNationsArg;
 use crate::commands::SizeVarianceArg;
 use crate::commands::RiverThresholdArg;
 use crate::commands::ExpansionFactorArg;
+use crate::commands::NationExpansionArg;
+use crate::commands::NationCountArg;
 use super::colors::Luminosity;
+use super::cultures::tally_unclaimed_tiles;
 use crate::world_map::fields::NeighborAndDirection;
 use crate::world_map::fields::Neighbor;
 
-pub(crate) fn generate_nations<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer + CultureWithType>(target: &mut WorldMapTransaction, rng: &mut Random, culture_lookup: &EntityLookup<CultureSchema,Culture>, namers: &mut NamerSet, size_variance: &SizeVarianceArg, overwrite_layer: &OverwriteNationsArg, progress: &mut Progress) -> Result<(),CommandError> {
+pub(crate) fn generate_nations<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer + CultureWithType>(target: &mut WorldMapTransaction, rng: &mut Random, culture_lookup: &EntityLookup<CultureSchema,Culture>, namers: &mut NamerSet, size_variance: &SizeVarianceArg, nation_count: &NationCountArg, overwrite_layer: &OverwriteNationsArg, progress: &mut Progress) -> Result<(),CommandError> {
 
     let mut towns = target.edit_towns_layer()?;
 
-    let mut nations = Vec::new();
+    let mut capitals = Vec::new();
 
     for town in towns.read_features().into_entities::<TownForNations>().watch(progress,"Reading towns.","Towns read.") {
         let (_,town) = town?;
         if *town.is_capital() {
-            let culture = town.culture().clone();
-            let culture_data = culture.as_ref().map(|c| culture_lookup.try_get(c)).transpose()?;
-            let namer = Culture::get_namer(culture_data, namers)?;
-            let name = namer.make_state_name(rng);
-            let type_ = culture_data.map(CultureWithType::type_).cloned().unwrap_or(CultureType::Generic);
-            let center_tile_id = town.tile_id().clone();
-            let capital_town_id = town.fid().clone();
-            let expansionism = rng.gen_range(0.1f64..1.0f64).mul_add(size_variance.size_variance, 1.0);
-            nations.push(NewNation {
-                name,
-                center_tile_id,
-                culture,
-                type_,
-                expansionism,
-                capital_town_id,
-                color: Rgb::new(0,0,0)
-            })
-
+            capitals.push(town);
         }
     }
 
+    let (capitals, demoted) = select_capitals(capitals, nation_count.nation_count, progress);
+
+    for town in demoted.iter().watch(progress,"Demoting extra capitals.","Extra capitals demoted.") {
+        let mut feature = towns.try_feature_by_id(town.fid())?;
+        feature.set_is_capital(&false)?;
+        towns.update_feature(feature)?;
+    }
+
+    let mut nations = Vec::new();
+
+    for town in capitals {
+        let culture = town.culture().clone();
+        let culture_data = culture.as_ref().map(|c| culture_lookup.try_get(c)).transpose()?;
+        let namer = Culture::get_namer(culture_data, namers)?;
+        let name = namer.make_state_name(rng);
+        let type_ = culture_data.map(CultureWithType::type_).cloned().unwrap_or(CultureType::Generic);
+        let center_tile_id = town.tile_id().clone();
+        let capital_town_id = town.fid().clone();
+        let expansionism = rng.gen_range(0.1f64..1.0f64).mul_add(size_variance.size_variance, 1.0);
+        nations.push(NewNation {
+            name,
+            center_tile_id,
+            culture,
+            type_,
+            expansionism,
+            capital_town_id,
+            color: Rgb::new(0,0,0)
+        })
+    }
+
+    namers.report_fallbacks(progress);
+
     let mut colors = RandomColorGenerator::new(None,Some(Luminosity::Light)).generate_colors(nations.len(), rng).into_iter();
 
     for nation in nations.iter_mut().watch(progress, "Assigning colors.", "Colors assigned") {
@@ -81,7 +99,39 @@ pub(crate) fn generate_nations<Random: Rng, Progress: ProgressObserver, Culture:
     Ok(())
 }
 
-pub(crate) fn expand_nations<Progress: ProgressObserver>(target: &mut WorldMapTransaction, river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, progress: &mut Progress) -> Result<(),CommandError> {
+/// Limits the capitals to the requested number of nations, keeping the most populous ones. Returns the capitals to keep, in their original order, and the capitals to demote.
+pub(crate) fn select_capitals<Progress: ProgressObserver>(capitals: Vec<TownForNations>, nation_count: Option<usize>, progress: &Progress) -> (Vec<TownForNations>,Vec<TownForNations>) {
+    let Some(nation_count) = nation_count else {
+        if capitals.is_empty() {
+            progress.warning(|| "There are no capitals to found nations in. No nations will be created.")
+        }
+        return (capitals,Vec::new())
+    };
+
+    if nation_count > capitals.len() {
+        let available = capitals.len();
+        progress.warning(|| format!("There aren't enough capitals to create {nation_count} nations. Only {available} nations will be created."));
+        return (capitals,Vec::new())
+    }
+
+    let mut by_population: Vec<usize> = (0..capitals.len()).collect();
+    // stable sort, so ties are broken by the original order.
+    by_population.sort_by_key(|index| Reverse(*capitals[*index].population()));
+    let keep: HashSet<usize> = by_population.into_iter().take(nation_count).collect();
+
+    let mut kept = Vec::new();
+    let mut demoted = Vec::new();
+    for (index,capital) in capitals.into_iter().enumerate() {
+        if keep.contains(&index) {
+            kept.push(capital)
+        } else {
+            demoted.push(capital)
+        }
+    }
+    (kept,demoted)
+}
+
+pub(crate) fn expand_nations<Progress: ProgressObserver>(target: &mut WorldMapTransaction, river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, nation_expansion: &NationExpansionArg, progress: &mut Progress) -> Result<(),CommandError> {
 
     let world_shape = target.edit_properties_layer()?.get_world_shape()?;
 
@@ -159,9 +209,13 @@ pub(crate) fn expand_nations<Progress: ProgressObserver>(target: &mut WorldMapTr
     
                     let biome_cost = get_biome_cost(&nation_biome,neighbor_biome,nation.type_());
     
-                    let height_cost = get_height_cost(neighbor, nation.type_());
+                    let height_cost = get_water_cost(neighbor, nation.type_(), nation_expansion.nation_water_cost).unwrap_or_else(|| get_height_cost(neighbor, nation.type_()));
     
-                    let river_cost = get_river_cost(neighbor, river_threshold.river_threshold, nation.type_());
+                    let river_cost = get_river_cost(neighbor, river_threshold.river_threshold, nation.type_()) + if nation_expansion.rivers_as_borders {
+                        get_river_border_cost(tile, neighbor, river_threshold.river_threshold, nation.type_())
+                    } else {
+                        0.0
+                    };
     
                     let shore_cost = get_shore_cost(neighbor, nation.type_());
     
@@ -209,6 +263,8 @@ pub(crate) fn expand_nations<Progress: ProgressObserver>(target: &mut WorldMapTr
 
     }
 
+    let (unclaimed_count,unclaimed_area) = tally_unclaimed_tiles(tile_map.iter().map(|(_,tile)| (*tile.habitability(),*tile.area(),tile.nation_id().is_some())));
+    progress.message(|| format!("{unclaimed_count} habitable tiles, covering {unclaimed_area:.1} square degrees, were not reached by any nation. A higher expansion factor will let nations expand further."));
 
     for (fid,tile) in tile_map.iter().watch(progress,"Writing nations.","Nations written.") {
 
@@ -284,53 +340,68 @@ pub(crate) fn get_river_cost(neighbor: &TileForNationExpand, river_threshold: f6
     }
 }
 
-pub(crate) fn get_height_cost(neighbor: &TileForNationExpand, culture_type: &CultureType) -> f64 {
-    // This is similar to the way cultures work, but not exactly.
+/// Returns the cost of crossing the neighbor if it's water, or None if it's land. The cost is scaled by the tile area along with the others.
+/// The cost of crossing onto a river from land, used when rivers are treated as borders. River cultures use the rivers as corridors instead, and following a river from another river tile costs nothing extra.
+pub(crate) fn get_river_border_cost(tile: &TileForNationExpand, neighbor: &TileForNationExpand, river_threshold: f64, culture_type: &CultureType) -> f64 {
+    const RIVER_BORDER_COST: f64 = 1000.0;
+
+    let is_river = |entity: &TileForNationExpand| !entity.grouping().is_water() && (entity.water_flow() > &river_threshold);
+
     match culture_type {
-        CultureType::Lake => if neighbor.lake_id().is_some() {
-            // low lake crossing penalty for lake cultures
-            10.0
-        } else if (neighbor.shore_distance() < &-1) || neighbor.grouping().is_ocean() { // allow them to enter lakes up to -1 for better appearance
-            // general sea/lake crossing penalty
-            1000.0
-        } else if neighbor.elevation_scaled() >= &67 {
-            // mountain crossing penalty
-            2200.0 
-        } else if neighbor.elevation_scaled() > &44 {
-            // hill crossing penalt
-            300.0
+        CultureType::River => 0.0,
+        CultureType::Generic |
+        CultureType::Lake |
+        CultureType::Naval |
+        CultureType::Nomadic |
+        CultureType::Hunting |
+        CultureType::Highland => if is_river(neighbor) && !is_river(tile) {
+            RIVER_BORDER_COST
         } else {
             0.0
-        },
-        CultureType::Naval => if (neighbor.shore_distance() < &-1) || neighbor.grouping().is_ocean() { // allow them to enter lakes up to -1 for better appearance
+        }
+    }
+}
+
+/// Returns the cost of crossing the neighbor if it's water, or None if it's land. Unlike cultures, the water cost doesn't depend on the tile's area.
+pub(crate) fn get_water_cost(neighbor: &TileForNationExpand, culture_type: &CultureType, water_cost: f64) -> Option<f64> {
+    // This is similar to the way cultures work, but not exactly.
+    if let (CultureType::Lake, Some(_)) = (culture_type, neighbor.lake_id()) {
+        // low lake crossing penalty for lake cultures
+        Some(10.0)
+    } else if (neighbor.shore_distance() < &-1) || neighbor.grouping().is_ocean() { // allow them to enter lakes up to -1 for better appearance
+        Some(match culture_type {
             // low water crossing penalty
-            300.0
-        } else if neighbor.elevation_scaled() >= &67 {
-            // mountain crossing penalty
-            2200.0 
-        } else if neighbor.elevation_scaled() > &44 {
-            // hill crossing penalt
-            300.0
-        } else {
-            0.0
-        },
-        CultureType::Highland => if (neighbor.shore_distance() < &-1) || neighbor.grouping().is_ocean() { // allow them to enter lakes up to -1 for better appearance
-            // general sea/lake corssing penalty
-            1000.0
-        } else if neighbor.elevation_scaled() < &62 {
+            CultureType::Naval => 300.0,
+            // general sea/lake crossing penalty, which may be infinite
+            CultureType::Lake |
+            CultureType::Highland |
+            CultureType::Nomadic |
+            CultureType::Generic |
+            CultureType::River |
+            CultureType::Hunting => water_cost
+        })
+    } else {
+        None
+    }
+}
+
+/// Returns the cost of crossing the neighbor based on its elevation. Water is handled by [`get_water_cost`].
+pub(crate) fn get_height_cost(neighbor: &TileForNationExpand, culture_type: &CultureType) -> f64 {
+    // This is similar to the way cultures work, but not exactly.
+    match culture_type {
+        CultureType::Highland => if neighbor.elevation_scaled() < &62 {
             // smaller but still big penalty for hills
             1100.0
         } else {
             // no penalty in highlands
             0.0
         },
+        CultureType::Lake |
+        CultureType::Naval |
         CultureType::Nomadic |
         CultureType::Generic |
         CultureType::River |
-        CultureType::Hunting => if (neighbor.shore_distance() < &-1) || neighbor.grouping().is_ocean() { // allow them to enter lakes up to -1 for better appearance
-            // general sea/lake corssing penalty
-            1000.0
-        } else if neighbor.elevation_scaled() >= &67 {
+        CultureType::Hunting => if neighbor.elevation_scaled() >= &67 {
             // mountain crossing penalty
             2200.0 
         } else if neighbor.elevation_scaled() > &44 {
@@ -459,3 +530,63 @@ pub(crate) fn normalize_nations<Progress: ProgressObserver>(target: &mut WorldMa
 
     Ok(()) 
 }
+
+#[cfg(test)]
+mod test {
+    use super::select_capitals;
+    use super::get_river_border_cost;
+    use super::get_river_cost;
+    use crate::world_map::tile_layer::TileForNationExpand;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::CultureType;
+    use crate::world_map::town_layer::TownForNations;
+    use crate::typed_map::fields::IdRef;
+
+    fn capitals() -> Vec<TownForNations> {
+        [500,2000,100,2000,800].into_iter().zip(1..).map(|(population,fid)| {
+            TownForNations::new(IdRef::new(fid),IdRef::new(fid + 10),population)
+        }).collect()
+    }
+
+    #[test]
+    fn test_select_capitals() {
+        let fids = |towns: &Vec<TownForNations>| towns.iter().map(|town| town.fid().clone()).collect::<Vec<_>>();
+
+        let (kept,demoted) = select_capitals(capitals(), Some(3), &());
+        assert_eq!(fids(&kept),vec![IdRef::new(2),IdRef::new(4),IdRef::new(5)]);
+        assert_eq!(fids(&demoted),vec![IdRef::new(1),IdRef::new(3)]);
+
+        // asking for more nations than there are capitals falls back to one nation per capital
+        let (too_many,none_demoted) = select_capitals(capitals(), Some(8), &());
+        assert_eq!(too_many.len(),5);
+        assert!(none_demoted.is_empty());
+
+        let (unlimited,unlimited_demoted) = select_capitals(capitals(), None, &());
+        assert_eq!(unlimited.len(),5);
+        assert!(unlimited_demoted.is_empty());
+    }
+
+    #[test]
+    fn test_river_border_cost() {
+        let land = TileForNationExpand::new(Grouping::Continent, 1.0);
+        let river = TileForNationExpand::new(Grouping::Continent, 500.0);
+
+        // the cost of stepping from one tile to the next, without the parts that don't depend on rivers.
+        let step = |from: &TileForNationExpand, to: &TileForNationExpand, borders: bool| {
+            get_river_cost(to, 10.0, &CultureType::Generic) + if borders { get_river_border_cost(from, to, 10.0, &CultureType::Generic) } else { 0.0 }
+        };
+
+        // reaching the land on the far side of the river, or going around the end of it through three land tiles.
+        let across = |borders| step(&land, &river, borders) + step(&river, &land, borders);
+        let around = |borders| step(&land, &land, borders) * 3.0;
+
+        assert!(across(false) < around(false) + 100.0,"without borders, crossing a river costs about the same as a short detour");
+        assert!(across(true) > around(true) + 500.0,"with borders, the river should be much harder to cross than going around");
+
+        // following a river isn't any more expensive.
+        assert_eq!(step(&river, &river, true).to_bits(),step(&river, &river, false).to_bits());
+
+        // river cultures use the rivers as corridors.
+        assert_eq!(get_river_border_cost(&land, &river, 10.0, &CultureType::River).to_bits(),0.0f64.to_bits());
+    }
+}
@@ -0,0 +1,112 @@
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Result as FormatResult;
+
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::entities::EntityIndex;
+use crate::typed_map::fields::IdRef;
+use crate::world_map::fields::Neighbor;
+use crate::world_map::fields::NeighborAndDirection;
+use crate::world_map::tile_layer::TileSchema;
+use crate::world_map::tile_layer::TileWithNeighbors;
+
+/// A problem found in the neighbor lists of the tiles.
+#[derive(Debug,PartialEq)]
+pub(crate) enum NeighborViolation {
+    /// The tile lists a neighbor which isn't in the tiles layer.
+    MissingNeighbor{ tile: IdRef, neighbor: IdRef },
+    /// The tile lists a neighbor which doesn't list the tile back, or lists it as the wrong kind of neighbor.
+    NotReciprocated{ tile: IdRef, neighbor: IdRef, cross_map: bool },
+}
+
+impl Display for NeighborViolation {
+
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match self {
+            Self::MissingNeighbor { tile, neighbor } => write!(f,"Tile {tile} lists neighbor {neighbor}, which does not exist."),
+            Self::NotReciprocated { tile, neighbor, cross_map: false } => write!(f,"Tile {tile} lists neighbor {neighbor}, which does not list it back."),
+            Self::NotReciprocated { tile, neighbor, cross_map: true } => write!(f,"Tile {tile} lists neighbor {neighbor} across the map edge, which does not list it back across the edge."),
+        }
+    }
+}
+
+/// Checks that every neighbor listed by a tile exists and lists that tile back. Neighbors across the map edge must be listed back across the edge, although the edges may differ at the corners. Off-map neighbors aren't tiles, so have nothing to check.
+pub(crate) fn check_neighbors<TileType: TileWithNeighbors, Progress: ProgressObserver>(tiles: &EntityIndex<TileSchema,TileType>, progress: &mut Progress) -> Vec<NeighborViolation> {
+
+    let mut violations = Vec::new();
+
+    for (fid,tile) in tiles.iter().watch(progress,"Checking neighbors.","Neighbors checked.") {
+        for NeighborAndDirection(neighbor,_) in tile.neighbors() {
+            let (neighbor_id,cross_map) = match neighbor {
+                Neighbor::Tile(neighbor_id) => (neighbor_id,false),
+                Neighbor::CrossMap(neighbor_id,_) => (neighbor_id,true),
+                Neighbor::OffMap(_) => continue,
+            };
+
+            if let Some(neighbor_tile) = tiles.maybe_get(neighbor_id) {
+                let reciprocated = neighbor_tile.neighbors().iter().any(|NeighborAndDirection(back,_)| match back {
+                    Neighbor::Tile(back_id) => !cross_map && (back_id == fid),
+                    Neighbor::CrossMap(back_id,_) => cross_map && (back_id == fid),
+                    Neighbor::OffMap(_) => false,
+                });
+                if !reciprocated {
+                    violations.push(NeighborViolation::NotReciprocated { tile: fid.clone(), neighbor: neighbor_id.clone(), cross_map });
+                }
+            } else {
+                violations.push(NeighborViolation::MissingNeighbor { tile: fid.clone(), neighbor: neighbor_id.clone() });
+            }
+        }
+    }
+
+    violations
+
+}
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+
+    use super::check_neighbors;
+    use super::NeighborViolation;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::utils::edge::Edge;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileForNeighborCheck;
+    use crate::world_map::tile_layer::TileSchema;
+
+    fn tiles(neighbors: Vec<Vec<Neighbor>>) -> EntityIndex<TileSchema,TileForNeighborCheck> {
+        neighbors.into_iter().enumerate().map(|(fid,list)| {
+            (IdRef::new(fid as u64 + 1),TileForNeighborCheck::new(list.into_iter().map(|neighbor| NeighborAndDirection(neighbor,Deg(0.0))).collect()))
+        }).collect()
+    }
+
+    #[test]
+    fn test_check_neighbors() {
+        let tile = |id| Neighbor::Tile(IdRef::new(id));
+
+        let consistent = tiles(vec![
+            vec![tile(2),Neighbor::CrossMap(IdRef::new(3),Edge::West),Neighbor::OffMap(Edge::North)],
+            vec![tile(1),tile(3)],
+            // corner tiles may list the crossing on a different edge
+            vec![tile(2),Neighbor::CrossMap(IdRef::new(1),Edge::Northeast)],
+        ]);
+        assert_eq!(check_neighbors(&consistent,&mut ()),Vec::new());
+
+        let broken = tiles(vec![
+            // 1 lists 2, but 2 doesn't list 1
+            vec![tile(2)],
+            vec![tile(3)],
+            // 3 lists 2 across the edge, but 2 lists 3 as an ordinary neighbor
+            vec![Neighbor::CrossMap(IdRef::new(2),Edge::East),tile(4)],
+        ]);
+        assert_eq!(check_neighbors(&broken,&mut ()),vec![
+            NeighborViolation::NotReciprocated { tile: IdRef::new(1), neighbor: IdRef::new(2), cross_map: false },
+            NeighborViolation::NotReciprocated { tile: IdRef::new(2), neighbor: IdRef::new(3), cross_map: false },
+            NeighborViolation::NotReciprocated { tile: IdRef::new(3), neighbor: IdRef::new(2), cross_map: true },
+            NeighborViolation::MissingNeighbor { tile: IdRef::new(3), neighbor: IdRef::new(4) },
+        ]);
+    }
+}
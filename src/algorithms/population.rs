@@ -11,12 +11,55 @@ use crate::world_map::fields::LakeType;
 use crate::world_map::water_layers::LakeForPopulation;
 use crate::commands::RiverThresholdArg;
 use crate::world_map::fields::Neighbor;
+use crate::world_map::tile_layer::TileSchema;
+use crate::typed_map::schema::Schema;
+
+/// The extra tile fields written by '--debug-habitability', in the same order as `HabitabilityComponents::values`.
+const DEBUG_HABITABILITY_FIELDS: [&str; 5] = [
+    "debug_habitability_biome",
+    "debug_habitability_river",
+    "debug_habitability_elevation",
+    "debug_habitability_estuary",
+    "debug_habitability_harbor",
+];
+
+/// The terms which are added up to get a tile's suitability for population. They are kept apart so they can be written out when tuning the population model.
+#[derive(Default)]
+pub(crate) struct HabitabilityComponents {
+    biome: f64,
+    river: f64,
+    elevation: f64,
+    estuary: f64,
+    harbor: f64
+}
+
+impl HabitabilityComponents {
 
-pub(crate) fn generate_populations<Progress: ProgressObserver>(target: &mut WorldMapTransaction, estuary_threshold: &RiverThresholdArg, progress: &mut Progress) -> Result<(),CommandError> {
+    const fn values(&self) -> [f64; 5] {
+        [self.biome, self.river, self.elevation, self.estuary, self.harbor]
+    }
+
+    /// Tiles in an uninhabitable biome, or in a lake, aren't habitable no matter what the other terms are.
+    fn habitability(&self) -> f64 {
+        if self.biome > 0.0 {
+            self.values().iter().sum::<f64>() / 5.0 // I don't know why 5, but that's what AFMG did.
+        } else {
+            0.0
+        }
+    }
+}
+
+pub(crate) fn generate_populations<Progress: ProgressObserver>(target: &mut WorldMapTransaction, estuary_threshold: &RiverThresholdArg, debug_habitability: bool, progress: &mut Progress) -> Result<(),CommandError> {
 
     // This algorithm is almost the same as found in AFMG
 
-    let world_shape = target.edit_properties_layer()?.get_world_shape()?;
+    if !debug_habitability {
+        // clean up after a previous debugging run
+        let dropped = target.drop_extra_fields(TileSchema::LAYER_NAME, &DEBUG_HABITABILITY_FIELDS)?;
+        if !dropped.is_empty() {
+            progress.message(|| format!("Removed habitability debugging fields: {}",dropped.join(", ")));
+        }
+    }
 
     // we need a lake information map
     let mut lakes_layer = target.edit_lakes_layer()?;
@@ -28,6 +71,10 @@ pub(crate) fn generate_populations<Progress: ProgressObserver>(target: &mut Worl
 
     let mut tiles = target.edit_tile_layer()?;
 
+    if debug_habitability {
+        tiles.add_extra_fields(&DEBUG_HABITABILITY_FIELDS)?;
+    }
+
     let mut work_queue = Vec::new();
     let mut flow_sum = 0.0;
     let mut flow_max: f64 = 0.0;
@@ -38,7 +85,8 @@ pub(crate) fn generate_populations<Progress: ProgressObserver>(target: &mut Worl
         let water_flow = feature.water_flow()?;
         flow_sum += water_flow;
         flow_max = flow_max.max(water_flow);
-        area_sum += feature.geometry()?.shaped_area(&world_shape)?;
+        // the area was calculated for the world shape when the tile was created, so there's no need to do it again.
+        area_sum += feature.area()?;
         work_queue.push(fid);
 
     }
@@ -49,41 +97,44 @@ pub(crate) fn generate_populations<Progress: ProgressObserver>(target: &mut Worl
 
     let mut work_queue = work_queue.watch_queue(progress, "Calculating population.", "Population calculated.");
     while let Some(fid) = work_queue.pop() {
-        let (habitability,population) = {
+        let (components,habitability,population) = {
             let tile = tiles.try_entity_by_id::<TileForPopulation>(&fid)?; 
-            let mut suitability = if tile.lake_id().is_some() {
-                0.0
-            } else {
-                *biome_map.try_get(tile.biome())?.habitability() as f64
+            let mut components = HabitabilityComponents {
+                biome: if tile.lake_id().is_some() {
+                    0.0
+                } else {
+                    *biome_map.try_get(tile.biome())?.habitability() as f64
+                },
+                ..HabitabilityComponents::default()
             };
-            if suitability > 0.0 {
+            if components.biome > 0.0 {
                 if flow_mean > 0.0 {
-                    suitability += ((tile.water_flow() - flow_mean)/flow_divisor).clamp(0.0,1.0) * 250.0; // big rivers are nice.
+                    components.river = ((tile.water_flow() - flow_mean)/flow_divisor).clamp(0.0,1.0) * 250.0; // big rivers are nice.
                 }
-                suitability -= (tile.elevation_scaled() - 50) as f64/5.0; // low elevation is preferred
+                components.elevation = -((tile.elevation_scaled() - 50) as f64/5.0); // low elevation is preferred
                 if tile.shore_distance() == &1 {
                     if tile.water_flow() > &estuary_threshold.river_threshold {
-                        suitability += 15.0 // estuaries are liked
+                        components.estuary = 15.0 // estuaries are liked
                     }
                     if let Some(water_cell) = tile.harbor_tile_id() {
                         match water_cell {
                             Neighbor::Tile(water_cell) | Neighbor::CrossMap(water_cell, _) => {
                                 let water_cell = tiles.try_entity_by_id::<TileForPopulationNeighbor>(water_cell)?;
                                 if let Some(lake_type) = water_cell.lake_id().as_ref().map(|id| lake_map.try_get(id)).transpose()?.map(LakeForPopulation::type_) {
-                                    match lake_type {
-                                        LakeType::Fresh => suitability += 30.0,
-                                        LakeType::Salt => suitability += 10.0,
-                                        LakeType::Frozen => suitability += 1.0,
-                                        LakeType::Pluvial => suitability -= 2.0,
-                                        LakeType::Dry => suitability -= 5.0,
-                                        LakeType::Marsh => suitability += 5.0,
+                                    components.harbor = match lake_type {
+                                        LakeType::Fresh => 30.0,
+                                        LakeType::Salt => 10.0,
+                                        LakeType::Frozen => 1.0,
+                                        LakeType::Pluvial => -2.0,
+                                        LakeType::Dry => -5.0,
+                                        LakeType::Marsh => 5.0,
                                     }
                                 } else if water_cell.grouping().is_ocean() {
-                                    suitability += 5.0;
+                                    components.harbor = 5.0;
                                     if tile.water_count() == &Some(1) { // let pattern unecessary
                                         // since it's a land cell bordering a single cell on the ocean, that single cell is a small bay, which
                                         // probably makes a good harbor.
-                                        suitability += 20.0
+                                        components.harbor += 20.0
                                     }
                                 }
         
@@ -94,12 +145,12 @@ pub(crate) fn generate_populations<Progress: ProgressObserver>(target: &mut Worl
 
                     }
                 }
-                let habitability = suitability / 5.0; // I don't know why 5, but that's what AFMG did.
+                let habitability = components.habitability();
                 // AFMG Just shows population in thousands, I'm actually going to have more precision, just for looks.
                 let population = (((habitability * tile.area())/area_mean) * 1000.0).floor() as i32;
-                (habitability,population)
+                (components,habitability,population)
             } else {
-                (0.0,0)
+                (components,0.0,0)
             }
         };
 
@@ -108,9 +159,44 @@ pub(crate) fn generate_populations<Progress: ProgressObserver>(target: &mut Worl
         feature.set_habitability(&habitability)?;
         feature.set_population(&population)?;
 
+        if debug_habitability {
+            let mut debug_feature = feature.into_feature();
+            for (field,value) in DEBUG_HABITABILITY_FIELDS.iter().zip(components.values()) {
+                debug_feature.set_field_double(field, value)?;
+            }
+            feature = debug_feature.into();
+        }
+
         tiles.update_feature(feature)?;
 
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::HabitabilityComponents;
+
+    #[test]
+    fn test_habitability_components() {
+        let components = HabitabilityComponents {
+            biome: 80.0,
+            river: 125.0,
+            elevation: -4.0,
+            estuary: 15.0,
+            harbor: 25.0
+        };
+        // the debugging fields add up to five times the habitability
+        assert_eq!(components.values().iter().sum::<f64>().to_bits(),241.0f64.to_bits());
+        assert_eq!(components.habitability().to_bits(),(241.0f64 / 5.0).to_bits());
+
+        // uninhabitable tiles aren't helped by their other terms
+        let lake = HabitabilityComponents {
+            biome: 0.0,
+            river: 125.0,
+            ..HabitabilityComponents::default()
+        };
+        assert_eq!(lake.habitability().to_bits(),0.0f64.to_bits());
+    }
+}
@@ -0,0 +1,373 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use gdal::DriverManager;
+use gdal::raster::Buffer;
+use gdal::raster::RasterCreationOptions;
+use prisma::Rgb;
+
+use crate::algorithms::colors::ColorRamp;
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::entities::EntityIndex;
+use crate::typed_map::entities::EntityLookup;
+use crate::typed_map::features::TypedFeature;
+use crate::utils::extent::Extent;
+use crate::world_map::WorldMapTransaction;
+use crate::world_map::biome_layer::BiomeForPreview;
+use crate::world_map::biome_layer::BiomeSchema;
+use crate::world_map::nation_layers::NationForPreview;
+use crate::world_map::nation_layers::NationSchema;
+use crate::world_map::property_layer::ElevationLimits;
+use crate::world_map::tile_layer::TileFeature;
+use crate::world_map::water_layers::LakeLayer;
+use crate::world_map::water_layers::OceanLayer;
+use crate::geometry::MultiPolygon;
+use crate::geometry::Polygon;
+
+/// The tile data which is used to color a preview image.
+#[derive(Clone,ValueEnum)]
+pub enum PreviewAttribute {
+    /// Blues for the depth of water tiles, and greens through browns to white for the elevation of land tiles, unless a color ramp is given
+    Elevation,
+    /// The color of the biome in the biomes layer
+    Biome,
+    /// The color of the nation in the nations layer, with gray for unclaimed land
+    Nation,
+    /// Blue for cold through red for hot, from -30 to 30 degrees, unless a color ramp is given
+    Temperature
+}
+
+/// The polygon layers which can be rendered, each colored by its own color field.
+#[derive(Clone,ValueEnum)]
+pub enum RenderLayer {
+    /// The biomes layer
+    Biomes,
+    /// The cultures layer
+    Cultures,
+    /// The nations layer
+    Nations,
+    /// The subnations layer
+    Subnations
+}
+
+// prisma's constructors aren't const, so these can't be constants.
+fn water_color() -> Rgb<u8> {
+    Rgb::new(120,170,230)
+}
+
+fn unclaimed_color() -> Rgb<u8> {
+    Rgb::new(200,200,200)
+}
+
+enum TileColors {
+    Elevation(ColorRamp),
+    // the default elevation colors, which are picked by whether the tile is water or land, since lakes can be above sea level and land can be below it.
+    GroupedElevation {
+        water: ColorRamp,
+        land: ColorRamp
+    },
+    Biome(EntityLookup<BiomeSchema,BiomeForPreview>),
+    Nation(EntityIndex<NationSchema,NationForPreview>),
+    Temperature(ColorRamp)
+}
+
+impl TileColors {
+
+    fn load<Progress: ProgressObserver>(target: &WorldMapTransaction, attribute: &PreviewAttribute, color_ramp: Option<ColorRamp>, progress: &mut Progress) -> Result<Self,CommandError> {
+        Ok(match attribute {
+            PreviewAttribute::Elevation => match color_ramp {
+                Some(color_ramp) => Self::Elevation(color_ramp),
+                None => {
+                    let (water,land) = Self::default_elevation_ramps(&target.edit_properties_layer()?.get_elevation_limits()?)?;
+                    Self::GroupedElevation { water, land }
+                }
+            },
+            PreviewAttribute::Biome => Self::Biome(target.edit_biomes_layer()?.read_features().into_named_entities_index::<_,BiomeForPreview>(progress)?),
+            PreviewAttribute::Nation => Self::Nation(target.edit_nations_layer()?.read_features().into_entities_index::<_,NationForPreview>(progress)?),
+            PreviewAttribute::Temperature => Self::Temperature(match color_ramp {
+                Some(color_ramp) => color_ramp,
+                None => ColorRamp::new(vec![(-30.0,Rgb::new(40,60,200)),(0.0,Rgb::new(235,235,235)),(30.0,Rgb::new(200,40,30))])?
+            }),
+        })
+    }
+
+    /// Returns the ramps for water tiles and land tiles. Water above sea level gets the shallowest color, and land below it gets the lowest.
+    fn default_elevation_ramps(limits: &ElevationLimits) -> Result<(ColorRamp,ColorRamp),CommandError> {
+        let max_elevation = limits.max_elevation().max(1.0);
+        let water = ColorRamp::new(vec![
+            (limits.min_elevation().min(-1.0),Rgb::new(0,0,96)),
+            (0.0,water_color())
+        ])?;
+        let land = ColorRamp::new(vec![
+            (0.0,Rgb::new(60,140,60)),
+            (max_elevation/2.0,Rgb::new(150,120,70)),
+            (max_elevation,Rgb::new(255,255,255))
+        ])?;
+        Ok((water,land))
+    }
+
+    fn tile_color(&self, tile: &TileFeature) -> Result<Rgb<u8>,CommandError> {
+        Ok(match self {
+            Self::Elevation(ramp) => ramp.sample(tile.elevation()?),
+            Self::GroupedElevation { water, land } => if tile.grouping()?.is_water() {
+                water.sample(tile.elevation()?)
+            } else {
+                land.sample(tile.elevation()?)
+            },
+            Self::Biome(biomes) => *biomes.try_get(&tile.biome()?)?.color(),
+            Self::Nation(nations) => match tile.nation_id()? {
+                Some(nation_id) => *nations.try_get(&nation_id)?.color(),
+                None if tile.grouping()?.is_water() => water_color(),
+                None => unclaimed_color()
+            },
+            Self::Temperature(ramp) => ramp.sample(tile.temperature()?),
+        })
+    }
+}
+
+/// A small equirectangular image of the world, for looking at the results without a GIS.
+pub(crate) struct PreviewImage {
+    extent: Extent,
+    width: usize,
+    height: usize,
+    // row by row, starting from the north. Pixels not covered by a tile are transparent.
+    pixels: Vec<Option<Rgb<u8>>>
+}
+
+impl PreviewImage {
+
+    pub(crate) fn new(extent: Extent, width: usize) -> Self {
+        let width = width.max(1);
+        let height = ((width as f64 * extent.height() / extent.width()).round() as usize).max(1);
+        Self {
+            extent,
+            width,
+            height,
+            pixels: vec![None; width * height]
+        }
+    }
+
+    pub(crate) const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub(crate) const fn height(&self) -> usize {
+        self.height
+    }
+
+    #[cfg(test)]
+    pub(crate) const fn extent(&self) -> &Extent {
+        &self.extent
+    }
+
+    #[cfg(test)]
+    pub(crate) fn pixel(&self, column: usize, row: usize) -> Option<&Rgb<u8>> {
+        self.pixels.get(row * self.width + column).and_then(Option::as_ref)
+    }
+
+    /// Colors the pixels whose centers are inside the polygon, using the even-odd rule so holes in the rings are left alone.
+    pub(crate) fn fill_polygon(&mut self, rings: &[Vec<(f64,f64)>], color: Rgb<u8>) {
+        let pixel_width = self.extent.width() / self.width as f64;
+        let pixel_height = self.extent.height() / self.height as f64;
+        let north = self.extent.north();
+        let west = self.extent.west();
+
+        let (min_y,max_y) = rings.iter().flatten().fold((f64::INFINITY,f64::NEG_INFINITY), |(min,max),(_,y)| (min.min(*y),max.max(*y)));
+        if min_y > max_y {
+            return;
+        }
+        let first_row = ((north - max_y)/pixel_height - 0.5).ceil().max(0.0) as usize;
+        let last_row = (((north - min_y)/pixel_height - 0.5).floor().max(0.0) as usize).min(self.height - 1);
+
+        for row in first_row..=last_row {
+            let y = (row as f64 + 0.5).mul_add(-pixel_height, north);
+            let mut crossings = Vec::new();
+            for ring in rings {
+                for (start,end) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                    if (start.1 <= y) != (end.1 <= y) {
+                        crossings.push((y - start.1).mul_add((end.0 - start.0) / (end.1 - start.1), start.0));
+                    }
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+
+            for (start_x,end_x) in crossings.iter().step_by(2).zip(crossings.iter().skip(1).step_by(2)) {
+                let first_column = ((start_x - west)/pixel_width - 0.5).ceil();
+                let last_column = ((end_x - west)/pixel_width - 0.5).floor();
+                if (last_column < 0.0) || (first_column > last_column) {
+                    continue;
+                }
+                let first_column = first_column.max(0.0) as usize;
+                let last_column = (last_column as usize).min(self.width - 1);
+                for column in first_column..=last_column {
+                    self.pixels[row * self.width + column] = Some(color);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn fill_polygon_geometry(&mut self, polygon: &Polygon, color: Rgb<u8>) -> Result<(),CommandError> {
+        let mut rings = Vec::new();
+        for index in 0..polygon.len() {
+            rings.push(polygon.get_ring(index)?.into_iter().collect());
+        }
+        self.fill_polygon(&rings, color);
+        Ok(())
+    }
+
+    pub(crate) fn fill_multipolygon_geometry(&mut self, multipolygon: MultiPolygon, color: Rgb<u8>) -> Result<(),CommandError> {
+        for polygon in multipolygon {
+            self.fill_polygon_geometry(&polygon?, color)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn save<FilePath: AsRef<Path>>(&self, path: FilePath) -> Result<(),CommandError> {
+        let mut bands = [Vec::new(),Vec::new(),Vec::new(),Vec::new()];
+        for pixel in &self.pixels {
+            let (red,green,blue,alpha) = pixel.map_or((0,0,0,0), |color| (color.red(),color.green(),color.blue(),255));
+            bands[0].push(red);
+            bands[1].push(green);
+            bands[2].push(blue);
+            bands[3].push(alpha);
+        }
+
+        let memory = DriverManager::get_driver_by_name("MEM")?.create_with_band_type::<u8,_>("", self.width, self.height, bands.len())?;
+        for (index,data) in bands.into_iter().enumerate() {
+            let mut buffer = Buffer::new((self.width,self.height), data);
+            memory.rasterband(index + 1)?.write((0,0), (self.width,self.height), &mut buffer)?;
+        }
+
+        let png = DriverManager::get_driver_by_name("PNG")?;
+        _ = memory.create_copy(&png, path, &RasterCreationOptions::new())?;
+        Ok(())
+    }
+}
+
+pub(crate) fn generate_preview<Progress: ProgressObserver>(target: &WorldMapTransaction, attribute: &PreviewAttribute, color_ramp: Option<ColorRamp>, width: usize, region: Option<Extent>, progress: &mut Progress) -> Result<PreviewImage,CommandError> {
+
+    let colors = TileColors::load(target, attribute, color_ramp, progress)?;
+
+    let mut tiles = target.edit_tile_layer()?;
+
+    let extent = if let Some(region) = region {
+        // only the tiles which reach into the region need to be drawn, the rest would be clipped off anyway.
+        tiles.set_spatial_filter(&region);
+        region
+    } else {
+        tiles.get_extent()?
+    };
+
+    let mut image = PreviewImage::new(extent, width);
+
+    for feature in tiles.read_features().watch(progress,"Drawing tiles.","Tiles drawn.") {
+        let color = colors.tile_color(&feature)?;
+        image.fill_polygon_geometry(&feature.geometry()?, color)?;
+    }
+
+    Ok(image)
+}
+
+/// Draws the polygons of a layer over the oceans and lakes, instead of drawing each tile, so the image looks like the finished map.
+pub(crate) fn generate_render<Progress: ProgressObserver>(target: &WorldMapTransaction, layer: &RenderLayer, width: usize, progress: &mut Progress) -> Result<PreviewImage,CommandError> {
+
+    let extent = target.edit_tile_layer()?.get_extent()?;
+
+    let mut image = PreviewImage::new(extent, width);
+
+    // The water is drawn first, so the layer covers it wherever they overlap. The water layers might not have been generated yet.
+    if target.has_layer(OceanLayer::layer_name()) {
+        for feature in target.edit_ocean_layer()?.read_features().watch(progress,"Drawing oceans.","Oceans drawn.") {
+            image.fill_polygon_geometry(&feature.geometry()?, water_color())?;
+        }
+    }
+
+    if target.has_layer(LakeLayer::layer_name()) {
+        for feature in target.edit_lakes_layer()?.read_features().watch(progress,"Drawing lakes.","Lakes drawn.") {
+            image.fill_multipolygon_geometry(feature.geometry()?, water_color())?;
+        }
+    }
+
+    match layer {
+        RenderLayer::Biomes => for feature in target.edit_biomes_layer()?.read_features().watch(progress,"Drawing biomes.","Biomes drawn.") {
+            image.fill_multipolygon_geometry(feature.geometry()?, feature.color()?)?;
+        },
+        RenderLayer::Cultures => for feature in target.edit_cultures_layer()?.read_features().watch(progress,"Drawing cultures.","Cultures drawn.") {
+            image.fill_multipolygon_geometry(feature.geometry()?, feature.color()?)?;
+        },
+        RenderLayer::Nations => for feature in target.edit_nations_layer()?.read_features().watch(progress,"Drawing nations.","Nations drawn.") {
+            image.fill_multipolygon_geometry(feature.geometry()?, feature.color()?)?;
+        },
+        RenderLayer::Subnations => for feature in target.edit_subnations_layer()?.read_features().watch(progress,"Drawing subnations.","Subnations drawn.") {
+            image.fill_multipolygon_geometry(feature.geometry()?, feature.color()?)?;
+        },
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod test {
+    use prisma::Rgb;
+
+    use super::PreviewImage;
+    use super::TileColors;
+    use super::water_color;
+    use crate::utils::extent::Extent;
+    use crate::world_map::property_layer::ElevationLimits;
+
+    #[test]
+    fn test_fill_polygon() {
+        let mut image = PreviewImage::new(Extent::from_bounds(-180.0, -90.0, 180.0, 90.0), 36);
+        assert_eq!((image.width(),image.height()),(36,18));
+
+        // a square with a square hole in it, in the northwest quarter of the world.
+        let red = Rgb::new(255,0,0);
+        image.fill_polygon(&[
+            vec![(-180.0,90.0),(0.0,90.0),(0.0,0.0),(-180.0,0.0),(-180.0,90.0)],
+            vec![(-120.0,60.0),(-60.0,60.0),(-60.0,30.0),(-120.0,30.0),(-120.0,60.0)]
+        ], red);
+
+        assert_eq!(image.pixel(0,0),Some(&red));
+        assert_eq!(image.pixel(17,8),Some(&red));
+        // inside the hole
+        assert_eq!(image.pixel(9,4),None);
+        // outside the polygon
+        assert_eq!(image.pixel(18,0),None);
+        assert_eq!(image.pixel(0,9),None);
+        let filled = (0..image.height()).flat_map(|row| (0..image.width()).map(move |column| (column,row))).filter(|(column,row)| image.pixel(*column,*row).is_some()).count();
+        assert_eq!(filled,18*9 - 6*3);
+    }
+
+    #[test]
+    fn test_fill_polygon_in_region() {
+        let region = Extent::from_bounds(0.0, 0.0, 40.0, 20.0);
+        let mut image = PreviewImage::new(region, 40);
+        assert_eq!((image.width(),image.height()),(40,20));
+        assert_eq!((image.extent().west(),image.extent().south(),image.extent().east(),image.extent().north()),(0.0,0.0,40.0,20.0));
+
+        // one tile straddles the west edge of the region, the other is entirely outside of it.
+        let red = Rgb::new(255,0,0);
+        let blue = Rgb::new(0,0,255);
+        image.fill_polygon(&[vec![(-10.0,20.0),(10.0,20.0),(10.0,0.0),(-10.0,0.0),(-10.0,20.0)]], red);
+        image.fill_polygon(&[vec![(50.0,20.0),(60.0,20.0),(60.0,0.0),(50.0,0.0),(50.0,20.0)]], blue);
+
+        let colors = (0..image.height()).flat_map(|row| (0..image.width()).map(move |column| (column,row))).filter_map(|(column,row)| image.pixel(column,row)).collect::<Vec<_>>();
+        // only the part of the red tile inside the region is drawn, at one pixel per degree.
+        assert_eq!(colors.len(),10*20);
+        assert!(colors.iter().all(|color| **color == red));
+    }
+
+    #[test]
+    fn test_default_elevation_ramps() {
+        let limits = ElevationLimits::new(-1000.0, 1000.0).expect("limits should be valid");
+        let (water,land) = TileColors::default_elevation_ramps(&limits).expect("ramps should be valid");
+        // a lake above sea level is still drawn as water, and land below sea level is still drawn as land.
+        assert_eq!(water.sample(200.0),water_color());
+        assert_eq!(land.sample(-50.0),Rgb::new(60,140,60));
+        assert_eq!(water.sample(-1000.0),Rgb::new(0,0,96));
+        assert_eq!(land.sample(1000.0),Rgb::new(255,255,255));
+    }
+}
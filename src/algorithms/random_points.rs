@@ -1,4 +1,8 @@
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
 use rand::Rng;
+use clap::ValueEnum;
 
 use crate::utils::extent::Extent;
 use crate::utils::world_shape::WorldShape;
@@ -8,16 +12,78 @@ use crate::progress::ProgressObserver;
 use crate::progress::WatchableIterator;
 use crate::geometry::Point;
 
+/// Specifies which points are added outside of the extent, so that the tiles along the edges can be clipped to it.
+#[derive(Clone,ValueEnum)]
+pub enum BoundaryPoints {
+    /// No points are added. Tiles along the edges will be ragged, and may not reach the edge of the extent.
+    None,
+    /// Four points are added far beyond the corners of the extent. Tiles along the edges reach the extent, but tend to be long and narrow.
+    Corners,
+    /// A ring of jittered points is added just outside each edge, at the same spacing as the rest of the points. Tiles along the edges are closer in shape to the tiles inside.
+    Ring
+}
+
+/// Specifies how the random points are spread across the world.
+#[derive(Clone,ValueEnum)]
+pub enum PointDistribution {
+    /// Points are placed in jittered rows. On a spherical world, the points along each row are spread further apart towards the poles.
+    Grid,
+    /// Points are placed along a spiral which winds around the globe from pole to pole, then jittered. Each point covers about the same area of the globe at every latitude. This requires a spherical world.
+    Fibonacci
+}
+
+/// Catches points which land on top of one that was already generated, such as when jitter is clamped to the edge of the extent. Coincident points make degenerate triangles, which turn into broken tiles.
+pub(crate) struct DuplicatePointFilter {
+    epsilon: f64,
+    // points are bucketed into a grid of epsilon-sized cells, so the nearby points are found in the same or neighboring cells.
+    seen: HashSet<(i64,i64)>,
+    removed: usize
+}
+
+impl DuplicatePointFilter {
+
+    pub(crate) fn new(epsilon: f64) -> Self {
+        Self {
+            epsilon,
+            seen: HashSet::new(),
+            removed: 0
+        }
+    }
+
+    fn cell(&self, x: f64, y: f64) -> (i64,i64) {
+        ((x / self.epsilon).round() as i64,(y / self.epsilon).round() as i64)
+    }
+
+    /// Returns true, and counts the point as removed, if it is within about epsilon of a point already checked. Otherwise, remembers the point.
+    pub(crate) fn is_duplicate(&mut self, x: f64, y: f64) -> bool {
+        let (column,row) = self.cell(x, y);
+        let found = (-1..=1).any(|column_offset| (-1..=1).any(|row_offset| self.seen.contains(&(column + column_offset,row + row_offset))));
+        if found {
+            self.removed += 1;
+        } else {
+            _ = self.seen.insert((column,row));
+        }
+        found
+    }
+
+    pub(crate) const fn removed(&self) -> usize {
+        self.removed
+    }
+}
+
 pub(crate) enum PointGeneratorPhase {
-    NortheastInfinity,
-    SoutheastInfinity,
-    SouthwestInfinity,
-    NorthwestInfinity,
+    Boundary,
     Random{ 
         x: f64, 
         y: f64,
         x_spacing: f64
     },
+    Fibonacci{
+        index: usize,
+        end: usize,
+        count: usize,
+        rotation: f64
+    },
     Done
 }
 
@@ -29,6 +95,10 @@ pub(crate) struct PointGenerator<Random: Rng> {
     world_shape: WorldShape,
     spacing: f64,
     estimated_points: usize,
+    boundary_points: VecDeque<(f64,f64)>,
+    region: Option<Extent>,
+    distribution: PointDistribution,
+    duplicates: DuplicatePointFilter,
     phase: PointGeneratorPhase,
 
 }
@@ -38,22 +108,193 @@ impl<Random: Rng> PointGenerator<Random> {
     // You would think I'd be able to start generating at 0, but that appears to be one pixel below the bottom of the grid on my test.
     // FUTURE: Revisit this, could this have just been bad starting data?
     pub(crate) const START_Y: f64 = 1.0;
+    // points closer than this fraction of the spacing are considered the same. Jitter alone can't bring points this close together, only clamping can.
+    const DUPLICATE_FRACTION: f64 = 0.001;
 
-    pub(crate) fn new(random: Random, extent: Extent, world_shape: WorldShape, estimated_points: usize) -> Self {
+    pub(crate) fn new(random: Random, extent: Extent, world_shape: WorldShape, estimated_points: usize, boundary: &BoundaryPoints) -> Self {
         let density = estimated_points as f64/extent.shaped_area(&world_shape); // number of points per unit square
         let unit_point_count = density.sqrt(); // number of points along a line of unit length
         let spacing = 1.0/unit_point_count; // if there are x points along a unit, then it divides it into x spaces.
-        let phase = PointGeneratorPhase::NortheastInfinity;
+        Self::with_parts(random, extent, world_shape, spacing, estimated_points, boundary)
+    }
 
-        Self {
+    /// Generates rows of points this many degrees apart, instead of calculating the spacing from a number of points. On a spherical world, the points along each row are spread further apart towards the poles.
+    pub(crate) fn with_latitude_spacing(random: Random, extent: Extent, world_shape: WorldShape, latitude_spacing: f64, boundary: &BoundaryPoints) -> Self {
+        let estimated_points = (extent.shaped_area(&world_shape) / (latitude_spacing * latitude_spacing)).ceil() as usize;
+        Self::with_parts(random, extent, world_shape, latitude_spacing, estimated_points, boundary)
+    }
+
+    fn with_parts(random: Random, extent: Extent, world_shape: WorldShape, spacing: f64, estimated_points: usize, boundary: &BoundaryPoints) -> Self {
+        let phase = PointGeneratorPhase::Boundary;
+
+        let mut result = Self {
             random,
             extent,
             world_shape,
             spacing,
             estimated_points,
+            boundary_points: VecDeque::new(),
+            region: None,
+            distribution: PointDistribution::Grid,
+            duplicates: DuplicatePointFilter::new(spacing * Self::DUPLICATE_FRACTION),
             phase
+        };
+
+        result.boundary_points = match boundary {
+            BoundaryPoints::None => VecDeque::new(),
+            BoundaryPoints::Corners => result.corner_points(),
+            BoundaryPoints::Ring => result.ring_points(),
+        };
+
+        result
+
+    }
+
+    // TODO: These points look weird, are they really necessary? See the ring option for an alternative.
+    fn corner_points(&self) -> VecDeque<(f64,f64)> {
+        let width = self.extent.width();
+        let height = self.extent.height();
+        [
+            (width*2.0, height*2.0), // northeast
+            (width*2.0, -height), // southeast
+            (-width, -height), // southwest
+            (-width, height*2.0) // northwest
+        ].into()
+    }
+
+    fn ring_points(&mut self) -> VecDeque<(f64,f64)> {
+        let width = self.extent.width();
+        let height = self.extent.height();
+        // The ring sits half a space outside the edges, so the voronoi vertices between it and the edge tiles lie close to the edge.
+        let offset = self.spacing / 2.0;
+        let mut result = VecDeque::new();
+
+        // south and north edges, which extend past the corners to close the ring.
+        for y in [-offset, height + offset] {
+            // use the spacing at the edge itself, since the ring is off the map and may be past the poles.
+            let x_spacing = match self.world_shape {
+                WorldShape::Cylinder => self.spacing,
+                WorldShape::Sphere => self.spherical_spacing(y.clamp(0.0,height))
+            };
+            for x in Self::steps(-offset, width + offset, x_spacing) {
+                let jittered_x = x + Self::jitter(&mut self.random, x_spacing);
+                result.push_back((jittered_x,y));
+            }
+            result.push_back((width + offset,y));
+        }
+
+        // west and east edges, the corners are already covered above.
+        for x in [-offset, width + offset] {
+            for y in Self::steps(offset, height, self.spacing) {
+                let jittered_y = y + Self::jitter(&mut self.random, self.spacing);
+                result.push_back((x,jittered_y));
+            }
         }
 
+        result
+    }
+
+    fn steps(start: f64, end: f64, spacing: f64) -> Vec<f64> {
+        let count = ((end - start) / spacing).ceil().max(0.0) as usize;
+        (0..count).map(|i| (i as f64).mul_add(spacing, start)).collect()
+    }
+
+    /**
+    Limits the points generated to those near the region, without changing where they are placed. This allows a large world to be generated in pieces that still fit together.
+
+    Points are kept for a margin of several spaces around the region, so the tiles inside it have the same shapes they would have had without the limit.
+    */
+    pub(crate) fn set_region(&mut self, region: &Extent) {
+        const MARGIN_SPACES: f64 = 4.0;
+        // the rows get further apart towards the poles on a sphere, so the margin must be wide enough for the row nearest to them.
+        let x_spacing = match self.world_shape {
+            WorldShape::Cylinder => self.spacing,
+            WorldShape::Sphere => {
+                // spherical_spacing expects an offset from the south of the extent, but the direction of the latitude doesn't matter.
+                let polar_latitude = region.south().abs().max(region.north().abs());
+                self.spherical_spacing(polar_latitude - self.extent.south())
+            }
+        };
+        let x_margin = x_spacing * MARGIN_SPACES;
+        let y_margin = self.spacing * MARGIN_SPACES;
+        self.region = Some(Extent::from_bounds(region.west() - x_margin, region.south() - y_margin, region.east() + x_margin, region.north() + y_margin));
+    }
+
+    pub(crate) fn set_distribution(&mut self, distribution: &PointDistribution) {
+        self.distribution = distribution.clone();
+    }
+
+    fn start_random(&mut self) {
+        if matches!(self.distribution,PointDistribution::Fibonacci) {
+            self.start_fibonacci();
+            return;
+        }
+        let y = Self::START_Y;
+        let x_spacing = self.init_x_spacing(y);
+        self.phase = PointGeneratorPhase::Random{ 
+            x: Self::START_X + (x_spacing/2.0), 
+            y,
+            x_spacing
+        };
+    }
+
+    fn start_fibonacci(&mut self) {
+        // the spiral covers the whole globe, so it needs enough points that the part inside the extent has about the estimated number.
+        let globe_area = Extent::from_bounds(-180.0, -90.0, 180.0, 90.0).shaped_area(&WorldShape::Sphere);
+        let extent_area = self.extent.shaped_area(&WorldShape::Sphere);
+        let count = (self.estimated_points as f64 * (globe_area / extent_area)).ceil() as usize;
+
+        // the spiral runs from north to south, so only the part between the latitudes of the extent needs to be followed.
+        let index_at = |latitude: f64| (1.0 - latitude.clamp(-90.0,90.0).to_radians().sin()).mul_add(count as f64 / 2.0, -0.5);
+        let start = index_at(self.extent.north()).ceil().max(0.0) as usize;
+        let end = ((index_at(self.extent.south()).floor() + 1.0).max(0.0) as usize).min(count);
+
+        // turning the spiral keeps the points from landing in the same places for every seed.
+        let rotation = self.random.gen_range(0.0..360.0);
+        self.phase = PointGeneratorPhase::Fibonacci {
+            index: start,
+            end,
+            count,
+            rotation
+        };
+    }
+
+    fn next_fibonacci_offset(&mut self, mut index: usize, end: usize, count: usize, rotation: f64) -> Option<(f64,f64)> {
+        // the golden angle, in degrees, spaces each point as far as possible from the ones before it.
+        let golden_angle = 180.0 * (3.0 - 5.0_f64.sqrt());
+        while index < end {
+            let y = (1.0 - ((2 * index + 1) as f64 / count as f64)).asin().to_degrees() - self.extent.south();
+            let x = (index as f64).mul_add(golden_angle, rotation).rem_euclid(360.0);
+            index += 1;
+            if (x <= self.extent.width()) && (0.0..=self.extent.height()).contains(&y) {
+                self.phase = PointGeneratorPhase::Fibonacci {
+                    index,
+                    end,
+                    count,
+                    rotation
+                };
+                // the spacing grows without limit near the poles, but the points can't be moved further than once around the globe.
+                let x_jitter = Self::jitter(&mut self.random, self.spherical_spacing(y).min(360.0));
+                let y_jitter = Self::jitter(&mut self.random, self.spacing);
+                let jittered_x = x + x_jitter;
+                // on a world that goes all the way around, points jittered past the edge belong on the other side instead of piling up against it.
+                let jittered_x = if self.extent.width() >= 360.0 {
+                    jittered_x.rem_euclid(360.0)
+                } else {
+                    jittered_x.clamp(Self::START_X,self.extent.width())
+                };
+                return Some((jittered_x,(y + y_jitter).clamp(Self::START_Y,self.extent.height())))
+            }
+        }
+        self.phase = PointGeneratorPhase::Done;
+        None
+    }
+
+    fn init_x_spacing(&self, y: f64) -> f64 {
+        match self.world_shape {
+            WorldShape::Cylinder => self.spacing,
+            WorldShape::Sphere => self.spherical_spacing(y)
+        }
     }
 
     pub(crate) fn make_point(&self, x: f64, y: f64) -> Result<Point,CommandError> {
@@ -120,49 +361,36 @@ impl<Random: Rng> PointGenerator<Random> {
         ratio * self.spacing
     }
 
+    /// The number of points which were dropped because they coincided with earlier points.
+    pub(crate) const fn duplicates_removed(&self) -> usize {
+        self.duplicates.removed()
+    }
 
-}
-
-impl<Random: Rng> Iterator for PointGenerator<Random> {
-
-    type Item = Result<Point,CommandError>;
+    // The points are generated as offsets from the southwest corner of the extent, so they can be checked without creating geometries.
+    fn next_offset(&mut self) -> Option<(f64,f64)> {
+        loop {
+            let (x,y) = self.next_unlimited_offset()?;
+            let in_region = self.region.as_ref().is_none_or(|region| {
+                // boundary points are always outside of the extent, and are kept no matter where the region is.
+                let is_boundary = !(0.0..=self.extent.width()).contains(&x) || !(0.0..=self.extent.height()).contains(&y);
+                is_boundary || region.contains_tuple(self.extent.west() + x, self.extent.south() + y)
+            });
+            if in_region && !self.duplicates.is_duplicate(x, y) {
+                return Some((x,y))
+            }
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_unlimited_offset(&mut self) -> Option<(f64,f64)> {
 
         // Randomizing algorithms borrowed from AFMG with many modifications
 
-        macro_rules! init_x_spacing {
-            ($y: ident) => {
-                match self.world_shape {
-                    WorldShape::Cylinder => self.spacing,
-                    WorldShape::Sphere => self.spherical_spacing($y)
-                }
-            };
-        }
-
-
         match &self.phase { 
-            PointGeneratorPhase::NortheastInfinity => {
-                self.phase = PointGeneratorPhase::SoutheastInfinity;
-                Some(self.make_point(self.extent.width()*2.0, self.extent.height()*2.0))
-            },
-            PointGeneratorPhase::SoutheastInfinity => {
-                self.phase = PointGeneratorPhase::SouthwestInfinity;
-                Some(self.make_point(self.extent.width()*2.0, -self.extent.height()))
-            },
-            PointGeneratorPhase::SouthwestInfinity => {
-                self.phase = PointGeneratorPhase::NorthwestInfinity;
-                Some(self.make_point(-self.extent.width(), -self.extent.height()))
-            },
-            PointGeneratorPhase::NorthwestInfinity => {
-                let y = Self::START_Y;
-                let x_spacing = init_x_spacing!(y);
-                self.phase = PointGeneratorPhase::Random{ 
-                    x: Self::START_X + (x_spacing/2.0), 
-                    y,
-                    x_spacing
-                };
-                Some(self.make_point(-self.extent.width(), self.extent.height()*2.0))
+            PointGeneratorPhase::Boundary => if let Some(point) = self.boundary_points.pop_front() {
+                Some(point)
+            } else {
+                self.start_random();
+                self.next_unlimited_offset()
             },
             PointGeneratorPhase::Random{x, y, x_spacing} => if y < &self.extent.height() {
                 let y_spacing = self.spacing;
@@ -180,29 +408,40 @@ impl<Random: Rng> Iterator for PointGenerator<Random> {
                         y: *y,
                         x_spacing: *x_spacing
                     };
-                    Some(self.make_point(jittered_x,jittered_y))
+                    Some((jittered_x,jittered_y))
                 } else {
 
                     let y = y + y_spacing;
-                    let next_x_spacing = init_x_spacing!(y);
+                    let next_x_spacing = self.init_x_spacing(y);
     
                     self.phase = PointGeneratorPhase::Random{
                         x: Self::START_X + (next_x_spacing/2.0), 
                         y,
                         x_spacing: next_x_spacing
                     };
-                    self.next()
+                    self.next_unlimited_offset()
                 }
             
             } else {
                 self.phase = PointGeneratorPhase::Done;
-                self.next()
+                self.next_unlimited_offset()
             },
+            &PointGeneratorPhase::Fibonacci{index, end, count, rotation} => self.next_fibonacci_offset(index, end, count, rotation),
             PointGeneratorPhase::Done => None,
         }
 
     }
 
+}
+
+impl<Random: Rng> Iterator for PointGenerator<Random> {
+
+    type Item = Result<Point,CommandError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_offset().map(|(x,y)| self.make_point(x,y))
+    }
+
     fn size_hint(&self) -> (usize, Option<usize>) {
         // size_hint is supposed to talk about how many remaining, not a range from start to end
         // but this is still fair, because I don't know how many are remaining and it's too difficult
@@ -227,3 +466,191 @@ pub(crate) fn load_points_layer<Generator: Iterator<Item=Result<Point,CommandErr
 }
 
 
+
+#[cfg(test)]
+mod test {
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::BoundaryPoints;
+    use super::PointDistribution;
+    use super::PointGenerator;
+    use super::DuplicatePointFilter;
+    use crate::utils::extent::Extent;
+    use crate::utils::world_shape::WorldShape;
+
+    fn generate_offsets(boundary: &BoundaryPoints) -> (f64,Vec<(f64,f64)>) {
+        generate_region_offsets(boundary, None)
+    }
+
+    fn generate_region_offsets(boundary: &BoundaryPoints, region: Option<&Extent>) -> (f64,Vec<(f64,f64)>) {
+        let extent = Extent::from_bounds(-10.0, -5.0, 10.0, 5.0);
+        let mut generator = PointGenerator::new(StdRng::seed_from_u64(7), extent, WorldShape::Cylinder, 200, boundary);
+        if let Some(region) = region {
+            generator.set_region(region);
+        }
+        let spacing = generator.spacing;
+        let mut result = Vec::new();
+        while let Some(offset) = generator.next_offset() {
+            result.push(offset);
+        }
+        (spacing,result)
+    }
+
+    fn is_outside((x,y): (f64,f64)) -> bool {
+        !(0.0..=20.0).contains(&x) || !(0.0..=10.0).contains(&y)
+    }
+
+    fn assert_no_gaps(mut positions: Vec<f64>, spacing: f64) {
+        positions.sort_by(f64::total_cmp);
+        assert!(positions.windows(2).all(|pair| (pair[1] - pair[0]) < (spacing * 2.0)));
+    }
+
+    #[test]
+    fn test_boundary_points() {
+
+        // without boundary points, every site is inside the extent, so none of them are thrown away by clipping.
+        let (_,none) = generate_offsets(&BoundaryPoints::None);
+        assert!(none.len() > 100);
+        assert!(!none.iter().copied().any(is_outside));
+
+        let (_,corners) = generate_offsets(&BoundaryPoints::Corners);
+        let corner_points: Vec<_> = corners.iter().copied().filter(|point| is_outside(*point)).collect();
+        assert_eq!(corner_points,vec![(40.0,20.0),(40.0,-10.0),(-20.0,-10.0),(-20.0,20.0)]);
+        // the boundary points are drawn before the random ones, so they don't change the rest of the points.
+        assert_eq!(corners[4..],none[..]);
+
+        let (spacing,ring) = generate_offsets(&BoundaryPoints::Ring);
+        let ring_points: Vec<_> = ring.iter().copied().filter(|point| is_outside(*point)).collect();
+        assert!(ring_points.len() > corner_points.len());
+        // every ring point is close to the edge, so the edge tiles are not stretched out towards far away points.
+        for (x,y) in &ring_points {
+            let distance = (-x).max(x - 20.0).max(-y).max(y - 10.0);
+            assert!(distance > 0.0 && distance <= spacing, "({x},{y}) is not just outside the extent");
+        }
+        // and there are no large gaps along any edge.
+        assert_no_gaps(ring_points.iter().filter(|(_,y)| y < &0.0).map(|(x,_)| *x).collect(), spacing);
+        assert_no_gaps(ring_points.iter().filter(|(_,y)| y > &10.0).map(|(x,_)| *x).collect(), spacing);
+        assert_no_gaps(ring_points.iter().filter(|(x,_)| x < &0.0).map(|(_,y)| *y).collect(), spacing);
+        assert_no_gaps(ring_points.iter().filter(|(x,_)| x > &20.0).map(|(_,y)| *y).collect(), spacing);
+
+    }
+
+    #[test]
+    fn test_region() {
+        let region = Extent::from_bounds(0.0, -5.0, 5.0, 0.0);
+        // offsets are from the southwest corner of the extent at (-10,-5)
+        let in_region = |(x,y): &(f64,f64)| region.contains_tuple(x - 10.0, y - 5.0);
+
+        let (spacing,full) = generate_offsets(&BoundaryPoints::Corners);
+        let (_,limited) = generate_region_offsets(&BoundaryPoints::Corners, Some(&region));
+
+        assert!(limited.len() < full.len());
+        // the sites of the tiles which will be generated are the same as those in the full world
+        let full_sites: Vec<_> = full.iter().filter(|point| in_region(point)).collect();
+        let limited_sites: Vec<_> = limited.iter().filter(|point| in_region(point)).collect();
+        assert!(!limited_sites.is_empty());
+        assert_eq!(full_sites,limited_sites);
+        // and every other point is either a boundary point or within the margin around the region
+        let margin = region.expanded_by(spacing * 4.0);
+        for (x,y) in &limited {
+            let is_boundary = !(0.0..=20.0).contains(x) || !(0.0..=10.0).contains(y);
+            assert!(is_boundary || margin.contains_tuple(x - 10.0, y - 5.0), "({x},{y}) is too far from the region");
+        }
+
+    }
+
+    #[test]
+    fn test_latitude_spacing() {
+        let extent = Extent::from_bounds(-180.0, -90.0, 180.0, 90.0);
+        let mut generator = PointGenerator::with_latitude_spacing(StdRng::seed_from_u64(7), extent, WorldShape::Sphere, 10.0, &BoundaryPoints::None);
+        let mut rows = [0_usize; 18];
+        while let Some((_,y)) = generator.next_offset() {
+            // the rows start at START_Y, and the jitter never moves a point halfway to the next row.
+            let row = (y - PointGenerator::<StdRng>::START_Y) / 10.0;
+            let nearest_row = row.round();
+            assert!((row - nearest_row).abs() < 0.5, "{y} is not near a row");
+            rows[nearest_row as usize] += 1;
+        }
+
+        // every row has points, and the number of points follows the length of the line of latitude.
+        assert!(rows.iter().all(|count| count > &0));
+        assert_eq!(rows[9],36);
+        assert!(rows[0] < rows[4]);
+        assert!(rows[4] < rows[9]);
+        assert!(rows[17] < rows[13]);
+        assert!(rows[13] < rows[9]);
+
+    }
+
+    #[test]
+    fn test_fibonacci_distribution() {
+        // counts the points in bands of 30 degrees latitude
+        let band_counts = |mut generator: PointGenerator<StdRng>| {
+            let mut counts = [0_usize; 6];
+            while let Some((_,y)) = generator.next_offset() {
+                counts[((y / 30.0) as usize).min(5)] += 1;
+            }
+            counts
+        };
+        // how much the number of points for each unit of area on the globe varies between the bands, relative to the average
+        let variation = |counts: [usize; 6]| {
+            let densities: Vec<f64> = counts.iter().enumerate().map(|(band,count)| {
+                let south = (band as f64).mul_add(30.0, -90.0);
+                *count as f64 / ((south + 30.0).to_radians().sin() - south.to_radians().sin())
+            }).collect();
+            let mean = densities.iter().sum::<f64>() / densities.len() as f64;
+            let variance = densities.iter().map(|density| (density - mean).powi(2)).sum::<f64>() / densities.len() as f64;
+            variance.sqrt() / mean
+        };
+
+        let extent = Extent::from_bounds(-180.0, -90.0, 180.0, 90.0);
+        let grid = PointGenerator::new(StdRng::seed_from_u64(7), extent.clone(), WorldShape::Cylinder, 5000, &BoundaryPoints::None);
+        let mut fibonacci = PointGenerator::new(StdRng::seed_from_u64(7), extent, WorldShape::Sphere, 5000, &BoundaryPoints::None);
+        fibonacci.set_distribution(&PointDistribution::Fibonacci);
+
+        let grid_counts = band_counts(grid);
+        let mut fibonacci_offsets = Vec::new();
+        while let Some(offset) = fibonacci.next_offset() {
+            fibonacci_offsets.push(offset);
+        }
+        let mut fibonacci_counts = [0_usize; 6];
+        for (_,y) in &fibonacci_offsets {
+            fibonacci_counts[((y / 30.0) as usize).min(5)] += 1;
+        }
+        let fibonacci_total: usize = fibonacci_counts.iter().sum();
+        assert!((4500..=5500).contains(&fibonacci_total), "{fibonacci_total} points is too far from the estimate");
+
+        // the world goes all the way around, so the points near the edges wrap to the other side instead of being clamped to it.
+        assert!(fibonacci_offsets.iter().all(|(x,_)| (0.0..360.0).contains(x)));
+        assert!(!fibonacci_offsets.iter().any(|(x,_)| (x.to_bits() == 0.0_f64.to_bits()) || (x.to_bits() == 360.0_f64.to_bits())));
+
+        // the points of the rows crowd together near the poles, while the points of the spiral do not.
+        let grid_variation = variation(grid_counts);
+        let fibonacci_variation = variation(fibonacci_counts);
+        assert!(fibonacci_variation * 10.0 < grid_variation, "{fibonacci_variation} is not much less than {grid_variation}");
+
+    }
+
+    #[test]
+    fn test_duplicate_points() {
+        let mut filter = DuplicatePointFilter::new(0.001);
+        let points = [(0.0,0.0),(1.0,1.0),(0.0,0.0),(1.0004,0.9996),(0.5,0.5),(0.0,1.0),(0.0005,1.0)];
+        let kept: Vec<_> = points.into_iter().filter(|(x,y)| !filter.is_duplicate(*x, *y)).collect();
+        assert_eq!(kept,vec![(0.0,0.0),(1.0,1.0),(0.5,0.5),(0.0,1.0)]);
+        assert_eq!(filter.removed(),3);
+
+        // the generator clamps jittered points to the edges, but still never returns the same point twice.
+        let extent = Extent::from_bounds(-10.0, -5.0, 10.0, 5.0);
+        let mut generator = PointGenerator::new(StdRng::seed_from_u64(7), extent, WorldShape::Cylinder, 2000, &BoundaryPoints::None);
+        let mut offsets = Vec::new();
+        while let Some(offset) = generator.next_offset() {
+            offsets.push((offset.0.to_bits(),offset.1.to_bits()));
+        }
+        let count = offsets.len();
+        offsets.sort_unstable();
+        offsets.dedup();
+        assert_eq!(offsets.len(),count);
+    }
+}
@@ -0,0 +1,111 @@
+use serde_json::json;
+use serde_json::Value;
+
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::entities::EntityIndex;
+use crate::typed_map::fields::IdRef;
+use crate::world_map::water_layers::RiverForExport;
+use crate::world_map::water_layers::RiverSchema;
+
+/// A river segment with the properties a web map needs to draw it.
+#[derive(Debug)]
+pub(crate) struct StyledRiverSegment {
+    fid: IdRef,
+    /// The most water the segment carries, at either end.
+    flow: f64,
+    /// The suggested line width, in pixels, which grows with the square root of the flow.
+    width: f64,
+    /// The Strahler stream order: 1 for segments with nothing upstream, increasing by one where two streams of the same order meet.
+    strahler: i32,
+    lines: Vec<Vec<(f64,f64)>>
+}
+
+/// Adds style hints to the river segments, sorted so the smallest rivers come first. Web maps draw features in order, so the larger rivers are drawn over the smaller ones where they meet.
+pub(crate) fn style_river_segments<Progress: ProgressObserver>(segments: EntityIndex<RiverSchema,RiverForExport>, max_width: f64, progress: &mut Progress) -> Vec<StyledRiverSegment> {
+
+    let max_flow = segments.iter().map(|(_,segment)| segment.peak_flow()).fold(0.0,f64::max);
+
+    let mut styled: Vec<StyledRiverSegment> = segments.into_iter().watch(progress,"Styling rivers.","Rivers styled.").map(|(fid,segment)| {
+        let flow = segment.peak_flow();
+        let width = if max_flow > 0.0 {
+            (flow / max_flow).sqrt() * max_width
+        } else {
+            0.0
+        };
+        StyledRiverSegment {
+            fid,
+            flow,
+            width,
+            strahler: *segment.strahler(),
+            lines: segment.lines().clone()
+        }
+    }).collect();
+
+    styled.sort_by(|a,b| a.flow.total_cmp(&b.flow).then(a.strahler.cmp(&b.strahler)).then(a.fid.cmp(&b.fid)));
+
+    styled
+
+}
+
+/// Writes the styled segments as a GeoJSON feature collection, in the order given.
+pub(crate) fn write_river_geojson(segments: &[StyledRiverSegment]) -> Result<String,CommandError> {
+    let features: Vec<Value> = segments.iter().map(|segment| json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "MultiLineString",
+            "coordinates": segment.lines.iter().map(|line| line.iter().map(|(x,y)| [*x,*y]).collect::<Vec<_>>()).collect::<Vec<_>>()
+        },
+        "properties": {
+            "fid": segment.fid.to_inner(),
+            "flow": segment.flow,
+            "width": segment.width,
+            "strahler": segment.strahler
+        }
+    })).collect();
+
+    Ok(serde_json::to_string(&json!({
+        "type": "FeatureCollection",
+        "features": features
+    }))?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::style_river_segments;
+    use super::write_river_geojson;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::world_map::water_layers::RiverForExport;
+    use crate::world_map::water_layers::RiverSchema;
+
+    #[test]
+    fn test_river_geojson() {
+        let segment = |fid: u64, flow: f64, strahler: i32| {
+            (IdRef::new(fid),RiverForExport::new(flow, flow, strahler, vec![vec![(fid as f64,0.0),(fid as f64,1.0)]]))
+        };
+        // two sources meet, then a smaller stream joins before the river reaches the sea.
+        let segments: EntityIndex<RiverSchema,RiverForExport> = [
+            segment(1, 4.0, 1),
+            segment(2, 9.0, 1),
+            segment(3, 13.0, 2),
+            segment(4, 16.0, 2),
+            segment(5, 1.0, 1),
+        ].into_iter().collect();
+
+        let styled = style_river_segments(segments, 8.0, &mut ());
+        let json: serde_json::Value = serde_json::from_str(&write_river_geojson(&styled).unwrap()).unwrap();
+
+        assert_eq!(json["type"],"FeatureCollection");
+        let features = json["features"].as_array().unwrap();
+        let property = |name: &str| features.iter().map(|feature| feature["properties"][name].clone()).collect::<Vec<_>>();
+
+        // the smallest rivers come first, so the largest are drawn on top.
+        assert_eq!(property("fid"),vec![5,1,2,3,4]);
+        assert_eq!(property("strahler"),vec![1,1,1,2,2]);
+        assert_eq!(property("width"),vec![2.0,4.0,6.0,13.0f64.sqrt() * 2.0,8.0]);
+        assert_eq!(features[4]["geometry"]["type"],"MultiLineString");
+        assert_eq!(features[4]["geometry"]["coordinates"],serde_json::json!([[[4.0,0.0],[4.0,1.0]]]));
+    }
+}
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 use crate::world_map::tile_layer::TileForRiverConnect;
+use crate::world_map::tile_layer::TileForRiverLength;
 use crate::world_map::tile_layer::TileLayer;
 use crate::world_map::fields::RiverSegmentTo;
 use crate::world_map::fields::RiverSegmentFrom;
 use crate::world_map::water_layers::NewRiver;
+use crate::world_map::water_layers::RiverForLength;
 use crate::algorithms::beziers::bezierify_points_with_phantoms;
 use crate::algorithms::beziers::find_curve_making_point;
 use crate::errors::CommandError;
@@ -21,6 +24,11 @@ use crate::world_map::tile_layer::TileSchema;
 use crate::world_map::tile_layer::TileFeature;
 use crate::utils::coordinates::Coordinates;
 use crate::typed_map::fields::IdRef;
+use crate::typed_map::entities::EntityIndex;
+use crate::world_map::fields::NeighborAndDirection;
+use crate::commands::DeltaBranchingArg;
+use crate::algorithms::water_flow::flow_portions;
+use indexmap::IndexMap;
 
 #[derive(Debug)]
 pub(crate) struct RiverSegment {
@@ -46,14 +54,18 @@ fn find_flowingest_tile(list: &Vec<Rc<RiverSegment>>) -> (Rc<RiverSegment>,f64)
     (chosen_segment.expect("Whoever called this function passed an empty list.").clone(),total_flow)
 }
 
-pub(crate) fn generate_water_rivers<Progress: ProgressObserver>(target: &mut WorldMapTransaction, bezier_scale: &BezierScaleArg, overwrite_layer: &OverwriteRiversArg, progress: &mut Progress) -> Result<(),CommandError> {
+pub(crate) fn generate_water_rivers<Progress: ProgressObserver>(target: &mut WorldMapTransaction, bezier_scale: &BezierScaleArg, delta_branching: &DeltaBranchingArg, overwrite_layer: &OverwriteRiversArg, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let world_shape = target.edit_properties_layer()?.get_world_shape()?;
+    let flow_concentration = target.edit_properties_layer()?.get_flow_concentration()?;
 
     let mut tiles = target.edit_tile_layer()?;
     let extents = tiles.get_extent()?;
 
     let mut segments = Vec::new();
+    let mut upstream_segments = Vec::new();
 
-    let segment_clean_queue = gen_water_rivers_find_segments(&mut tiles, progress)?;
+    let segment_clean_queue = gen_water_rivers_find_segments(&mut tiles, delta_branching, flow_concentration, progress)?;
 
     let CleanedAndIndexedSegments {tile_from_index, tile_to_index, segment_draw_queue} = generate_water_rivers_clean_and_index(segment_clean_queue, progress);
 
@@ -72,6 +84,7 @@ pub(crate) fn generate_water_rivers<Progress: ProgressObserver>(target: &mut Wor
         let from_tile = tiles.try_feature_by_id(&from_tile_id)?;
         let start_point = from_tile.site()?;
         let to_tile_id = segment.to.clone();
+        let from_lake = from_tile.lake_id()?;
 
 
 
@@ -84,7 +97,6 @@ pub(crate) fn generate_water_rivers<Progress: ProgressObserver>(target: &mut Wor
                 };
 
                 let to_tile = tiles.try_feature_by_id(segment_to)?;
-                let from_lake = from_tile.lake_id()?;
                 let to_lake = to_tile.lake_id()?;
 
                 if from_lake.is_none() || to_lake.is_none() || from_lake != to_lake {
@@ -129,7 +141,7 @@ pub(crate) fn generate_water_rivers<Progress: ProgressObserver>(target: &mut Wor
                         }
                     };
 
-                    Some((to_tile_id,previous_point,end_point,next_point))
+                    Some((to_tile_id,to_lake,previous_point,end_point,next_point))
 
                 } else {
                     None
@@ -142,24 +154,34 @@ pub(crate) fn generate_water_rivers<Progress: ProgressObserver>(target: &mut Wor
                 let previous_point = generate_previous_segment_point(previous_tile, &tiles, &end_point, &start_point)?;
                 let next_point = find_curve_making_point(&start_point,&end_point);
                 
-                Some((to_tile_id,previous_point,end_point,next_point))
+                Some((to_tile_id,None,previous_point,end_point,next_point))
             },
             
         };
 
         #[allow(clippy::shadow_unrelated)] // to_tile_id *is* related
-        if let Some((to_tile_id,previous_point,end_point,next_point)) = new_river_data {
+        if let Some((to_tile_id,to_lake,previous_point,end_point,next_point)) = new_river_data {
             // create the bezier
             let line = bezierify_points_with_phantoms(Some(&previous_point), &[start_point,end_point], Some(&next_point), bezier_scale.bezier_scale)?;
             let lines = Coordinates::clip_point_vec_across_antimeridian(line,&extents)?;
+            upstream_segments.push(UpstreamSegment {
+                from_tile_id: from_tile_id.clone(),
+                to_tile_id: to_tile_id.clone(),
+                from_lake: from_lake.clone(),
+                to_lake: to_lake.clone(),
+                distance: start_point.shaped_distance(&end_point,&world_shape)
+            });
             segments.push((NewRiver {
                 from_tile_id,
                 from_type,
                 from_flow,
                 to_tile_id,
                 to_type,
-                to_flow
-            },lines));
+                to_flow,
+                // these are calculated once all of the segments are known
+                length: 0.0,
+                strahler: 0
+            },lines,from_lake,to_lake));
 
         }
 
@@ -167,17 +189,206 @@ pub(crate) fn generate_water_rivers<Progress: ProgressObserver>(target: &mut Wor
 
     }
 
+    let lengths = find_river_lengths(&upstream_segments);
+    let strahler_orders = find_strahler_orders(&upstream_segments);
+
     let mut segments_layer = target.create_rivers_layer(overwrite_layer)?;
 
     
-    for (river,segment) in segments.into_iter().watch(progress,"Writing rivers.","Rivers written.") {
-        _ = segments_layer.add_segment(&river,segment)?;
+    let mut lake_connections = Vec::new();
+    
+    for (((mut river,segment,from_lake,to_lake),length),strahler) in segments.into_iter().zip(lengths).zip(strahler_orders).watch(progress,"Writing rivers.","Rivers written.") {
+        river.length = length;
+        river.strahler = strahler;
+        let segment_id = segments_layer.add_segment(&river,segment)?;
+        lake_connections.push(LakeConnection {
+            segment_id,
+            from_lake,
+            to_lake,
+            flow: river.to_flow
+        });
+    }
+
+    let lake_segments = find_lake_segments(lake_connections);
+
+    if !lake_segments.is_empty() {
+        let lakes_layer = target.edit_lakes_layer()?;
+
+        for (lake_id,(inlets,outlet)) in lake_segments.into_iter().watch(progress,"Connecting lakes to rivers.","Lakes connected.") {
+            let mut feature = lakes_layer.try_feature_by_id(&lake_id)?;
+            feature.set_inlet_segment_ids(&inlets)?;
+            feature.set_outlet_segment_id(&outlet)?;
+            lakes_layer.update_feature(feature)?;
+        }
     }
 
     Ok(())
 
 }
 
+/// Recalculates the length of the river segments already in the rivers layer, without regenerating them.
+pub(crate) fn calculate_river_lengths<Progress: ProgressObserver>(target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let world_shape = target.edit_properties_layer()?.get_world_shape()?;
+
+    let tiles = target.edit_tile_layer()?;
+    let extents = tiles.get_extent()?;
+
+    let mut rivers = target.edit_rivers_layer()?;
+
+    let mut fids = Vec::new();
+    let mut upstream_segments = Vec::new();
+
+    for river in rivers.read_features().into_entities::<RiverForLength>().watch(progress,"Reading rivers.","Rivers read.") {
+        let (fid,river) = river?;
+        let from_tile = tiles.try_entity_by_id::<TileForRiverLength>(river.from_tile_id())?;
+
+        let (end_point,to_lake) = match river.to_tile_id() {
+            Neighbor::Tile(to_tile_id) => {
+                let to_tile = tiles.try_entity_by_id::<TileForRiverLength>(to_tile_id)?;
+                (to_tile.site().clone(),to_tile.lake_id().clone())
+            },
+            Neighbor::CrossMap(to_tile_id, _) => {
+                let to_tile = tiles.try_entity_by_id::<TileForRiverLength>(to_tile_id)?;
+                (to_tile.site().across_antimeridian(from_tile.site()),to_tile.lake_id().clone())
+            },
+            Neighbor::OffMap(edge) => (from_tile.site().to_edge(&extents,edge)?,None),
+        };
+
+        upstream_segments.push(UpstreamSegment {
+            from_tile_id: river.from_tile_id().clone(),
+            to_tile_id: river.to_tile_id().clone(),
+            from_lake: from_tile.lake_id().clone(),
+            to_lake,
+            distance: from_tile.site().shaped_distance(&end_point,&world_shape)
+        });
+        fids.push(fid);
+    }
+
+    let lengths: BTreeMap<_,_> = fids.into_iter().zip(find_river_lengths(&upstream_segments)).collect();
+
+    rivers.update_features_by_id(lengths, progress, |feature,length| feature.set_length(&length))
+
+}
+
+struct UpstreamSegment {
+    from_tile_id: IdRef,
+    to_tile_id: Neighbor,
+    from_lake: Option<IdRef>,
+    to_lake: Option<IdRef>,
+    distance: f64
+}
+
+/// Finds the length of each segment, from the source of the river to the end of the segment. Where rivers join, the longest one upstream is followed. Segments leaving a lake continue from the longest river flowing into it, but the distance across the lake isn't counted. Each branch of a delta gets its own length.
+fn find_river_lengths(segments: &[UpstreamSegment]) -> Vec<f64> {
+    follow_upstream(segments, |segment,upstream| {
+        upstream.iter().copied().fold(0.0,f64::max) + segment.distance
+    }).into_iter().map(Option::unwrap_or_default).collect()
+}
+
+/// Finds the Strahler stream order of each segment. Sources are 1, and where rivers join the order is the highest order flowing in, plus one if more than one river has that order. Segments leaving a lake are treated as a confluence of the rivers flowing into it. The branches of a delta keep the order of the river they split from, since the Strahler order doesn't describe branching.
+fn find_strahler_orders(segments: &[UpstreamSegment]) -> Vec<i32> {
+    follow_upstream(segments, |_,upstream| {
+        let highest = upstream.iter().copied().max().unwrap_or_default();
+        match upstream.iter().filter(|order| **order == highest).count() {
+            0 => 1,
+            1 => highest,
+            _ => highest + 1
+        }
+    }).into_iter().map(|order| order.unwrap_or(1)).collect()
+}
+
+/// Calculates a value for each segment from the values of the segments flowing into it, which are calculated first. Segments leaving a lake are fed by every segment flowing into that lake.
+fn follow_upstream<Value: Copy, Calculate: FnMut(&UpstreamSegment,&[Value]) -> Value>(segments: &[UpstreamSegment], mut calculate: Calculate) -> Vec<Option<Value>> {
+
+    let mut tile_inflows: HashMap<&IdRef,Vec<usize>> = HashMap::new();
+    let mut lake_inflows: HashMap<&IdRef,Vec<usize>> = HashMap::new();
+    for (index,segment) in segments.iter().enumerate() {
+        match &segment.to_tile_id {
+            Neighbor::Tile(to_tile_id) | Neighbor::CrossMap(to_tile_id, _) => tile_inflows.entry(to_tile_id).or_default().push(index),
+            Neighbor::OffMap(_) => (),
+        }
+        if let Some(to_lake) = &segment.to_lake {
+            if segment.from_lake.as_ref() != Some(to_lake) {
+                lake_inflows.entry(to_lake).or_default().push(index)
+            }
+        }
+    }
+
+    let inflows = |index: usize| {
+        let segment = &segments[index];
+        let from_tile = tile_inflows.get(&segment.from_tile_id).into_iter().flatten();
+        let from_lake = segment.from_lake.as_ref().and_then(|lake| lake_inflows.get(lake)).into_iter().flatten();
+        // a segment ending on the tile the lake drains from is an inflow to both, but must only be counted once.
+        let mut result: Vec<usize> = from_tile.chain(from_lake).copied().filter(|inflow| *inflow != index).collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+    };
+
+    let mut values: Vec<Option<Value>> = vec![None; segments.len()];
+    // marks segments waiting on their inflows, so a loop in the rivers can't be followed forever.
+    let mut visiting = vec![false; segments.len()];
+
+    for start in 0..segments.len() {
+        // walk upstream with a stack instead of recursion, as rivers can be much longer than the call stack.
+        let mut stack = vec![start];
+        while let Some(&current) = stack.last() {
+            if values[current].is_some() {
+                _ = stack.pop();
+                continue;
+            }
+            visiting[current] = true;
+
+            let current_inflows = inflows(current);
+            let pending: Vec<usize> = current_inflows.iter().copied().filter(|inflow| values[*inflow].is_none() && !visiting[*inflow]).collect();
+            if pending.is_empty() {
+                let upstream: Vec<Value> = current_inflows.into_iter().filter_map(|inflow| values[inflow]).collect();
+                values[current] = Some(calculate(&segments[current],&upstream));
+                visiting[current] = false;
+                _ = stack.pop();
+            } else {
+                stack.extend(pending);
+            }
+        }
+    }
+
+    values
+}
+
+struct LakeConnection {
+    segment_id: IdRef,
+    from_lake: Option<IdRef>,
+    to_lake: Option<IdRef>,
+    flow: f64
+}
+
+/// Finds the river segments flowing into each lake, and the one which drains it. If there is more than one segment leaving the lake, the one with the highest flow is the outlet.
+fn find_lake_segments(connections: Vec<LakeConnection>) -> IndexMap<IdRef,(Vec<IdRef>,Option<IdRef>)> {
+    let mut result: IndexMap<IdRef,(Vec<IdRef>,Option<IdRef>)> = IndexMap::new();
+    let mut outlet_flows = HashMap::new();
+
+    for connection in connections {
+        if connection.from_lake == connection.to_lake {
+            continue;
+        }
+
+        if let Some(to_lake) = connection.to_lake {
+            result.entry(to_lake).or_default().0.push(connection.segment_id.clone());
+        }
+
+        if let Some(from_lake) = connection.from_lake {
+            let outlet_flow = outlet_flows.entry(from_lake.clone()).or_insert(f64::NEG_INFINITY);
+            if connection.flow > *outlet_flow {
+                *outlet_flow = connection.flow;
+                result.entry(from_lake).or_default().1 = Some(connection.segment_id);
+            }
+        }
+    }
+
+    result
+}
+
 fn generate_previous_segment_point<'feature>(previous_tile: Option<IdRef>, tiles: &MapLayer<'_, 'feature, TileSchema, TileFeature<'feature>>, end_point: &Coordinates, start_point: &Coordinates) -> Result<Coordinates, CommandError> {
     Ok(if let Some(x) = previous_tile {
         tiles.try_feature_by_id(&x)?.site()?
@@ -366,17 +577,32 @@ fn generate_water_rivers_clean_and_index<Progress: ProgressObserver>(segment_cle
 
 }
 
-pub(crate) fn gen_water_rivers_find_segments<Progress: ProgressObserver>(tiles: &mut TileLayer<'_,'_>, progress: &mut Progress) -> Result<Vec<Rc<RiverSegment>>, CommandError> {
+pub(crate) fn gen_water_rivers_find_segments<Progress: ProgressObserver>(tiles: &mut TileLayer<'_,'_>, delta_branching: &DeltaBranchingArg, flow_concentration: f64, progress: &mut Progress) -> Result<Vec<Rc<RiverSegment>>, CommandError> {
+
+    let tile_map = tiles.read_features().into_entities_index::<_,TileForRiverConnect>(progress)?;
+
+    find_segments(&tile_map, delta_branching, flow_concentration, progress)
+
+}
+
+/// The flow is split among the tiles the same way `gen-water` sent it, with the favored neighbor listed first in the flow links. Distributaries added for a delta share the flow evenly instead.
+fn find_segments<Progress: ProgressObserver>(tile_map: &EntityIndex<TileSchema,TileForRiverConnect>, delta_branching: &DeltaBranchingArg, flow_concentration: f64, progress: &mut Progress) -> Result<Vec<Rc<RiverSegment>>, CommandError> {
     let mut result = Vec::new();
 
-    for entity in tiles.read_features().into_entities::<TileForRiverConnect>().watch(progress,"Finding segments.","Segments found.") {
-        let (fid,tile) = entity?;
-        for flow_to in tile.flow_to() {
-            let flow_to_len = tile.flow_to().len() as f64;
+    for (fid,tile) in tile_map.iter().watch(progress,"Finding segments.","Segments found.") {
+        let flow_to = find_delta_distributaries(tile, tile_map, delta_branching)?;
+        let (favored_portion,shared_portion) = if &flow_to == tile.flow_to() {
+            flow_portions(flow_to.len(), flow_concentration)
+        } else {
+            let portion = 1.0 / flow_to.len() as f64;
+            (portion,portion)
+        };
+        for (i,to) in flow_to.into_iter().enumerate() {
+            let portion = if i == 0 { favored_portion } else { shared_portion };
             result.push(Rc::from(RiverSegment {
                 from: fid.clone(),
-                to: flow_to.clone(),
-                to_flow: tile.water_flow()/flow_to_len,
+                to,
+                to_flow: tile.water_flow() * portion,
                 from_lake: false,
             }))
         }
@@ -401,3 +627,237 @@ pub(crate) fn gen_water_rivers_find_segments<Progress: ProgressObserver>(tiles:
 
     Ok(result)
 }
+
+/// Returns the tiles the river flows to from this tile. If delta branching is on, and a high-flow river reaches the ocean from a low coastal tile, all of the other ocean neighbors are added so the mouth splits into distributaries.
+fn find_delta_distributaries(tile: &TileForRiverConnect, tile_map: &EntityIndex<TileSchema,TileForRiverConnect>, delta_branching: &DeltaBranchingArg) -> Result<Vec<Neighbor>, CommandError> {
+    let mut result = tile.flow_to().clone();
+
+    if !delta_branching.delta_branching || 
+       tile.grouping().is_water() || 
+       (tile.water_flow() < &delta_branching.min_delta_flow) || 
+       (tile.elevation() > &delta_branching.max_delta_elevation) {
+        return Ok(result)
+    }
+
+    let is_ocean = |neighbor: &Neighbor| -> Result<bool,CommandError> {
+        Ok(match neighbor {
+            Neighbor::Tile(neighbor) | Neighbor::CrossMap(neighbor,_) => tile_map.try_get(neighbor)?.grouping().is_ocean(),
+            Neighbor::OffMap(_) => false,
+        })
+    };
+
+    let mut reaches_ocean = false;
+    for neighbor in tile.flow_to() {
+        if is_ocean(neighbor)? {
+            reaches_ocean = true;
+            break;
+        }
+    }
+
+    if reaches_ocean {
+        for NeighborAndDirection(neighbor,_) in tile.neighbors() {
+            if !result.contains(neighbor) && is_ocean(neighbor)? {
+                result.push(neighbor.clone())
+            }
+        }
+    }
+
+    Ok(result)
+
+}
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+
+    use super::find_segments;
+    use super::generate_water_rivers_clean_and_index;
+    use super::generate_water_river_to_type;
+    use super::generate_water_river_from_type;
+    use super::CleanedAndIndexedSegments;
+    use super::find_lake_segments;
+    use super::LakeConnection;
+    use super::find_river_lengths;
+    use super::find_strahler_orders;
+    use super::UpstreamSegment;
+    use crate::algorithms::water_flow::distribute_flow;
+    use crate::commands::DeltaBranchingArg;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::fields::RiverSegmentFrom;
+    use crate::world_map::fields::RiverSegmentTo;
+    use crate::world_map::tile_layer::TileForRiverConnect;
+    use crate::world_map::tile_layer::TileSchema;
+
+    fn neighbors(ids: &[u64]) -> Vec<NeighborAndDirection> {
+        ids.iter().enumerate().map(|(i,id)| NeighborAndDirection(Neighbor::Tile(IdRef::new(*id)),Deg(i as f64 * 90.0))).collect()
+    }
+
+    // A river comes from tile 1 into coastal tile 2, which flows into ocean tile 3, but is also next to ocean tiles 4 and 5.
+    fn create_tile_map(mouth_elevation: f64) -> EntityIndex<TileSchema,TileForRiverConnect> {
+        let ocean = |id| (IdRef::new(id),TileForRiverConnect::new(-100.0, Grouping::Ocean, neighbors(&[2]), 0.0, Vec::new(), None));
+        [
+            (IdRef::new(1),TileForRiverConnect::new(50.0, Grouping::Continent, neighbors(&[2]), 1000.0, vec![Neighbor::Tile(IdRef::new(2))], None)),
+            (IdRef::new(2),TileForRiverConnect::new(mouth_elevation, Grouping::Continent, neighbors(&[1,3,4,5]), 1200.0, vec![Neighbor::Tile(IdRef::new(3))], None)),
+            ocean(3),
+            ocean(4),
+            ocean(5)
+        ].into_iter().collect()
+    }
+
+    fn mouths(tile_map: &EntityIndex<TileSchema,TileForRiverConnect>, delta_branching: bool) -> Vec<(RiverSegmentFrom,RiverSegmentTo,f64)> {
+        let delta_branching = DeltaBranchingArg {
+            delta_branching,
+            min_delta_flow: 500.0,
+            max_delta_elevation: 20.0
+        };
+        let segments = find_segments(tile_map, &delta_branching, 0.0, &mut ()).unwrap();
+        let CleanedAndIndexedSegments { tile_from_index, tile_to_index, segment_draw_queue } = generate_water_rivers_clean_and_index(segments, &mut ());
+        let mut result = Vec::new();
+        for segment in &segment_draw_queue {
+            let (to_type,_) = generate_water_river_to_type(segment, &tile_to_index, &tile_from_index);
+            if matches!(to_type,RiverSegmentTo::Mouth) {
+                let (from_type,_,from_flow) = generate_water_river_from_type(segment, &tile_from_index, &tile_to_index);
+                result.push((from_type,to_type,from_flow));
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_delta_branching() {
+        let tile_map = create_tile_map(5.0);
+
+        let single = mouths(&tile_map, false);
+        assert_eq!(single.len(),1);
+        assert_eq!(single[0].0,RiverSegmentFrom::Continuing);
+
+        let delta = mouths(&tile_map, true);
+        assert_eq!(delta.len(),3);
+        for (from_type,_,from_flow) in delta {
+            assert_eq!(from_type,RiverSegmentFrom::Branch);
+            assert!((from_flow - (1000.0/3.0)).abs() < f64::EPSILON * 1000.0);
+        }
+
+        // a mouth on a high coast doesn't split
+        let high_coast = create_tile_map(100.0);
+        assert_eq!(mouths(&high_coast, true).len(),1);
+    }
+
+    #[test]
+    fn test_segment_flow_shares() {
+        // tile 1 flows to two equally low neighbors, and the flow was concentrated toward one of them.
+        let distributed = distribute_flow(100.0, vec![(Neighbor::Tile(IdRef::new(3)),10.0),(Neighbor::Tile(IdRef::new(2)),10.0)], 0.5);
+        let flow_to = distributed.iter().map(|(neighbor,_)| neighbor.clone()).collect();
+        let low = |id| (IdRef::new(id),TileForRiverConnect::new(10.0, Grouping::Continent, neighbors(&[1]), 0.0, Vec::new(), None));
+        let tile_map: EntityIndex<TileSchema,TileForRiverConnect> = [
+            (IdRef::new(1),TileForRiverConnect::new(50.0, Grouping::Continent, neighbors(&[2,3]), 100.0, flow_to, None)),
+            low(2),
+            low(3)
+        ].into_iter().collect();
+        let delta_branching = DeltaBranchingArg {
+            delta_branching: false,
+            min_delta_flow: 500.0,
+            max_delta_elevation: 20.0
+        };
+
+        let segments = find_segments(&tile_map, &delta_branching, 0.5, &mut ()).unwrap();
+        let shares: Vec<_> = segments.iter().map(|segment| (segment.to.clone(),segment.to_flow)).collect();
+        assert_eq!(shares,distributed);
+        assert_eq!(shares,vec![(Neighbor::Tile(IdRef::new(2)),75.0),(Neighbor::Tile(IdRef::new(3)),25.0)]);
+    }
+
+    #[test]
+    fn test_lake_segments() {
+        let connection = |segment_id,from_lake: Option<u64>,to_lake: Option<u64>,flow| LakeConnection {
+            segment_id: IdRef::new(segment_id),
+            from_lake: from_lake.map(IdRef::new),
+            to_lake: to_lake.map(IdRef::new),
+            flow
+        };
+
+        let lake_segments = find_lake_segments(vec![
+            connection(1, None, None, 10.0),
+            // the inlet
+            connection(2, None, Some(7), 10.0),
+            // the outlet
+            connection(3, Some(7), None, 12.0),
+            connection(4, None, None, 12.0),
+        ]);
+
+        assert_eq!(lake_segments.len(),1);
+        let (inlets,outlet) = &lake_segments[&IdRef::new(7)];
+        assert_eq!(inlets,&vec![IdRef::new(2)]);
+        assert_eq!(outlet,&Some(IdRef::new(3)));
+    }
+
+    #[test]
+    fn test_river_lengths() {
+        let segment = |from,to,from_lake: Option<u64>,to_lake: Option<u64>,distance| UpstreamSegment {
+            from_tile_id: IdRef::new(from),
+            to_tile_id: Neighbor::Tile(IdRef::new(to)),
+            from_lake: from_lake.map(IdRef::new),
+            to_lake: to_lake.map(IdRef::new),
+            distance
+        };
+
+        let lengths = find_river_lengths(&[
+            // a delta, listed before the rivers upstream of it.
+            segment(6, 7, None, None, 1.0),
+            segment(6, 8, None, None, 2.0),
+            // two rivers join at tile 3, and flow into a lake at tile 4
+            segment(1, 3, None, None, 1.0),
+            segment(2, 3, None, None, 2.0),
+            segment(3, 4, None, Some(9), 1.0),
+            // the lake drains from another of its tiles
+            segment(5, 6, Some(9), None, 1.0),
+            // a loop, which shouldn't happen, but mustn't hang
+            segment(10, 11, None, None, 1.0),
+            segment(11, 10, None, None, 1.0),
+        ]);
+
+        // the longer river is followed at the confluence, and through the lake.
+        assert_eq!(&lengths[2..6],&[1.0,2.0,3.0,4.0]);
+        // each branch of the delta has its own length.
+        assert_eq!(&lengths[0..2],&[5.0,6.0]);
+        assert_eq!(&lengths[6..8],&[2.0,1.0]);
+    }
+
+    #[test]
+    fn test_strahler_orders() {
+        let segment = |from,to,from_lake: Option<u64>,to_lake: Option<u64>| UpstreamSegment {
+            from_tile_id: IdRef::new(from),
+            to_tile_id: Neighbor::Tile(IdRef::new(to)),
+            from_lake: from_lake.map(IdRef::new),
+            to_lake: to_lake.map(IdRef::new),
+            distance: 1.0
+        };
+
+        let orders = find_strahler_orders(&[
+            // two sources join, then a smaller river joins without raising the order
+            segment(1, 3, None, None),
+            segment(2, 3, None, None),
+            segment(3, 4, None, None),
+            segment(20, 4, None, None),
+            segment(4, 5, None, None),
+            segment(5, 6, None, Some(9)),
+            // another river of the same order flows into the lake
+            segment(30, 32, None, None),
+            segment(31, 32, None, None),
+            segment(32, 33, None, Some(9)),
+            // so the outlet is a confluence of both
+            segment(34, 35, Some(9), None),
+            // and the branches of the delta keep its order
+            segment(35, 36, None, None),
+            segment(35, 37, None, None),
+            // a river flowing into a lake on the tile it drains from is only counted once
+            segment(50, 51, None, Some(19)),
+            segment(51, 52, Some(19), None),
+        ]);
+
+        assert_eq!(orders,vec![1,1,2,1,2,2,1,1,2,3,3,3,1,1]);
+    }
+}
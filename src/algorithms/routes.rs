@@ -0,0 +1,481 @@
+use core::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+use ordered_float::OrderedFloat;
+use priority_queue::PriorityQueue;
+
+use crate::commands::OverwriteRoutesArg;
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::entities::EntityIndex;
+use crate::typed_map::entities::EntityLookup;
+use crate::typed_map::fields::IdRef;
+use crate::utils::coordinates::Coordinates;
+use crate::utils::extent::Extent;
+use crate::utils::world_shape::WorldShape;
+use crate::world_map::WorldMapTransaction;
+use crate::world_map::biome_layer::BiomeForRoutes;
+use crate::world_map::biome_layer::BiomeSchema;
+use crate::world_map::fields::Neighbor;
+use crate::world_map::fields::NeighborAndDirection;
+use crate::world_map::fields::RouteType;
+use crate::world_map::route_layer::NewRoute;
+use crate::world_map::tile_layer::TileForRoutes;
+use crate::world_map::tile_layer::TileSchema;
+use crate::world_map::town_layer::TownForRoutes;
+use crate::world_map::town_layer::TownSchema;
+
+/// Which pairs of towns are connected with routes.
+#[derive(Clone,ValueEnum)]
+pub enum RouteDetail {
+    /// Each capital is connected to its nearest capitals
+    Capitals,
+    /// Each town is connected to its nearest towns
+    Towns,
+    /// Every pair of towns within the route distance of each other is connected
+    Within
+}
+
+/// A least-cost path between two tiles, including the tiles at both ends, along with the cost of reaching each tile from the start.
+#[derive(Clone)]
+pub(crate) struct FoundPath {
+    tiles: Vec<IdRef>,
+    costs: Vec<f64>
+}
+
+impl FoundPath {
+
+    fn cost(&self) -> f64 {
+        self.costs.last().copied().unwrap_or(0.0)
+    }
+
+    fn reversed(&self) -> Self {
+        // step costs are the same in both directions, so the reversed path is just as cheap.
+        let total = self.cost();
+        Self {
+            tiles: self.tiles.iter().rev().cloned().collect(),
+            costs: self.costs.iter().rev().map(|cost| total - cost).collect()
+        }
+    }
+
+    fn sub_path(&self, start: usize, end: usize) -> Self {
+        let start_cost = self.costs.get(start).copied().unwrap_or(0.0);
+        Self {
+            tiles: self.tiles.iter().skip(start).take(end - start + 1).cloned().collect(),
+            costs: self.costs.iter().skip(start).take(end - start + 1).map(|cost| cost - start_cost).collect()
+        }
+    }
+}
+
+/// Finds least-cost paths between towns, remembering paths that have already been found so they don't have to be searched again.
+pub(crate) struct RouteSearch<'search> {
+    tile_map: &'search EntityIndex<TileSchema,TileForRoutes>,
+    world_shape: &'search WorldShape,
+    // the cost of travelling a unit of distance over each land tile. Water tiles aren't included, as roads don't cross them.
+    land_costs: HashMap<IdRef,f64>,
+    // the cheapest land cost is used to keep the A* heuristic from overestimating
+    min_land_cost: f64,
+    // paths are cached by route type and the tiles at each end, with the lesser tile id first.
+    cache: HashMap<(bool,IdRef,IdRef),Option<FoundPath>>
+}
+
+impl<'search> RouteSearch<'search> {
+
+    pub(crate) fn new(tile_map: &'search EntityIndex<TileSchema,TileForRoutes>, biome_map: &EntityLookup<BiomeSchema,BiomeForRoutes>, world_shape: &'search WorldShape) -> Result<Self,CommandError> {
+        let mut land_costs = HashMap::new();
+        let mut min_land_cost = f64::INFINITY;
+        for (tile_id,tile) in tile_map.iter() {
+            if tile.grouping().is_water() {
+                continue;
+            }
+            let biome_cost = f64::from(*biome_map.try_get(tile.biome())?.movement_cost());
+            // these are the same penalties that cultures use to avoid high elevations.
+            let height_cost = if tile.elevation_scaled() >= &67 {
+                // mountain crossing penalty
+                200.0
+            } else if tile.elevation_scaled() > &44 {
+                // hill crossing penalty
+                30.0
+            } else {
+                0.0
+            };
+            let cost = biome_cost + height_cost;
+            min_land_cost = min_land_cost.min(cost);
+            _ = land_costs.insert(tile_id.clone(), cost);
+        }
+
+        Ok(Self {
+            tile_map,
+            world_shape,
+            land_costs,
+            min_land_cost: min_land_cost.max(0.0),
+            cache: HashMap::new()
+        })
+    }
+
+    fn step_cost(&self, route_type: &RouteType, tile_id: &IdRef, neighbor_id: &IdRef, distance: f64, goal_tile_id: &IdRef) -> Option<f64> {
+        match route_type {
+            // the cost of each step is averaged from both tiles, so the route costs the same in both directions.
+            RouteType::Land => match (self.land_costs.get(tile_id),self.land_costs.get(neighbor_id)) {
+                (Some(tile_cost),Some(neighbor_cost)) => Some(distance * (tile_cost + neighbor_cost) / 2.0),
+                _ => None
+            },
+            // ships stay on the ocean, except to come into port at the end.
+            RouteType::Sea => self.tile_map.maybe_get(neighbor_id).filter(|neighbor| neighbor.grouping().is_ocean() || neighbor_id == goal_tile_id).map(|_| distance)
+        }
+    }
+
+    /// The shortest distance between two sites, including the distance across the antimeridian.
+    fn straight_distance(&self, site: &Coordinates, goal_site: &Coordinates) -> f64 {
+        let direct = site.shaped_distance(goal_site, self.world_shape);
+        let across = site.shaped_distance(&goal_site.across_antimeridian(site), self.world_shape);
+        direct.min(across)
+    }
+
+    /// Searches for the least-cost path from start to goal. Without the heuristic this is Dijkstra's algorithm, with it it's A*.
+    fn search(&self, route_type: &RouteType, start_tile_id: &IdRef, goal_tile_id: &IdRef, use_heuristic: bool) -> Result<Option<FoundPath>,CommandError> {
+
+        let goal_site = self.tile_map.try_get(goal_tile_id)?.site();
+        let heuristic_factor = match route_type {
+            RouteType::Land => self.min_land_cost,
+            RouteType::Sea => 1.0
+        };
+        let heuristic = |site: &Coordinates| if use_heuristic {
+            self.straight_distance(site, goal_site) * heuristic_factor
+        } else {
+            0.0
+        };
+
+        let mut queue = PriorityQueue::new();
+        let mut costs = HashMap::new();
+        let mut came_from: HashMap<IdRef,IdRef> = HashMap::new();
+        let mut visited = HashSet::new();
+
+        _ = costs.insert(start_tile_id.clone(), OrderedFloat::from(0.0));
+        _ = queue.push(start_tile_id.clone(), Reverse(OrderedFloat::from(heuristic(self.tile_map.try_get(start_tile_id)?.site()))));
+
+        while let Some((tile_id,_)) = queue.pop() {
+
+            if &tile_id == goal_tile_id {
+                let mut tiles = vec![tile_id.clone()];
+                let mut current = &tile_id;
+                while let Some(previous) = came_from.get(current) {
+                    tiles.push(previous.clone());
+                    current = previous;
+                }
+                tiles.reverse();
+                let costs = tiles.iter().map(|tile| costs.get(tile).map_or(0.0, |cost| cost.into_inner())).collect();
+                return Ok(Some(FoundPath { tiles, costs }))
+            }
+
+            _ = visited.insert(tile_id.clone());
+
+            let cost = costs.get(&tile_id).copied().unwrap_or_else(|| OrderedFloat::from(0.0));
+            let tile = self.tile_map.try_get(&tile_id)?;
+
+            for NeighborAndDirection(neighbor,_) in tile.neighbors() {
+                let (neighbor_id,across_map) = match neighbor {
+                    Neighbor::Tile(neighbor_id) => (neighbor_id,false),
+                    Neighbor::CrossMap(neighbor_id,_) => (neighbor_id,true),
+                    Neighbor::OffMap(_) => continue,
+                };
+
+                if visited.contains(neighbor_id) {
+                    continue;
+                }
+
+                let neighbor_site = if across_map {
+                    self.tile_map.try_get(neighbor_id)?.site().across_antimeridian(tile.site())
+                } else {
+                    self.tile_map.try_get(neighbor_id)?.site().clone()
+                };
+                let distance = tile.site().shaped_distance(&neighbor_site, self.world_shape);
+
+                if let Some(step_cost) = self.step_cost(route_type, &tile_id, neighbor_id, distance, goal_tile_id) {
+                    let total_cost = cost + step_cost;
+                    if costs.get(neighbor_id).is_none_or(|previous_cost| &total_cost < previous_cost) {
+                        _ = costs.insert(neighbor_id.clone(), total_cost);
+                        _ = came_from.insert(neighbor_id.clone(), tile_id.clone());
+                        _ = queue.push(neighbor_id.clone(), Reverse(total_cost + heuristic(&neighbor_site)));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Finds the least-cost path between two tiles, using a cached path if one is known. Any hub tiles the new path passes through are also cached, since the parts of the path between them are also least-cost paths.
+    pub(crate) fn find_route(&mut self, route_type: &RouteType, start_tile_id: &IdRef, goal_tile_id: &IdRef, hubs: &HashSet<IdRef>) -> Result<Option<FoundPath>,CommandError> {
+        let is_sea = matches!(route_type,RouteType::Sea);
+        let cache_key = |start: &IdRef, goal: &IdRef| if start < goal {
+            ((is_sea,start.clone(),goal.clone()),false)
+        } else {
+            ((is_sea,goal.clone(),start.clone()),true)
+        };
+
+        let (key,reversed) = cache_key(start_tile_id, goal_tile_id);
+        if let Some(path) = self.cache.get(&key) {
+            return Ok(path.as_ref().map(|path| if reversed { path.reversed() } else { path.clone() }))
+        }
+
+        let path = self.search(route_type, start_tile_id, goal_tile_id, true)?;
+
+        if let Some(path) = &path {
+            let hub_indexes: Vec<usize> = path.tiles.iter().enumerate().filter(|(_,tile_id)| hubs.contains(tile_id)).map(|(index,_)| index).collect();
+            for (position,start) in hub_indexes.iter().enumerate() {
+                for end in hub_indexes.iter().skip(position + 1) {
+                    let sub_path = path.sub_path(*start, *end);
+                    if let (Some(first),Some(last)) = (sub_path.tiles.first(),sub_path.tiles.last()) {
+                        let (sub_key,sub_reversed) = cache_key(first, last);
+                        _ = self.cache.entry(sub_key).or_insert_with(|| Some(if sub_reversed { sub_path.reversed() } else { sub_path }));
+                    }
+                }
+            }
+        }
+
+        _ = self.cache.insert(key, path.as_ref().map(|path| if reversed { path.reversed() } else { path.clone() }));
+
+        Ok(path)
+    }
+
+}
+
+fn path_to_lines(tile_map: &EntityIndex<TileSchema,TileForRoutes>, path: &FoundPath, extent: &Extent) -> Result<Vec<Vec<Coordinates>>,CommandError> {
+    let mut line = Vec::new();
+    // once the route has crossed the antimeridian, the rest of the points have to be shifted along with it so the line stays continuous.
+    let mut shift = 0.0;
+    let mut previous: Option<&TileForRoutes> = None;
+    for tile_id in &path.tiles {
+        let tile = tile_map.try_get(tile_id)?;
+        let site = tile.site();
+        if let Some(previous) = previous {
+            let across_map = previous.neighbors().iter().any(|NeighborAndDirection(neighbor,_)| matches!(neighbor,Neighbor::CrossMap(neighbor_id,_) if neighbor_id == tile_id));
+            if across_map {
+                shift += site.across_antimeridian(previous.site()).to_tuple().0 - site.to_tuple().0;
+            }
+        }
+        let (x,y) = site.to_tuple();
+        line.push(Coordinates::try_from((x + shift,y))?);
+        previous = Some(tile);
+    }
+    Coordinates::clip_point_vec_across_antimeridian(line, extent)
+}
+
+/// Chooses which pairs of towns get routes. Roads are only built between towns on the same landmass, while sea routes are built between ports.
+fn select_town_pairs(town_map: &EntityIndex<TownSchema,TownForRoutes>, tile_map: &EntityIndex<TileSchema,TileForRoutes>, detail: &RouteDetail, connections: usize, max_distance: f64) -> Result<Vec<(IdRef,IdRef,RouteType)>,CommandError> {
+
+    let mut pairs = Vec::new();
+    let mut connected = HashSet::new();
+
+    let towns = town_map.iter().filter(|(_,town)| !matches!(detail,RouteDetail::Capitals) || *town.is_capital()).map(|(town_id,town)| {
+        Ok((town_id,town,tile_map.try_get(town.tile_id())?.site()))
+    }).collect::<Result<Vec<_>,CommandError>>()?;
+
+    for (town_id,town,site) in &towns {
+
+        let mut searches = vec![RouteType::Land];
+        if *town.is_port() {
+            searches.push(RouteType::Sea);
+        }
+
+        for route_type in searches {
+            let mut candidates: Vec<(&IdRef,f64)> = towns.iter().filter(|(other_id,other,_)| {
+                (other_id != town_id) && match route_type {
+                    RouteType::Land => other.grouping_id() == town.grouping_id(),
+                    RouteType::Sea => *other.is_port(),
+                }
+            }).map(|(other_id,_,other_site)| {
+                // this is only for choosing neighbors, so the flat distance in degrees is good enough.
+                (*other_id,site.distance(other_site).min(site.distance(&other_site.across_antimeridian(site))))
+            }).collect();
+            candidates.sort_by(|(_,a),(_,b)| a.total_cmp(b));
+
+            let chosen: Vec<&IdRef> = match detail {
+                RouteDetail::Capitals | RouteDetail::Towns => candidates.into_iter().take(connections).map(|(other_id,_)| other_id).collect(),
+                RouteDetail::Within => candidates.into_iter().take_while(|(_,distance)| distance <= &max_distance).map(|(other_id,_)| other_id).collect(),
+            };
+
+            for other_id in chosen {
+                // two towns that are each other's neighbors only need one route between them.
+                let is_sea = matches!(route_type,RouteType::Sea);
+                let pair = if *town_id < other_id {
+                    ((*town_id).clone(),other_id.clone(),is_sea)
+                } else {
+                    (other_id.clone(),(*town_id).clone(),is_sea)
+                };
+                if connected.insert(pair) {
+                    pairs.push(((*town_id).clone(),other_id.clone(),route_type.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(pairs)
+}
+
+pub(crate) fn generate_routes<Progress: ProgressObserver>(target: &mut WorldMapTransaction, detail: &RouteDetail, connections: usize, max_distance: f64, overwrite_layer: &OverwriteRoutesArg, progress: &mut Progress) -> Result<(),CommandError> {
+
+    let world_shape = target.edit_properties_layer()?.get_world_shape()?;
+
+    let mut tiles = target.edit_tile_layer()?;
+    let extent = tiles.get_extent()?;
+    let tile_map = tiles.read_features().into_entities_index::<_,TileForRoutes>(progress)?;
+
+    let biome_map = target.edit_biomes_layer()?.read_features().into_named_entities_index::<_,BiomeForRoutes>(progress)?;
+
+    let town_map = target.edit_towns_layer()?.read_features().into_entities_index::<_,TownForRoutes>(progress)?;
+
+    let pairs = select_town_pairs(&town_map, &tile_map, detail, connections, max_distance)?;
+
+    let hubs = town_map.iter().map(|(_,town)| town.tile_id().clone()).collect();
+
+    let mut search = RouteSearch::new(&tile_map, &biome_map, &world_shape)?;
+
+    let mut routes = Vec::new();
+
+    for (from_town_id,to_town_id,route_type) in pairs.into_iter().watch(progress,"Finding routes.","Routes found.") {
+        let from_tile_id = town_map.try_get(&from_town_id)?.tile_id();
+        let to_tile_id = town_map.try_get(&to_town_id)?.tile_id();
+        if let Some(path) = search.find_route(&route_type, from_tile_id, to_tile_id, &hubs)? {
+            let lines = path_to_lines(&tile_map, &path, &extent)?;
+            routes.push((NewRoute {
+                type_: route_type,
+                from_town_id,
+                to_town_id,
+                cost: path.cost()
+            },lines));
+        }
+    }
+
+    let mut routes_layer = target.create_routes_layer(overwrite_layer)?;
+
+    for (route,lines) in routes.into_iter().watch(progress,"Writing routes.","Routes written.") {
+        _ = routes_layer.add_route(&route, lines)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::collections::HashSet;
+
+    use angular_units::Deg;
+
+    use super::RouteSearch;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::entities::EntityLookup;
+    use crate::typed_map::fields::IdRef;
+    use crate::utils::coordinates::Coordinates;
+    use crate::utils::world_shape::WorldShape;
+    use crate::world_map::biome_layer::BiomeForRoutes;
+    use crate::world_map::biome_layer::BiomeSchema;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::fields::RouteType;
+    use crate::world_map::tile_layer::TileForRoutes;
+    use crate::world_map::tile_layer::TileSchema;
+
+    const BIOMES: [(&str,i32);3] = [("Grassland",10),("Forest",17),("Swamp",29)];
+
+    fn biome_map() -> EntityLookup<BiomeSchema,BiomeForRoutes> {
+        EntityLookup::from(BIOMES.iter().map(|(name,cost)| ((*name).to_owned(),BiomeForRoutes::new((*name).to_owned(), *cost))).collect::<HashMap<_,_>>())
+    }
+
+    /// A square grid of land tiles, about one degree apart, numbered from 1 in rows starting at the northwest. Sites are shifted up to `jitter` degrees, so that there aren't many paths that cost the same.
+    fn grid(size: u64, jitter: f64, tile: impl Fn(u64) -> (i32,&'static str)) -> EntityIndex<TileSchema,TileForRoutes> {
+        (0..size).flat_map(|row: u64| (0..size).map(move |column: u64| (row,column))).map(|(row,column)| {
+            let id = row * size + column + 1;
+            let mut neighbors = Vec::new();
+            if row > 0 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - size)),Deg(0.0)));
+            }
+            if column + 1 < size {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + 1)),Deg(90.0)));
+            }
+            if row + 1 < size {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id + size)),Deg(180.0)));
+            }
+            if column > 0 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(id - 1)),Deg(270.0)));
+            }
+            let site = Coordinates::try_from(((id as f64).sin().mul_add(jitter, column as f64),(id as f64).cos().mul_add(jitter, -(row as f64)))).unwrap();
+            let (elevation_scaled,biome) = tile(id);
+            (IdRef::new(id),TileForRoutes::new(site, elevation_scaled, biome.to_owned(), Grouping::Continent, neighbors))
+        }).collect()
+    }
+
+    // a pseudo-random mix of biomes and hills
+    fn varied_tile(id: u64) -> (i32,&'static str) {
+        let elevation_scaled = if (id * 7).is_multiple_of(13) { 90 } else if (id * 5).is_multiple_of(11) { 50 } else { 30 };
+        let biome = BIOMES.get(((id * 31) % 3) as usize).map_or("Grassland", |(name,_)| *name);
+        (elevation_scaled,biome)
+    }
+
+    #[test]
+    fn test_route_around_mountain() {
+        // a three by three grid of grassland, with a mountain in the middle between two towns on the west and east sides.
+        // 1 2 3
+        // 4 5 6
+        // 7 8 9
+        let mountain = 5;
+        let tile_map = grid(3, 0.0, |id| (if id == mountain { 90 } else { 30 },"Grassland"));
+        let biome_map = biome_map();
+        let mut search = RouteSearch::new(&tile_map, &biome_map, &WorldShape::Cylinder).unwrap();
+
+        let route = search.find_route(&RouteType::Land, &IdRef::new(4), &IdRef::new(6), &HashSet::new()).unwrap().unwrap();
+
+        let path: Vec<u64> = route.tiles.iter().map(|id| id.clone().to_inner()).collect();
+        // going around takes four steps instead of two, but it's still cheaper than climbing the mountain.
+        assert_eq!(path.len(),5);
+        assert_eq!(path.first(),Some(&4));
+        assert_eq!(path.last(),Some(&6));
+        assert!(!path.contains(&mountain));
+        assert_eq!(route.cost().to_bits(),40.0_f64.to_bits());
+    }
+
+    #[test]
+    fn test_astar_matches_dijkstra() {
+        let tile_map = grid(8, 0.2, varied_tile);
+        let biome_map = biome_map();
+        let search = RouteSearch::new(&tile_map, &biome_map, &WorldShape::Cylinder).unwrap();
+
+        for (start,goal) in [(1,64),(8,57),(3,46),(60,12),(20,21)] {
+            let (start,goal) = (IdRef::new(start),IdRef::new(goal));
+            let dijkstra = search.search(&RouteType::Land, &start, &goal, false).unwrap().unwrap();
+            let astar = search.search(&RouteType::Land, &start, &goal, true).unwrap().unwrap();
+            assert_eq!(astar.tiles,dijkstra.tiles);
+            assert!((astar.cost() - dijkstra.cost()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_route_cache() {
+        // a grassland road through the middle of a hilly swamp, with three hub towns along it
+        let tile_map = grid(5, 0.0, |id| if (11..=15).contains(&id) { (30,"Grassland") } else { (50,"Swamp") });
+        let biome_map = biome_map();
+        let mut search = RouteSearch::new(&tile_map, &biome_map, &WorldShape::Cylinder).unwrap();
+        let hubs = HashSet::from([IdRef::new(11),IdRef::new(13),IdRef::new(15)]);
+
+        let route = search.find_route(&RouteType::Land, &IdRef::new(11), &IdRef::new(15), &hubs).unwrap().unwrap();
+        assert_eq!(route.tiles,(11..=15).map(IdRef::new).collect::<Vec<_>>());
+
+        // the parts of the route between hubs were cached along with it
+        assert!(search.cache.contains_key(&(false,IdRef::new(11),IdRef::new(13))));
+        assert!(search.cache.contains_key(&(false,IdRef::new(13),IdRef::new(15))));
+
+        // a cached route in the other direction must still be correct
+        let reversed = search.find_route(&RouteType::Land, &IdRef::new(15), &IdRef::new(13), &hubs).unwrap().unwrap();
+        let expected = search.search(&RouteType::Land, &IdRef::new(15), &IdRef::new(13), false).unwrap().unwrap();
+        assert_eq!(reversed.tiles,expected.tiles);
+        assert!((reversed.cost() - expected.cost()).abs() < 1e-9);
+        assert_eq!(reversed.costs.first().map(|cost| cost.to_bits()),Some(0.0_f64.to_bits()));
+    }
+
+}
@@ -0,0 +1,161 @@
+use core::fmt::Write as _;
+
+use clap::ValueEnum;
+use prisma::Rgb;
+use serde::Serialize;
+
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::world_map::WorldMapTransaction;
+use crate::world_map::fields::ColorConversion;
+
+/// A layer whose features have a name and a color.
+#[derive(Clone,ValueEnum)]
+pub enum StyleLayer {
+    /// The biomes layer, for coloring biome polygons by name
+    Biomes,
+    /// The cultures layer, for coloring culture polygons by name
+    Cultures,
+    /// The nations layer, for coloring nation polygons by name
+    Nations,
+    /// The subnations layer, for coloring subnation polygons by name
+    Subnations
+}
+
+/// The kind of style file to write.
+#[derive(Clone,ValueEnum)]
+pub enum StyleFormat {
+    /// A QGIS layer style file with a categorized renderer
+    Qml,
+    /// A generic JSON legend listing each category and its color
+    Json
+}
+
+#[derive(Serialize)]
+pub(crate) struct StyleCategory {
+    value: String,
+    #[serde(serialize_with = "serialize_color")]
+    color: Rgb<u8>
+}
+
+#[derive(Serialize)]
+struct Legend<'categories> {
+    attribute: &'static str,
+    categories: &'categories [StyleCategory]
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref,reason="serde passes the field by reference.")]
+fn serialize_color<Target: serde::Serializer>(color: &Rgb<u8>, serializer: Target) -> Result<Target::Ok,Target::Error> {
+    serializer.serialize_str(&color.into_hex_string())
+}
+
+// all of the styled layers are categorized on this field
+const CATEGORY_ATTRIBUTE: &str = "name";
+
+/// Reduces the named colors to one category per distinct name, sorted by name. If a name appears more than once, the first color is used.
+fn distinct_categories<Features: IntoIterator<Item = (String,Rgb<u8>)>>(features: Features) -> Vec<StyleCategory> {
+    let mut result: Vec<StyleCategory> = Vec::new();
+    for (value,color) in features {
+        if !result.iter().any(|category| category.value == value) {
+            result.push(StyleCategory { value, color });
+        }
+    }
+    result.sort_by(|a,b| a.value.cmp(&b.value));
+    result
+}
+
+pub(crate) fn read_style_categories<Progress: ProgressObserver>(target: &WorldMapTransaction, layer: &StyleLayer, progress: &mut Progress) -> Result<Vec<StyleCategory>,CommandError> {
+    let features = match layer {
+        StyleLayer::Biomes => target.edit_biomes_layer()?.read_features().watch(progress,"Reading biomes.","Biomes read.").map(|feature| Ok((feature.name()?,feature.color()?))).collect::<Result<Vec<_>,CommandError>>()?,
+        StyleLayer::Cultures => target.edit_cultures_layer()?.read_features().watch(progress,"Reading cultures.","Cultures read.").map(|feature| Ok((feature.name()?,feature.color()?))).collect::<Result<Vec<_>,CommandError>>()?,
+        StyleLayer::Nations => target.edit_nations_layer()?.read_features().watch(progress,"Reading nations.","Nations read.").map(|feature| Ok((feature.name()?,feature.color()?))).collect::<Result<Vec<_>,CommandError>>()?,
+        StyleLayer::Subnations => target.edit_subnations_layer()?.read_features().watch(progress,"Reading subnations.","Subnations read.").map(|feature| Ok((feature.name()?,feature.color()?))).collect::<Result<Vec<_>,CommandError>>()?,
+    };
+    Ok(distinct_categories(features))
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+/// Writes a QGIS style with a categorized renderer, which can be loaded into a layer through its properties.
+pub(crate) fn write_qml(categories: &[StyleCategory]) -> String {
+    let mut result = String::new();
+    result.push_str("<!DOCTYPE qgis PUBLIC 'http://mrcc.com/qgis.dtd' 'SYSTEM'>\n");
+    result.push_str("<qgis styleCategories=\"Symbology\">\n");
+    _ = writeln!(result,"  <renderer-v2 type=\"categorizedSymbol\" attr=\"{CATEGORY_ATTRIBUTE}\" symbollevels=\"0\" enableorderby=\"0\" forceraster=\"0\">");
+    result.push_str("    <categories>\n");
+    for (index,category) in categories.iter().enumerate() {
+        let value = escape_xml(&category.value);
+        _ = writeln!(result,"      <category symbol=\"{index}\" value=\"{value}\" label=\"{value}\" render=\"true\"/>");
+    }
+    result.push_str("    </categories>\n");
+    result.push_str("    <symbols>\n");
+    for (index,category) in categories.iter().enumerate() {
+        let (red,green,blue) = (category.color.red(),category.color.green(),category.color.blue());
+        _ = writeln!(result,"      <symbol type=\"fill\" name=\"{index}\" alpha=\"1\" clip_to_extent=\"1\" force_rhr=\"0\">");
+        result.push_str("        <layer class=\"SimpleFill\" enabled=\"1\" locked=\"0\" pass=\"0\">\n");
+        result.push_str("          <Option type=\"Map\">\n");
+        _ = writeln!(result,"            <Option type=\"QString\" name=\"color\" value=\"{red},{green},{blue},255\"/>");
+        result.push_str("            <Option type=\"QString\" name=\"outline_style\" value=\"no\"/>\n");
+        result.push_str("            <Option type=\"QString\" name=\"style\" value=\"solid\"/>\n");
+        result.push_str("          </Option>\n");
+        result.push_str("        </layer>\n");
+        result.push_str("      </symbol>\n");
+    }
+    result.push_str("    </symbols>\n");
+    result.push_str("  </renderer-v2>\n");
+    result.push_str("</qgis>\n");
+    result
+}
+
+/// Writes a legend as JSON, with the attribute to categorize on and a hex color for each category.
+pub(crate) fn write_json_legend(categories: &[StyleCategory]) -> Result<String,CommandError> {
+    Ok(serde_json::to_string_pretty(&Legend {
+        attribute: CATEGORY_ATTRIBUTE,
+        categories
+    })?)
+}
+
+pub(crate) fn write_style(categories: &[StyleCategory], format: &StyleFormat) -> Result<String,CommandError> {
+    match format {
+        StyleFormat::Qml => Ok(write_qml(categories)),
+        StyleFormat::Json => write_json_legend(categories),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use prisma::Rgb;
+
+    use super::distinct_categories;
+    use super::write_json_legend;
+    use super::write_qml;
+
+    #[test]
+    fn test_style_categories() {
+        let categories = distinct_categories([
+            ("Taiga".to_owned(),Rgb::new(0x4B,0x6B,0x32)),
+            ("Ocean".to_owned(),Rgb::new(0x1F,0x78,0xB4)),
+            ("Taiga".to_owned(),Rgb::new(0x4B,0x6B,0x32)),
+            ("Salt & Pepper".to_owned(),Rgb::new(1,2,3)),
+        ]);
+        assert_eq!(categories.len(),3);
+
+        let qml = write_qml(&categories);
+        assert!(qml.contains("<category symbol=\"0\" value=\"Ocean\" label=\"Ocean\" render=\"true\"/>"));
+        assert!(qml.contains("value=\"Salt &amp; Pepper\""));
+        assert!(qml.contains("value=\"Taiga\""));
+        assert!(qml.contains("name=\"color\" value=\"31,120,180,255\""));
+        assert_eq!(qml.matches("<category ").count(),3);
+        assert_eq!(qml.matches("<symbol ").count(),3);
+
+        let json = write_json_legend(&categories).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["attribute"],"name");
+        let values: Vec<&str> = parsed["categories"].as_array().unwrap().iter().map(|category| category["value"].as_str().unwrap()).collect();
+        assert_eq!(values,vec!["Ocean","Salt & Pepper","Taiga"]);
+        assert_eq!(parsed["categories"][0]["color"],"#1F78B4");
+    }
+}
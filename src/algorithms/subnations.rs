@@ -102,6 +102,7 @@ pub(crate) fn generate_subnations<Random: Rng, Progress: ProgressObserver, Cultu
         }
     }
 
+    namers.report_fallbacks(progress);
 
     Ok(())
 }
@@ -391,6 +392,8 @@ pub(crate) fn fill_empty_subnations<Random: Rng, Progress: ProgressObserver, Cul
     }
 
 
+    namers.report_fallbacks(progress);
+
     // create the new subnations and get their real id for assigning to tiles.
     let mut assigned_ids = HashMap::new();
 
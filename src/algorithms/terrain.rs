@@ -5,6 +5,9 @@ use core::cmp::Reverse;
 
 use rand::Rng;
 use ordered_float::OrderedFloat;
+use indexmap::IndexMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 use crate::errors::CommandError;
 use crate::typed_map::entities::EntityIndex;
@@ -15,6 +18,8 @@ use crate::world_map::WorldMapTransaction;
 use crate::progress::ProgressObserver;
 use crate::progress::WatchableIterator;
 use crate::raster::RasterMap;
+use crate::raster::RasterSampler;
+use crate::raster::ResampleMethod;
 use crate::world_map::fields::Grouping;
 use crate::world_map::property_layer::ElevationLimits;
 use crate::utils::point_finder::TileFinder;
@@ -24,6 +29,7 @@ use crate::utils::world_shape::WorldShape;
 use crate::progress::WatchableDeque;
 use crate::progress::WatchableQueue;
 use crate::commands::terrain::Multiply;
+use crate::commands::terrain::Normalize;
 use crate::utils::arg_range::ArgRange;
 use crate::commands::terrain::ClearOcean;
 use crate::commands::terrain::RandomUniform;
@@ -34,6 +40,9 @@ use crate::commands::terrain::StraitDirection;
 use crate::commands::terrain::Mask;
 use crate::commands::terrain::Invert;
 use crate::commands::terrain::InvertAxes;
+use crate::commands::terrain::FlipHorizontal;
+use crate::commands::terrain::FlipVertical;
+use crate::commands::terrain::InvertElevations;
 use crate::commands::terrain::Add;
 use crate::commands::terrain::Smooth;
 use crate::commands::terrain::Erode;
@@ -45,6 +54,8 @@ use crate::algorithms::tiles::find_lowest_tile;
 use crate::world_map::fields::NeighborAndDirection;
 use crate::world_map::fields::Neighbor;
 use crate::typed_map::fields::IdRef;
+use crate::commands::ContinentCountArg;
+use crate::utils::random::derive_retry_seed;
 use core::mem;
 
 
@@ -54,6 +65,79 @@ enum RelativeHeightTruncation {
     UnTruncated,
 }
 
+#[derive(Debug,Default,PartialEq,Eq)]
+/// Counts of tiles which were changed by terrain processes.
+pub(crate) struct TerrainChanges {
+    elevation: usize,
+    grouping: usize
+}
+
+impl TerrainChanges {
+
+    pub(crate) const fn elevation(&self) -> usize {
+        self.elevation
+    }
+
+    pub(crate) const fn grouping(&self) -> usize {
+        self.grouping
+    }
+
+    fn count(tile_map: &EntityIndex<TileSchema,TileForTerrain>) -> Self {
+        let mut result = Self::default();
+        for (_,tile) in tile_map.iter() {
+            if tile.elevation_changed() {
+                result.elevation += 1;
+            }
+            if tile.grouping_changed() {
+                result.grouping += 1;
+            }
+        }
+        result
+    }
+}
+
+/// Counts the connected landmasses which are large enough to be classified as continents when the map is grouped. This uses the
+/// same size threshold as the grouping, more than one percent of the tiles, so that the counts agree. Land is anything not grouped
+/// as ocean, so the ocean has to be marked first, by the ocean processes in the recipe or by an earlier run.
+pub(crate) fn count_continents(tile_map: &EntityIndex<TileSchema,TileForTerrain>) -> usize {
+    let minimum_size = tile_map.len().div_euclid(100);
+    let mut visited = HashSet::new();
+    let mut result = 0;
+
+    for (fid,tile) in tile_map.iter() {
+        if tile.grouping().is_ocean() || visited.contains(fid) {
+            continue;
+        }
+
+        _ = visited.insert(fid.clone());
+        let mut size = 0;
+        let mut queue = vec![fid.clone()];
+        while let Some(land_id) = queue.pop() {
+            size += 1;
+            if let Some(land) = tile_map.maybe_get(&land_id) {
+                for NeighborAndDirection(neighbor_id,_) in land.neighbors() {
+                    match neighbor_id {
+                        Neighbor::Tile(neighbor_id) | Neighbor::CrossMap(neighbor_id,_) => {
+                            if let Some(neighbor) = tile_map.maybe_get(neighbor_id) {
+                                if !neighbor.grouping().is_ocean() && visited.insert(neighbor_id.clone()) {
+                                    queue.push(neighbor_id.clone())
+                                }
+                            }
+                        },
+                        Neighbor::OffMap(_) => ()
+                    }
+                }
+            }
+        }
+
+        if size > minimum_size {
+            result += 1;
+        }
+    }
+
+    result
+}
+
 struct TerrainParameters {
     elevations: ElevationLimits,
     world_shape: WorldShape,
@@ -133,14 +217,14 @@ impl TerrainParameters {
     }
 
     /// whatever
-    fn gen_x<Random: Rng>(&self, rng: &mut Random, range: &ArgRange<f64>) -> f64 {
-        let x = ((range.choose(rng) / 100.0) * self.extents.width()).clamp(0.0, self.extents.width());
-        self.extents.west() + x
+    fn gen_x<Random: Rng>(&self, rng: &mut Random, range: &ArgRange<f64>) -> Result<f64,CommandError> {
+        let x = ((range.choose(rng)? / 100.0) * self.extents.width()).clamp(0.0, self.extents.width());
+        Ok(self.extents.west() + x)
     }
 
-    fn gen_y<Random: Rng>(&self, rng: &mut Random, range: &ArgRange<f64>) -> f64 {
-        let y = ((range.choose(rng) / 100.0) * self.extents.height()).clamp(0.0, self.extents.height());
-        self.extents.south() + y
+    fn gen_y<Random: Rng>(&self, rng: &mut Random, range: &ArgRange<f64>) -> Result<f64,CommandError> {
+        let y = ((range.choose(rng)? / 100.0) * self.extents.height()).clamp(0.0, self.extents.height());
+        Ok(self.extents.south() + y)
     }
 
     fn get_height_delta(&self, height_delta: i8) -> (f64,f64) {
@@ -161,14 +245,14 @@ impl TerrainParameters {
         value.copysign(sign)
     }
 
-    fn gen_height_delta<Random: Rng>(&self, rng: &mut Random, height_delta: &ArgRange<i8>) -> (f64,f64) {
-        let chosen = height_delta.choose(rng);
-        self.get_height_delta(chosen)
+    fn gen_height_delta<Random: Rng>(&self, rng: &mut Random, height_delta: &ArgRange<i8>) -> Result<(f64,f64),CommandError> {
+        let chosen = height_delta.choose(rng)?;
+        Ok(self.get_height_delta(chosen))
     }
 
-    fn gen_signed_height_delta<Random: Rng>(&self, rng: &mut Random, height_delta: &ArgRange<i8>) -> f64 {
-        let (value,sign) = self.gen_height_delta(rng, height_delta);
-        value.copysign(sign)
+    fn gen_signed_height_delta<Random: Rng>(&self, rng: &mut Random, height_delta: &ArgRange<i8>) -> Result<f64,CommandError> {
+        let (value,sign) = self.gen_height_delta(rng, height_delta)?;
+        Ok(value.copysign(sign))
     }
 
 
@@ -218,6 +302,15 @@ impl TerrainParameters {
                     self.convert_relative_height(single, &RelativeHeightTruncation::Ceil,true)
                 )
             },
+            Some(ArgRange::From(min)) => ArgRange::From(
+                self.convert_relative_height(*min, &RelativeHeightTruncation::Floor,true)
+            ),
+            Some(ArgRange::To(max)) => ArgRange::To(
+                self.convert_relative_height(*max, &RelativeHeightTruncation::Ceil,true)
+            ),
+            Some(ArgRange::ToInclusive(max)) => ArgRange::ToInclusive(
+                self.convert_relative_height(*max, &RelativeHeightTruncation::Ceil,true)
+            ),
             None => ArgRange::Inclusive(self.elevations.min_elevation(), self.elevations.max_elevation())
         }
     }
@@ -298,15 +391,17 @@ pub(crate) trait LoadTerrainTask {
 
 pub(crate) struct SampleOceanBelowLoaded {
     raster: RasterMap,
-    elevation: f64
+    elevation: f64,
+    coast_is_ocean: bool
 }
 
 impl SampleOceanBelowLoaded {
 
-    pub(crate) const fn new(raster: RasterMap, elevation: f64) -> Self {
+    pub(crate) const fn new(raster: RasterMap, elevation: f64, coast_is_ocean: bool) -> Self {
         Self {
             raster,
             elevation,
+            coast_is_ocean
         }
     }
 }
@@ -319,36 +414,16 @@ impl ProcessTerrainTiles for SampleOceanBelowLoaded {
 
         progress.start_unknown_endpoint(|| "Reading raster");
 
-        let band = self.raster.read_band::<f64>(1)?;
-        let bounds = self.raster.bounds()?;
-        let no_data_value = band.no_data_value();
+        // the ocean raster is read with its own bounds, so it does not need to match the resolution of the elevation source.
+        let ocean = self.raster.read_sampler(1)?;
     
         progress.finish(|| "Raster read.");
     
         for (_,tile) in tile_map.iter_mut().watch(progress,"Sampling oceans.","Oceans sampled.") {
     
             let (tile_x,tile_y) = tile.site().to_tuple();
-            let (x,y) = bounds.coords_to_pixels(tile_x, tile_y);
-
-            let is_ocean = if let Some(elevation) = band.get_value(x, y) {
-                let is_no_data = match no_data_value {
-                    Some(no_data_value) if no_data_value.is_nan() => elevation.is_nan(),
-                    Some(no_data_value) => (elevation - no_data_value).abs() < f64::EPSILON,
-                    None => false,
-                };
-
-                if is_no_data {
-                    false
-                } else {
-                    elevation < &self.elevation
-                }
 
-
-            } else {
-
-                false
-
-            };
+            let is_ocean = ocean.is_below(tile_x, tile_y, self.elevation, self.coast_is_ocean);
 
             // only apply if the data actually is ocean now, so one can use multiple ocean methods
             if is_ocean {
@@ -384,29 +459,15 @@ impl ProcessTerrainTiles for SampleOceanMaskedLoaded {
 
         progress.start_unknown_endpoint(|| "Reading raster");
 
-        let band = self.raster.read_band::<f64>(1)?;
-        let bounds = self.raster.bounds()?;
-        let no_data_value = band.no_data_value();
+        let ocean = self.raster.read_sampler(1)?;
     
         progress.finish(|| "Raster read.");
     
         for (_,tile) in tile_map.iter_mut().watch(progress,"Sampling oceans.","Oceans sampled.") {
     
             let (tile_x,tile_y) = tile.site().to_tuple();
-            let (x,y) = bounds.coords_to_pixels(tile_x, tile_y);
 
-            let is_ocean = if let Some(elevation) = band.get_value(x, y) {
-                match no_data_value {
-                    Some(no_data_value) if no_data_value.is_nan() => !elevation.is_nan(),
-                    Some(no_data_value) => (elevation - no_data_value).abs() > f64::EPSILON,
-                    None => true,
-                }
-
-            } else {
-
-                false
-
-            };
+            let is_ocean = ocean.has_data(tile_x, tile_y);
 
             // only apply if the data actually is ocean now, so one can use multiple ocean methods
             if is_ocean {
@@ -420,13 +481,17 @@ impl ProcessTerrainTiles for SampleOceanMaskedLoaded {
 }
 
 pub(crate) struct SampleElevationLoaded {
-    raster: RasterMap
+    raster: RasterMap,
+    overlays: Vec<RasterMap>,
+    resample: ResampleMethod
 }
 
 impl SampleElevationLoaded {
-    pub(crate) const fn new(raster: RasterMap) -> Self {
+    pub(crate) const fn new(raster: RasterMap, overlays: Vec<RasterMap>, resample: ResampleMethod) -> Self {
         Self {
-            raster
+            raster,
+            overlays,
+            resample
         }
     }
 }
@@ -439,21 +504,20 @@ impl ProcessTerrainTiles for SampleElevationLoaded {
 
         progress.start_unknown_endpoint(|| "Reading raster");
 
-        let raster = &self.raster;
-
-        let band = raster.read_band::<f64>(1)?;
-        let bounds = raster.bounds()?;
+        let base = self.raster.read_sampler(1)?;
+        let overlays = self.overlays.iter().map(|overlay| overlay.read_sampler(1)).collect::<Result<Vec<_>,_>>()?;
     
         progress.finish(|| "Raster read.");
     
         for (_,tile) in tile_map.iter_mut().watch(progress,"Sampling elevations.","Elevations sampled.") {
     
             let (tile_x,tile_y) = tile.site().to_tuple();
-            let (x,y) = bounds.coords_to_pixels(tile_x, tile_y);
 
-            if let Some(elevation) = band.get_value(x, y) {
+            if let Some(elevation) = RasterSampler::sample_with_overlays(&base, &overlays, tile_x, tile_y, self.resample) {
 
-                tile.set_elevation(*elevation);
+                tile.set_elevation(elevation);
+                // The depth is only recorded here, before the elevation is clamped to the world's limits, so it keeps the full depth from the source.
+                tile.set_depth((elevation < 0.0).then_some(elevation));
     
             }
     
@@ -469,20 +533,21 @@ impl ProcessTerrainTilesWithPointIndex for AddHill {
 
     fn process_terrain_tiles_with_point_index<Random: Rng, Progress: ProgressObserver>(&self, rng: &mut Random, parameters: &TerrainParameters, point_index: &TileFinder, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
         
-        let count = self.count.choose(rng);
+        let count = self.count.choose(rng)?;
 
         progress.announce(&format!("Generating {count} hills."));
 
 
         for i in 0..count {
-            let mut change_map = HashMap::new();
-            let (height_delta,sign) = parameters.gen_height_delta(rng, &self.height_delta);
+            // an IndexMap keeps the changes in the order they were generated, so they are applied the same way every time.
+            let mut change_map = IndexMap::new();
+            let (height_delta,sign) = parameters.gen_height_delta(rng, &self.height_delta)?;
 
             let mut start;
             let mut limit = 0;
             loop {
-                let x = parameters.gen_x(rng, &self.x_filter);
-                let y = parameters.gen_y(rng, &self.y_filter);
+                let x = parameters.gen_x(rng, &self.x_filter)?;
+                let y = parameters.gen_y(rng, &self.y_filter)?;
                 start = point_index.find_nearest_tile(&(x,y).try_into()?)?;
                 let start_tile = tile_map.try_get(&start)?;
 
@@ -518,7 +583,6 @@ impl ProcessTerrainTilesWithPointIndex for AddHill {
                 }
             }
 
-            #[allow(clippy::iter_over_hash_type)] // TODO: Maybe go through and find where I've allowed this, and change those to Sortable HashSets and HashMaps, just to allow for better reproducibility
             for (tile_id,calculated_height_delta) in change_map {
                 *tile_map.try_get_mut(&tile_id)?.elevation_mut() += calculated_height_delta.copysign(sign);
             }
@@ -534,7 +598,7 @@ impl ProcessTerrainTilesWithPointIndex for AddRange {
 
     fn process_terrain_tiles_with_point_index<Random: Rng, Progress: ProgressObserver>(&self, rng: &mut Random, parameters: &TerrainParameters, point_index: &TileFinder, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
 
-        let count = self.count.choose(rng);
+        let count = self.count.choose(rng)?;
 
         progress.announce(&format!("Generating {count} ranges."));
 
@@ -545,11 +609,11 @@ impl ProcessTerrainTilesWithPointIndex for AddRange {
             // add one range
 
             let mut used = HashSet::new();
-            let (mut height_delta,sign) = parameters.gen_height_delta(rng, &self.height_delta);
+            let (mut height_delta,sign) = parameters.gen_height_delta(rng, &self.height_delta)?;
 
             // find start and end points
-            let start_x = parameters.gen_x(rng, &self.x_filter);
-            let start_y = parameters.gen_y(rng, &self.y_filter);
+            let start_x = parameters.gen_x(rng, &self.x_filter)?;
+            let start_y = parameters.gen_y(rng, &self.y_filter)?;
             let start_point: Coordinates = (start_x, start_y).try_into()?;
             let mut end_point;
 
@@ -716,7 +780,7 @@ impl ProcessTerrainTilesWithPointIndex for AddStrait {
         let tiles_x = ((parameters.extents.width()/parameters.extents.height())*tile_map.len() as f64).sqrt();
 
         // don't let it get more than one third as wide as the map.
-        let mut width = self.width.choose(rng).min(tiles_x/3.0);
+        let mut width = self.width.choose(rng)?.min(tiles_x/3.0);
         // if it's too small, return
         if width < 1.0 && rng.gen_bool(width) {
             progress.announce("Strait improbable, will not be generated.");
@@ -861,68 +925,116 @@ impl ProcessTerrainTilesWithPointIndex for Invert {
 
         progress.announce("Inverting elevations.");
 
-        // I can't modify the elevations inline as I need access to the other tile elevations as I do it.
-        let mut inverted_heights = Vec::new();
-        let mut switches = HashMap::new();
+        mirror_tiles(&self.axes, false, parameters, point_index, tile_map, progress)
 
-        for (fid,tile) in tile_map.iter().watch(progress, "Inverting elevations.", "Elevations inverted.") {
-            let (x,y) = tile.site().to_tuple();
+    }
 
-            macro_rules! switch_x {
-                () => {{
-                    let x = x - parameters.extents.west();
-                    let switch_x = parameters.extents.width() - x;
-                    parameters.extents.west() + switch_x
-                }};
-            }
+}
 
-            macro_rules! switch_y {
-                () => {{
-                    let y = y - parameters.extents.south();
-                    let switch_y = parameters.extents.height() - y;
-                    parameters.extents.south() + switch_y
-                }};
-            }
+impl ProcessTerrainTilesWithPointIndex for FlipHorizontal {
+    fn process_terrain_tiles_with_point_index<Random: Rng, Progress: ProgressObserver>(&self, _: &mut Random, parameters: &TerrainParameters, point_index: &TileFinder, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
 
-            // reducing this down to one check on self.axes did not produce significant speed improvements
-            let (switch_x, switch_y) = match self.axes {
-                InvertAxes::X => (switch_x!(),y),
-                InvertAxes::Y => (x,switch_y!()),
-                InvertAxes::Both => (switch_x!(),switch_y!()),
-            };
+        progress.announce("Flipping map horizontally.");
 
-            let switch_point = (switch_x, switch_y).try_into()?;
+        mirror_tiles(&InvertAxes::X, true, parameters, point_index, tile_map, progress)
 
-            // cache the calculation
-            let switch_tile_id = match switches.get(fid) {
-                None => {
-                    // NOTE: This is where the most time is spent. Removing this and setting switch_tile_id to a constant value 
-                    // sped up things about 90%. Of course, it also broke the algorithm.
-                    let switch_tile_id = point_index.find_nearest_tile(&switch_point)?;
-                    _ = switches.insert(switch_tile_id.clone(), fid.clone());     
-                    switch_tile_id               
-                },
-                Some(id) => id.clone(),
-            };
+    }
 
+}
 
-            let switch_tile = tile_map.try_get(&switch_tile_id)?;
+impl ProcessTerrainTilesWithPointIndex for FlipVertical {
+    fn process_terrain_tiles_with_point_index<Random: Rng, Progress: ProgressObserver>(&self, _: &mut Random, parameters: &TerrainParameters, point_index: &TileFinder, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
 
-            // removing this command did not produce significant speed improvements for this part of the progress,
-            // so this isn't adding to the time. (And would have broken the algorithm)
-            inverted_heights.push((fid.clone(), *switch_tile.elevation()));
+        progress.announce("Flipping map vertically.");
 
+        mirror_tiles(&InvertAxes::Y, true, parameters, point_index, tile_map, progress)
+
+    }
+
+}
+
+/// Replaces the elevations of tiles with the tile on the opposite side of the map along the specified axes, and optionally the groupings as well.
+fn mirror_tiles<Progress: ProgressObserver>(axes: &InvertAxes, include_groupings: bool, parameters: &TerrainParameters, point_index: &TileFinder, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
+
+    // I can't modify the elevations inline as I need access to the other tile elevations as I do it.
+    let mut inverted_heights = Vec::new();
+    let mut switches = HashMap::new();
+
+    for (fid,tile) in tile_map.iter().watch(progress, "Inverting elevations.", "Elevations inverted.") {
+        let (x,y) = tile.site().to_tuple();
+
+        macro_rules! switch_x {
+            () => {{
+                let x = x - parameters.extents.west();
+                let switch_x = parameters.extents.width() - x;
+                parameters.extents.west() + switch_x
+            }};
         }
 
-        for (fid,elevation) in inverted_heights.into_iter().watch(progress, "Writing inversions.", "Inversions written.") {
-            tile_map.try_get_mut(&fid)?.set_elevation(elevation);
+        macro_rules! switch_y {
+            () => {{
+                let y = y - parameters.extents.south();
+                let switch_y = parameters.extents.height() - y;
+                parameters.extents.south() + switch_y
+            }};
         }
 
-        Ok(())
+        // reducing this down to one check on self.axes did not produce significant speed improvements
+        let (switch_x, switch_y) = match axes {
+            InvertAxes::X => (switch_x!(),y),
+            InvertAxes::Y => (x,switch_y!()),
+            InvertAxes::Both => (switch_x!(),switch_y!()),
+        };
+
+        let switch_point = (switch_x, switch_y).try_into()?;
+
+        // cache the calculation
+        let switch_tile_id = match switches.get(fid) {
+            None => {
+                // NOTE: This is where the most time is spent. Removing this and setting switch_tile_id to a constant value 
+                // sped up things about 90%. Of course, it also broke the algorithm.
+                let switch_tile_id = point_index.find_nearest_tile(&switch_point)?;
+                _ = switches.insert(switch_tile_id.clone(), fid.clone());     
+                switch_tile_id               
+            },
+            Some(id) => id.clone(),
+        };
+
+
+        let switch_tile = tile_map.try_get(&switch_tile_id)?;
 
+        // removing this command did not produce significant speed improvements for this part of the progress,
+        // so this isn't adding to the time. (And would have broken the algorithm)
+        inverted_heights.push((fid.clone(), *switch_tile.elevation(), include_groupings.then(|| switch_tile.grouping().clone())));
 
     }
 
+    for (fid,elevation,grouping) in inverted_heights.into_iter().watch(progress, "Writing inversions.", "Inversions written.") {
+        let tile = tile_map.try_get_mut(&fid)?;
+        tile.set_elevation(elevation);
+        if let Some(grouping) = grouping {
+            tile.set_grouping(grouping);
+        }
+    }
+
+    Ok(())
+
+}
+
+impl ProcessTerrainTiles for InvertElevations {
+    fn process_terrain_tiles<Random: Rng, Progress: ProgressObserver>(&self, _: &mut Random, parameters: &TerrainParameters, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
+        progress.announce("Turning elevations upside down.");
+
+        // reflecting around the middle of the limits, instead of subtracting from the maximum, keeps the elevations inside the limits.
+        let pivot = parameters.elevations.max_elevation() + parameters.elevations.min_elevation();
+
+        for (_,tile) in tile_map.iter_mut().watch(progress, "Inverting elevations.", "Elevations inverted.") {
+            let elevation = tile.elevation_mut();
+            *elevation = pivot - *elevation;
+        }
+
+        Ok(())
+    }
 }
 
 
@@ -932,11 +1044,12 @@ impl ProcessTerrainTiles for Add {
         progress.announce(&format!("Adding {} to some elevations.",self.height_delta));
 
         let filter = parameters.convert_height_filter(&self.height_filter);
+        let tile_filter = self.filter.filter()?;
         let height_delta = parameters.get_signed_height_delta(self.height_delta);
 
         for (_,tile) in tile_map.iter_mut().watch(progress, "Adding heights.", "Heights added.") {
 
-            if filter.includes(tile.elevation()) {
+            if filter.includes(tile.elevation()) && tile_filter.includes(tile.site()) {
                 *tile.elevation_mut() += height_delta;
             }
         }
@@ -952,10 +1065,11 @@ impl ProcessTerrainTiles for Multiply {
         progress.announce(&format!("Multiplying some elevations by {}.",self.height_factor));
 
         let filter = parameters.convert_height_filter(&self.height_filter);
+        let tile_filter = self.filter.filter()?;
 
         for (_,tile) in tile_map.iter_mut().watch(progress, "Multiplying heights.", "Heights multiplied.") {
 
-            if filter.includes(tile.elevation()) {
+            if filter.includes(tile.elevation()) && tile_filter.includes(tile.site()) {
                 *tile.elevation_mut() *= self.height_factor;
             }
         }
@@ -966,6 +1080,52 @@ impl ProcessTerrainTiles for Multiply {
 }
 
 
+impl ProcessTerrainTiles for Normalize {
+    fn process_terrain_tiles<Random: Rng, Progress: ProgressObserver>(&self, _: &mut Random, parameters: &TerrainParameters, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
+        let target = ElevationLimits::new(
+            self.min_elevation.unwrap_or_else(|| parameters.elevations.min_elevation()),
+            self.max_elevation.unwrap_or_else(|| parameters.elevations.max_elevation())
+        )?;
+
+        progress.announce(&format!("Normalizing elevations to between {} and {}.",target.min_elevation(),target.max_elevation()));
+
+        let Some(stats) = tile_map.reduce_field(|tile| *tile.elevation()) else {
+            progress.warning(|| "There are no tiles, so elevations can not be normalized.");
+            return Ok(())
+        };
+
+        // Sea level stays where it is, so that no tile moves into or out of the ocean. The elevations below and above it are scaled separately.
+        let below_factor = if stats.min() < 0.0 {
+            if target.min_elevation() < 0.0 {
+                target.min_elevation() / stats.min()
+            } else {
+                progress.warning(|| "The new minimum elevation is not below sea level, so elevations below sea level were not changed.");
+                1.0
+            }
+        } else {
+            1.0
+        };
+        let above_factor = if stats.max() > 0.0 {
+            target.max_elevation() / stats.max()
+        } else {
+            1.0
+        };
+
+        for (_,tile) in tile_map.iter_mut().watch(progress, "Normalizing heights.", "Heights normalized.") {
+            let elevation = tile.elevation_mut();
+            if *elevation < 0.0 {
+                *elevation *= below_factor;
+            } else {
+                *elevation *= above_factor;
+            }
+        }
+
+        Ok(())
+
+    }
+}
+
+
 impl ProcessTerrainTiles for Smooth {
     fn process_terrain_tiles<Random: Rng, Progress: ProgressObserver>(&self, _: &mut Random, parameters: &TerrainParameters, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
 
@@ -1148,14 +1308,14 @@ impl ProcessTerrainTilesWithPointIndex for SeedOcean {
             progress.announce("World is above sea level, ocean seeds will not be placed.")
         }
 
-        let count = self.count.choose(rng);
+        let count = self.count.choose(rng)?;
 
         progress.announce(&format!("Placing {count} ocean seeds."));
 
         for _ in 0..count {
 
-            let x = parameters.gen_x(rng, &self.x_filter);
-            let y = parameters.gen_y(rng, &self.y_filter);
+            let x = parameters.gen_x(rng, &self.x_filter)?;
+            let y = parameters.gen_y(rng, &self.y_filter)?;
             let mut seed_id = point_index.find_nearest_tile(&(x,y).try_into()?)?;
 
             progress.start_unknown_endpoint(|| "Tracing seed down hill.");
@@ -1304,11 +1464,12 @@ impl ProcessTerrainTiles for RandomUniform {
         progress.announce("Generating random noise.");
 
         let filter = parameters.convert_height_filter(&self.height_filter);
+        let tile_filter = self.filter.filter()?;
 
         for (_,tile) in tile_map.iter_mut().watch(progress, "Making some noise.", "Noise made.") {
 
-            if filter.includes(tile.elevation()) {
-                let height_delta = parameters.gen_signed_height_delta(rng, &self.height_delta);
+            if filter.includes(tile.elevation()) && tile_filter.includes(tile.site()) {
+                let height_delta = parameters.gen_signed_height_delta(rng, &self.height_delta)?;
                 *tile.elevation_mut() += height_delta;
             }
         }
@@ -1327,8 +1488,12 @@ pub(crate) enum TerrainTask {
     AddStrait(AddStrait),
     Mask(Mask),
     Invert(Invert),
+    FlipHorizontal(FlipHorizontal),
+    FlipVertical(FlipVertical),
+    InvertElevations(InvertElevations),
     Add(Add),
     Multiply(Multiply),
+    Normalize(Normalize),
     Smooth(Smooth),
     Erode(Erode),
     SeedOcean(SeedOcean),
@@ -1341,13 +1506,20 @@ pub(crate) enum TerrainTask {
 
 impl TerrainTask {
 
-    pub(crate) fn process_terrain<Random: Rng, Progress: ProgressObserver>(selves: &[Self], rng: &mut Random, target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(),CommandError> {
+    pub(crate) fn process_terrain<Random: Rng, Progress: ProgressObserver>(selves: &[Self], continent_count: &ContinentCountArg, rng: &mut Random, target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<TerrainChanges,CommandError> {
 
-        if !selves.is_empty() {
+        if selves.is_empty() {
+            // there are no processes, so don't bother doing anything.
+            Ok(TerrainChanges::default())
+        } else {
 
 
             progress.announce("Preparing for processes.");
 
+            // The processes draw from their own stream, seeded once from the caller's generator. This way, the tasks in a recipe
+            // don't change the random numbers available to whatever runs after them.
+            let seed = rng.gen();
+
             let mut properties = target.edit_properties_layer()?;
             let limits = properties.get_elevation_limits()?;
             let world_shape = properties.get_world_shape()?;
@@ -1361,7 +1533,7 @@ impl TerrainTask {
     
             // I only want to create the point index if any of the tasks require it. If none of them
             // require it, it's a waste of time to create it.
-            let tile_map = if selves.iter().any(Self::requires_point_index) {
+            let (point_index,tile_map) = if selves.iter().any(Self::requires_point_index) {
                 // estimate the spacing between tiles:
                 // * divide the area of the extents up by the number of tiles to get the average area covered by a tile.
                 // * the distance across, if the tiles were square, is the square root of this area.
@@ -1370,27 +1542,21 @@ impl TerrainTask {
     
     
                 let mut point_index = TileFinder::new(&tile_extents, parameters.world_shape.clone(), tile_count, tile_search_radius);
-                let mut tile_map = layer.read_features().into_entities_index_for_each::<_,TileForTerrain,_>(|fid,tile| {
+                let tile_map = layer.read_features().into_entities_index_for_each::<_,TileForTerrain,_>(|fid,tile| {
                     point_index.add_tile(tile.site().clone(), fid.clone())
                 }, progress)?;
     
-                for me in selves {
-                    me.process_terrain_tiles_with_point_index(rng, &parameters, &point_index, &mut tile_map, progress)?;
-                }
-    
-                tile_map    
+                (Some(point_index),tile_map)
     
             } else {
-                let mut tile_map = layer.read_features().into_entities_index::<_,TileForTerrain>(progress)?;
-                for me in selves {
-                    me.process_terrain_tiles(rng, &parameters, &mut tile_map, progress)?;
-                }
-    
-                tile_map
-        
+                (None,layer.read_features().into_entities_index::<_,TileForTerrain>(progress)?)
             };
+
+            let tile_map = Self::process_terrain_tiles_to_continent_count(selves, continent_count, seed, &parameters, point_index.as_ref(), tile_map, progress)?;
     
         
+            let changes = TerrainChanges::count(&tile_map);
+
             let mut bad_ocean_tiles_found = Vec::new();
         
             for (fid,tile) in tile_map.into_iter().watch(progress,"Writing data.","Data written.") {
@@ -1398,7 +1564,8 @@ impl TerrainTask {
                 
                 let elevation_changed = tile.elevation_changed();
                 let grouping_changed = tile.grouping_changed();
-                if elevation_changed || grouping_changed {
+                let depth_changed = tile.depth_changed();
+                if elevation_changed || grouping_changed || depth_changed {
     
                     // warn user if a tile was set to ocean that's above 0.
                     if matches!(tile.grouping(),Grouping::Ocean) && (tile.elevation() > &0.0) {
@@ -1422,6 +1589,9 @@ impl TerrainTask {
                         // Should I check to make sure?
                         feature.set_grouping(tile.grouping())?;
                     }
+                    if depth_changed {
+                        feature.set_depth(tile.depth())?;
+                    }
                     layer.update_feature(feature)?;
     
                 }
@@ -1431,14 +1601,69 @@ impl TerrainTask {
             if !bad_ocean_tiles_found.is_empty() {
                 progress.warning(|| format!("At least one ocean tile was found with an elevation above 0 (id: {}).",bad_ocean_tiles_found[0]))
             }
-                
 
-        } // else there are no processes, so don't bother doing anything.
+            Ok(changes)
 
+        }
+
+    }
 
+    fn process_recipe_tiles<Random: Rng, Progress: ProgressObserver>(selves: &[Self], rng: &mut Random, parameters: &TerrainParameters, point_index: Option<&TileFinder>, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<(),CommandError> {
+        for me in selves {
+            match point_index {
+                Some(point_index) => me.process_terrain_tiles_with_point_index(rng, parameters, point_index, tile_map, progress)?,
+                None => me.process_terrain_tiles(rng, parameters, tile_map, progress)?
+            }
+        }
         Ok(())
     }
 
+    /// Runs the processes over the tiles. If a continent count was asked for, each attempt runs on a fresh copy of the original tiles
+    /// with a seed derived from the last one, until the count is met or the attempts run out. The attempt closest to the count is kept.
+    fn process_terrain_tiles_to_continent_count<Progress: ProgressObserver>(selves: &[Self], continent_count: &ContinentCountArg, seed: u64, parameters: &TerrainParameters, point_index: Option<&TileFinder>, mut tile_map: EntityIndex<TileSchema,TileForTerrain>, progress: &mut Progress) -> Result<EntityIndex<TileSchema,TileForTerrain>,CommandError> {
+
+        let Some(target_count) = continent_count.continent_count else {
+            Self::process_recipe_tiles(selves, &mut StdRng::seed_from_u64(seed), parameters, point_index, &mut tile_map, progress)?;
+            return Ok(tile_map)
+        };
+
+        if !selves.iter().any(Self::marks_ocean) {
+            progress.warning(|| "The terrain recipe has no ocean processes, so continents are counted against the ocean already on the map.")
+        }
+
+        let attempts = continent_count.continent_attempts.max(1);
+        let mut seed = seed;
+        let mut best: Option<(usize,EntityIndex<TileSchema,TileForTerrain>)> = None;
+
+        for attempt in 1..=attempts {
+            let mut attempt_map = tile_map.clone();
+            Self::process_recipe_tiles(selves, &mut StdRng::seed_from_u64(seed), parameters, point_index, &mut attempt_map, progress)?;
+            let found = count_continents(&attempt_map);
+            progress.message(|| format!("Terrain attempt {attempt} of {attempts} produced {found} continents."));
+
+            let is_closer = best.as_ref().is_none_or(|(best_found,_)| found.abs_diff(target_count) < best_found.abs_diff(target_count));
+            if is_closer {
+                best = Some((found,attempt_map));
+            }
+            if found == target_count {
+                break;
+            }
+            seed = derive_retry_seed(seed);
+        }
+
+        let (found,best_map) = best.expect("There is always at least one attempt.");
+        if found != target_count {
+            progress.warning(|| format!("Could not produce {target_count} continents in {attempts} attempts, keeping the closest result with {found}."))
+        }
+        Ok(best_map)
+
+    }
+
+    /// Whether the process can turn tiles into ocean, which is needed before continents can be counted.
+    const fn marks_ocean(&self) -> bool {
+        matches!(self,Self::SeedOcean(_) | Self::FillOcean(_) | Self::FloodOcean(_) | Self::SampleOceanMasked(_) | Self::SampleOceanBelow(_))
+    }
+
     fn requires_point_index(&self) -> bool {
         match self {
             Self::ClearOcean(params) => params.requires_point_index(),
@@ -1448,8 +1673,12 @@ impl TerrainTask {
             Self::AddStrait(params) => params.requires_point_index(),
             Self::Mask(params) => params.requires_point_index(),
             Self::Invert(params) => params.requires_point_index(),
+            Self::FlipHorizontal(params) => params.requires_point_index(),
+            Self::FlipVertical(params) => params.requires_point_index(),
+            Self::InvertElevations(params) => params.requires_point_index(),
             Self::Add(params) => params.requires_point_index(),
             Self::Multiply(params) => params.requires_point_index(),
+            Self::Normalize(params) => params.requires_point_index(),
             Self::Smooth(params) => params.requires_point_index(),
             Self::Erode(params) => params.requires_point_index(),
             Self::SeedOcean(params) => params.requires_point_index(),
@@ -1470,8 +1699,12 @@ impl TerrainTask {
             Self::AddStrait(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
             Self::Mask(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
             Self::Invert(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
+            Self::FlipHorizontal(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
+            Self::FlipVertical(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
+            Self::InvertElevations(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
             Self::Add(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
             Self::Multiply(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
+            Self::Normalize(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
             Self::Smooth(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
             Self::Erode(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
             Self::SeedOcean(params) => params.process_terrain_tiles(rng,limits,tile_map,progress),
@@ -1492,8 +1725,12 @@ impl TerrainTask {
             Self::AddStrait(params) => params.process_terrain_tiles_with_point_index(rng, limits, point_index, tile_map, progress),
             Self::Mask(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
             Self::Invert(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
+            Self::FlipHorizontal(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
+            Self::FlipVertical(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
+            Self::InvertElevations(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
             Self::Add(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
             Self::Multiply(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
+            Self::Normalize(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
             Self::Smooth(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
             Self::Erode(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
             Self::SeedOcean(params) => params.process_terrain_tiles_with_point_index(rng,limits,point_index,tile_map,progress),
@@ -1508,3 +1745,301 @@ impl TerrainTask {
 
 }
 
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::TerrainChanges;
+    use super::TerrainParameters;
+    use super::TerrainTask;
+    use super::count_continents;
+    use crate::commands::ContinentCountArg;
+    use crate::progress::DiagnosticsObserver;
+    use crate::commands::terrain::ClearOcean;
+    use crate::commands::terrain::FillOcean;
+    use crate::commands::TileFilterArg;
+    use crate::commands::terrain::Add;
+    use crate::commands::terrain::AddHill;
+    use crate::commands::terrain::AddRange;
+    use crate::commands::terrain::FlipHorizontal;
+    use crate::commands::terrain::InvertElevations;
+    use crate::commands::terrain::Multiply;
+    use crate::commands::terrain::Normalize;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::utils::arg_range::ArgRange;
+    use crate::utils::extent::Extent;
+    use crate::utils::point_finder::TileFinder;
+    use crate::utils::world_shape::WorldShape;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::property_layer::ElevationLimits;
+    use crate::world_map::tile_layer::TileForTerrain;
+    use crate::world_map::tile_layer::TileSchema;
+
+    fn tile_id(column: i32, row: i32) -> IdRef {
+        grid_tile_id(column, row, 10)
+    }
+
+    fn grid_tile_id(column: i32, row: i32, size: i32) -> IdRef {
+        IdRef::new(u64::try_from(row * size + column + 1).unwrap())
+    }
+
+    /// Creates a ten by ten grid of tiles, with elevations rising from west to east, and an ocean along the western edge.
+    fn create_tile_map() -> EntityIndex<TileSchema,TileForTerrain> {
+        create_grid_tile_map(10, |column| f64::from(column).mul_add(1000.0, -1000.0))
+    }
+
+    /// Creates a square grid of tiles, with elevations chosen by column. Tiles below sea level are ocean.
+    fn create_grid_tile_map(size: i32, elevation: fn(i32) -> f64) -> EntityIndex<TileSchema,TileForTerrain> {
+        let mut tiles = Vec::new();
+        for row in 0..size {
+            for column in 0..size {
+                let elevation = elevation(column);
+                let grouping = if elevation < 0.0 {
+                    Grouping::Ocean
+                } else {
+                    Grouping::Continent
+                };
+                let site = (f64::from(column) + 0.5, f64::from(row) + 0.5).try_into().unwrap();
+                let neighbors = [(0,1,0.0),(1,0,90.0),(0,-1,180.0),(-1,0,270.0)].into_iter().filter_map(|(column_delta,row_delta,direction)| {
+                    let neighbor_column = column + column_delta;
+                    let neighbor_row = row + row_delta;
+                    ((0..size).contains(&neighbor_column) && (0..size).contains(&neighbor_row)).then(|| NeighborAndDirection(Neighbor::Tile(grid_tile_id(neighbor_column, neighbor_row, size)),Deg(direction)))
+                }).collect();
+                tiles.push((grid_tile_id(column, row, size),TileForTerrain::new(site, elevation, grouping, neighbors)));
+            }
+        }
+        tiles.into_iter().collect()
+    }
+
+    fn create_parameters(tile_map: &EntityIndex<TileSchema,TileForTerrain>) -> TerrainParameters {
+        let size = (tile_map.len() as f64).sqrt();
+        TerrainParameters::new(WorldShape::Cylinder, ElevationLimits::new(-1000.0, 9000.0).unwrap(), Extent::new_with_dimensions(0.0, 0.0, size, size), tile_map.len())
+    }
+
+    fn run_tasks(tasks: &[TerrainTask], seed: u64, tile_map: &mut EntityIndex<TileSchema,TileForTerrain>) {
+        let parameters = create_parameters(tile_map);
+        let mut random = StdRng::seed_from_u64(seed);
+        if tasks.iter().any(TerrainTask::requires_point_index) {
+            let mut point_index = TileFinder::new(&parameters.extents, parameters.world_shape.clone(), tile_map.len(), 2.0);
+            for (fid,tile) in tile_map.iter() {
+                point_index.add_tile(tile.site().clone(), fid.clone()).unwrap();
+            }
+            for task in tasks {
+                task.process_terrain_tiles_with_point_index(&mut random, &parameters, &point_index, tile_map, &mut ()).unwrap();
+            }
+        } else {
+            for task in tasks {
+                task.process_terrain_tiles(&mut random, &parameters, tile_map, &mut ()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_op_recipe_changes_nothing() {
+        let mut tile_map = create_tile_map();
+        run_tasks(&[
+            TerrainTask::Multiply(Multiply {
+                height_filter: None,
+                filter: TileFilterArg::default(),
+                height_factor: 1.0
+            }),
+            TerrainTask::Add(Add {
+                height_filter: None,
+                filter: TileFilterArg::default(),
+                height_delta: 0
+            })
+        ], 0, &mut tile_map);
+
+        assert_eq!(TerrainChanges::count(&tile_map),TerrainChanges::default());
+    }
+
+    #[test]
+    fn test_changes_counted() {
+        let mut tile_map = create_tile_map();
+        run_tasks(&[
+            TerrainTask::Add(Add {
+                height_filter: None,
+                filter: TileFilterArg::default(),
+                height_delta: 10
+            })
+        ], 0, &mut tile_map);
+
+        let changes = TerrainChanges::count(&tile_map);
+        assert_eq!(changes.elevation(),tile_map.len());
+        assert_eq!(changes.grouping(),0);
+    }
+
+    #[test]
+    fn test_latitude_band() {
+        let mut tile_map = create_tile_map();
+        run_tasks(&[
+            TerrainTask::Add(Add {
+                height_filter: None,
+                filter: TileFilterArg {
+                    latitudes: Some("3..5".parse().unwrap()),
+                    region: None
+                },
+                height_delta: 10
+            })
+        ], 0, &mut tile_map);
+
+        // only the two rows of tiles with sites in the band are changed.
+        assert_eq!(TerrainChanges::count(&tile_map).elevation(),20);
+        let original = create_tile_map();
+        for (fid,tile) in tile_map.iter() {
+            let changed = tile.elevation().to_bits() != original.try_get(fid).unwrap().elevation().to_bits();
+            assert_eq!(changed,(3.0..5.0).contains(&tile.site().y().into_inner()),"tile {fid}");
+        }
+    }
+
+    #[test]
+    fn test_recipe_is_reproducible() {
+        let recipe = || [
+            TerrainTask::AddHill(AddHill {
+                count: ArgRange::Inclusive(2, 4),
+                height_delta: ArgRange::Inclusive(20, 40),
+                x_filter: ArgRange::Exclusive(20.0, 80.0),
+                y_filter: ArgRange::Exclusive(20.0, 80.0)
+            }),
+            TerrainTask::AddRange(AddRange {
+                count: ArgRange::Single(1),
+                height_delta: ArgRange::Inclusive(20, 40),
+                x_filter: ArgRange::Exclusive(20.0, 80.0),
+                y_filter: ArgRange::Exclusive(20.0, 80.0)
+            })
+        ];
+
+        let mut first = create_tile_map();
+        run_tasks(&recipe(), 42, &mut first);
+        let mut second = create_tile_map();
+        run_tasks(&recipe(), 42, &mut second);
+
+        assert_ne!(TerrainChanges::count(&first),TerrainChanges::default());
+
+        let elevations = |tile_map: &EntityIndex<TileSchema,TileForTerrain>| tile_map.iter().map(|(fid,tile)| (fid.clone(),tile.elevation().to_bits())).collect::<Vec<_>>();
+        assert_eq!(elevations(&first),elevations(&second));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let elevation_range = |tile_map: &EntityIndex<TileSchema,TileForTerrain>| tile_map.iter().fold((f64::INFINITY,f64::NEG_INFINITY),|(min,max),(_,tile)| (min.min(*tile.elevation()),max.max(*tile.elevation())));
+
+        // the map starts out between -1000 and 8000, which doesn't reach the world maximum
+        let mut tile_map = create_tile_map();
+        assert_eq!(elevation_range(&tile_map),(-1000.0,8000.0));
+
+        run_tasks(&[TerrainTask::Normalize(Normalize {
+            min_elevation: None,
+            max_elevation: None
+        })], 0, &mut tile_map);
+        assert_eq!(elevation_range(&tile_map),(-1000.0,9000.0));
+
+        run_tasks(&[TerrainTask::Normalize(Normalize {
+            min_elevation: Some(-500.0),
+            max_elevation: Some(4500.0)
+        })], 0, &mut tile_map);
+        assert_eq!(elevation_range(&tile_map),(-500.0,4500.0));
+
+        // a range that is mostly below sea level would move the coast if it were scaled linearly
+        run_tasks(&[TerrainTask::Normalize(Normalize {
+            min_elevation: Some(-5000.0),
+            max_elevation: Some(1000.0)
+        })], 0, &mut tile_map);
+        assert_eq!(elevation_range(&tile_map),(-5000.0,1000.0));
+
+        // ocean is not recalculated, but it is still exactly the tiles below sea level.
+        assert_eq!(TerrainChanges::count(&tile_map).grouping(),0);
+        for (fid,tile) in tile_map.iter() {
+            assert_eq!(matches!(tile.grouping(),Grouping::Ocean),tile.elevation() < &0.0,"tile {fid} is on the wrong side of sea level");
+        }
+        assert_eq!(tile_map.try_get(&tile_id(1, 0)).unwrap().elevation().to_bits(),0.0_f64.to_bits());
+    }
+
+    #[test]
+    fn test_invert_elevations_twice() {
+        let original = create_tile_map();
+        let mut tile_map = create_tile_map();
+
+        run_tasks(&[TerrainTask::InvertElevations(InvertElevations{})], 0, &mut tile_map);
+        // the lowest tile is now the highest
+        assert_eq!(tile_map.try_get(&tile_id(0, 0)).unwrap().elevation().to_bits(),9000.0_f64.to_bits());
+
+        run_tasks(&[TerrainTask::InvertElevations(InvertElevations{})], 0, &mut tile_map);
+        for (fid,tile) in tile_map.iter() {
+            assert_eq!(tile.elevation().to_bits(),original.try_get(fid).unwrap().elevation().to_bits());
+        }
+    }
+
+    #[test]
+    fn test_flip_horizontal() {
+        // the map starts out high in the east, with ocean in the west
+        let mut tile_map = create_tile_map();
+
+        run_tasks(&[TerrainTask::FlipHorizontal(FlipHorizontal{})], 0, &mut tile_map);
+
+        for row in 0..10 {
+            let west = tile_map.try_get(&tile_id(0, row)).unwrap();
+            let east = tile_map.try_get(&tile_id(9, row)).unwrap();
+            assert_eq!(west.elevation().to_bits(),8000.0_f64.to_bits());
+            assert_eq!(west.grouping(),&Grouping::Continent);
+            assert_eq!(east.elevation().to_bits(),(-1000.0_f64).to_bits());
+            assert_eq!(east.grouping(),&Grouping::Ocean);
+        }
+    }
+
+    #[test]
+    fn test_continent_count() {
+        // a sunken grid with a few hills raised out of it. The number of hills varies, so some attempts won't have the right count.
+        let recipe = [
+            TerrainTask::AddHill(AddHill {
+                count: ArgRange::Inclusive(1, 6),
+                height_delta: ArgRange::Inclusive(8, 12),
+                x_filter: ArgRange::Exclusive(10.0, 90.0),
+                y_filter: ArgRange::Exclusive(10.0, 90.0)
+            }),
+            TerrainTask::ClearOcean(ClearOcean {}),
+            TerrainTask::FillOcean(FillOcean {})
+        ];
+
+        let tile_map = create_grid_tile_map(20, |_| -500.0);
+        let parameters = create_parameters(&tile_map);
+        let mut point_index = TileFinder::new(&parameters.extents, parameters.world_shape.clone(), tile_map.len(), 2.0);
+        for (fid,tile) in tile_map.iter() {
+            point_index.add_tile(tile.site().clone(), fid.clone()).unwrap();
+        }
+
+        let unconstrained = ContinentCountArg {
+            continent_count: None,
+            continent_attempts: 20
+        };
+        let counts = (0..10).map(|seed| {
+            let result = TerrainTask::process_terrain_tiles_to_continent_count(&recipe, &unconstrained, seed, &parameters, Some(&point_index), tile_map.clone(), &mut ()).unwrap();
+            count_continents(&result)
+        }).collect::<Vec<_>>();
+        // make sure the recipe alone doesn't already give the answer.
+        assert!(counts.iter().any(|count| *count != 3));
+
+        let constrained = ContinentCountArg {
+            continent_count: Some(3),
+            continent_attempts: 20
+        };
+        for seed in 0..10 {
+            let result = TerrainTask::process_terrain_tiles_to_continent_count(&recipe, &constrained, seed, &parameters, Some(&point_index), tile_map.clone(), &mut ()).unwrap();
+            assert!(count_continents(&result).abs_diff(3) <= 1,"seed {seed}");
+        }
+
+        // without the ocean processes, nothing separates the hills, so that is warned about.
+        let mut progress = DiagnosticsObserver::new(());
+        _ = TerrainTask::process_terrain_tiles_to_continent_count(&recipe[..1], &constrained, 0, &parameters, Some(&point_index), tile_map.clone(), &mut progress).unwrap();
+        assert!(progress.warnings().iter().any(|warning| warning.message().starts_with("The terrain recipe has no ocean processes")));
+        let mut progress = DiagnosticsObserver::new(());
+        _ = TerrainTask::process_terrain_tiles_to_continent_count(&recipe, &constrained, 0, &parameters, Some(&point_index), tile_map, &mut progress).unwrap();
+        assert!(!progress.warnings().iter().any(|warning| warning.message().starts_with("The terrain recipe has no ocean processes")));
+    }
+}
@@ -0,0 +1,167 @@
+use core::fmt::Write as _;
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::entities::EntityIndex;
+use crate::world_map::fields::Neighbor;
+use crate::world_map::fields::NeighborAndDirection;
+use crate::world_map::tile_layer::TileForGraph;
+use crate::world_map::tile_layer::TileSchema;
+
+/// The kind of file to write the tile graph to.
+#[derive(Clone,ValueEnum)]
+pub enum GraphFormat {
+    /// A CSV edge list with a header row
+    Csv,
+    /// A JSON array of edge objects
+    Json
+}
+
+/// A connection from a tile to one of its neighbors. Each pair of neighbors appears twice, once in each direction.
+#[derive(Serialize,Debug,PartialEq)]
+pub(crate) struct GraphEdge {
+    from: u64,
+    to: u64,
+    /// The bearing from the tile to the neighbor, in degrees.
+    direction: f64,
+    /// Whether the neighbor is across the edge of a wrapped map.
+    cross_map: bool,
+    /// The length of the border shared by the two tiles, in map units. This isn't calculated for neighbors across the map edge, whose borders are on opposite sides of the map.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shared_edge_length: Option<f64>
+}
+
+impl GraphEdge {
+
+    #[cfg(test)]
+    pub(crate) const fn from(&self) -> u64 {
+        self.from
+    }
+
+    #[cfg(test)]
+    pub(crate) const fn to(&self) -> u64 {
+        self.to
+    }
+
+    #[cfg(test)]
+    pub(crate) const fn shared_edge_length(&self) -> Option<f64> {
+        self.shared_edge_length
+    }
+}
+
+fn segment_key(start: (f64,f64), end: (f64,f64)) -> [(u64,u64);2] {
+    // the points are compared exactly, as neighbors were found by their shared vertices in the first place. The ends
+    // are sorted so the segment matches no matter which way the ring runs.
+    let mut ends = [(start.0.to_bits(),start.1.to_bits()),(end.0.to_bits(),end.1.to_bits())];
+    ends.sort_unstable();
+    ends
+}
+
+fn outline_segments(outline: &[(f64,f64)]) -> impl Iterator<Item = (&(f64,f64),&(f64,f64))> {
+    outline.iter().zip(outline.iter().skip(1))
+}
+
+fn shared_edge_length(tile: &TileForGraph, neighbor: &TileForGraph) -> f64 {
+    let neighbor_segments: HashSet<_> = outline_segments(neighbor.outline()).map(|(start,end)| segment_key(*start, *end)).collect();
+    outline_segments(tile.outline()).filter(|(start,end)| neighbor_segments.contains(&segment_key(**start, **end))).map(|(start,end)| (end.0 - start.0).hypot(end.1 - start.1)).sum()
+}
+
+/// Lists an edge from each tile to each of the neighbors it lists. Off-map neighbors aren't tiles, so they are skipped.
+pub(crate) fn tile_graph_edges<Progress: ProgressObserver>(tiles: &EntityIndex<TileSchema,TileForGraph>, edge_lengths: bool, progress: &mut Progress) -> Result<Vec<GraphEdge>,CommandError> {
+
+    let mut edges = Vec::new();
+
+    for (fid,tile) in tiles.iter().watch(progress,"Listing tile edges.","Tile edges listed.") {
+        for NeighborAndDirection(neighbor,direction) in tile.neighbors() {
+            let (neighbor_id,cross_map) = match neighbor {
+                Neighbor::Tile(neighbor_id) => (neighbor_id,false),
+                Neighbor::CrossMap(neighbor_id,_) => (neighbor_id,true),
+                Neighbor::OffMap(_) => continue,
+            };
+
+            let shared_edge_length = if edge_lengths && !cross_map {
+                Some(shared_edge_length(tile, tiles.try_get(neighbor_id)?))
+            } else {
+                None
+            };
+
+            edges.push(GraphEdge {
+                from: fid.to_inner(),
+                to: neighbor_id.to_inner(),
+                direction: direction.0,
+                cross_map,
+                shared_edge_length
+            });
+        }
+    }
+
+    Ok(edges)
+
+}
+
+fn write_csv(edges: &[GraphEdge]) -> String {
+    let mut result = String::from("from,to,direction,cross_map,shared_edge_length\n");
+    for edge in edges {
+        let length = edge.shared_edge_length.map(|length| length.to_string()).unwrap_or_default();
+        _ = writeln!(result,"{},{},{},{},{length}",edge.from,edge.to,edge.direction,edge.cross_map);
+    }
+    result
+}
+
+pub(crate) fn write_graph(edges: &[GraphEdge], format: &GraphFormat) -> Result<String,CommandError> {
+    match format {
+        GraphFormat::Csv => Ok(write_csv(edges)),
+        GraphFormat::Json => Ok(serde_json::to_string_pretty(edges)?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+
+    use super::GraphFormat;
+    use super::tile_graph_edges;
+    use super::write_graph;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::utils::edge::Edge;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileForGraph;
+    use crate::world_map::tile_layer::TileSchema;
+
+    #[test]
+    fn test_tile_graph_edges() {
+        // two unit squares side by side, with the west one also wrapping around to the east one.
+        let tiles: EntityIndex<TileSchema,TileForGraph> = [
+            (IdRef::new(1),TileForGraph::new(vec![
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(90.0)),
+                NeighborAndDirection(Neighbor::CrossMap(IdRef::new(2),Edge::West),Deg(270.0)),
+                NeighborAndDirection(Neighbor::OffMap(Edge::North),Deg(0.0)),
+            ],vec![(0.0,0.0),(0.0,1.0),(1.0,1.0),(1.0,0.0),(0.0,0.0)])),
+            (IdRef::new(2),TileForGraph::new(vec![
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(1)),Deg(270.0)),
+                NeighborAndDirection(Neighbor::CrossMap(IdRef::new(1),Edge::East),Deg(90.0)),
+            ],vec![(1.0,0.0),(2.0,0.0),(2.0,1.0),(1.0,1.0),(1.0,0.0)])),
+        ].into_iter().collect();
+
+        let edges = tile_graph_edges(&tiles, true, &mut ()).unwrap();
+        let pairs: Vec<(u64,u64)> = edges.iter().map(|edge| (edge.from(),edge.to())).collect();
+        assert_eq!(pairs,vec![(1,2),(1,2),(2,1),(2,1)]);
+        let lengths: Vec<Option<u64>> = edges.iter().map(|edge| edge.shared_edge_length().map(f64::to_bits)).collect();
+        assert_eq!(lengths,vec![Some(1.0_f64.to_bits()),None,Some(1.0_f64.to_bits()),None]);
+
+        let csv = write_graph(&edges, &GraphFormat::Csv).unwrap();
+        assert_eq!(csv.lines().next(),Some("from,to,direction,cross_map,shared_edge_length"));
+        assert_eq!(csv.lines().nth(2),Some("1,2,270,true,"));
+
+        let json: serde_json::Value = serde_json::from_str(&write_graph(&edges, &GraphFormat::Json).unwrap()).unwrap();
+        assert_eq!(json[0]["shared_edge_length"],1.0);
+        assert!(json[1].get("shared_edge_length").is_none());
+    }
+}
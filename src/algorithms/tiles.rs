@@ -43,12 +43,16 @@ use crate::utils::extent::Extent;
 use crate::algorithms::voronoi::VoronoiGenerator;
 use crate::algorithms::triangles::DelaunayGenerator;
 use crate::algorithms::random_points::PointGenerator;
+use crate::algorithms::random_points::BoundaryPoints;
+use crate::algorithms::random_points::PointDistribution;
 use crate::utils::coordinates::ToGeometryCollection;
 use crate::typed_map::features::NamedFeature;
 use crate::commands::OverwriteTilesArg;
+use crate::commands::IntegerIdsArg;
 use crate::commands::OverwriteCoastlineArg;
 use crate::commands::OverwriteOceanArg;
 use crate::commands::BezierScaleArg;
+use crate::commands::CoastDensifyArg;
 use crate::geometry::MultiPolygon;
 use crate::geometry::VariantArealGeometry;
 use crate::world_map::fields::NeighborAndDirection;
@@ -58,18 +62,38 @@ use crate::typed_map::fields::IdRef;
 use crate::utils::world_shape::WorldShape;
 
 
-pub(crate) fn generate_random_tiles<Random: Rng, Progress: ProgressObserver>(random: &mut Random, extent: Extent, shape: WorldShape, tile_count: usize, progress: &mut Progress) -> Result<VoronoiGenerator<DelaunayGenerator>, CommandError> {
+pub(crate) fn generate_random_tiles<Random: Rng, Progress: ProgressObserver>(random: &mut Random, extent: Extent, shape: WorldShape, tile_count: usize, latitude_spacing: Option<f64>, point_distribution: &PointDistribution, boundary_points: &BoundaryPoints, region: Option<Extent>, progress: &mut Progress) -> Result<VoronoiGenerator<DelaunayGenerator>, CommandError> {
 
     progress.announce("Generate random tiles");
 
     // yes, the random variable is a mutable reference, and PointGenerator doesn't take a reference as it's generic, 
     // but the reference implements the random number generator stuff so it works.
     // I assume if I was leaking the PointGenerator out of the function that I would get an error.
-    let mut points = PointGenerator::new(random, extent.clone(), shape.clone(), tile_count);
-    let mut triangles = DelaunayGenerator::new(points.to_geometry_collection(progress)?, shape.clone());
+    if matches!(point_distribution,PointDistribution::Fibonacci) && matches!(shape,WorldShape::Cylinder) {
+        return Err(CommandError::FibonacciRequiresSphere)
+    }
+
+    let mut points = if let Some(latitude_spacing) = latitude_spacing {
+        if !latitude_spacing.is_finite() || (latitude_spacing <= 0.0) {
+            return Err(CommandError::InvalidLatitudeSpacing(latitude_spacing))
+        }
+        PointGenerator::with_latitude_spacing(random, extent.clone(), shape.clone(), latitude_spacing, boundary_points)
+    } else {
+        PointGenerator::new(random, extent.clone(), shape.clone(), tile_count, boundary_points)
+    };
+    points.set_distribution(point_distribution);
+    if let Some(region) = &region {
+        points.set_region(region);
+    }
+    let points_collection = points.to_geometry_collection(progress)?;
+    if points.duplicates_removed() > 0 {
+        progress.warning(|| format!("Removed {} points which coincided with other points.",points.duplicates_removed()));
+    }
+    let mut triangles = DelaunayGenerator::new(points_collection, shape.clone());
     
     triangles.start(progress)?;
     let mut voronois = VoronoiGenerator::new(triangles,extent,shape)?;
+    voronois.set_region(region);
     
     voronois.start(progress)?;
     
@@ -78,7 +102,16 @@ pub(crate) fn generate_random_tiles<Random: Rng, Progress: ProgressObserver>(ran
 
 
 
-pub(crate) fn load_tile_layer<Generator: Iterator<Item=Result<NewTileSite,CommandError>>, Progress: ProgressObserver>(target: &mut WorldMapTransaction, overwrite_layer: &OverwriteTilesArg, generator: Generator, limits: &ElevationLimits, world_shape: &WorldShape, progress: &mut Progress) -> Result<(),CommandError> {
+pub(crate) fn load_tile_layer<Generator: Iterator<Item=Result<NewTileSite,CommandError>>, Progress: ProgressObserver>(target: &mut WorldMapTransaction, overwrite_layer: &OverwriteTilesArg, generator: Generator, limits: &ElevationLimits, world_shape: &WorldShape, integer_ids: &IntegerIdsArg, progress: &mut Progress) -> Result<(),CommandError> {
+
+    // the properties are written first, since the id setting is needed to create the tiles.
+    let mut props = target.create_properties_layer()?;
+
+    _ = props.set_elevation_limits(limits)?;
+
+    _ = props.set_world_shape(world_shape)?;
+
+    _ = props.set_integer_ids(integer_ids.integer_ids)?;
 
     let tiles = target.create_tile_layer(overwrite_layer)?;
 
@@ -94,12 +127,6 @@ pub(crate) fn load_tile_layer<Generator: Iterator<Item=Result<NewTileSite,Comman
         tiles.add_tile(tile)?;
     }
 
-    let mut props = target.create_properties_layer()?;
-
-    _ = props.set_elevation_limits(limits)?;
-
-    _ = props.set_world_shape(world_shape)?;
-
     Ok(())
 
 }
@@ -238,9 +265,7 @@ pub(crate) fn calculate_tile_neighbors<Progress: ProgressObserver>(target: &mut
  */        }
 
         if (!active_east_tiles.is_empty()) || (!active_west_tiles.is_empty()) {
-            println!("east: {active_east_tiles:?}");
-            println!("west: {active_west_tiles:?}");
-            panic!("Why would there be any tiles left active? A tile should always has exactly two nodes along a side.")
+            panic!("Why would there be any tiles left active? A tile should always has exactly two nodes along a side. (east: {active_east_tiles:?}, west: {active_west_tiles:?})")
         }
 
         true
@@ -326,8 +351,7 @@ pub(crate) fn calculate_tile_neighbors<Progress: ProgressObserver>(target: &mut
             
         }
 
-        // sort the neighbors by tile_id, to help ensure random reproducibility
-        neighbors.sort_by_cached_key(|n| n.0.clone());
+        sort_neighbors(&mut neighbors);
 
         let mut feature = layer.try_feature_by_id(fid)?;
         feature.set_neighbors(&neighbors)?;
@@ -339,6 +363,11 @@ pub(crate) fn calculate_tile_neighbors<Progress: ProgressObserver>(target: &mut
 
 }
 
+/// Sorts neighbors by direction, then by neighbor, so that algorithms which iterate through neighbors, and choose between ties, always see them in the same order.
+pub(crate) fn sort_neighbors(neighbors: &mut [NeighborAndDirection]) {
+    neighbors.sort_by_cached_key(|NeighborAndDirection(neighbor,direction)| (OrderedFloat(direction.0),neighbor.clone()));
+}
+
 fn calculate_neighbor_angle(tile: &TileForCalcNeighbors, neighbor_id: &IdRef, tile_map: &EntityIndex<TileSchema, TileForCalcNeighbors>, world_shape: &WorldShape, across_anti_meridian: bool) -> Result<Deg<f64>, CommandError> {
     let neighbor = tile_map.try_get(neighbor_id)?;
     let neighbor_angle = {
@@ -388,7 +417,7 @@ pub(crate) fn find_lowest_tile<Data: Entity<TileSchema>, GetElevation: Fn(Option
 
 }
 
-pub(crate) fn calculate_coastline<Progress: ProgressObserver>(target: &mut WorldMapTransaction, bezier_scale: &BezierScaleArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, progress: &mut Progress) -> Result<(),CommandError> {
+pub(crate) fn calculate_coastline<Progress: ProgressObserver>(target: &mut WorldMapTransaction, bezier_scale: &BezierScaleArg, coast_densify: &CoastDensifyArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, progress: &mut Progress) -> Result<(),CommandError> {
 
     // FUTURE: There is an issue with coastlines extending over the edge of the borders after curving. I will have to deal with these someday.
     // FUTURE: After curving, towns which are along the coastline will sometimes now be in the ocean. I may need to deal with that as well, someday.
@@ -396,10 +425,15 @@ pub(crate) fn calculate_coastline<Progress: ProgressObserver>(target: &mut World
     let mut tile_layer = target.edit_tile_layer()?;
     let extent_polygon = tile_layer.get_extent()?.create_polygon()?;
 
+    let mut has_ocean = false;
+
     let mut iterator = tile_layer.read_features().filter_map(|f| {
         match f.grouping() {
             Ok(g) if !g.is_ocean() => Some(Ok(f)),
-            Ok(_) => None,
+            Ok(_) => {
+                has_ocean = true;
+                None
+            },
             Err(err) => Some(Err(err)),
         }
     } ).watch(progress, "Gathering tiles.", "Tiles gathered.");
@@ -424,11 +458,19 @@ pub(crate) fn calculate_coastline<Progress: ProgressObserver>(target: &mut World
     let ocean = tile_layer.get_extent()?.create_boundary_geometry()?; 
     progress.finish(|| "Ocean polygon created.");
 
-    let (land_polygons,ocean) = if let Some(tile_union) = tile_union {
+    let (land_polygons,ocean) = if !has_ocean {
+        // Without any ocean tiles there is no coast. The land would cover the whole extent, and curving it would only leave
+        // slivers of ocean along the edges, so both layers are left empty.
+        (None,None)
+    } else if let Some(tile_union) = tile_union {
         let mut ocean = ocean;
         let mut polygons = Vec::new();
         for polygon in tile_union.into_iter().watch(progress,"Making coastlines curvy.","Coastlines are curvy.") {
-            for new_polygon in polygon?.bezierify(bezier_scale.bezier_scale)? {
+            let polygon = match coast_densify.coast_densify {
+                0 => polygon?,
+                count => polygon?.densify(count)?
+            };
+            for new_polygon in polygon.bezierify(bezier_scale.bezier_scale)? {
                 let new_polygon = new_polygon?;
                 ocean = ocean.difference(&VariantArealGeometry::Polygon(new_polygon.clone()))?;
 
@@ -439,13 +481,13 @@ pub(crate) fn calculate_coastline<Progress: ProgressObserver>(target: &mut World
                 polygons.extend(new_polygon);
             }
         }
-        (Some(polygons),ocean)
+        (Some(polygons),Some(ocean))
     } else {
-        (None,ocean)
+        (None,Some(ocean))
     };
 
     // snip the ocean polygon as well.
-    let ocean = ocean.intersection(&extent_polygon.into())?;
+    let ocean = ocean.map(|ocean| ocean.intersection(&extent_polygon.into())).transpose()?;
 
     let mut coastline_layer = target.create_coastline_layer(overwrite_coastline)?;
     if let Some(land_polygons) = land_polygons {
@@ -455,8 +497,10 @@ pub(crate) fn calculate_coastline<Progress: ProgressObserver>(target: &mut World
     }
 
     let mut ocean_layer = target.create_ocean_layer(overwrite_ocean)?;
-    for polygon in ocean.into_iter().watch(progress, "Writing oceans.", "Oceans written.") {
-        _ = ocean_layer.add_ocean(polygon?)?;
+    if let Some(ocean) = ocean {
+        for polygon in ocean.into_iter().watch(progress, "Writing oceans.", "Oceans written.") {
+            _ = ocean_layer.add_ocean(polygon?)?;
+        }
     }
 
     Ok(())
@@ -714,3 +758,81 @@ pub(crate) fn dissolve_tiles_by_theme<Progress: ProgressObserver, ThemeType: The
     Ok(())
 }
 
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::find_lowest_tile;
+    use super::generate_random_tiles;
+    use super::sort_neighbors;
+    use crate::algorithms::random_points::BoundaryPoints;
+    use crate::algorithms::random_points::PointDistribution;
+    use crate::errors::CommandError;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::utils::edge::Edge;
+    use crate::utils::extent::Extent;
+    use crate::utils::world_shape::WorldShape;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileForTerrain;
+    use crate::world_map::tile_layer::TileSchema;
+
+    fn neighbor(id: u64, direction: f64) -> NeighborAndDirection {
+        NeighborAndDirection(Neighbor::Tile(IdRef::new(id)),Deg(direction))
+    }
+
+    #[test]
+    fn test_sort_neighbors() {
+        let mut first = vec![neighbor(4, 270.0), neighbor(2, 90.0), NeighborAndDirection(Neighbor::OffMap(Edge::North),Deg(0.0)), neighbor(3, 90.0), neighbor(1, 0.0)];
+        let mut second = first.clone();
+        second.reverse();
+
+        sort_neighbors(&mut first);
+        sort_neighbors(&mut second);
+
+        assert_eq!(first,second);
+        assert_eq!(first,vec![NeighborAndDirection(Neighbor::OffMap(Edge::North),Deg(0.0)), neighbor(1, 0.0), neighbor(2, 90.0), neighbor(3, 90.0), neighbor(4, 270.0)]);
+    }
+
+    #[test]
+    fn test_lowest_tile_tie_is_stable() {
+        let create_tile = |elevation,neighbors| TileForTerrain::new((0.0,0.0).try_into().unwrap(), elevation, Grouping::Continent, neighbors);
+
+        // tiles 2 and 3 are tied for lowest
+        let create_tile_map = |neighbors: Vec<NeighborAndDirection>| -> EntityIndex<TileSchema,TileForTerrain> {
+            let mut neighbors = neighbors;
+            sort_neighbors(&mut neighbors);
+            [
+                (IdRef::new(1),create_tile(10.0, neighbors)),
+                (IdRef::new(2),create_tile(5.0, Vec::new())),
+                (IdRef::new(3),create_tile(5.0, Vec::new())),
+                (IdRef::new(4),create_tile(7.0, Vec::new())),
+            ].into_iter().collect()
+        };
+
+        let first = create_tile_map(vec![neighbor(2, 180.0), neighbor(3, 45.0), neighbor(4, 90.0)]);
+        let second = create_tile_map(vec![neighbor(4, 90.0), neighbor(3, 45.0), neighbor(2, 180.0)]);
+
+        let lowest = |tile_map: &EntityIndex<TileSchema,TileForTerrain>| {
+            let (lowest,_) = find_lowest_tile(tile_map.try_get(&IdRef::new(1)).unwrap(), tile_map, |tile| tile.map_or(f64::NEG_INFINITY, |(tile,_)| *tile.elevation()), TileForTerrain::neighbors).unwrap();
+            lowest
+        };
+
+        assert_eq!(lowest(&first),lowest(&second));
+        assert_eq!(lowest(&first)[0],Neighbor::Tile(IdRef::new(3)));
+    }
+
+    #[test]
+    fn test_fibonacci_requires_sphere() {
+        let extent = Extent::from_bounds(-180.0, -90.0, 180.0, 90.0);
+        let result = generate_random_tiles(&mut StdRng::seed_from_u64(7), extent, WorldShape::Cylinder, 100, None, &PointDistribution::Fibonacci, &BoundaryPoints::None, None, &mut ());
+        assert!(matches!(result,Err(CommandError::FibonacciRequiresSphere)));
+    }
+
+}
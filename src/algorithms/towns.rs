@@ -38,7 +38,6 @@ pub(crate) struct ScoredTileForTowns {
     town_score: OrderedFloat<f64>
 }
 
-
 pub(crate) fn generate_towns<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer>(target: &mut WorldMapTransaction, rng: &mut Random, culture_lookup: &EntityLookup<CultureSchema,Culture>, namers: &mut NamerSet, town_counts: &TownCountsArg, overwrite_layer: &OverwriteTownsArg, progress: &mut Progress) -> Result<(),CommandError> {
 
     // a lot of this is ported from AFMG
@@ -59,11 +58,24 @@ pub(crate) fn generate_towns<Random: Rng, Progress: ProgressObserver, Culture: N
 
     let mut towns_layer = target.create_towns_layer(overwrite_layer)?;
 
+    if namers.wants_fallback_namers() {
+        progress.warning(|| format!("No culture sets were loaded to weight the default namer, using default namer '{}' for tiles without a culture.",namers.default_namer()))
+    }
+
     let mut placed_towns = HashMap::new(); 
     for town in capitals.into_iter().chain(towns.into_iter()).watch(progress,"Writing towns.","Towns written.") {
         let (ScoredTileForTowns{tile,..},is_capital) = town;
         let culture = tile.culture();
-        let namer = Culture::get_namer(culture.as_ref().map(|c| culture_lookup.try_get(c)).transpose()?, namers)?;
+        let fallback = if culture.is_none() {
+            namers.fallback_namers().map(|fallback| fallback.choose(rng).to_owned())
+        } else {
+            None
+        };
+        let namer = if let Some(fallback) = fallback {
+            namers.get_mut(Some(&fallback))?
+        } else {
+            Culture::get_namer(culture.as_ref().map(|c| culture_lookup.try_get(c)).transpose()?, namers)?
+        };
         let name = namer.make_name(rng);
         let fid = towns_layer.add_town(&NewTown {
             name,
@@ -77,6 +89,8 @@ pub(crate) fn generate_towns<Random: Rng, Progress: ProgressObserver, Culture: N
         _ = placed_towns.insert(tile.fid().clone(),fid); 
     }
 
+    namers.report_fallbacks(progress);
+
     // even though we have the town locations indicated in the towns layer, there are going to be occasions
     // where I want to easily figure out if a tile has a town, so write that there as well.
     // FUTURE: If the user re-writes the towns with a different seed, there's going to be erroneous data in tiles.
@@ -101,7 +115,8 @@ pub(crate) fn place_towns<Random: Rng, Progress: ProgressObserver>(rng: &mut Ran
 
     let town_count = if let Some(town_count) = town_count {
         if town_count > &tiles.len() {
-            let reduced_town_count = tiles.len() - placed_capital_count;
+            // on a world with very little land, the capitals may have taken every populated tile.
+            let reduced_town_count = tiles.len().saturating_sub(placed_capital_count);
             if tiles.is_empty() {
                 progress.warning(|| "There aren't enough populated cells left to generate any towns.")
             } else {
@@ -114,7 +129,7 @@ pub(crate) fn place_towns<Random: Rng, Progress: ProgressObserver>(rng: &mut Ran
     } else {
         let map_size = extent.width() * extent.height();
         let generated_town_count = (map_size/100.0).floor() as usize;
-        let generated_town_count = generated_town_count.min(tiles.len() - placed_capital_count);
+        let generated_town_count = generated_town_count.min(tiles.len().saturating_sub(placed_capital_count));
         if generated_town_count == 0 {
             progress.warning(|| "There aren't enough populated cells to generate towns.")
         }
@@ -25,7 +25,8 @@ pub(crate) struct VoronoiGenerator<GeometryIterator: Iterator<Item=Result<Polygo
     phase: VoronoiGeneratorPhase<GeometryIterator>,
     world_shape: WorldShape,
     extent: Extent,
-    extent_geo: Polygon
+    extent_geo: Polygon,
+    region: Option<Extent>
 
 }
 
@@ -42,12 +43,18 @@ impl<GeometryIterator: Iterator<Item=Result<Polygon,CommandError>>> VoronoiGener
             phase,
             extent,
             world_shape: shape,
-            extent_geo
+            extent_geo,
+            region: None
         })
     }
 
-    pub(crate) fn create_voronoi(site: &Coordinates, voronoi: VoronoiInfo, extent: &Extent, world_shape: &WorldShape, extent_geo: &Polygon) -> Result<Option<NewTileSite>,CommandError> {
-        if (voronoi.vertices.len() >= 3) && extent.contains(site) {
+    /// Only tiles with sites inside the region will be generated. Tiles which cross the region's boundary are marked as being on that edge, so they get off-map neighbors.
+    pub(crate) const fn set_region(&mut self, region: Option<Extent>) {
+        self.region = region
+    }
+
+    pub(crate) fn create_voronoi(site: &Coordinates, voronoi: VoronoiInfo, extent: &Extent, world_shape: &WorldShape, extent_geo: &Polygon, region: Option<&Extent>) -> Result<Option<NewTileSite>,CommandError> {
+        if (voronoi.vertices.len() >= 3) && extent.contains(site) && region.is_none_or(|region| region.contains(site)) {
             // * if there are less than 3 vertices, its either a line or a point, not even a sliver.
             // * if the site is not contained in the extent, it's one of our infinity points created to make it easier for us
             // to clip the edges.
@@ -119,6 +126,17 @@ impl<GeometryIterator: Iterator<Item=Result<Polygon,CommandError>>> VoronoiGener
                     
                 }
 
+            } else if let Some(region) = region {
+                // the tiles are not clipped to the region, so they will match up with tiles generated for the neighboring regions.
+                match region.is_extent_on_edge(&polygon.get_envelope()) {
+                    Ok(edge) => edge,
+                    // a tile wider or taller than a small region crosses opposite sides of it, so give it the side nearest its site instead.
+                    Err(CommandError::InvalidTileEdge(..)) => {
+                        let (x,y) = site.to_tuple();
+                        Some(region.nearest_edge_to_tuple(x, y))
+                    },
+                    Err(err) => return Err(err)
+                }
             } else {
                 None
             };
@@ -207,7 +225,7 @@ impl<GeometryIterator: Iterator<Item=Result<Polygon,CommandError>>> Iterator for
                 for value in iter.by_ref() {
                     // create_voronoi returns none for various reasons if the polygon shouldn't be written. 
                     // If it does that, I have to keep trying. 
-                    result = Self::create_voronoi(&value.0, value.1,&self.extent,&self.world_shape,&self.extent_geo).transpose();
+                    result = Self::create_voronoi(&value.0, value.1,&self.extent,&self.world_shape,&self.extent_geo,self.region.as_ref()).transpose();
                     if result.is_some() {
                         break;
                     }
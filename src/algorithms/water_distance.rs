@@ -30,10 +30,13 @@ pub(crate) fn generate_water_distance<Progress: ProgressObserver>(target: &mut W
 
     let mut queue = queue.watch_queue(progress, "Finding shoreline tiles.", "Shoreline tiles found.");
     let mut shore_distances = HashMap::new();
+    let mut land_edge_tiles = Vec::new();
+    let mut water_edge_tiles = Vec::new();
 
     while let Some(fid) = queue.pop() {
 
         let mut on_shore = false;
+        let mut on_edge = false;
         let mut closest_water = None;
         let mut water_distance = None;
         let mut water_count = None;
@@ -69,7 +72,7 @@ pub(crate) fn generate_water_distance<Progress: ProgressObserver>(target: &mut W
                         on_shore = true;
                     }
                 }
-                Neighbor::OffMap(_) => (),
+                Neighbor::OffMap(_) => on_edge = true,
             } // else ignore off the map, it's as if there were no neighbors
 
         }
@@ -85,10 +88,28 @@ pub(crate) fn generate_water_distance<Progress: ProgressObserver>(target: &mut W
                 _ = shore_distances.insert(fid.clone(),-1);
                 _ = water_queue.push(fid,Reverse(1));
             }
+        } else if on_edge {
+            if is_land {
+                land_edge_tiles.push(fid);
+            } else {
+                water_edge_tiles.push(fid);
+            }
         }
 
     }
 
+    // If there's no shoreline at all, such as in a world with no water, distances are measured from the edges of the map instead.
+    if land_queue.is_empty() && water_queue.is_empty() {
+        for fid in land_edge_tiles {
+            _ = shore_distances.insert(fid.clone(),1);
+            _ = land_queue.push(fid,Reverse(1));
+        }
+        for fid in water_edge_tiles {
+            _ = shore_distances.insert(fid.clone(),-1);
+            _ = water_queue.push(fid,Reverse(1));
+        }
+    }
+
     // use the cost-expansion algorithm, as was done with expanding cultures, nations, subnations, etc. Except
     // there is no limit and cost is exactly 1 per tile.
 
@@ -150,10 +171,18 @@ pub(crate) fn generate_water_distance<Progress: ProgressObserver>(target: &mut W
 
     }
 
+    // A world with neither a shore nor an edge, such as a sphere with no water, has nothing to measure from, so those tiles are
+    // given a distance farther than any tile could be from a shore.
+    let unmeasured_distance = i32::try_from(tile_map.len()).unwrap_or(i32::MAX);
+
     for (fid,tile) in tile_map.into_iter().watch(progress, "Writing data.", "Data written.") {
 
         let mut feature = tiles.try_feature_by_id(&fid)?;
-        let shore_distance = shore_distances.remove(&fid).expect("Why wouldn't this value have been generated for the tile?");
+        let shore_distance = match shore_distances.remove(&fid) {
+            Some(shore_distance) => shore_distance,
+            None if tile.grouping().is_water() => -unmeasured_distance,
+            None => unmeasured_distance
+        };
         feature.set_shore_distance(&shore_distance)?;
         feature.set_harbor_tile_id(tile.closest_water_tile_id())?;
         feature.set_water_count(tile.water_count())?;
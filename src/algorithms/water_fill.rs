@@ -15,6 +15,11 @@ use crate::commands::BezierScaleArg;
 use crate::commands::LakeBufferScaleArg;
 use crate::algorithms::tiles::find_lowest_tile;
 use super::water_flow::WaterFlowResult;
+use super::water_flow::EdgeDrainage;
+use super::water_flow::find_wrapped_edges;
+use super::water_flow::wrap_edge_neighbors;
+use crate::commands::EdgeDrainageArg;
+use crate::world_map::tile_layer::TileForEdgeWrap;
 use crate::typed_map::entities::EntityIndex;
 use crate::world_map::tile_layer::TileSchema;
 use crate::world_map::tile_layer::TileForWaterFill;
@@ -112,12 +117,12 @@ enum WaterFillTask {
 
 
 // this one is quite tight with generate_water_flow, it even shares some pre-initialized data.
-pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut WorldMapTransaction, water_flow_result: WaterFlowResult, lake_bezier_scale: &BezierScaleArg, lake_buffer_scale: &LakeBufferScaleArg, overwrite_layer: &OverwriteLakesArg, progress: &mut Progress) -> Result<(),CommandError> {
+pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut WorldMapTransaction, water_flow_result: WaterFlowResult, lake_bezier_scale: &BezierScaleArg, lake_buffer_scale: &LakeBufferScaleArg, overwrite_layer: &OverwriteLakesArg, edge_drainage: &EdgeDrainageArg, progress: &mut Progress) -> Result<(),CommandError> {
 
 
     let world_shape = target.edit_properties_layer()?.get_world_shape()?;
 
-    let tiles_layer = target.edit_tile_layer()?;
+    let mut tiles_layer = target.edit_tile_layer()?;
 
     // FUTURE: This is actually a kludge to get around a bug in the algorithm that I haven't found yet.
     // Every once in a while, water will fill a lake, the lake's level will rise above the neighbors, causing
@@ -127,9 +132,18 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
     // this will keep the algorithm out of that cycle.
     let max_cycles_per_tile = tiles_layer.feature_count();
 
+    let mut tile_map = water_flow_result.tile_map;
+    let edge_drainage = &edge_drainage.edge_drainage;
+    if matches!(edge_drainage,EdgeDrainage::Wrap) {
+        // the tiles might have come from the layer, rather than the flow calculation, so they need to be wrapped again.
+        let wrapped_edges = find_wrapped_edges(&tiles_layer.read_features().into_entities_index::<_,TileForEdgeWrap>(progress)?);
+        for (fid,tile) in tile_map.iter_mut() {
+            wrap_edge_neighbors(fid, tile.neighbors_mut(), &wrapped_edges);
+        }
+    }
+
     let mut tile_queue = water_flow_result.lake_queue.watch_queue(progress,"Filling lakes.","Lakes filled.");
 
-    let mut tile_map = water_flow_result.tile_map;
     let mut next_lake_id = 1..;
     let mut lake_map = HashMap::new();
     let mut cycle_map = HashMap::new();
@@ -160,7 +174,7 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
 
 
         // figure out what we've got to do. I don't figure out the task until now in order to avoid having to do extra neighbor checks every time we fill.
-        if let Some(task) = determine_water_fill_task(&tile_fid, tile, tile_accumulation, &tile_map, &mut next_lake_id, &mut tile_queue, &mut lake_map)? {
+        if let Some(task) = determine_water_fill_task(&tile_fid, tile, tile_accumulation, &tile_map, edge_drainage, &mut next_lake_id, &mut tile_queue, &mut lake_map)? {
             match task {
                 WaterFillTask::AddToFlow(accumulation) => {
                     let edit_tile = tile_map.try_get_mut(&tile_fid)?; 
@@ -174,7 +188,7 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
                 }
                 WaterFillTask::FillLake(lake_id,accumulation) => {
                     let (new_lake,accumulation,delete_lakes) = if let Some(lake) = lake_map.get(&lake_id) {
-                        grow_or_flow_lake(lake, accumulation, &tile_map, &lake_map, &mut tile_queue)?
+                        grow_or_flow_lake(lake, accumulation, &tile_map, &lake_map, edge_drainage, &mut tile_queue)?
     
                     } else {
                         continue;
@@ -211,18 +225,7 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
     
                         } else {
                             // this is the same as above, but with the new lake.
-                            // we can automatically flow to those tiles.
-                            let neighbor_flow = accumulation/outlet_tiles.len() as f64;
-
-                            for (_,neighbor_fid) in outlet_tiles {
-                                match neighbor_fid {
-                                    Neighbor::Tile(neighbor_fid) | Neighbor::CrossMap(neighbor_fid,_) => {
-                                        // add a task to the queue to flow this down.
-                                        tile_queue.push((neighbor_fid.clone(),neighbor_flow));
-                                    }
-                                    Neighbor::OffMap(_) => (),
-                                }
-                            }
+                            flow_to_outlets(outlet_tiles, accumulation, &mut tile_queue);
                         }
     
                     }
@@ -242,10 +245,11 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
     }
 
 
+    merge_adjacent_lakes(&mut lake_map, &mut tile_map)?;
+
     // figure out some numbers for generating curvy lakes.
     let tile_area = tiles_layer.estimate_average_tile_area(&world_shape)?;
     let tile_width = tile_area.sqrt();
-    let buffer_distance = (tile_width/10.0) * -lake_buffer_scale.lake_buffer_scale;
     // the next isn't customizable, it just seems to work right. 
     let simplify_tolerance = tile_width/10.0;
     let mut new_lake_map = HashMap::new();
@@ -255,6 +259,7 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
         if !lake.contained_tiles.is_empty() {
             let lake_geometry = lake.dissolve_tiles(&tiles_layer)?;
             let (lake_temp,lake_evap,lake_type) = lake.get_temp_evap_and_type();
+            let buffer_distance = lake_buffer_distance(tile_width, lake_buffer_scale, &lake_type);
 
             let geometry = make_curvy_lakes(lake_geometry, lake_bezier_scale, buffer_distance, simplify_tolerance)?;
             let lake = NewLake {
@@ -263,7 +268,12 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
                 flow: lake.flow,
                 size: lake.contained_tiles.len() as i32,
                 temperature: lake_temp,
-                evaporation: lake_evap
+                evaporation: lake_evap,
+                // these are filled in when the rivers are generated
+                inlet_segment_ids: Vec::new(),
+                outlet_segment_id: None,
+                // this is filled in when the lakes are named
+                name: None
             };
             _ = new_lake_map.insert(id, (lake,geometry));
 
@@ -314,7 +324,7 @@ pub(crate) fn generate_water_fill<Progress: ProgressObserver>(target: &mut World
 
 }
 
-fn determine_water_fill_task<Progress: ProgressObserver>(tile_fid: &IdRef, tile: &TileForWaterFill, tile_accumulation: f64, tile_map: &EntityIndex<TileSchema, TileForWaterFill>, next_lake_id: &mut RangeFrom<u64>, tile_queue: &mut QueueWatcher<&str, Progress, (IdRef, f64)>, lake_map: &mut HashMap<IdRef, Lake>) -> Result<Option<WaterFillTask>,CommandError> {
+fn determine_water_fill_task<Progress: ProgressObserver>(tile_fid: &IdRef, tile: &TileForWaterFill, tile_accumulation: f64, tile_map: &EntityIndex<TileSchema, TileForWaterFill>, edge_drainage: &EdgeDrainage, next_lake_id: &mut RangeFrom<u64>, tile_queue: &mut QueueWatcher<&str, Progress, (IdRef, f64)>, lake_map: &mut HashMap<IdRef, Lake>) -> Result<Option<WaterFillTask>,CommandError> {
     if let Some(lake_id) = &tile.lake_id() {
         // we're already in a lake, so the accumulation is intended to fill it.
         Ok(Some(WaterFillTask::FillLake(lake_id.clone(), tile_accumulation)))
@@ -328,9 +338,9 @@ fn determine_water_fill_task<Progress: ProgressObserver>(tile_fid: &IdRef, tile:
             let (_,lowest_elevation) = find_lowest_tile(tile,tile_map,|t| {
                 match t {
                     Some((t,_)) => *t.elevation(),
-                    // for off the map, assume that the tile is the lowest possible elevation. This will force
-                    // water to flow off the map rather than accumulate.
-                    None => f64::NEG_INFINITY,
+                    // for off the map, this depends on the edge drainage. If the edge is an ocean, the lowest possible
+                    // elevation will force water to flow off the map rather than accumulate.
+                    None => edge_drainage.off_map_elevation(),
                 }
             }, |t| t.neighbors())?;
 
@@ -382,7 +392,7 @@ fn determine_water_fill_task<Progress: ProgressObserver>(tile_fid: &IdRef, tile:
 }
 
 
-fn grow_or_flow_lake<Progress: ProgressObserver>(lake: &Lake, accumulation: f64, tile_map: &EntityIndex<TileSchema, TileForWaterFill>, lake_map: &HashMap<IdRef, Lake>, tile_queue: &mut QueueWatcher<&str, Progress, (IdRef, f64)>) -> Result<(Lake, f64, Vec<IdRef>), CommandError> {
+fn grow_or_flow_lake<Progress: ProgressObserver>(lake: &Lake, accumulation: f64, tile_map: &EntityIndex<TileSchema, TileForWaterFill>, lake_map: &HashMap<IdRef, Lake>, edge_drainage: &EdgeDrainage, tile_queue: &mut QueueWatcher<&str, Progress, (IdRef, f64)>) -> Result<(Lake, f64, Vec<IdRef>), CommandError> {
     let outlet_tiles = &lake.outlet_tiles;
     if outlet_tiles.is_empty() {
         // no outlet tiles, so we have to grow the lake.
@@ -436,9 +446,11 @@ fn grow_or_flow_lake<Progress: ProgressObserver>(lake: &Lake, accumulation: f64,
             while let Some((sponsor_fid,check_fid)) = walk_queue.pop() {
                 match check_fid {
                     Neighbor::OffMap(_) => {
-                        // the edge of the map acts like an ocean
-                        new_outlets.push((sponsor_fid.clone(),check_fid.clone()));
-                        new_shoreline.push((sponsor_fid,check_fid))
+                        if edge_drainage.drains_off_map() {
+                            // the edge of the map acts like an ocean
+                            new_outlets.push((sponsor_fid.clone(),check_fid.clone()));
+                            new_shoreline.push((sponsor_fid,check_fid))
+                        } // else the edge of the map is a wall, which the lake can never rise over.
                     },
                     ref neighbor @ (Neighbor::Tile(ref check_fid) | Neighbor::CrossMap(ref check_fid,_)) => {
                         if checked_tiles.contains(check_fid) {
@@ -524,17 +536,7 @@ fn grow_or_flow_lake<Progress: ProgressObserver>(lake: &Lake, accumulation: f64,
 
     } else {
         // we can automatically flow to those tiles.
-        let neighbor_flow = accumulation/outlet_tiles.len() as f64;
-
-        for (_,neighbor_fid) in outlet_tiles {
-            match neighbor_fid {
-                Neighbor::Tile(neighbor_fid) | Neighbor::CrossMap(neighbor_fid,_) => {
-                    // add a task to the queue to flow this down.
-                    tile_queue.push((neighbor_fid.clone(),neighbor_flow));
-                }
-                Neighbor::OffMap(_) => (),
-            }
-        }
+        flow_to_outlets(outlet_tiles, accumulation, tile_queue);
 
         // but we need to increase the flow
         Ok((Lake {
@@ -552,6 +554,111 @@ fn grow_or_flow_lake<Progress: ProgressObserver>(lake: &Lake, accumulation: f64,
     }
 }
 
+/// Lakes whose elevations are within this distance of each other are considered level.
+const LAKE_MERGE_TOLERANCE: f64 = 0.001;
+
+/// Merges lakes whose tiles touch and whose elevations match. Separate basins can be filled in different iterations and end up touching, which would otherwise leave an artificial boundary between them. The merged lake keeps the lowest id.
+fn merge_adjacent_lakes(lake_map: &mut HashMap<IdRef,Lake>, tile_map: &mut EntityIndex<TileSchema,TileForWaterFill>) -> Result<(),CommandError> {
+
+    fn find_root(merge_into: &HashMap<IdRef,IdRef>, lake_id: &IdRef) -> IdRef {
+        let mut root = lake_id;
+        while let Some(next) = merge_into.get(root) {
+            root = next;
+        }
+        root.clone()
+    }
+
+    let mut lake_ids: Vec<IdRef> = lake_map.keys().cloned().collect();
+    lake_ids.sort();
+
+    let mut merge_into = HashMap::new();
+
+    for lake_id in &lake_ids {
+        let lake = &lake_map[lake_id];
+        for tile_id in &lake.contained_tiles {
+            for NeighborAndDirection(neighbor,_) in tile_map.try_get(tile_id)?.neighbors() {
+                let (Neighbor::Tile(neighbor) | Neighbor::CrossMap(neighbor,_)) = neighbor else {
+                    continue;
+                };
+                let Some(other_id) = tile_map.try_get(neighbor)?.lake_id() else {
+                    continue;
+                };
+                let Some(other_lake) = lake_map.get(other_id) else {
+                    continue;
+                };
+                if (other_lake.elevation - lake.elevation).abs() <= LAKE_MERGE_TOLERANCE {
+                    let root = find_root(&merge_into, lake_id);
+                    let other_root = find_root(&merge_into, other_id);
+                    if root < other_root {
+                        _ = merge_into.insert(other_root, root);
+                    } else if other_root < root {
+                        _ = merge_into.insert(root, other_root);
+                    }
+                }
+            }
+        }
+    }
+
+    // since the ids are sorted, the root lake is always merged into before it could be merged itself, and roots are never removed.
+    for lake_id in &lake_ids {
+        let root = find_root(&merge_into, lake_id);
+        if &root == lake_id {
+            continue;
+        }
+        let Some(merged) = lake_map.remove(lake_id) else {
+            continue;
+        };
+        for tile_id in &merged.contained_tiles {
+            tile_map.try_get_mut(tile_id)?.set_lake_id(Some(root.clone()));
+        }
+        let lake = lake_map.get_mut(&root).expect("Root lakes should never be removed.");
+        lake.elevation = lake.elevation.max(merged.elevation);
+        lake.flow += merged.flow;
+        lake.bottom_elevation = lake.bottom_elevation.min(merged.bottom_elevation);
+        lake.spillover_elevation = lake.spillover_elevation.min(merged.spillover_elevation);
+        lake.contained_tiles.extend(merged.contained_tiles);
+        lake.tile_temperatures.extend(merged.tile_temperatures);
+        lake.shoreline_tiles.extend(merged.shoreline_tiles);
+        lake.outlet_tiles.extend(merged.outlet_tiles);
+    }
+
+    // now that the lakes are merged, the tiles where they touched are no longer shoreline.
+    for lake_id in &lake_ids {
+        let Some(lake) = lake_map.get_mut(lake_id) else {
+            continue;
+        };
+        let contained: HashSet<&IdRef> = lake.contained_tiles.iter().collect();
+        let is_outside = |(_,neighbor): &(IdRef,Neighbor)| match neighbor {
+            Neighbor::Tile(neighbor) | Neighbor::CrossMap(neighbor,_) => !contained.contains(neighbor),
+            Neighbor::OffMap(_) => true,
+        };
+        lake.shoreline_tiles.retain(is_outside);
+        lake.outlet_tiles.retain(is_outside);
+    }
+
+    Ok(())
+}
+
+/// Divides the accumulation equally among the lake's outlets. A lake may have more than one outlet if several of its shoreline tiles sit at the spillover elevation, each with lower tiles beyond them.
+fn flow_to_outlets<Progress: ProgressObserver>(outlet_tiles: &[(IdRef,Neighbor)], accumulation: f64, tile_queue: &mut QueueWatcher<&str, Progress, (IdRef, f64)>) {
+    let neighbor_flow = accumulation/outlet_tiles.len() as f64;
+
+    for (_,neighbor_fid) in outlet_tiles {
+        match neighbor_fid {
+            Neighbor::Tile(neighbor_fid) | Neighbor::CrossMap(neighbor_fid,_) => {
+                // add a task to the queue to flow this down.
+                tile_queue.push((neighbor_fid.clone(),neighbor_flow));
+            }
+            Neighbor::OffMap(_) => (), // else it just disappears off the map
+        }
+    }
+}
+
+/// The buffer distance is negative, so the lake shrinks away from the edges of the tiles.
+fn lake_buffer_distance(tile_width: f64, lake_buffer_scale: &LakeBufferScaleArg, lake_type: &LakeType) -> f64 {
+    (tile_width/10.0) * -lake_buffer_scale.buffer_scale(lake_type)
+}
+
 pub(crate) fn make_curvy_lakes(lake_geometry: VariantArealGeometry, bezier_scale: &BezierScaleArg, buffer_distance: f64, simplify_tolerance: f64) -> Result<MultiPolygon, CommandError> {
     let lake_geometry = simplify_lake_geometry(lake_geometry,buffer_distance,simplify_tolerance)?;
     // occasionally, the simplification or other tasks turns the lakes into a multipolygon, which is why the lakes layer has to be multipolygon
@@ -605,3 +712,267 @@ pub(crate) fn simplify_lake_geometry(lake_geometry: VariantArealGeometry, buffer
     };
     Ok(lake_geometry)
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use angular_units::Deg;
+
+    use super::determine_water_fill_task;
+    use super::grow_or_flow_lake;
+    use super::WaterFillTask;
+    use crate::algorithms::water_flow::calculate_water_flow;
+    use crate::commands::FlowConcentrationArg;
+    use crate::world_map::tile_layer::TileForWaterflow;
+    use super::merge_adjacent_lakes;
+    use super::lake_buffer_distance;
+    use super::simplify_lake_geometry;
+    use crate::geometry::LinearRing;
+    use crate::geometry::Polygon;
+    use crate::geometry::VariantArealGeometry;
+    use super::EdgeDrainage;
+    use crate::commands::LakeBufferScaleArg;
+    use crate::world_map::fields::LakeType;
+    use super::Lake;
+    use crate::progress::WatchableQueue;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::typed_map::fields::IdRef;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileForWaterFill;
+    use crate::world_map::tile_layer::TileSchema;
+    use crate::utils::edge::Edge;
+
+    // A row of tiles, with a basin in the middle and a ridge of the same height on either side.
+    fn create_tile_map(elevations: &[f64]) -> EntityIndex<TileSchema,TileForWaterFill> {
+        (0..elevations.len()).map(|i| {
+            let mut neighbors = Vec::new();
+            if i > 0 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(i as u64)),Deg(270.0)));
+            }
+            if i < (elevations.len() - 1) {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(i as u64 + 2)),Deg(90.0)));
+            }
+            (IdRef::new(i as u64 + 1),TileForWaterFill::new(elevations[i], Grouping::Continent, neighbors, 10.0))
+        }).collect()
+    }
+
+    #[test]
+    fn test_symmetric_basin_outlets() {
+        let tile_map = create_tile_map(&[0.0,10.0,5.0,10.0,0.0]);
+        let lake_map = HashMap::new();
+        let basin = tile_map.try_get(&IdRef::new(3)).unwrap();
+
+        let lake = Lake {
+            elevation: 5.0,
+            flow: 0.0,
+            bottom_elevation: 5.0,
+            spillover_elevation: 10.0,
+            contained_tiles: vec![IdRef::new(3)],
+            tile_temperatures: vec![10.0],
+            shoreline_tiles: basin.neighbors().iter().map(|NeighborAndDirection(neighbor,_)| (IdRef::new(3),neighbor.clone())).collect(),
+            outlet_tiles: Vec::new()
+        };
+
+        let mut progress = ();
+        let mut tile_queue = Vec::new().watch_queue(&mut progress, "", "");
+
+        // fill the lake until it reaches the ridges
+        let (lake,remaining,_) = grow_or_flow_lake(&lake, 100.0, &tile_map, &lake_map, &EdgeDrainage::Ocean, &mut tile_queue).unwrap();
+        assert!(remaining > 0.0);
+        assert_eq!(lake.contained_tiles.len(),3);
+        let mut outlets: Vec<_> = lake.outlet_tiles.iter().map(|(_,outlet)| outlet.clone()).collect();
+        outlets.sort();
+        assert_eq!(outlets,vec![Neighbor::Tile(IdRef::new(1)),Neighbor::Tile(IdRef::new(5))]);
+        assert!(tile_queue.pop().is_none());
+
+        // now that it has outlets, more water should be divided between them.
+        _ = grow_or_flow_lake(&lake, 10.0, &tile_map, &lake_map, &EdgeDrainage::Ocean, &mut tile_queue).unwrap();
+        let mut flows = Vec::new();
+        while let Some(flow) = tile_queue.pop() {
+            flows.push(flow);
+        }
+        flows.sort_by(|(a,_),(b,_)| a.cmp(b));
+        assert_eq!(flows,vec![(IdRef::new(1),5.0),(IdRef::new(5),5.0)]);
+    }
+
+    #[test]
+    fn test_edge_drainage_outlets() {
+        // a basin on the west edge of the map, next to a ridge which drops down to lower land.
+        let mut tile_map = create_tile_map(&[5.0,10.0,0.0]);
+        tile_map.try_get_mut(&IdRef::new(1)).unwrap().neighbors_mut().insert(0,NeighborAndDirection(Neighbor::OffMap(Edge::West),Deg(270.0)));
+        let lake_map = HashMap::new();
+        let basin = tile_map.try_get(&IdRef::new(1)).unwrap();
+
+        let lake = Lake {
+            elevation: 5.0,
+            flow: 0.0,
+            bottom_elevation: 5.0,
+            spillover_elevation: 10.0,
+            contained_tiles: vec![IdRef::new(1)],
+            tile_temperatures: vec![10.0],
+            shoreline_tiles: basin.neighbors().iter().map(|NeighborAndDirection(neighbor,_)| (IdRef::new(1),neighbor.clone())).collect(),
+            outlet_tiles: Vec::new()
+        };
+
+        let outlets = |edge_drainage: &EdgeDrainage| {
+            let mut progress = ();
+            let mut tile_queue = Vec::new().watch_queue(&mut progress, "", "");
+            let (lake,_,_) = grow_or_flow_lake(&lake, 100.0, &tile_map, &lake_map, edge_drainage, &mut tile_queue).unwrap();
+            let mut outlets: Vec<_> = lake.outlet_tiles.into_iter().map(|(_,outlet)| outlet).collect();
+            outlets.sort();
+            outlets
+        };
+
+        // as an ocean, the edge is an outlet as well as the land beyond the ridge.
+        assert_eq!(outlets(&EdgeDrainage::Ocean),vec![Neighbor::OffMap(Edge::West),Neighbor::Tile(IdRef::new(3))]);
+        // as a wall, the lake can only spill over the ridge.
+        assert_eq!(outlets(&EdgeDrainage::Wall),vec![Neighbor::Tile(IdRef::new(3))]);
+    }
+
+    #[test]
+    fn test_lake_temperature_and_evaporation() {
+        let lake = |temperatures: &[f64]| Lake {
+            elevation: 5.0,
+            flow: 10.0,
+            bottom_elevation: 4.0,
+            spillover_elevation: 10.0,
+            contained_tiles: (1..=temperatures.len() as u64).map(IdRef::new).collect(),
+            tile_temperatures: temperatures.to_vec(),
+            shoreline_tiles: Vec::new(),
+            outlet_tiles: Vec::new()
+        };
+
+        let (warm_temp,warm_evap) = lake(&[24.0,26.0,25.0]).calc_temp_and_evap();
+        let (cold_temp,cold_evap) = lake(&[4.0,6.0,5.0]).calc_temp_and_evap();
+
+        // the temperature is the mean of the tiles
+        assert!((warm_temp - 25.0).abs() < f64::EPSILON);
+        assert!((cold_temp - 5.0).abs() < f64::EPSILON);
+        assert!(warm_evap > cold_evap,"a warm lake should evaporate more than a cold one of the same size");
+
+        // and a larger lake evaporates more than a smaller one at the same temperature
+        let (_,small_evap) = lake(&[25.0]).calc_temp_and_evap();
+        assert!(warm_evap > small_evap);
+    }
+
+    #[test]
+    fn test_merge_adjacent_lakes() {
+        // two basins next to each other, filled to the same level, and a third which is separated by a ridge.
+        let mut tile_map = create_tile_map(&[10.0,5.0,4.0,10.0,5.0,10.0]);
+        let mut lake_map = HashMap::new();
+
+        let mut add_lake = |lake_id: u64, tiles: &[u64], elevation: f64| {
+            for tile in tiles {
+                tile_map.try_get_mut(&IdRef::new(*tile)).unwrap().set_lake_id(Some(IdRef::new(lake_id)));
+            }
+            let tile_ids: Vec<_> = tiles.iter().copied().map(IdRef::new).collect();
+            let shoreline_tiles = tiles.iter().flat_map(|id| [(IdRef::new(*id),Neighbor::Tile(IdRef::new(id - 1))),(IdRef::new(*id),Neighbor::Tile(IdRef::new(id + 1)))]).collect();
+            _ = lake_map.insert(IdRef::new(lake_id), Lake {
+                elevation,
+                flow: 10.0,
+                bottom_elevation: elevation - 1.0,
+                spillover_elevation: 10.0,
+                contained_tiles: tile_ids,
+                tile_temperatures: vec![10.0; tiles.len()],
+                shoreline_tiles,
+                outlet_tiles: Vec::new()
+            });
+        };
+        add_lake(1, &[2], 7.0);
+        add_lake(2, &[3], 7.0);
+        add_lake(3, &[5], 7.0);
+
+        merge_adjacent_lakes(&mut lake_map, &mut tile_map).unwrap();
+
+        assert_eq!(lake_map.len(),2);
+        let merged = &lake_map[&IdRef::new(1)];
+        assert_eq!(merged.contained_tiles,vec![IdRef::new(2),IdRef::new(3)]);
+        assert!((merged.flow - 20.0).abs() < f64::EPSILON);
+        let mut shoreline: Vec<_> = merged.shoreline_tiles.iter().map(|(_,neighbor)| neighbor.clone()).collect();
+        shoreline.sort();
+        assert_eq!(shoreline,vec![Neighbor::Tile(IdRef::new(1)),Neighbor::Tile(IdRef::new(4))]);
+        assert_eq!(tile_map.try_get(&IdRef::new(3)).unwrap().lake_id(),&Some(IdRef::new(1)));
+        assert!(lake_map.contains_key(&IdRef::new(3)));
+    }
+
+    #[test]
+    fn test_lake_buffer_per_type() {
+        let mut lake_buffer_scale = LakeBufferScaleArg {
+            lake_buffer_scale: 2.0,
+            fresh_buffer_scale: None,
+            salt_buffer_scale: None,
+            frozen_buffer_scale: None,
+            pluvial_buffer_scale: None,
+            dry_buffer_scale: None,
+            marsh_buffer_scale: None,
+        };
+
+        // without overrides, every type uses the global value
+        assert_eq!(lake_buffer_distance(10.0, &lake_buffer_scale, &LakeType::Marsh).to_bits(),lake_buffer_distance(10.0, &lake_buffer_scale, &LakeType::Fresh).to_bits());
+        assert_eq!(lake_buffer_distance(10.0, &lake_buffer_scale, &LakeType::Fresh).to_bits(),(-2.0f64).to_bits());
+
+        lake_buffer_scale.marsh_buffer_scale = Some(0.5);
+        assert_eq!(lake_buffer_distance(10.0, &lake_buffer_scale, &LakeType::Marsh).to_bits(),(-0.5f64).to_bits());
+        assert_eq!(lake_buffer_distance(10.0, &lake_buffer_scale, &LakeType::Fresh).to_bits(),(-2.0f64).to_bits());
+
+        // the buffer shrinks a ten unit square lake by the distance on every side.
+        let lake_area = |lake_type: &LakeType| {
+            let square = Polygon::from_rings([LinearRing::from_vertices([(0.0,0.0),(10.0,0.0),(10.0,10.0),(0.0,10.0),(0.0,0.0)]).unwrap()]).unwrap();
+            simplify_lake_geometry(VariantArealGeometry::Polygon(square), lake_buffer_distance(10.0, &lake_buffer_scale, lake_type), 0.0).unwrap().area()
+        };
+        assert!((lake_area(&LakeType::Marsh) - 81.0).abs() < 1e-6,"marsh should be 9 units across");
+        assert!((lake_area(&LakeType::Fresh) - 36.0).abs() < 1e-6,"fresh lake should be 6 units across");
+    }
+
+    #[test]
+    fn test_flat_depression_forms_lake() {
+        // a slope running into a shallow, flat-bottomed depression of two tiles, with a low rim between it and the ocean.
+        let elevations = [20.0,5.0,5.0,8.0,-1.0];
+        let precipitation = [1.0,0.5,0.5,0.5,0.0];
+        let row = |i: usize| {
+            let mut neighbors = Vec::new();
+            if i > 0 {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(i as u64)),Deg(270.0)));
+            }
+            if i < (elevations.len() - 1) {
+                neighbors.push(NeighborAndDirection(Neighbor::Tile(IdRef::new(i as u64 + 2)),Deg(90.0)));
+            }
+            let grouping = if elevations[i] < 0.0 { Grouping::Ocean } else { Grouping::Continent };
+            (IdRef::new(i as u64 + 1),TileForWaterflow::new(elevations[i], grouping, neighbors, precipitation[i]))
+        };
+        let tile_list = [(IdRef::new(1),20.0),(IdRef::new(4),8.0),(IdRef::new(2),5.0),(IdRef::new(3),5.0)];
+        let concentration = FlowConcentrationArg { flow_concentration: 0.0 };
+
+        let flow = |min_lake_accumulation: f64| {
+            let mut tile_map: EntityIndex<TileSchema,TileForWaterflow> = (0..elevations.len()).map(row).collect();
+            let potential_lakes = calculate_water_flow(&mut tile_map, &tile_list, 1.0, 1.0, min_lake_accumulation, &concentration, &EdgeDrainage::Ocean, &mut ()).unwrap();
+            (tile_map,potential_lakes)
+        };
+
+        // the water runs along the flat bottom to the last tile there, which has nowhere else to send it.
+        let (tile_map,potential_lakes) = flow(0.0);
+        assert_eq!(potential_lakes.len(),1);
+        let (sink,accumulation) = potential_lakes[0].clone();
+        assert_eq!(sink,IdRef::new(3));
+        assert_eq!(accumulation.to_bits(),2.0f64.to_bits());
+
+        // the sink starts a lake. There isn't enough water to outpace evaporation, so it stays a tiny lake in the one tile.
+        let tile_map: EntityIndex<TileSchema,TileForWaterFill> = tile_map.into_iter().map(|(fid,tile)| (fid,tile.into())).collect();
+        let mut lake_map = HashMap::new();
+        let mut progress = ();
+        let mut tile_queue = Vec::new().watch_queue(&mut progress, "", "");
+        let task = determine_water_fill_task(&sink, tile_map.try_get(&sink).unwrap(), accumulation, &tile_map, &EdgeDrainage::Ocean, &mut (1..), &mut tile_queue, &mut lake_map).unwrap();
+        let Some(WaterFillTask::FillLake(lake_id,lake_accumulation)) = task else {
+            panic!("the sink should start a lake");
+        };
+        let (lake,_,_) = grow_or_flow_lake(&lake_map[&lake_id], lake_accumulation, &tile_map, &lake_map, &EdgeDrainage::Ocean, &mut tile_queue).unwrap();
+        assert_eq!(lake.contained_tiles,vec![IdRef::new(3)]);
+        assert_eq!(lake.flow.to_bits(),2.0f64.to_bits());
+
+        // with a higher minimum, the depression stays dry.
+        assert!(flow(5.0).1.is_empty());
+    }
+}
@@ -1,4 +1,8 @@
 use core::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use clap::ValueEnum;
 
 use crate::world_map::tile_layer::TileForWaterflow;
 use crate::errors::CommandError;
@@ -11,6 +15,45 @@ use crate::world_map::tile_layer::TileSchema;
 use super::tiles::find_lowest_tile;
 use crate::world_map::fields::Neighbor;
 use crate::typed_map::fields::IdRef;
+use crate::commands::FlowConcentrationArg;
+use crate::commands::FlowScaleArg;
+use crate::commands::MinLakeAccumulationArg;
+use crate::commands::EdgeDrainageArg;
+use crate::world_map::tile_layer::TileForFlowGraph;
+use crate::world_map::tile_layer::TileForEdgeWrap;
+use crate::world_map::fields::NeighborAndDirection;
+use crate::utils::edge::Edge;
+use indexmap::IndexMap;
+
+/// Specifies what happens to water which reaches the edge of the map.
+#[derive(Clone,ValueEnum)]
+pub enum EdgeDrainage {
+    /// The edge acts like a coast. Water flows off of the map, and rivers reaching it end in a mouth.
+    Ocean,
+    /// The edge acts like a wall. Water can't flow off of the map, so it pools into lakes along the edge.
+    Wall,
+    /// Water crossing the east or west edge enters the map on the opposite edge, as on a cylindrical world. Water crossing the north or south edge still flows off of the map.
+    Wrap
+}
+
+impl EdgeDrainage {
+
+    /// The elevation to use for a neighbor which is off of the map, when looking for the lowest neighbor.
+    pub(crate) const fn off_map_elevation(&self) -> f64 {
+        match self {
+            Self::Ocean | Self::Wrap => f64::NEG_INFINITY,
+            Self::Wall => f64::INFINITY,
+        }
+    }
+
+    /// Whether a lake which reaches the edge of the map can spill off of it.
+    pub(crate) const fn drains_off_map(&self) -> bool {
+        match self {
+            Self::Ocean | Self::Wrap => true,
+            Self::Wall => false,
+        }
+    }
+}
 
 
 
@@ -19,15 +62,119 @@ pub(crate) struct WaterFlowResult  {
     pub lake_queue: Vec<(IdRef,f64)> 
 }
 
-pub(crate) fn generate_water_flow<Progress: ProgressObserver>(target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<WaterFlowResult,CommandError> {
+/// The flow of water between tiles, as calculated by `gen-water`. This allows examining the drainage network, such as for finding basins and divides, without running the flow algorithms again.
+pub(crate) struct FlowGraph {
+    tiles: EntityIndex<TileSchema,TileForFlowGraph>,
+    flow_from: IndexMap<IdRef,Vec<IdRef>>
+}
+
+impl FlowGraph {
+
+    pub(crate) fn new(tiles: EntityIndex<TileSchema,TileForFlowGraph>) -> Self {
+        let mut flow_from: IndexMap<IdRef,Vec<IdRef>> = IndexMap::new();
+        for (fid,tile) in tiles.iter() {
+            for neighbor in tile.flow_to() {
+                match neighbor {
+                    Neighbor::Tile(neighbor) | Neighbor::CrossMap(neighbor,_) => flow_from.entry(neighbor.clone()).or_default().push(fid.clone()),
+                    Neighbor::OffMap(_) => (),
+                }
+            }
+        }
+        Self {
+            tiles,
+            flow_from
+        }
+    }
+
+    pub(crate) const fn tiles(&self) -> &EntityIndex<TileSchema,TileForFlowGraph> {
+        &self.tiles
+    }
+
+    /// The tiles that water flows into the specified tile from.
+    pub(crate) fn flow_from(&self, tile: &IdRef) -> &[IdRef] {
+        self.flow_from.get(tile).map_or(&[], Vec::as_slice)
+    }
+
+}
+
+/// Splits the flow from a tile among its lowest neighbors, which are paired with their elevations. At a concentration of 0, every neighbor gets an equal share. Any higher concentration moves that portion of the flow to the neighbor with the steepest drop. The lowest neighbors are only equal within rounding, so that is the lowest of them, and exact ties go to the smallest neighbor, so the choice doesn't depend on the order the neighbors are listed in. Neighbors whose share is taken entirely by that neighbor are left out of the result, so they don't become part of a river, but a tile with no flow still keeps all of its links. The favored neighbor is always listed first, since it has the largest share.
+pub(crate) fn distribute_flow(water_flow: f64, lowest: Vec<(Neighbor,f64)>, concentration: f64) -> Vec<(Neighbor,f64)> {
+    let (favored_portion,shared_portion) = flow_portions(lowest.len(), concentration);
+    let favored = lowest.iter().min_by(|(a,a_elevation),(b,b_elevation)| a_elevation.total_cmp(b_elevation).then_with(|| a.cmp(b))).map(|(neighbor,_)| neighbor.clone());
+    let mut result: Vec<_> = lowest.into_iter().filter_map(|(neighbor,_)| {
+        let portion = if favored.as_ref() == Some(&neighbor) {
+            favored_portion
+        } else {
+            shared_portion
+        };
+        (portion > 0.0).then_some((neighbor,water_flow * portion))
+    }).collect();
+    result.sort_by_key(|(neighbor,_)| favored.as_ref() != Some(neighbor));
+    result
+}
+
+/// The portions of a tile's flow which `distribute_flow` gives to the favored neighbor and to each of the others, when the flow is split among the specified number of neighbors.
+pub(crate) fn flow_portions(count: usize, concentration: f64) -> (f64,f64) {
+    let shared_portion = (1.0 - concentration) / count as f64;
+    (concentration + shared_portion,shared_portion)
+}
+
+/// Matches the tiles along the east edge of the map with the tiles along the west edge which are closest in latitude, so that neighbors off of one edge can be replaced with tiles on the other. The result is keyed by the tile and the edge it touches.
+pub(crate) fn find_wrapped_edges(tiles: &EntityIndex<TileSchema,TileForEdgeWrap>) -> HashMap<(IdRef,Edge),Neighbor> {
+    let mut east_tiles = Vec::new();
+    let mut west_tiles = Vec::new();
+    for (fid,tile) in tiles.iter() {
+        for NeighborAndDirection(neighbor,_) in tile.neighbors() {
+            match neighbor {
+                Neighbor::OffMap(edge @ (Edge::Northeast | Edge::East | Edge::Southeast)) => east_tiles.push((fid.clone(),edge.clone(),*tile.site_y())),
+                Neighbor::OffMap(edge @ (Edge::Northwest | Edge::West | Edge::Southwest)) => west_tiles.push((fid.clone(),edge.clone(),*tile.site_y())),
+                Neighbor::OffMap(Edge::North | Edge::South) | Neighbor::Tile(_) | Neighbor::CrossMap(_,_) => (),
+            }
+        }
+    }
+
+    let nearest = |site_y: f64, candidates: &[(IdRef,Edge,f64)]| {
+        candidates.iter().min_by(|(_,_,a),(_,_,b)| (a - site_y).abs().total_cmp(&(b - site_y).abs())).map(|(fid,_,_)| fid.clone())
+    };
+
+    let mut result = HashMap::new();
+    for (fid,edge,site_y) in &east_tiles {
+        if let Some(partner) = nearest(*site_y,&west_tiles) {
+            _ = result.insert((fid.clone(),edge.clone()),Neighbor::CrossMap(partner,Edge::East));
+        }
+    }
+    for (fid,edge,site_y) in &west_tiles {
+        if let Some(partner) = nearest(*site_y,&east_tiles) {
+            _ = result.insert((fid.clone(),edge.clone()),Neighbor::CrossMap(partner,Edge::West));
+        }
+    }
+    result
+
+}
+
+/// Replaces the neighbors of a tile which are off of the map with the tiles they wrap around to, as found by `find_wrapped_edges`.
+pub(crate) fn wrap_edge_neighbors(fid: &IdRef, neighbors: &mut [NeighborAndDirection], wrapped_edges: &HashMap<(IdRef,Edge),Neighbor>) {
+    for NeighborAndDirection(neighbor,_) in neighbors {
+        if let Neighbor::OffMap(edge) = neighbor {
+            if let Some(wrapped) = wrapped_edges.get(&(fid.clone(),edge.clone())) {
+                *neighbor = wrapped.clone();
+            }
+        }
+    }
+}
+
+pub(crate) fn generate_water_flow<Progress: ProgressObserver>(target: &mut WorldMapTransaction, flow_concentration: &FlowConcentrationArg, flow_scale: &FlowScaleArg, min_lake_accumulation: &MinLakeAccumulationArg, edge_drainage: &EdgeDrainageArg, progress: &mut Progress) -> Result<WaterFlowResult,CommandError> {
+
+    // rivers are found later from the flow links alone, and need this to split the flow among them the same way.
+    _ = target.edit_properties_layer()?.set_flow_concentration(flow_concentration.flow_concentration)?;
 
     let mut layer = target.edit_tile_layer()?;
 
     // from the AFMG code, this is also done in calculating precipitation. I'm wondering if it's unscaling the precipitation somehow?
-    let cells_number_modifier = (layer.feature_count() as f64 / 10000.0).powf(0.25);
+    // The reference count was fixed at 10000 there, here it can be changed along with the flow scale to calibrate the rivers.
+    let cells_number_modifier = (layer.feature_count() as f64 / flow_scale.flow_reference_tiles as f64).powf(0.25);
 
     let mut tile_list = Vec::new();
-    let mut potential_lakes = Vec::new();
 
     let mut tile_map = layer.read_features().into_entities_index_for_each::<_,TileForWaterflow,_>(|fid,tile| {
         if !tile.grouping().is_ocean() {
@@ -38,6 +185,13 @@ pub(crate) fn generate_water_flow<Progress: ProgressObserver>(target: &mut World
         Ok(())
 
     },progress)?;
+
+    if matches!(edge_drainage.edge_drainage,EdgeDrainage::Wrap) {
+        let wrapped_edges = find_wrapped_edges(&layer.read_features().into_entities_index::<_,TileForEdgeWrap>(progress)?);
+        for (fid,tile) in tile_map.iter_mut() {
+            wrap_edge_neighbors(fid, tile.neighbors_mut(), &wrapped_edges);
+        }
+    }
     
     // sort tile list so the highest is first.
     tile_list.sort_by(|(_,a),(_,b)| // FUTURE: could use sort by key if I conver the values to OrderedFloats.
@@ -50,34 +204,74 @@ pub(crate) fn generate_water_flow<Progress: ProgressObserver>(target: &mut World
         }
     );
 
+    let potential_lakes = calculate_water_flow(&mut tile_map, &tile_list, cells_number_modifier, flow_scale.flow_scale, min_lake_accumulation.min_lake_accumulation, flow_concentration, &edge_drainage.edge_drainage, progress)?;
+
+    for (fid,tile) in tile_map.iter().watch(progress,"Writing flow.","Flow written.") {
+        let mut working_feature = layer.try_feature_by_id(fid)?;
+        
+        working_feature.set_water_flow(tile.water_flow())?;
+        working_feature.set_water_accumulation(tile.water_accumulation())?;
+        working_feature.set_flow_to(tile.flow_to())?;
+
+        layer.update_feature(working_feature)?;
+
+
+    }
+
+    Ok(WaterFlowResult {
+        tile_map: tile_map.into_iter().map(|(k,v)| (k,v.into())).collect(),
+        lake_queue: potential_lakes,
+    })
+
+
+}
+
+/// Moves water downhill from each land tile, starting with the highest, so the tiles below receive the flow from above. Whether water which reaches the edge of the map flows off of it depends on the edge drainage. Returns the tiles with no lower neighbors, where the water collects and may form lakes, if at least the minimum accumulation collects there.
+pub(super) fn calculate_water_flow<Progress: ProgressObserver>(tile_map: &mut EntityIndex<TileSchema,TileForWaterflow>, tile_list: &[(IdRef,f64)], cells_number_modifier: f64, flow_scale: f64, min_lake_accumulation: f64, flow_concentration: &FlowConcentrationArg, edge_drainage: &EdgeDrainage, progress: &mut Progress) -> Result<Vec<(IdRef,f64)>,CommandError> {
+
+    let mut potential_lakes = Vec::new();
+    let mut processed = HashSet::new();
+
     for (fid,elevation) in tile_list.iter().watch(progress,"Calculating initial flow.","Flow calculated.") {
         let entity = tile_map.try_get(fid)?;
-        let water_flow = entity.water_flow() + entity.precipitation() / cells_number_modifier;
-        let (lowest,lowest_elevation) = find_lowest_tile(entity,&tile_map,|t| {
+        let water_flow = entity.water_flow() + entity.precipitation() / cells_number_modifier * flow_scale;
+        let (lowest,lowest_elevation) = find_lowest_tile(entity,tile_map,|t| {
             match t {
                 Some((t,_)) => *t.elevation(),
-                // water always flows off the map
-                None => f64::NEG_INFINITY,
+                None => edge_drainage.off_map_elevation(),
             }
         }, |t| t.neighbors())?;
 
+        // A neighbor at the same elevation may already have been processed, and any water sent there now would never
+        // move on. If those are the only ways out, this is the bottom of a flat depression, and the water collects here.
+        let lowest = lowest.into_iter().filter_map(|neighbor| match &neighbor {
+            Neighbor::Tile(neighbor_id) | Neighbor::CrossMap(neighbor_id,_) => if processed.contains(neighbor_id) {
+                None
+            } else {
+                Some(tile_map.try_get(neighbor_id).map(|neighbor_tile| (neighbor,*neighbor_tile.elevation())))
+            },
+            Neighbor::OffMap(_) => Some(Ok((neighbor,edge_drainage.off_map_elevation()))),
+        }).collect::<Result<Vec<_>,_>>()?;
+
         let (water_accumulation,flow_to) = if let Some(lowest_elevation) = lowest_elevation {
 
-            if &lowest_elevation <= elevation {
-                let neighbor_flow = water_flow/lowest.len() as f64;
-                //println!("flowing {} to {} neighbors",neighbor_flow,lowest.len());
-                for neighbor in &lowest {
-                    match neighbor {
+            if (&lowest_elevation <= elevation) && !lowest.is_empty() {
+                let mut flow_to = Vec::new();
+                for (neighbor,neighbor_flow) in distribute_flow(water_flow, lowest, flow_concentration.flow_concentration) {
+                    match &neighbor {
                         Neighbor::Tile(neighbor) | Neighbor::CrossMap(neighbor,_) => {
                             let neighbor = tile_map.try_get_mut(neighbor)?;
                             *neighbor.water_flow_mut() += neighbor_flow;
                         }
                         Neighbor::OffMap(_) => (),
                     } // else it just disappears off the map
+                    flow_to.push(neighbor);
                 }
-                (0.0,lowest)
+                (0.0,flow_to)
             } else {
-                potential_lakes.push((fid.clone(),water_flow));
+                if water_flow >= min_lake_accumulation {
+                    potential_lakes.push((fid.clone(),water_flow));
+                }
                 (water_flow,Vec::new())
             }
 
@@ -89,26 +283,254 @@ pub(crate) fn generate_water_flow<Progress: ProgressObserver>(target: &mut World
         tile.set_water_flow(water_flow);
         *tile.water_accumulation_mut() += water_accumulation;
         tile.set_flow_to(flow_to);
+        _ = processed.insert(fid.clone());
 
     }
 
+    Ok(potential_lakes)
+}
 
-    for (fid,tile) in tile_map.iter().watch(progress,"Writing flow.","Flow written.") {
-        let mut working_feature = layer.try_feature_by_id(fid)?;
-        
-        working_feature.set_water_flow(tile.water_flow())?;
-        working_feature.set_water_accumulation(tile.water_accumulation())?;
-        working_feature.set_flow_to(tile.flow_to())?;
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
 
-        layer.update_feature(working_feature)?;
+    use super::distribute_flow;
+    use super::calculate_water_flow;
+    use super::find_wrapped_edges;
+    use super::wrap_edge_neighbors;
+    use super::EdgeDrainage;
+    use super::FlowGraph;
+    use crate::commands::FlowConcentrationArg;
+    use crate::world_map::tile_layer::TileForWaterflow;
+    use crate::world_map::tile_layer::TileForEdgeWrap;
+    use crate::world_map::fields::Grouping;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::typed_map::entities::EntityIndex;
+    use crate::world_map::tile_layer::TileForFlowGraph;
+    use crate::world_map::tile_layer::TileSchema;
+    use crate::utils::edge::Edge;
+    use crate::world_map::fields::Neighbor;
+    use crate::typed_map::fields::IdRef;
+
+    #[test]
+    fn test_flow_concentration_extremes() {
+        let lowest = vec![(Neighbor::Tile(IdRef::new(1)),5.0),(Neighbor::Tile(IdRef::new(2)),5.0)];
 
+        let split = distribute_flow(10.0, lowest.clone(), 0.0);
+        assert_eq!(split,vec![(Neighbor::Tile(IdRef::new(1)),5.0),(Neighbor::Tile(IdRef::new(2)),5.0)]);
 
+        let single = distribute_flow(10.0, lowest.clone(), 1.0);
+        assert_eq!(single,vec![(Neighbor::Tile(IdRef::new(1)),10.0)]);
+
+        let partial = distribute_flow(10.0, lowest, 0.5);
+        assert_eq!(partial,vec![(Neighbor::Tile(IdRef::new(1)),7.5),(Neighbor::Tile(IdRef::new(2)),2.5)]);
     }
 
-    Ok(WaterFlowResult {
-        tile_map: tile_map.into_iter().map(|(k,v)| (k,v.into())).collect(),
-        lake_queue: potential_lakes,
-    })
+    #[test]
+    fn test_largest_flow_share_listed_first() {
+        let lowest = vec![(Neighbor::Tile(IdRef::new(1)),5.0),(Neighbor::Tile(IdRef::new(2)),4.0)];
+
+        let partial = distribute_flow(10.0, lowest, 0.5);
+        assert_eq!(partial,vec![(Neighbor::Tile(IdRef::new(2)),7.5),(Neighbor::Tile(IdRef::new(1)),2.5)]);
+    }
+
+    #[test]
+    fn test_flow_concentration_favors_steepest_drop() {
+        // tile 3 is slightly lower, so it gets the concentrated flow no matter where it is listed.
+        let lowest = vec![(Neighbor::Tile(IdRef::new(2)),5.0),(Neighbor::Tile(IdRef::new(3)),4.999_999),(Neighbor::Tile(IdRef::new(1)),5.0)];
+        let mut reversed = lowest.clone();
+        reversed.reverse();
 
+        assert_eq!(distribute_flow(10.0, lowest, 1.0),vec![(Neighbor::Tile(IdRef::new(3)),10.0)]);
+        assert_eq!(distribute_flow(10.0, reversed, 1.0),vec![(Neighbor::Tile(IdRef::new(3)),10.0)]);
 
+        // exact ties don't depend on the order either.
+        let tied = vec![(Neighbor::Tile(IdRef::new(2)),5.0),(Neighbor::Tile(IdRef::new(1)),5.0)];
+        assert_eq!(distribute_flow(10.0, tied, 1.0),vec![(Neighbor::Tile(IdRef::new(1)),10.0)]);
+    }
+
+    #[test]
+    fn test_dry_tiles_keep_flow_links() {
+        let lowest = vec![(Neighbor::Tile(IdRef::new(1)),5.0),(Neighbor::Tile(IdRef::new(2)),5.0)];
+
+        assert_eq!(distribute_flow(0.0, lowest.clone(), 0.0),vec![(Neighbor::Tile(IdRef::new(1)),0.0),(Neighbor::Tile(IdRef::new(2)),0.0)]);
+        assert_eq!(distribute_flow(0.0, lowest, 1.0),vec![(Neighbor::Tile(IdRef::new(1)),0.0)]);
+    }
+
+    #[test]
+    fn test_flow_graph() {
+        // tiles 1 and 3 flow into tile 2, which flows off the map.
+        let tiles: EntityIndex<TileSchema,TileForFlowGraph> = [
+            (IdRef::new(1),TileForFlowGraph::new(20.0, 1.0, 0.0, vec![Neighbor::Tile(IdRef::new(2))], None)),
+            (IdRef::new(2),TileForFlowGraph::new(10.0, 3.0, 0.0, vec![Neighbor::OffMap(Edge::West)], None)),
+            (IdRef::new(3),TileForFlowGraph::new(20.0, 2.0, 0.0, vec![Neighbor::Tile(IdRef::new(2))], None)),
+        ].into_iter().collect();
+
+        let graph = FlowGraph::new(tiles);
+
+        assert_eq!(graph.tiles().len(),3);
+        assert_eq!(graph.flow_from(&IdRef::new(2)),&[IdRef::new(1),IdRef::new(3)]);
+        assert!(graph.flow_from(&IdRef::new(1)).is_empty());
+        // flow off the map doesn't come from anywhere.
+        assert!(graph.flow_from(&IdRef::new(4)).is_empty());
+    }
+
+    #[test]
+    fn test_flow_off_top_edge() {
+        // tile 1 sits on the north edge of the map, above tile 2, which is above the ocean at tile 3.
+        let mut tile_map: EntityIndex<TileSchema,TileForWaterflow> = [
+            (IdRef::new(1),TileForWaterflow::new(10.0, Grouping::Continent, vec![
+                NeighborAndDirection(Neighbor::OffMap(Edge::North),Deg(0.0)),
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(180.0))
+            ], 4.0)),
+            (IdRef::new(2),TileForWaterflow::new(5.0, Grouping::Continent, vec![
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(1)),Deg(0.0)),
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(3)),Deg(180.0))
+            ], 2.0)),
+            (IdRef::new(3),TileForWaterflow::new(-1.0, Grouping::Ocean, vec![
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(0.0))
+            ], 0.0)),
+        ].into_iter().collect();
+        let tile_list = [(IdRef::new(1),10.0),(IdRef::new(2),5.0)];
+        let concentration = FlowConcentrationArg { flow_concentration: 0.0 };
+
+        let potential_lakes = calculate_water_flow(&mut tile_map, &tile_list, 1.0, 1.0, 0.0, &concentration, &EdgeDrainage::Ocean, &mut ()).unwrap();
+
+        // the edge of the map acts as a coast, so the water leaves the map instead of pooling or running down to tile 2.
+        assert!(potential_lakes.is_empty());
+        let top = tile_map.try_get(&IdRef::new(1)).unwrap();
+        assert_eq!(top.flow_to(),&vec![Neighbor::OffMap(Edge::North)]);
+        assert_eq!(top.water_flow().to_bits(),4.0f64.to_bits());
+        assert_eq!(top.water_accumulation().to_bits(),0.0f64.to_bits());
+        let below = tile_map.try_get(&IdRef::new(2)).unwrap();
+        assert_eq!(below.water_flow().to_bits(),2.0f64.to_bits());
+        assert_eq!(below.flow_to(),&vec![Neighbor::Tile(IdRef::new(3))]);
+    }
+
+    #[test]
+    fn test_flow_scale() {
+        // a slope of three land tiles running down into the ocean, so each tile's flow includes the flow from above.
+        let run = |flow_scale: f64| {
+            let mut tile_map: EntityIndex<TileSchema,TileForWaterflow> = [
+                (IdRef::new(1),TileForWaterflow::new(10.0, Grouping::Continent, vec![
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(180.0))
+                ], 3.0)),
+                (IdRef::new(2),TileForWaterflow::new(6.0, Grouping::Continent, vec![
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(1)),Deg(0.0)),
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(3)),Deg(180.0))
+                ], 1.5)),
+                (IdRef::new(3),TileForWaterflow::new(2.0, Grouping::Continent, vec![
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(0.0)),
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(4)),Deg(180.0))
+                ], 0.25)),
+                (IdRef::new(4),TileForWaterflow::new(-1.0, Grouping::Ocean, vec![
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(3)),Deg(0.0))
+                ], 0.0)),
+            ].into_iter().collect();
+            let tile_list = [(IdRef::new(1),10.0),(IdRef::new(2),6.0),(IdRef::new(3),2.0)];
+            let concentration = FlowConcentrationArg { flow_concentration: 0.0 };
+            _ = calculate_water_flow(&mut tile_map, &tile_list, 1.5, flow_scale, 0.0, &concentration, &EdgeDrainage::Ocean, &mut ()).unwrap();
+            tile_map.iter().map(|(_,tile)| *tile.water_flow()).collect::<Vec<_>>()
+        };
+
+        let single = run(1.0);
+        let double = run(2.0);
+        assert!(single.iter().take(3).all(|flow| flow > &0.0));
+        for (single_flow,double_flow) in single.iter().zip(&double) {
+            assert_eq!((single_flow * 2.0).to_bits(),double_flow.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_dry_sink_queued() {
+        // a dry hollow below a dry slope still goes to the lake fill, unless a minimum accumulation is asked for.
+        let flow = |min_lake_accumulation: f64| {
+            let mut tile_map: EntityIndex<TileSchema,TileForWaterflow> = [
+                (IdRef::new(1),TileForWaterflow::new(10.0, Grouping::Continent, vec![
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(180.0))
+                ], 0.0)),
+                (IdRef::new(2),TileForWaterflow::new(5.0, Grouping::Continent, vec![
+                    NeighborAndDirection(Neighbor::Tile(IdRef::new(1)),Deg(0.0))
+                ], 0.0)),
+            ].into_iter().collect();
+            let tile_list = [(IdRef::new(1),10.0),(IdRef::new(2),5.0)];
+            let concentration = FlowConcentrationArg { flow_concentration: 0.0 };
+            calculate_water_flow(&mut tile_map, &tile_list, 1.0, 1.0, min_lake_accumulation, &concentration, &EdgeDrainage::Ocean, &mut ()).unwrap()
+        };
+
+        assert_eq!(flow(0.0),vec![(IdRef::new(2),0.0)]);
+        assert!(flow(0.5).is_empty());
+    }
+
+    // A row of three tiles across the whole map: tile 1 on the west edge, a ridge at tile 2, and tile 3 on the east edge, whose lowest neighbor is off the map.
+    fn run_edge_drainage(edge_drainage: &EdgeDrainage) -> (EntityIndex<TileSchema,TileForWaterflow>,Vec<(IdRef,f64)>) {
+        let neighbors = [
+            vec![
+                NeighborAndDirection(Neighbor::OffMap(Edge::West),Deg(270.0)),
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(90.0))
+            ],
+            vec![
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(1)),Deg(270.0)),
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(3)),Deg(90.0))
+            ],
+            vec![
+                NeighborAndDirection(Neighbor::Tile(IdRef::new(2)),Deg(270.0)),
+                NeighborAndDirection(Neighbor::OffMap(Edge::East),Deg(90.0))
+            ]
+        ];
+        let elevations = [3.0,12.0,10.0];
+
+        let mut tile_map: EntityIndex<TileSchema,TileForWaterflow> = neighbors.iter().zip(elevations).enumerate().map(|(i,(neighbors,elevation))| {
+            (IdRef::new(i as u64 + 1),TileForWaterflow::new(elevation, Grouping::Continent, neighbors.clone(), 1.0))
+        }).collect();
+
+        if matches!(edge_drainage,EdgeDrainage::Wrap) {
+            let edge_tiles: EntityIndex<TileSchema,TileForEdgeWrap> = neighbors.iter().enumerate().map(|(i,neighbors)| {
+                (IdRef::new(i as u64 + 1),TileForEdgeWrap::new(0.0, neighbors.clone()))
+            }).collect();
+            let wrapped_edges = find_wrapped_edges(&edge_tiles);
+            for (fid,tile) in tile_map.iter_mut() {
+                wrap_edge_neighbors(fid, tile.neighbors_mut(), &wrapped_edges);
+            }
+        }
+
+        let tile_list = [(IdRef::new(2),12.0),(IdRef::new(3),10.0),(IdRef::new(1),3.0)];
+        let concentration = FlowConcentrationArg { flow_concentration: 0.0 };
+        let potential_lakes = calculate_water_flow(&mut tile_map, &tile_list, 1.0, 1.0, 0.0, &concentration, edge_drainage, &mut ()).unwrap();
+        (tile_map,potential_lakes)
+    }
+
+    #[test]
+    fn test_edge_drainage_ocean() {
+        let (tile_map,potential_lakes) = run_edge_drainage(&EdgeDrainage::Ocean);
+
+        // both edge tiles drain off the map, like a river mouth.
+        assert!(potential_lakes.is_empty());
+        assert_eq!(tile_map.try_get(&IdRef::new(3)).unwrap().flow_to(),&vec![Neighbor::OffMap(Edge::East)]);
+        assert_eq!(tile_map.try_get(&IdRef::new(1)).unwrap().flow_to(),&vec![Neighbor::OffMap(Edge::West)]);
+    }
+
+    #[test]
+    fn test_edge_drainage_wall() {
+        let (tile_map,potential_lakes) = run_edge_drainage(&EdgeDrainage::Wall);
+
+        // the edge tiles are lower than anything they can flow to, so they pool.
+        let lakes: Vec<_> = potential_lakes.iter().map(|(fid,_)| fid.clone()).collect();
+        assert_eq!(lakes,vec![IdRef::new(3),IdRef::new(1)]);
+        let east = tile_map.try_get(&IdRef::new(3)).unwrap();
+        assert!(east.flow_to().is_empty());
+        assert_eq!(east.water_accumulation().to_bits(),1.0f64.to_bits());
+    }
+
+    #[test]
+    fn test_edge_drainage_wrap() {
+        let (tile_map,potential_lakes) = run_edge_drainage(&EdgeDrainage::Wrap);
+
+        // water from the east edge comes back in on the west edge, and pools there because its only way out leads uphill.
+        let east = tile_map.try_get(&IdRef::new(3)).unwrap();
+        assert_eq!(east.flow_to(),&vec![Neighbor::CrossMap(IdRef::new(1),Edge::East)]);
+        let west = tile_map.try_get(&IdRef::new(1)).unwrap();
+        assert_eq!(west.water_flow().to_bits(),3.0f64.to_bits());
+        assert_eq!(potential_lakes,vec![(IdRef::new(1),3.0)]);
+    }
 }
@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use clap::Args;
+use clap::ValueEnum;
 use rand::Rng;
 
 use crate::subcommand_def;
@@ -14,30 +17,165 @@ use crate::commands::create::LoadedSource;
 use crate::commands::create::Create;
 use crate::commands::gen_climate::GenClimate;
 use crate::commands::gen_water::GenWater;
+use crate::commands::gen_water::NameLakes;
 use crate::commands::gen_biome::GenBiome;
 use crate::commands::gen_people::GenPeople;
 use crate::world_map::culture_layer::CultureForNations;
+use crate::world_map::culture_layer::CultureLayer;
+use crate::world_map::culture_layer::CultureSchema;
+use crate::typed_map::schema::Schema;
+use crate::typed_map::entities::EntityLookup;
+use crate::world_map::auxiliary_layers::PointLayer;
+use crate::world_map::auxiliary_layers::TriangleLayer;
+use crate::world_map::tile_layer::TileLayer;
+use crate::world_map::property_layer::PropertyLayer;
+use crate::world_map::water_layers::CoastlineLayer;
+use crate::world_map::water_layers::OceanLayer;
+use crate::world_map::water_layers::LakeLayer;
+use crate::world_map::water_layers::RiverLayer;
+use crate::world_map::biome_layer::BiomeLayer;
+use crate::world_map::town_layer::TownLayer;
+use crate::world_map::nation_layers::NationLayer;
+use crate::world_map::nation_layers::SubnationLayer;
 use crate::commands::gen_towns::GenTowns;
 use crate::commands::gen_nations::GenNations;
 use crate::commands::gen_subnations::GenSubnations;
 use crate::commands::TileCountArg;
+use crate::commands::BoundaryPointsArg;
+use crate::commands::PointDistributionArg;
 use crate::commands::WorldShapeArg;
+use crate::commands::IntegerIdsArg;
+use crate::commands::ContinentCountArg;
 use crate::commands::RandomSeedArg;
+use crate::commands::PeopleSeedArg;
 use crate::commands::OverwriteAllArg;
 use crate::commands::BezierScaleArg;
+use crate::commands::CoastDensifyArg;
 use crate::commands::TemperatureRangeArg;
+use crate::commands::CurrentStrengthArg;
 use crate::commands::WindsArg;
 use crate::commands::PrecipitationArg;
 use crate::commands::NamerArg;
 use crate::commands::SizeVarianceArg;
 use crate::commands::RiverThresholdArg;
+use crate::commands::DebugHabitabilityArg;
 use crate::commands::ExpansionFactorArg;
+use crate::commands::CultureWaterCostArg;
+use crate::commands::NationExpansionArg;
+use crate::commands::MaxExpansionCostArg;
 use crate::commands::CulturesGenArg;
 use crate::commands::SubnationPercentArg;
 use crate::commands::TownCountsArg;
+use crate::commands::NationCountArg;
 use crate::commands::LakeBufferScaleArg;
+use crate::commands::FlowConcentrationArg;
+use crate::commands::FlowScaleArg;
+use crate::commands::MinLakeAccumulationArg;
+use crate::commands::MinNamedLakeSizeArg;
+use crate::commands::EdgeDrainageArg;
+use crate::commands::DeltaBranchingArg;
+use crate::commands::ShelfDepthArg;
+use crate::commands::IsolatedBasinsArg;
 use crate::commands::OverrideBiomeCriteriaArg;
-use crate::utils::random::random_number_generator;
+use crate::commands::IceCapArg;
+use crate::commands::CompactArg;
+use crate::utils::random::random_seed;
+use crate::utils::random::retry_with_derived_seeds;
+use crate::utils::random::people_seed;
+use crate::utils::random::stage_random_number_generator;
+use crate::utils::random::CREATE_STAGE;
+use crate::utils::random::NAMERS_STAGE;
+use crate::utils::random::PEOPLE_STAGE;
+use crate::utils::random::TOWNS_STAGE;
+use crate::utils::random::NATIONS_STAGE;
+use crate::utils::random::LAKE_NAMES_STAGE;
+use crate::utils::random::SUBNATIONS_STAGE;
+
+
+/// A stage of world generation which can be left out of big-bang.
+#[derive(Clone,Copy,PartialEq,Eq,ValueEnum)]
+pub enum BigBangStage {
+    /// Temperature, wind and precipitation
+    Climate,
+    /// Coastlines, oceans, lakes and rivers
+    Water,
+    /// Biomes
+    Biomes,
+    /// Population and cultures
+    People,
+    /// Towns
+    Towns,
+    /// Nations
+    Nations,
+    /// Subnations
+    Subnations
+}
+
+impl BigBangStage {
+
+    const ALL: [Self; 7] = [Self::Climate, Self::Water, Self::Biomes, Self::People, Self::Towns, Self::Nations, Self::Subnations];
+
+    /// The name of the stage as it is given to `--skip`.
+    fn name(self) -> String {
+        self.to_possible_value().expect("Every stage can be given on the command line.").get_name().to_owned()
+    }
+
+    /// The earlier stages whose output this stage reads.
+    const fn requires(self) -> &'static [Self] {
+        match self {
+            Self::Climate => &[],
+            Self::Water => &[Self::Climate],
+            Self::Biomes => &[Self::Climate, Self::Water],
+            Self::People => &[Self::Water, Self::Biomes],
+            Self::Towns => &[Self::Water, Self::People],
+            Self::Nations => &[Self::Biomes, Self::People, Self::Towns],
+            Self::Subnations => &[Self::Towns, Self::Nations],
+        }
+    }
+
+    /// The layers this stage generates. The other layers are generated by `create`, which is never skipped.
+    const fn layers(self) -> &'static [&'static str] {
+        match self {
+            Self::Climate => &[],
+            Self::Water => &[CoastlineLayer::layer_name(), OceanLayer::layer_name(), LakeLayer::layer_name(), RiverLayer::layer_name()],
+            Self::Biomes => &[BiomeLayer::layer_name()],
+            Self::People => &[CultureLayer::layer_name()],
+            Self::Towns => &[TownLayer::layer_name()],
+            Self::Nations => &[NationLayer::layer_name()],
+            Self::Subnations => &[SubnationLayer::layer_name()],
+        }
+    }
+
+    /// Makes sure that every layer requested for the output will be generated.
+    pub(crate) fn validate_output_layers(skip: &[Self], output_layers: &[String]) -> Result<(),CommandError> {
+        let created = [PointLayer::layer_name(), TriangleLayer::layer_name(), TileLayer::layer_name(), PropertyLayer::layer_name()];
+        for layer in output_layers {
+            if created.contains(&layer.as_str()) {
+                continue;
+            }
+            if let Some(stage) = Self::ALL.into_iter().find(|stage| stage.layers().contains(&layer.as_str())) {
+                if skip.contains(&stage) {
+                    return Err(CommandError::OutputLayerSkipped(layer.clone(), stage.name()))
+                }
+            } else if WorldMap::known_layer_names().contains(&layer.as_str()) {
+                return Err(CommandError::OutputLayerNotGenerated(layer.clone()))
+            } else {
+                return Err(CommandError::UnknownLayer(layer.clone()))
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes sure that no stage that will be run needs the output of a skipped stage.
+    pub(crate) fn validate_skipped(skip: &[Self]) -> Result<(),CommandError> {
+        for stage in Self::ALL.into_iter().filter(|stage| !skip.contains(stage)) {
+            if let Some(required) = stage.requires().iter().find(|required| skip.contains(required)) {
+                return Err(CommandError::SkippedStageRequired(required.name(), stage.name()))
+            }
+        }
+        Ok(())
+    }
+}
 
 
 #[derive(Args)]
@@ -46,12 +184,27 @@ pub struct PrimitiveArgs {
     #[clap(flatten)]
     pub tile_count: TileCountArg,
 
+    #[clap(flatten)]
+    pub boundary_points: BoundaryPointsArg,
+
+    #[clap(flatten)]
+    pub point_distribution: PointDistributionArg,
+
     #[clap(flatten)]
     pub world_shape: WorldShapeArg,
 
+    #[clap(flatten)]
+    pub integer_ids: IntegerIdsArg,
+
+    #[clap(flatten)]
+    pub continent_count: ContinentCountArg,
+
     #[clap(flatten)]
     pub temperature: TemperatureRangeArg,
 
+    #[clap(flatten)]
+    pub current_strength: CurrentStrengthArg,
+
     #[clap(flatten)]
     pub wind: WindsArg,
 
@@ -61,24 +214,66 @@ pub struct PrimitiveArgs {
     #[clap(flatten)]
     pub bezier_scale: BezierScaleArg,
 
+    #[clap(flatten)]
+    pub coast_densify: CoastDensifyArg,
+
+    #[clap(flatten)]
+    pub isolated_basins: IsolatedBasinsArg,
+
     #[clap(flatten)]
     pub lake_buffer_scale: LakeBufferScaleArg,
 
+    #[clap(flatten)]
+    pub flow_concentration: FlowConcentrationArg,
+
+    #[clap(flatten)]
+    pub flow_scale: FlowScaleArg,
+
+    #[clap(flatten)]
+    pub min_lake_accumulation: MinLakeAccumulationArg,
+
+    #[clap(flatten)]
+    pub min_named_lake_size: MinNamedLakeSizeArg,
+
+    #[clap(flatten)]
+    pub edge_drainage: EdgeDrainageArg,
+
+    #[clap(flatten)]
+    pub delta_branching: DeltaBranchingArg,
+
+    #[clap(flatten)]
+    pub shelf_depth: ShelfDepthArg,
+
     #[clap(flatten)]
     pub river_threshold: RiverThresholdArg,
 
+    #[clap(flatten)]
+    pub debug_habitability: DebugHabitabilityArg,
+
     #[clap(flatten)]
     pub override_biome_criteria: OverrideBiomeCriteriaArg,
 
+    #[clap(flatten)]
+    pub ice_cap: IceCapArg,
+
     #[clap(flatten)]
     pub size_variance: SizeVarianceArg,
 
     #[clap(flatten)]
     pub expansion_factor: ExpansionFactorArg,
 
+    #[clap(flatten)]
+    pub culture_water_cost: CultureWaterCostArg,
+
+    #[clap(flatten)]
+    pub nation_expansion: NationExpansionArg,
+
     #[clap(flatten)]
     pub town_counts: TownCountsArg,
 
+    #[clap(flatten)]
+    pub nation_count: NationCountArg,
+
     #[clap(flatten)]
     pub subnation_percent: SubnationPercentArg,
 
@@ -103,9 +298,31 @@ subcommand_def!{
         #[clap(flatten)]
         pub random_seed_arg: RandomSeedArg,
 
+        #[clap(flatten)]
+        pub people_seed_arg: PeopleSeedArg,
+
         #[clap(flatten)]
         pub primitive_args: PrimitiveArgs,
 
+        #[clap(flatten)]
+        pub compact_arg: CompactArg,
+
+        #[arg(long,value_delimiter=',')]
+        /// Generation stages to leave out, separated by commas. A stage can't be skipped if a later stage that isn't skipped needs it.
+        pub skip: Vec<BigBangStage>,
+
+        #[arg(long,value_delimiter=',')]
+        /// The layers to keep in the world map, separated by commas. The other layers, including intermediate ones such as points and triangles, are deleted once generation is finished. The properties layer is always kept. If not specified, all layers are kept.
+        pub output_layers: Vec<String>,
+
+        #[arg(long,default_value="0")]
+        /// If generation fails on an unusual shape in the generated data, retry up to this many times with new seeds derived from the original. Requires '--overwrite-all'.
+        pub retry: usize,
+
+        #[arg(long,default_value="0")]
+        /// Start with this retry of the seed, to generate the world from a retry that succeeded again. Retries after this one are still limited by '--retry'.
+        pub replay_retry: usize,
+
         #[command(subcommand)]
         pub source: Source,
 
@@ -117,13 +334,38 @@ impl Task for BigBang {
 
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
-        let mut random = random_number_generator(&self.random_seed_arg);
+        // check these before spending time on the terrain
+        _ = self.primitive_args.precipitation.validated_factor()?;
+        BigBangStage::validate_output_layers(&self.skip, &self.output_layers)?;
+
+        // a failed attempt leaves its layers in the target, so the retries need to be able to replace them.
+        if (self.retry > 0) && !self.primitive_args.overwrite_all.overwrite_all {
+            return Err(CommandError::RetryRequiresOverwriteAll)
+        }
+
+        let seed = random_seed(&self.random_seed_arg);
+
+        // Each stage uses a generator derived from the seed the same way its separate command does, so running the commands one at a time with the same seed generates the same world.
+        let mut loaded_namers = NamerSet::load_from(self.namer_arg, &mut stage_random_number_generator(people_seed(&self.people_seed_arg, seed), NAMERS_STAGE), progress)?;
 
-        let mut loaded_namers = NamerSet::load_from(self.namer_arg, &mut random, progress)?;
+        let mut random = stage_random_number_generator(seed, CREATE_STAGE);
 
         let loaded_source = self.source.load(&mut random, progress)?; 
 
-        Self::run_default(&mut random,&self.primitive_args,&self.cultures_arg,&mut loaded_namers,loaded_source,&self.target_arg,progress)
+        // The first attempt continues with the generator used for loading, so a run that doesn't need a retry is the same as without '--retry'. The namers and source are only loaded once, with the original seed, so a retry is reproduced by replaying it with '--replay-retry' and the original seed.
+        let mut first_random = Some(random);
+
+        _ = retry_with_derived_seeds(seed, self.replay_retry, self.retry, progress, |attempt_seed, retry, attempt_progress| {
+            let mut attempt_random = match (retry, first_random.take()) {
+                (0, Some(random)) => random,
+                _ => stage_random_number_generator(attempt_seed, CREATE_STAGE)
+            };
+            // a failed attempt may have used the namers.
+            loaded_namers.reset();
+            Self::run_default(&mut attempt_random,people_seed(&self.people_seed_arg, attempt_seed),&self.primitive_args,&self.cultures_arg,&mut loaded_namers,&loaded_source,&self.target_arg,&self.compact_arg,&self.skip,&self.output_layers,attempt_progress)
+        })?;
+
+        Ok(())
 
     }
 }
@@ -131,29 +373,122 @@ impl Task for BigBang {
 impl BigBang {
 
 
-    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(random: &mut Random, primitive_args: &PrimitiveArgs, cultures: &CulturesGenArg, namers: &mut NamerSet, loaded_source: LoadedSource, target_arg: &TargetArg, progress: &mut Progress) -> Result<(), CommandError> {
+    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(random: &mut Random, people_seed: u64, primitive_args: &PrimitiveArgs, cultures: &CulturesGenArg, namers: &mut NamerSet, loaded_source: &LoadedSource, target_arg: &TargetArg, compact: &CompactArg, skip: &[BigBangStage], output_layers: &[String], progress: &mut Progress) -> Result<(), CommandError> {
 
-        let mut target = WorldMap::create_or_edit(&target_arg.target)?;
+        BigBangStage::validate_skipped(skip)?;
 
-        Create::run_default(&primitive_args.tile_count, &primitive_args.world_shape, &primitive_args.overwrite_all.overwrite_tiles(), loaded_source, &mut target, random, progress)?;
+        let runs = |stage: BigBangStage| !skip.contains(&stage);
 
-        GenClimate::run_default(&primitive_args.temperature, &primitive_args.wind, &primitive_args.precipitation, &mut target, progress)?;
+        let mut target = WorldMap::create_or_edit(&target_arg.target)?;
 
-        GenWater::run_default(&primitive_args.bezier_scale, &primitive_args.lake_buffer_scale, &primitive_args.overwrite_all.overwrite_coastline(), &primitive_args.overwrite_all.overwrite_ocean(), &primitive_args.overwrite_all.overwrite_lakes(), &primitive_args.overwrite_all.overwrite_rivers(), &mut target, progress)?;
+        Create::run_default(&primitive_args.tile_count, &primitive_args.point_distribution, &primitive_args.boundary_points, None, &primitive_args.world_shape, &primitive_args.integer_ids, &primitive_args.overwrite_all.overwrite_tiles(), &primitive_args.continent_count, loaded_source, &mut target, random, progress)?;
+
+        if runs(BigBangStage::Climate) {
+            GenClimate::run_default(&primitive_args.temperature, &primitive_args.current_strength, &primitive_args.wind, &primitive_args.precipitation, &mut target, progress)?;
+        }
+
+        if runs(BigBangStage::Water) {
+            GenWater::run_default(&primitive_args.bezier_scale, &primitive_args.coast_densify, &primitive_args.isolated_basins, &primitive_args.lake_buffer_scale, &primitive_args.flow_concentration, &primitive_args.flow_scale, &primitive_args.min_lake_accumulation, &primitive_args.edge_drainage, &primitive_args.delta_branching, &primitive_args.shelf_depth, &primitive_args.overwrite_all.overwrite_coastline(), &primitive_args.overwrite_all.overwrite_ocean(), &primitive_args.overwrite_all.overwrite_lakes(), &primitive_args.overwrite_all.overwrite_rivers(), &mut target, progress)?;
+        }
+
+        if runs(BigBangStage::Biomes) {
+            GenBiome::run_default(&primitive_args.override_biome_criteria, &primitive_args.ice_cap,&primitive_args.overwrite_all.overwrite_biomes(), &primitive_args.bezier_scale, &mut target, progress)?;
+        }
+
+        // Everything after this point is people, whose generators are derived from their own seed so they can be rerolled on the same geography.
+        if runs(BigBangStage::People) {
+            let mut people_random = stage_random_number_generator(people_seed, PEOPLE_STAGE);
+            // The 'namer_set' here is not loaded, it's only used to verify that a namer exists for a culture while creating. Just to be clear, I'm not loading the namers twice, they are only loaded in `get_lookup_and_namers` below.
+            GenPeople::run_default(&primitive_args.river_threshold, &primitive_args.debug_habitability, cultures, None, namers, &primitive_args.size_variance, &primitive_args.overwrite_all.overwrite_cultures(), &primitive_args.expansion_factor, &primitive_args.max_expansion_cost, &primitive_args.culture_water_cost, &primitive_args.bezier_scale, &mut target, &mut people_random, progress)?;
+        }
+
+        // The lakes are named after the cultures are generated, so they can be named by the people living around them.
+        if runs(BigBangStage::Water) {
+            let culture_lookup = if target.has_layer(CultureSchema::LAYER_NAME) {
+                target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?
+            } else {
+                EntityLookup::from(HashMap::new())
+            };
+
+            namers.reset();
+            target.with_transaction(|transaction| {
+                NameLakes::run_with_parameters(&mut stage_random_number_generator(people_seed, LAKE_NAMES_STAGE), &culture_lookup, namers, &primitive_args.min_named_lake_size, transaction, progress)
+            })?;
+            target.save(progress)?;
+        }
+
+        if runs(BigBangStage::Towns) {
+            // CultureForNations implements everything that all the algorithms need.
+            let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?;
+
+            namers.reset();
+            GenTowns::run_default(&mut stage_random_number_generator(people_seed, TOWNS_STAGE), &culture_lookup, namers, &primitive_args.town_counts, &primitive_args.river_threshold, &primitive_args.overwrite_all.overwrite_towns(), &mut target, progress)?;
+
+            if runs(BigBangStage::Nations) {
+                namers.reset();
+                GenNations::run_default(&mut stage_random_number_generator(people_seed, NATIONS_STAGE), &culture_lookup, namers, &primitive_args.size_variance, &primitive_args.nation_count, &primitive_args.river_threshold, &primitive_args.expansion_factor, &primitive_args.nation_expansion, &primitive_args.bezier_scale, &primitive_args.overwrite_all.overwrite_nations(), &mut target, progress)?;
+            }
+
+            if runs(BigBangStage::Subnations) {
+                namers.reset();
+                GenSubnations::run_default(&mut stage_random_number_generator(people_seed, SUBNATIONS_STAGE), &culture_lookup, namers, &primitive_args.subnation_percent, &primitive_args.overwrite_all.overwrite_subnations(), &primitive_args.bezier_scale, &mut target, progress)?;
+            }
+        }
+
+        if !output_layers.is_empty() {
+            let mut keep: Vec<&str> = output_layers.iter().map(String::as_str).collect();
+            keep.push(PropertyLayer::layer_name());
+            target.delete_layers_except(&keep, progress)?;
+        }
+
+        if compact.compact {
+            target.compact(progress)?;
+        }
+
+        Ok(())
 
-        GenBiome::run_default(&primitive_args.override_biome_criteria,&primitive_args.overwrite_all.overwrite_biomes(), &primitive_args.bezier_scale, &mut target, progress)?;
+    }
+}
 
-        // The 'namer_set' here is not loaded, it's only used to verify that a namer exists for a culture while creating. Just to be clear, I'm not loading the namers twice, they are only loaded in `get_lookup_and_namers` below.
-        GenPeople::run_default(&primitive_args.river_threshold, cultures, namers, &primitive_args.size_variance, &primitive_args.overwrite_all.overwrite_cultures(), &primitive_args.expansion_factor, &primitive_args.bezier_scale, &mut target, random, progress)?;
+#[cfg(test)]
+mod test {
+    use super::BigBangStage;
+    use crate::errors::CommandError;
+
+    #[test]
+    fn test_validate_skipped_stages() {
+        BigBangStage::validate_skipped(&[]).expect("Nothing skipped should be valid.");
+        BigBangStage::validate_skipped(&[BigBangStage::Subnations]).expect("Skipping the last stage should be valid.");
+        BigBangStage::validate_skipped(&[BigBangStage::People, BigBangStage::Towns, BigBangStage::Nations, BigBangStage::Subnations]).expect("Skipping all people stages should be valid.");
+
+        match BigBangStage::validate_skipped(&[BigBangStage::People]) {
+            Err(CommandError::SkippedStageRequired(skipped,needed_by)) => {
+                assert_eq!(skipped,"people");
+                assert_eq!(needed_by,"towns");
+            },
+            _ => panic!("Skipping people without skipping towns should be an error.")
+        }
+
+        assert!(BigBangStage::validate_skipped(&[BigBangStage::Towns, BigBangStage::Subnations]).is_err(),"Nations needs towns.");
+    }
 
-        // CultureForNations implements everything that all the algorithms need.
-        let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?;
-    
-        GenTowns::run_default(random, &culture_lookup, namers, &primitive_args.town_counts, &primitive_args.river_threshold, &primitive_args.overwrite_all.overwrite_towns(), &mut target, progress)?;
+    #[test]
+    fn test_validate_output_layers() {
+        let layers = |names: &[&str]| names.iter().map(|name| (*name).to_owned()).collect::<Vec<_>>();
 
-        GenNations::run_default(random, &culture_lookup, namers, &primitive_args.size_variance, &primitive_args.river_threshold, &primitive_args.expansion_factor, &primitive_args.bezier_scale, &primitive_args.overwrite_all.overwrite_nations(), &mut target, progress)?;
+        BigBangStage::validate_output_layers(&[], &[]).expect("Keeping everything should be valid.");
+        BigBangStage::validate_output_layers(&[], &layers(&["tiles","biomes","rivers"])).expect("Generated layers should be valid.");
+        BigBangStage::validate_output_layers(&[BigBangStage::Subnations], &layers(&["tiles","points","nations"])).expect("Skipping a stage that generates none of the layers should be valid.");
 
-        GenSubnations::run_default(random, &culture_lookup, namers, &primitive_args.subnation_percent, &primitive_args.overwrite_all.overwrite_subnations(), &primitive_args.bezier_scale, &mut target, progress)
+        match BigBangStage::validate_output_layers(&[BigBangStage::Nations, BigBangStage::Subnations], &layers(&["tiles","nations"])) {
+            Err(CommandError::OutputLayerSkipped(layer,stage)) => {
+                assert_eq!(layer,"nations");
+                assert_eq!(stage,"nations");
+            },
+            _ => panic!("Keeping the layer of a skipped stage should be an error.")
+        }
 
+        assert!(matches!(BigBangStage::validate_output_layers(&[], &layers(&["routes"])),Err(CommandError::OutputLayerNotGenerated(_))),"big-bang doesn't generate routes.");
+        assert!(matches!(BigBangStage::validate_output_layers(&[], &layers(&["mountains"])),Err(CommandError::UnknownLayer(_))),"There is no mountains layer.");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,44 @@
+use clap::Args;
+
+use crate::algorithms::neighbor_check::check_neighbors;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::world_map::tile_layer::TileForNeighborCheck;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Checks that every neighbor listed by a tile exists and lists that tile back, printing each problem found. Asymmetric neighbors can make water flow and nation expansion behave differently in each direction.
+    pub struct CheckNeighbors {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+    }
+}
+
+impl Task for CheckNeighbors {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let tile_map = target.tiles_layer()?.read_features().into_entities_index::<_,TileForNeighborCheck>(progress)?;
+
+        let violations = check_neighbors(&tile_map, progress);
+
+        for violation in &violations {
+            println!("{violation}");
+        }
+
+        if violations.is_empty() {
+            println!("The neighbors of all {} tiles are consistent.",tile_map.len());
+            Ok(())
+        } else {
+            Err(CommandError::InconsistentNeighbors(violations.len()))
+        }
+
+    }
+}
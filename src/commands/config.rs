@@ -0,0 +1,282 @@
+use core::iter::once;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+use clap::ArgAction;
+use clap::ArgMatches;
+use clap::Command;
+use clap::CommandFactory;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::commands::Cosmopoeia;
+use crate::errors::CommandError;
+use crate::errors::ProgramError;
+use crate::progress::ProgressObserver;
+
+/// Options which are about how the program runs rather than what it generates, so they don't belong in a printed config.
+const UNPRINTED_OPTIONS: [&str; 4] = ["help","version","config","print-config"];
+
+/// Finds the value of `--config` in the arguments, without parsing them, since the config might supply some required arguments.
+fn find_config_path(args: &[OsString]) -> Option<OsString> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg_text = arg.to_string_lossy();
+        if arg_text == "--config" {
+            return args.next().cloned()
+        } else if let Some(path) = arg_text.strip_prefix("--config=") {
+            return Some(path.into())
+        }
+    }
+    None
+}
+
+fn read_config(path: &Path) -> Result<Map<String,Value>,CommandError> {
+    let text = fs::read_to_string(path).map_err(|e| CommandError::ConfigRead(format!("{}: {e}",path.display())))?;
+    serde_json::from_str(&text).map_err(|e| CommandError::ConfigRead(format!("{}: {e}",path.display())))
+}
+
+/// Finds the commands named in the arguments, and the index of the argument that named each. The main command is at index 0. Option values are skipped, so they aren't mistaken for commands.
+fn find_command_chain<'command>(root: &'command Command, args: &[OsString]) -> Vec<(usize,&'command Command)> {
+    let mut chain = vec![(0,root)];
+    let mut index = 1;
+    while index < args.len() {
+        let arg = args[index].to_string_lossy();
+        if let Some(long) = arg.strip_prefix("--") {
+            let takes_value = !long.contains('=') && chain.iter().rev().find_map(|(_,command)| command.get_arguments().find(|option| option.get_long() == Some(long))).is_some_and(|option| option.get_action().takes_values());
+            if takes_value {
+                index += 1;
+            }
+        } else if let Some(subcommand) = chain.last().and_then(|(_,command)| command.find_subcommand(arg.as_ref())) {
+            chain.push((index,subcommand));
+        }
+        index += 1;
+    }
+    chain
+}
+
+fn config_value_to_args(name: &str, value: &Value, takes_value: bool, result: &mut Vec<OsString>) -> Result<(),CommandError> {
+    let invalid = || CommandError::InvalidConfigValue(name.to_owned(),value.to_string());
+    match (value,takes_value) {
+        (Value::Null | Value::Bool(false),false) | (Value::Null,true) => (),
+        (Value::Bool(true),false) => result.push(format!("--{name}").into()),
+        (Value::String(text),true) => result.extend([format!("--{name}").into(),text.into()]),
+        (Value::Number(number),true) => result.extend([format!("--{name}").into(),number.to_string().into()]),
+        (Value::Bool(flag),true) => result.extend([format!("--{name}").into(),flag.to_string().into()]),
+        (Value::Array(values),true) => for item in values {
+            if matches!(item,Value::Array(_) | Value::Object(_) | Value::Null) {
+                return Err(invalid())
+            }
+            config_value_to_args(name, item, true, result)?
+        },
+        (Value::String(_) | Value::Number(_) | Value::Array(_),false) | (Value::Object(_),_) => return Err(invalid())
+    }
+    Ok(())
+}
+
+/// Adds the options from a config to the arguments. Each option is added after the command it belongs to, unless the option was already given in the arguments, so the command line overrides the config. Options which the command doesn't have are left out, and returned so they can be reported, since a config might be shared between commands.
+fn apply_config(args: Vec<OsString>, config: &Map<String,Value>) -> Result<(Vec<OsString>,Vec<String>),CommandError> {
+    let root = Cosmopoeia::command();
+    let chain = find_command_chain(&root, &args);
+
+    let mut inserts: Vec<Vec<OsString>> = vec![Vec::new(); args.len()];
+    let mut ignored = Vec::new();
+    for (name,value) in config {
+        let given = args.iter().any(|arg| {
+            let arg = arg.to_string_lossy();
+            arg.strip_prefix("--").is_some_and(|option| (option == name) || option.strip_prefix(name.as_str()).is_some_and(|rest| rest.starts_with('=')))
+        });
+        if given {
+            continue;
+        }
+
+        let Some((index,option)) = chain.iter().rev().find_map(|(index,command)| {
+            command.get_arguments().find(|option| option.get_long() == Some(name)).map(|option| (*index,option))
+        }) else {
+            ignored.push(name.clone());
+            continue;
+        };
+
+        config_value_to_args(name, value, option.get_action().takes_values(), &mut inserts[index])?;
+    }
+
+    Ok((args.into_iter().zip(inserts).flat_map(|(arg,insert)| once(arg).chain(insert)).collect(),ignored))
+}
+
+/// If the command takes a random seed and none was given, this chooses one and adds it to the arguments, so a printed config will reproduce the same world.
+fn fix_random_seed(args: Vec<OsString>) -> Vec<OsString> {
+    let root = Cosmopoeia::command();
+    let chain = find_command_chain(&root, &args);
+    let seed_index = chain.iter().rev().find_map(|(index,command)| command.get_arguments().any(|option| option.get_long() == Some("seed")).then_some(*index));
+    let seed_given = args.iter().any(|arg| {
+        let arg = arg.to_string_lossy();
+        (arg == "--seed") || arg.starts_with("--seed=")
+    });
+
+    match seed_index {
+        Some(seed_index) if !seed_given => {
+            let seed = StdRng::from_entropy().gen::<u64>();
+            let mut args = args;
+            args.insert(seed_index + 1, seed.to_string().into());
+            args.insert(seed_index + 1, "--seed".into());
+            args
+        },
+        Some(_) | None => args
+    }
+}
+
+/// Collects the values of every option of the commands in the matches, including defaults, keyed by their long names. A command's own options are left out if it doesn't allow them alongside the subcommand that was used.
+fn collect_resolved_options(command: &Command, matches: &ArgMatches, result: &mut Map<String,Value>) {
+    let subcommand = matches.subcommand().and_then(|(name,sub_matches)| command.find_subcommand(name).map(|sub_command| (sub_command,sub_matches)));
+
+    if subcommand.is_none() || !command.is_args_conflicts_with_subcommands_set() {
+        for option in command.get_arguments() {
+            let Some(long) = option.get_long() else {
+                continue;
+            };
+            if UNPRINTED_OPTIONS.contains(&long) {
+                continue;
+            }
+            let Ok(Some(raw_values)) = matches.try_get_raw(option.get_id().as_str()) else {
+                continue;
+            };
+            let values: Vec<String> = raw_values.map(|value| value.to_string_lossy().into_owned()).collect();
+            let value = match option.get_action() {
+                ArgAction::SetTrue | ArgAction::SetFalse => Value::Bool(values.iter().any(|value| value == "true")),
+                ArgAction::Append => Value::Array(values.into_iter().map(Value::String).collect()),
+                _ => if values.len() == 1 {
+                    Value::String(values.into_iter().next().expect("There should have been one value."))
+                } else {
+                    Value::Array(values.into_iter().map(Value::String).collect())
+                }
+            };
+            _ = result.insert(long.to_owned(), value);
+        }
+    }
+
+    if let Some((sub_command,sub_matches)) = subcommand {
+        collect_resolved_options(sub_command, sub_matches, result)
+    }
+}
+
+/// Parses the arguments, and returns every option with its value after defaults, in the same form as a config file. If the command takes a random seed and none was given, one is chosen and included.
+pub(crate) fn resolved_config(args: Vec<OsString>) -> Result<Map<String,Value>,ProgramError> {
+    let args = fix_random_seed(args);
+    let root = Cosmopoeia::command();
+    let matches = root.clone().try_get_matches_from(args)?;
+    let mut result = Map::new();
+    collect_resolved_options(&root, &matches, &mut result);
+    Ok(result)
+}
+
+/// If `--config` is in the arguments, loads that file and adds its options to the arguments, for parsing as usual. Also returns the options in the config which the command doesn't have.
+pub(crate) fn expand_config_args<Arg: Into<OsString>, Args: IntoIterator<Item = Arg>>(args: Args) -> Result<(Vec<OsString>,Vec<String>),CommandError> {
+    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    if let Some(path) = find_config_path(&args) {
+        let config = read_config(Path::new(&path))?;
+        apply_config(args, &config)
+    } else {
+        Ok((args,Vec::new()))
+    }
+}
+
+/// Warns about options in the config file which were ignored, since they may be misspelled.
+pub(crate) fn warn_ignored_options<Progress: ProgressObserver>(ignored: &[String], progress: &Progress) {
+    for name in ignored {
+        progress.warning(|| format!("The config file sets '{name}', which is not an option of the command, so it was ignored."));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::OsString;
+
+    use clap::Parser;
+    use serde_json::Map;
+    use serde_json::Value;
+
+    use super::apply_config;
+    use super::resolved_config;
+    use crate::commands::Cosmopoeia;
+    use crate::commands::MainCommand;
+    use crate::errors::CommandError;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    fn config(json: &str) -> Map<String,Value> {
+        serde_json::from_str(json).expect("Test config should parse.")
+    }
+
+    #[test]
+    fn test_apply_config() {
+        let settings = config(r#"{
+            "seed": 42,
+            "tile-count": 5000,
+            "namers": ["share/namers.json","extra.json"],
+            "overwrite-all": true,
+            "compact": false,
+            "min-elevation": -100
+        }"#);
+
+        let (args,ignored) = apply_config(os_args(&["cosmopoeia","big-bang","world.gpkg","--cultures","cultures.json","--tile-count","300","blank","180","360","-90","-180","--max-elevation","100"]),&settings).expect("Config should apply.");
+        assert!(ignored.is_empty());
+        assert_eq!(args,os_args(&[
+            "cosmopoeia",
+            "big-bang","--namers","share/namers.json","--namers","extra.json","--overwrite-all","--seed","42",
+            "world.gpkg","--cultures","cultures.json","--tile-count","300",
+            "blank","--min-elevation","-100",
+            "180","360","-90","-180","--max-elevation","100"
+        ]));
+
+        let MainCommand::BigBang(big_bang) = Cosmopoeia::try_parse_from(args).expect("Arguments should parse.").command else {
+            panic!("Should have parsed big-bang.")
+        };
+        // from the config
+        assert_eq!(big_bang.random_seed_arg.seed,Some(42));
+        assert!(big_bang.primitive_args.overwrite_all.overwrite_all);
+        assert!(!big_bang.compact_arg.compact);
+        // the command line overrides the config
+        assert_eq!(big_bang.primitive_args.tile_count.tile_count,300);
+
+        // unknown options are left out, so they can be warned about
+        let (args,ignored) = apply_config(os_args(&["cosmopoeia","big-bang","world.gpkg"]),&config(r#"{"no-such-option": 1, "seed": 42}"#)).expect("Config with an unknown option should apply.");
+        assert_eq!(args,os_args(&["cosmopoeia","big-bang","--seed","42","world.gpkg"]));
+        assert_eq!(ignored,vec!["no-such-option".to_owned()]);
+
+        match apply_config(os_args(&["cosmopoeia","big-bang","world.gpkg"]),&config(r#"{"overwrite-all": "yes"}"#)) {
+            Err(CommandError::InvalidConfigValue(name,_)) => assert_eq!(name,"overwrite-all"),
+            _ => panic!("Text for a flag should have been an error.")
+        }
+    }
+
+    #[test]
+    fn test_resolved_config_round_trip() {
+        let positional = ["cosmopoeia","big-bang","world.gpkg","blank","180","360","-90","-180"];
+        let args = os_args(&["cosmopoeia","big-bang","world.gpkg","--namers","namers.json","--cultures","cultures.json","--seed","42","--skip","nations,subnations","--overwrite-all","blank","180","360","-90","-180","--min-elevation","-500"]);
+
+        let printed = resolved_config(args).expect("Config should resolve.");
+        assert_eq!(printed["seed"],"42");
+        assert_eq!(printed["namers"],serde_json::json!(["namers.json"]));
+        assert_eq!(printed["skip"],serde_json::json!(["nations","subnations"]));
+        assert_eq!(printed["overwrite-all"],true);
+        assert_eq!(printed["compact"],false);
+        assert_eq!(printed["min-elevation"],"-500");
+        // defaults are included
+        assert_eq!(printed["tile-count"],"10000");
+        assert!(!printed.contains_key("print-config"));
+
+        // feeding the printed config back in with only the positional arguments resolves to the same thing
+        let (reapplied,_) = apply_config(os_args(&positional),&printed).expect("Printed config should apply.");
+        assert_eq!(resolved_config(reapplied).expect("Reapplied config should resolve."),printed);
+
+        // a seed is chosen when none was given
+        let unseeded = resolved_config(os_args(&["cosmopoeia","big-bang","world.gpkg","--namers","namers.json","--cultures","cultures.json","blank","180","360","-90","-180"])).expect("Config should resolve.");
+        _ = unseeded["seed"].as_str().expect("Seed should be text.").parse::<u64>().expect("Seed should be a number.");
+    }
+}
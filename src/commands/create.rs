@@ -1,4 +1,5 @@
 
+use std::path::PathBuf;
 use clap::Args;
 use clap::Subcommand;
 use rand::Rng;
@@ -7,6 +8,7 @@ use crate::commands::Task;
 use crate::errors::CommandError;
 use crate::subcommand_def;
 use crate::utils::random::random_number_generator;
+use crate::utils::random::CREATE_STAGE;
 use crate::utils::extent::Extent;
 use crate::raster::RasterMap;
 use crate::world_map::WorldMap;
@@ -20,10 +22,16 @@ use crate::world_map::property_layer::ElevationLimits;
 use crate::world_map::WorldMapTransaction;
 use crate::commands::TargetArg;
 use crate::commands::ElevationSourceArg;
+use crate::commands::ResampleArg;
 use crate::commands::terrain::Command as TerrainCommand;
 use crate::commands::ElevationLimitsArg;
 use crate::commands::TileCountArg;
+use crate::commands::BoundaryPointsArg;
+use crate::commands::PointDistributionArg;
+use crate::commands::TileRegionArg;
 use crate::commands::WorldShapeArg;
+use crate::commands::IntegerIdsArg;
+use crate::commands::ContinentCountArg;
 use crate::commands::RandomSeedArg;
 use crate::commands::OverwriteTilesArg;
 
@@ -98,6 +106,13 @@ subcommand_def!{
         #[clap(flatten)]
         pub heightmap_arg: ElevationSourceArg,
 
+        #[arg(long="overlay")]
+        /// Additional heightmaps to sample over the main one, in order. Wherever one has data, it replaces the elevations from the heightmaps before it.
+        pub overlays: Vec<PathBuf>,
+
+        #[clap(flatten)]
+        pub resample_arg: ResampleArg,
+
         #[clap(flatten)]
         pub post_process_arg: PostProcessArg
 
@@ -114,11 +129,24 @@ impl LoadSource for FromHeightmap {
         let extent = source.bounds()?.extent();
 
         progress.start_unknown_endpoint(|| "Calculating min/max from raster.");
-        let limits = source.compute_min_max(1,true)?;
+        let mut limits = source.compute_min_max(1,true)?;
         progress.finish(|| "Min/max calculated.");
 
+        let mut overlays = Vec::new();
+        for overlay_path in self.overlays {
+            progress.announce(&format!("Loading {}",overlay_path.to_string_lossy()));
+            let overlay = RasterMap::open(overlay_path)?;
+
+            progress.start_unknown_endpoint(|| "Calculating min/max from raster.");
+            let overlay_limits = overlay.compute_min_max(1,true)?;
+            limits = ElevationLimits::new(limits.min_elevation().min(overlay_limits.min_elevation()),limits.max_elevation().max(overlay_limits.max_elevation()))?;
+            progress.finish(|| "Min/max calculated.");
+
+            overlays.push(overlay);
+        }
+
         // the post_processes always starts with loading the samples from the source
-        let mut post_processes = vec![TerrainTask::SampleElevation(SampleElevationLoaded::new(source))];
+        let mut post_processes = vec![TerrainTask::SampleElevation(SampleElevationLoaded::new(source,overlays,self.resample_arg.resample))];
 
         if let Some(process) = self.post_process_arg.post_process {
             progress.announce("Loading terrain processes.");
@@ -231,9 +259,21 @@ subcommand_def!{
         #[clap(flatten)]
         pub world_shape_arg: WorldShapeArg,
 
+        #[clap(flatten)]
+        pub integer_ids_arg: IntegerIdsArg,
+
         #[clap(flatten)]
         pub tile_count_arg: TileCountArg,
 
+        #[clap(flatten)]
+        pub boundary_points_arg: BoundaryPointsArg,
+
+        #[clap(flatten)]
+        pub point_distribution_arg: PointDistributionArg,
+
+        #[clap(flatten)]
+        pub tile_region_arg: TileRegionArg,
+
         #[clap(flatten)]
         pub random_seed_arg: RandomSeedArg,
 
@@ -248,12 +288,12 @@ subcommand_def!{
 
 impl CreateTiles {
 
-    fn run_with_parameters<Random: Rng, Progress: ProgressObserver>(extent: Extent, limits: &ElevationLimits, world_shape: &WorldShapeArg, tiles: &TileCountArg, overwrite: &OverwriteTilesArg, random: &mut Random, target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(),CommandError> {
-        let voronois = generate_random_tiles(random, extent, world_shape.world_shape.clone(), tiles.tile_count, progress)?;
+    fn run_with_parameters<Random: Rng, Progress: ProgressObserver>(extent: Extent, limits: &ElevationLimits, world_shape: &WorldShapeArg, integer_ids: &IntegerIdsArg, tiles: &TileCountArg, point_distribution: &PointDistributionArg, boundary_points: &BoundaryPointsArg, region: Option<Extent>, overwrite: &OverwriteTilesArg, random: &mut Random, target: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(),CommandError> {
+        let voronois = generate_random_tiles(random, extent, world_shape.world_shape.clone(), tiles.tile_count, tiles.latitude_spacing, &point_distribution.point_distribution, &boundary_points.boundary_points, region, progress)?;
     
         progress.announce("Create tiles from voronoi polygons");
 
-        load_tile_layer(target, overwrite, voronois, limits, &world_shape.world_shape, progress)    
+        load_tile_layer(target, overwrite, voronois, limits, &world_shape.world_shape, integer_ids, progress)    
     }
 
 }
@@ -263,15 +303,17 @@ impl Task for CreateTiles {
 
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
-        let mut random = random_number_generator(&self.random_seed_arg);
+        let mut random = random_number_generator(&self.random_seed_arg, CREATE_STAGE);
 
         let loaded_source = self.source.load(&mut random, progress)?;
 
         let mut target = WorldMap::create_or_edit(&self.target_arg.target)?;
 
+        let region = self.tile_region_arg.region(&loaded_source.extent)?;
+
         target.with_transaction(|transaction| {
 
-            Self::run_with_parameters(loaded_source.extent, &loaded_source.limits, &self.world_shape_arg, &self.tile_count_arg, &self.overwrite_tiles_arg, &mut random, transaction, progress)
+            Self::run_with_parameters(loaded_source.extent, &loaded_source.limits, &self.world_shape_arg, &self.integer_ids_arg, &self.tile_count_arg, &self.point_distribution_arg, &self.boundary_points_arg, region, &self.overwrite_tiles_arg, &mut random, transaction, progress)
 
         })?;
 
@@ -291,15 +333,30 @@ subcommand_def!{
         #[clap(flatten)]
         pub tile_count_arg: TileCountArg,
 
+        #[clap(flatten)]
+        pub boundary_points_arg: BoundaryPointsArg,
+
+        #[clap(flatten)]
+        pub point_distribution_arg: PointDistributionArg,
+
+        #[clap(flatten)]
+        pub tile_region_arg: TileRegionArg,
+
         #[clap(flatten)]
         pub world_shape_arg: WorldShapeArg,
 
+        #[clap(flatten)]
+        pub integer_ids_arg: IntegerIdsArg,
+
         #[clap(flatten)]
         pub random_seed_arg: RandomSeedArg,
 
         #[clap(flatten)]
         pub overwrite_tiles_arg: OverwriteTilesArg,
 
+        #[clap(flatten)]
+        pub continent_count_arg: ContinentCountArg,
+
         #[command(subcommand)]
         pub source: Source,
 
@@ -311,25 +368,27 @@ impl Task for Create {
 
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
-        let mut random = random_number_generator(&self.random_seed_arg);
+        let mut random = random_number_generator(&self.random_seed_arg, CREATE_STAGE);
 
         let loaded_source = self.source.load(&mut random, progress)?; 
 
         let mut target = WorldMap::create_or_edit(&self.target_arg.target)?;
 
-        Self::run_default(&self.tile_count_arg,&self.world_shape_arg,&self.overwrite_tiles_arg,loaded_source, &mut target, &mut random, progress)
+        let region = self.tile_region_arg.region(&loaded_source.extent)?;
+
+        Self::run_default(&self.tile_count_arg,&self.point_distribution_arg,&self.boundary_points_arg,region,&self.world_shape_arg,&self.integer_ids_arg,&self.overwrite_tiles_arg,&self.continent_count_arg,&loaded_source, &mut target, &mut random, progress)
 
     }
 }
 
 impl Create {
-    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(tiles: &TileCountArg, world_shape: &WorldShapeArg, overwrite_tiles: &OverwriteTilesArg, loaded_source: LoadedSource, target: &mut WorldMap, random: &mut Random, progress: &mut Progress) -> Result<(), CommandError> {
+    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(tiles: &TileCountArg, point_distribution: &PointDistributionArg, boundary_points: &BoundaryPointsArg, region: Option<Extent>, world_shape: &WorldShapeArg, integer_ids: &IntegerIdsArg, overwrite_tiles: &OverwriteTilesArg, continent_count: &ContinentCountArg, loaded_source: &LoadedSource, target: &mut WorldMap, random: &mut Random, progress: &mut Progress) -> Result<(), CommandError> {
         target.with_transaction(|transaction| {
-            CreateTiles::run_with_parameters(loaded_source.extent, &loaded_source.limits, world_shape, tiles, overwrite_tiles, random, transaction, progress)?;
+            CreateTiles::run_with_parameters(loaded_source.extent.clone(), &loaded_source.limits, world_shape, integer_ids, tiles, point_distribution, boundary_points, region, overwrite_tiles, random, transaction, progress)?;
 
             CreateCalcNeighbors::run_with_parameters(transaction, progress)?;
 
-            TerrainTask::process_terrain(&loaded_source.post_processes, random, transaction,progress)?;
+            _ = TerrainTask::process_terrain(&loaded_source.post_processes, continent_count, random, transaction,progress)?;
 
             Ok(())
 
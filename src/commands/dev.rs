@@ -9,6 +9,7 @@ use crate::commands::TargetArg;
 use crate::errors::CommandError;
 use crate::subcommand_def;
 use crate::utils::random::random_number_generator;
+use crate::utils::random::DEV_STAGE;
 use crate::utils::extent::Extent;
 use crate::raster::RasterMap;
 use crate::world_map::WorldMap;
@@ -22,6 +23,7 @@ use crate::algorithms::triangles::load_triangles_layer;
 use crate::algorithms::tiles::load_tile_layer;
 use crate::algorithms::naming::NamerSetSource;
 use crate::algorithms::naming::NamerSet;
+use crate::algorithms::naming::NameGender;
 use crate::algorithms::culture_sets::CultureSet;
 use crate::algorithms::culture_sets::CultureSetItem;
 use crate::world_map::property_layer::ElevationLimits;
@@ -30,10 +32,14 @@ use crate::progress::ProgressObserver;
 use crate::commands::ElevationSourceArg;
 use crate::commands::ElevationLimitsArg;
 use crate::commands::WorldShapeArg;
+use crate::commands::IntegerIdsArg;
+use crate::commands::BoundaryPointsArg;
 use crate::commands::RandomSeedArg;
 use crate::commands::OverwriteTilesArg;
 use crate::commands::NamerArg;
 use crate::typed_map::features::TypedFeature;
+use crate::typed_map::fields::IdRef;
+use crate::utils::simple_serde::Serialize;
 
 
 subcommand_def!{
@@ -53,6 +59,9 @@ subcommand_def!{
         /// The rough number of pixels to generate for the image
         pub points: usize,
 
+        #[clap(flatten)]
+        pub boundary_points_arg: BoundaryPointsArg,
+
         #[clap(flatten)]
         pub random_seed_arg: RandomSeedArg,
 
@@ -68,8 +77,8 @@ impl Task for PointsFromHeightmap {
         let source = RasterMap::open(self.heightmap_arg.source)?;
         let extent = source.bounds()?.extent();
         let mut target = WorldMap::create_or_edit(&self.target_arg.target)?;
-        let random = random_number_generator(&self.random_seed_arg);
-        let generator = PointGenerator::new(random, extent, self.world_shape_arg.world_shape, self.points);
+        let random = random_number_generator(&self.random_seed_arg, DEV_STAGE);
+        let generator = PointGenerator::new(random, extent, self.world_shape_arg.world_shape, self.points, &self.boundary_points_arg.boundary_points);
 
         target.with_transaction(|transaction| {
             progress.announce("Generating random points");
@@ -112,6 +121,9 @@ subcommand_def!{
         /// The rough number of pixels to generate for the image
         pub points: usize,
 
+        #[clap(flatten)]
+        pub boundary_points_arg: BoundaryPointsArg,
+
         #[clap(flatten)]
         pub random_seed_arg: RandomSeedArg,
 
@@ -126,8 +138,8 @@ impl Task for PointsFromExtent {
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
         let extent = Extent::from_bounds(self.west,self.south,self.east,self.north);
         let mut target = WorldMap::create_or_edit(&self.target_arg.target)?;
-        let random = random_number_generator(&self.random_seed_arg);
-        let generator = PointGenerator::new(random, extent, self.world_shape_arg.world_shape, self.points);
+        let random = random_number_generator(&self.random_seed_arg, DEV_STAGE);
+        let generator = PointGenerator::new(random, extent, self.world_shape_arg.world_shape, self.points, &self.boundary_points_arg.boundary_points);
         
         target.with_transaction(|transaction| {
             progress.announce("Generating random points");
@@ -200,7 +212,7 @@ macro_rules! voronoi_from_triangle {
             #[allow(clippy::needless_collect)]
             let voronoi: Vec<_> = generator.watch($progress,"Copying voronoi.","Voronoi copied.").collect();
     
-            load_tile_layer(transaction,&$self.overwrite_tiles,voronoi.into_iter(),&limits,&$self.world_shape.world_shape,$progress)
+            load_tile_layer(transaction,&$self.overwrite_tiles,voronoi.into_iter(),&limits,&$self.world_shape.world_shape,&$self.integer_ids,$progress)
         })?;
 
         target.save($progress)
@@ -224,6 +236,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub world_shape: WorldShapeArg,
 
+        #[clap(flatten)]
+        pub integer_ids: IntegerIdsArg,
+
         #[clap(flatten)]
         pub overwrite_tiles: OverwriteTilesArg,
 
@@ -272,6 +287,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub world_shape: WorldShapeArg,
 
+        #[clap(flatten)]
+        pub integer_ids: IntegerIdsArg,
+
         #[clap(flatten)]
         pub overwrite_tiles: OverwriteTilesArg,
 
@@ -329,6 +347,11 @@ impl Task for Namers {
             println!("language: {language}");
             println!("    name: {}",namer.make_name(rng));
             println!("   state: {}",namer.make_state_name(rng));
+            if namer.is_gendered() {
+                for (gender,label) in [(NameGender::Masculine,"male"),(NameGender::Feminine,"female"),(NameGender::Neutral,"neutral")] {
+                    println!("{label:>8}: {}",namer.make_gendered_name(rng, gender));
+                }
+            }
         
         }
         
@@ -338,7 +361,7 @@ impl Task for Namers {
             print!("{}",namers.to_json()?)
 
         } else {
-            let mut random = random_number_generator(&self.random_seed_arg);
+            let mut random = random_number_generator(&self.random_seed_arg, DEV_STAGE);
             let mut namers = NamerSet::load_from(self.namer_arg, &mut random, progress)?;
 
             if let Some(key) = self.language {
@@ -351,6 +374,8 @@ impl Task for Namers {
                 }
     
             }
+
+            namers.report_fallbacks(progress);
     
         }
 
@@ -397,7 +422,7 @@ impl Task for Cultures {
         
         }
 
-        let mut random = random_number_generator(&self.random_seed_arg);
+        let mut random = random_number_generator(&self.random_seed_arg, DEV_STAGE);
 
         let mut loaded_namers = NamerSet::load_from(self.namer_arg, &mut random, progress)?;
 
@@ -424,6 +449,40 @@ impl Task for Cultures {
 }
 
 
+subcommand_def!{
+    /// Prints the elevation, water flow, accumulation, flow directions, the tiles flowing into it and lake of every tile as CSV, for finding problems in the water flow
+    pub struct DumpWaterflow {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+    }
+}
+
+impl Task for DumpWaterflow {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let mut tiles = target.tiles_layer()?;
+
+        let graph = tiles.read_flow_graph(progress)?;
+
+        println!("fid,elevation,water_flow,water_accumulation,flow_to,flow_from,lake_id");
+
+        for (fid,tile) in graph.tiles().iter().watch(progress,"Dumping water flow.","Water flow dumped.") {
+            let lake_id = tile.lake_id().as_ref().map_or_else(String::new, IdRef::to_string);
+            let flow_from = graph.flow_from(fid).iter().map(IdRef::to_string).collect::<Vec<_>>().join(",");
+            // the serialized neighbors contain commas, so they have to be quoted.
+            println!("{fid},{},{},{},\"{}\",\"{flow_from}\",{lake_id}",tile.elevation(),tile.water_flow(),tile.water_accumulation(),tile.flow_to().write_to_string());
+        }
+
+        Ok(())
+    }
+}
+
+
 command_def!(
     #[command(disable_help_subcommand(true))]
     pub DevCommand {
@@ -433,7 +492,8 @@ command_def!(
         VoronoiFromTrianglesHeightmap,
         VoronoiFromTrianglesExtent,
         Namers,
-        Cultures
+        Cultures,
+        DumpWaterflow
     }
 );
 
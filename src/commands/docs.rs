@@ -40,6 +40,8 @@ use crate::world_map::nation_layers::document_subnation_layer;
 use crate::world_map::water_layers::document_coastline_layer;
 use crate::world_map::water_layers::document_ocean_layer;
 use crate::world_map::property_layer::document_property_layer;
+use crate::world_map::route_layer::document_route_layer;
+use crate::world_map::tile_attribute_layer::document_tile_attribute_layer;
 use crate::typed_map::fields::FieldTypeDocumentation;
 use crate::commands::terrain::Command as TerrainCommand;
 use crate::algorithms::culture_sets::CultureSetItemSource;
@@ -56,7 +58,9 @@ fn list_schemas() -> Result<Vec<LayerDocumentation>,CommandError> {
         document_ocean_layer()?,
         document_property_layer()?,
         document_river_layer()?,
+        document_route_layer()?,
         document_subnation_layer()?,
+        document_tile_attribute_layer()?,
         document_town_layer()?
     ])
 
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use clap::Subcommand;
+
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::command_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Writes a layer from the world map as GeoJSON. Layers without geometry, such as properties, are written as a plain JSON array.
+    pub struct Geojson {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        /// The path to write the GeoJSON to
+        pub output: PathBuf,
+
+        #[arg(long,default_value="tiles")]
+        /// The name of the layer to export, as listed by the `info` command
+        pub layer: String,
+
+        #[arg(long)]
+        /// Adds the wind and neighbor directions of each tile as compass points, in `wind_compass` and `neighbors_compass` properties. This only applies to the tiles layer.
+        pub compass: bool,
+
+    }
+}
+
+impl Task for Geojson {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let count = target.write_layer_geojson(&self.layer, &self.output, self.compass, progress)?;
+
+        progress.message(|| format!("Wrote {count} features from {}.",self.layer));
+
+        Ok(())
+
+    }
+}
+
+command_def!{
+    pub ExportCommand {
+        Geojson
+    }
+}
+
+subcommand_def!{
+    /// Writes data from the world map into other formats, for tools which can't read GeoPackage.
+    pub struct Export {
+
+        #[command(subcommand)]
+        pub command: ExportCommand,
+
+    }
+}
+
+impl Task for Export {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        self.command.run(progress)
+
+    }
+}
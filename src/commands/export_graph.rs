@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::algorithms::tile_graph::GraphFormat;
+use crate::algorithms::tile_graph::tile_graph_edges;
+use crate::algorithms::tile_graph::write_graph;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::world_map::tile_layer::TileForGraph;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Writes the neighbor relationships of the tiles as an edge list, so the map can be used as a graph by network analysis and pathfinding tools. Each pair of neighbors is listed once in each direction.
+    pub struct ExportGraph {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        #[arg(long,default_value="csv")]
+        /// The kind of file to write
+        pub format: GraphFormat,
+
+        #[arg(long)]
+        /// Also calculates the length of the border each pair of neighbors share, from the tile geometry
+        pub edge_lengths: bool,
+
+        #[arg(long)]
+        /// The path to write the edge list to
+        pub output: PathBuf,
+
+    }
+}
+
+impl Task for ExportGraph {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let tile_map = target.tiles_layer()?.read_features().into_entities_index::<_,TileForGraph>(progress)?;
+
+        let edges = tile_graph_edges(&tile_map, self.edge_lengths, progress)?;
+
+        progress.message(|| format!("Writing {} edges between {} tiles.",edges.len(),tile_map.len()));
+
+        fs::write(&self.output, write_graph(&edges, &self.format)?)?;
+
+        Ok(())
+
+    }
+}
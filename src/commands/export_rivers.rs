@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::algorithms::river_export::style_river_segments;
+use crate::algorithms::river_export::write_river_geojson;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::world_map::water_layers::RiverForExport;
+use crate::progress::ProgressObserver;
+
+fn parse_max_width(value: &str) -> Result<f64, &'static str> {
+    const HELP_MESSAGE: &str = "Max width must be a number greater than 0.";
+    let value: f64 = value.parse().map_err(|_| HELP_MESSAGE)?;
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(HELP_MESSAGE)
+    }
+}
+
+subcommand_def!{
+    /// Writes the rivers as a single GeoJSON file with style hints, for use in web maps. Each segment has its flow, a suggested line width and its Strahler order as properties, and segments are ordered so larger rivers are drawn last.
+    pub struct ExportRivers {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        #[arg(long,default_value="6",value_parser(parse_max_width))]
+        /// The suggested line width, in pixels, of the river with the highest flow
+        pub max_width: f64,
+
+        #[arg(long)]
+        /// The path to write the GeoJSON to
+        pub output: PathBuf,
+
+    }
+}
+
+impl Task for ExportRivers {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let segments = target.rivers_layer()?.read_features().into_entities_index::<_,RiverForExport>(progress)?;
+
+        let styled = style_river_segments(segments, self.max_width, progress);
+
+        progress.message(|| format!("Writing {} river segments.",styled.len()));
+
+        fs::write(&self.output, write_river_geojson(&styled)?)?;
+
+        Ok(())
+
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::algorithms::styles::StyleFormat;
+use crate::algorithms::styles::StyleLayer;
+use crate::algorithms::styles::read_style_categories;
+use crate::algorithms::styles::write_style;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Writes a style file which colors the features of a layer by their name, using the colors stored in the layer. The QGIS style can be loaded through the layer properties, so the world looks the same every time it is opened.
+    pub struct ExportStyle {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        #[arg(long)]
+        /// The layer to create a style for
+        pub layer: StyleLayer,
+
+        #[arg(long,default_value="qml")]
+        /// The kind of style file to write
+        pub format: StyleFormat,
+
+        #[arg(long)]
+        /// The path to write the style file to
+        pub output: PathBuf,
+
+    }
+}
+
+impl Task for ExportStyle {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        let categories = target.with_transaction(|transaction| {
+            read_style_categories(transaction, &self.layer, progress)
+        })?;
+
+        progress.message(|| format!("Writing style with {} categories.",categories.len()));
+
+        fs::write(&self.output, write_style(&categories, &self.format)?)?;
+
+        Ok(())
+
+    }
+}
@@ -0,0 +1,34 @@
+use clap::Args;
+
+use crate::algorithms::fingerprint::fingerprint_tiles;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Prints a hash of the key fields of every tile, which can be compared to confirm that two worlds were generated the same
+    pub struct Fingerprint {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+    }
+}
+
+impl Task for Fingerprint {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let fingerprint = fingerprint_tiles(&mut target.tiles_layer()?, progress)?;
+
+        println!("{fingerprint}");
+
+        Ok(())
+
+    }
+}
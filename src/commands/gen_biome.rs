@@ -18,6 +18,7 @@ use crate::world_map::biome_layer::BiomeMatrix;
 use crate::commands::OverwriteBiomesArg;
 use crate::commands::BezierScaleArg;
 use crate::commands::OverrideBiomeCriteriaArg;
+use crate::commands::IceCapArg;
 
 subcommand_def!{
     /// Creates default biome layer
@@ -72,6 +73,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub target_arg: TargetArg,
 
+        #[clap(flatten)]
+        pub ice_cap: IceCapArg,
+
     }
 }
 
@@ -86,7 +90,7 @@ impl Task for Apply {
 
         target.with_transaction(|transaction| {
 
-            Self::run_with_parameters(transaction, &biomes, progress)
+            Self::run_with_parameters(transaction, &biomes, &self.ice_cap, progress)
 
         })?;
 
@@ -98,10 +102,10 @@ impl Task for Apply {
 
 impl Apply {
 
-    fn run_with_parameters<Progress: ProgressObserver>(target: &mut WorldMapTransaction<'_>, biomes: &BiomeMatrix, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(target: &mut WorldMapTransaction<'_>, biomes: &BiomeMatrix, ice_cap: &IceCapArg, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Applying biomes to tiles");
     
-        apply_biomes(target, biomes, progress)
+        apply_biomes(target, biomes, ice_cap, progress)
     }
     
 }
@@ -198,6 +202,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub override_criteria: OverrideBiomeCriteriaArg,
 
+        #[clap(flatten)]
+        pub ice_cap: IceCapArg,
+
         #[clap(flatten)]
         pub overwrite_biomes: OverwriteBiomesArg,
     
@@ -211,20 +218,20 @@ impl Task for All {
 
         let mut target = WorldMap::edit(&self.target.target)?;
 
-        Self::run_with_parameters(&self.override_criteria, &self.overwrite_biomes, &self.bezier_scale, &mut target, progress)
+        Self::run_with_parameters(&self.override_criteria, &self.ice_cap, &self.overwrite_biomes, &self.bezier_scale, &mut target, progress)
     
     }
 }
 
 impl All {
-    fn run_with_parameters<Progress: ProgressObserver>(override_criteria: &OverrideBiomeCriteriaArg, ovewrite_biomes: &OverwriteBiomesArg, bezier_scale: &BezierScaleArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(override_criteria: &OverrideBiomeCriteriaArg, ice_cap: &IceCapArg, ovewrite_biomes: &OverwriteBiomesArg, bezier_scale: &BezierScaleArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
         target.with_transaction(|transaction| {            
             Data::run_with_parameters(override_criteria, ovewrite_biomes, transaction, progress)
 
         })?;
         let biomes = target.biomes_layer()?.get_matrix(progress)?;
         target.with_transaction(|transaction| {            
-            Apply::run_with_parameters(transaction, &biomes, progress)?;
+            Apply::run_with_parameters(transaction, &biomes, ice_cap, progress)?;
 
             Dissolve::run_with_parameters(transaction, progress)?;
 
@@ -268,7 +275,7 @@ impl Task for GenBiome {
 }
 
 impl GenBiome {
-    pub(crate) fn run_default<Progress: ProgressObserver>(override_criteria: &OverrideBiomeCriteriaArg, ovewrite_biomes: &OverwriteBiomesArg, bezier_scale: &BezierScaleArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
-        All::run_with_parameters(override_criteria, ovewrite_biomes, bezier_scale, target, progress)
+    pub(crate) fn run_default<Progress: ProgressObserver>(override_criteria: &OverrideBiomeCriteriaArg, ice_cap: &IceCapArg, ovewrite_biomes: &OverwriteBiomesArg, bezier_scale: &BezierScaleArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
+        All::run_with_parameters(override_criteria, ice_cap, ovewrite_biomes, bezier_scale, target, progress)
     }
 }
\ No newline at end of file
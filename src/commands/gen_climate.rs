@@ -10,11 +10,15 @@ use crate::world_map::WorldMap;
 use crate::algorithms::climate::generate_temperatures;
 use crate::algorithms::climate::generate_winds;
 use crate::algorithms::climate::generate_precipitation;
+use crate::algorithms::climate::adjust_precipitation;
+use crate::algorithms::climate::apply_ocean_currents;
 use crate::progress::ProgressObserver;
 use crate::world_map::WorldMapTransaction;
 use crate::commands::TemperatureRangeArg;
+use crate::commands::CurrentStrengthArg;
 use crate::commands::WindsArg;
 use crate::commands::PrecipitationArg;
+use crate::commands::TileFilterArg;
 
 subcommand_def!{
     /// Generates temperature data
@@ -26,6 +30,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub temperatures_arg: TemperatureRangeArg,
 
+        #[clap(flatten)]
+        pub current_strength: CurrentStrengthArg,
+
     }
 }
 
@@ -37,7 +44,7 @@ impl Task for Temperature {
 
         target.with_transaction(|transaction| {
 
-            Self::run_with_parameters(&self.temperatures_arg, transaction, progress)
+            Self::run_with_parameters(&self.temperatures_arg, &self.current_strength, transaction, progress)
         })?;
 
         target.save(progress)
@@ -47,10 +54,18 @@ impl Task for Temperature {
 }
 
 impl Temperature {
-    fn run_with_parameters<Progress: ProgressObserver>(temperatures: &TemperatureRangeArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(temperatures: &TemperatureRangeArg, current_strength: &CurrentStrengthArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Generating temperatures");
 
-        generate_temperatures(target, temperatures, progress)
+        generate_temperatures(target, temperatures, progress)?;
+
+        if current_strength.current_strength > 0.0 {
+            progress.announce("Applying ocean currents");
+
+            apply_ocean_currents(target, current_strength.current_strength, progress)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -139,6 +154,45 @@ impl Precipitation {
 
 
 
+subcommand_def!{
+    /// Multiplies the existing precipitation, optionally only on tiles within some latitudes or a region
+    pub struct AdjustPrecipitation {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        #[arg(long,default_value="1")]
+        /// The amount to multiply the precipitation by
+        pub precipitation_multiplier: f64,
+
+        #[clap(flatten)]
+        pub filter: TileFilterArg,
+
+    }
+}
+
+impl Task for AdjustPrecipitation {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let filter = self.filter.filter()?;
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        target.with_transaction(|transaction| {
+
+            progress.announce("Adjusting precipitation");
+
+            adjust_precipitation(transaction, self.precipitation_multiplier, &filter, progress)
+
+        })?;
+
+        target.save(progress)
+    
+    }
+}
+
+
 subcommand_def!{
     /// Generates all climate data
     pub struct All {
@@ -148,6 +202,9 @@ subcommand_def!{
     
         #[clap(flatten)]
         pub temperature: TemperatureRangeArg,
+
+        #[clap(flatten)]
+        pub current_strength: CurrentStrengthArg,
     
         #[clap(flatten)]
         pub winds: WindsArg,
@@ -167,7 +224,7 @@ impl Task for All {
 
         target.with_transaction(|transaction| {
 
-            Self::run_with_parameters(&self.temperature, &self.winds, &self.precipitation, transaction, progress)
+            Self::run_with_parameters(&self.temperature, &self.current_strength, &self.winds, &self.precipitation, transaction, progress)
 
         })?;
 
@@ -177,12 +234,13 @@ impl Task for All {
 }
 
 impl All {
-    fn run_with_parameters<Progress: ProgressObserver>(temperatures: &TemperatureRangeArg, winds: &WindsArg, precipitation: &PrecipitationArg, transaction: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(), CommandError> {
-
-        Temperature::run_with_parameters(temperatures, transaction, progress)?;
+    fn run_with_parameters<Progress: ProgressObserver>(temperatures: &TemperatureRangeArg, current_strength: &CurrentStrengthArg, winds: &WindsArg, precipitation: &PrecipitationArg, transaction: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(), CommandError> {
 
+        // winds don't depend on temperature, so they're generated first for the ocean currents.
         Winds::run_with_parameters(winds, transaction, progress)?;
 
+        Temperature::run_with_parameters(temperatures, current_strength, transaction, progress)?;
+
         Precipitation::run_with_parameters(precipitation, transaction, progress)
 
     }
@@ -193,7 +251,8 @@ command_def!{
         All,
         Temperature,
         Winds,
-        Precipitation
+        Precipitation,
+        AdjustPrecipitation
     }
 }
 
@@ -217,10 +276,10 @@ impl Task for GenClimate {
 }
 
 impl GenClimate {
-    pub(crate) fn run_default<Progress: ProgressObserver>(temperatures: &TemperatureRangeArg, winds: &WindsArg, precipitation: &PrecipitationArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
+    pub(crate) fn run_default<Progress: ProgressObserver>(temperatures: &TemperatureRangeArg, current_strength: &CurrentStrengthArg, winds: &WindsArg, precipitation: &PrecipitationArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
         target.with_transaction(|transaction| {
 
-            All::run_with_parameters(temperatures, winds, precipitation, transaction, progress)
+            All::run_with_parameters(temperatures, current_strength, winds, precipitation, transaction, progress)
     
         })?;
             
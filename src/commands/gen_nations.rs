@@ -8,7 +8,10 @@ use crate::algorithms::nations::expand_nations;
 use crate::algorithms::nations::generate_nations;
 use crate::world_map::culture_layer::CultureForNations;
 use crate::world_map::WorldMap;
-use crate::utils::random::random_number_generator;
+use crate::utils::random::random_seed;
+use crate::utils::random::stage_random_number_generator;
+use crate::utils::random::NAMERS_STAGE;
+use crate::utils::random::NATIONS_STAGE;
 use crate::errors::CommandError;
 use crate::subcommand_def;
 use crate::command_def;
@@ -31,6 +34,8 @@ use crate::commands::NamerArg;
 use crate::commands::SizeVarianceArg;
 use crate::commands::RiverThresholdArg;
 use crate::commands::ExpansionFactorArg;
+use crate::commands::NationExpansionArg;
+use crate::commands::NationCountArg;
 
 subcommand_def!{
     /// Generates background population of tiles
@@ -46,6 +51,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub size_variance: SizeVarianceArg,
 
+        #[clap(flatten)]
+        pub nation_count: NationCountArg,
+
         #[clap(flatten)]
         pub random_seed: RandomSeedArg,
 
@@ -60,17 +68,18 @@ impl Task for Create {
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
 
-        let mut random = random_number_generator(&self.random_seed);
+        let seed = random_seed(&self.random_seed);
+        let mut random = stage_random_number_generator(seed, NATIONS_STAGE);
 
         let mut target = WorldMap::edit(&self.target.target)?;
 
-        let mut loaded_namers = NamerSet::load_from(self.namers, &mut random, progress)?;
+        let mut loaded_namers = NamerSet::load_from(self.namers, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
         let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?;
 
         target.with_transaction(|transaction| {
 
-            Self::run_with_parameters(&mut random, &culture_lookup, &mut loaded_namers, &self.size_variance, &self.overwrite_nations, transaction, progress)
+            Self::run_with_parameters(&mut random, &culture_lookup, &mut loaded_namers, &self.size_variance, &self.nation_count, &self.overwrite_nations, transaction, progress)
         })?;
 
         target.save(progress)
@@ -79,9 +88,9 @@ impl Task for Create {
 }
 
 impl Create {
-    fn run_with_parameters<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer + CultureWithType>(random: &mut Random, culture_lookup: &EntityLookup<CultureSchema, Culture>, loaded_namers: &mut NamerSet, size_variance: &SizeVarianceArg, overwrite_nations: &OverwriteNationsArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer + CultureWithType>(random: &mut Random, culture_lookup: &EntityLookup<CultureSchema, Culture>, loaded_namers: &mut NamerSet, size_variance: &SizeVarianceArg, nation_count: &NationCountArg, overwrite_nations: &OverwriteNationsArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Generating nations");
-        generate_nations(target, random, culture_lookup, loaded_namers, size_variance, overwrite_nations, progress)
+        generate_nations(target, random, culture_lookup, loaded_namers, size_variance, nation_count, overwrite_nations, progress)
     }
     
 }
@@ -101,6 +110,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub expansion_factor: ExpansionFactorArg,
 
+        #[clap(flatten)]
+        pub nation_expansion: NationExpansionArg,
+
     }
 }
 
@@ -111,7 +123,7 @@ impl Task for Expand {
 
         let mut target = WorldMap::edit(&self.target.target)?;
         target.with_transaction(|transaction| {
-            Self::run_with_parameters(&self.river_threshold, &self.expansion_factor, transaction, progress)
+            Self::run_with_parameters(&self.river_threshold, &self.expansion_factor, &self.nation_expansion, transaction, progress)
         })?;
 
         target.save(progress)
@@ -120,10 +132,10 @@ impl Task for Expand {
 }
 
 impl Expand {
-    fn run_with_parameters<Progress: ProgressObserver>(river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, nation_expansion: &NationExpansionArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Applying nations to tiles");
     
-        expand_nations(target, river_threshold, limit_factor, progress)
+        expand_nations(target, river_threshold, limit_factor, nation_expansion, progress)
     }
     
 }
@@ -269,6 +281,9 @@ pub struct DefaultArgs {
     #[clap(flatten)]
     pub size_variance: SizeVarianceArg,
 
+    #[clap(flatten)]
+    pub nation_count: NationCountArg,
+
     #[clap(flatten)]
     pub random_seed: RandomSeedArg,
 
@@ -278,6 +293,9 @@ pub struct DefaultArgs {
     #[clap(flatten)]
     pub expansion_factor: ExpansionFactorArg,
 
+    #[clap(flatten)]
+    pub nation_expansion: NationExpansionArg,
+
     #[clap(flatten)]
     pub bezier_scale: BezierScaleArg,
 
@@ -307,15 +325,16 @@ impl Task for GenNations {
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
         if let Some(default_args) = self.default_args {
-            let mut random = random_number_generator(&default_args.random_seed);
+            let seed = random_seed(&default_args.random_seed);
+            let mut random = stage_random_number_generator(seed, NATIONS_STAGE);
 
             let mut target = WorldMap::edit(&default_args.target.target)?;
     
-            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut random, progress)?;
+            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
             let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?;
     
-            Self::run_default(&mut random, &culture_lookup, &mut loaded_namers, &default_args.size_variance, &default_args.river_threshold, &default_args.expansion_factor, &default_args.bezier_scale, &default_args.overwrite_nations, &mut target, progress)
+            Self::run_default(&mut random, &culture_lookup, &mut loaded_namers, &default_args.size_variance, &default_args.nation_count, &default_args.river_threshold, &default_args.expansion_factor, &default_args.nation_expansion, &default_args.bezier_scale, &default_args.overwrite_nations, &mut target, progress)
 
         } else if let Some(command) = self.command {
 
@@ -330,12 +349,12 @@ impl Task for GenNations {
 
 impl GenNations {
 
-    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer + CultureWithType>(random: &mut Random, culture_lookup: &EntityLookup<CultureSchema, Culture>, loaded_namers: &mut NamerSet, size_variance: &SizeVarianceArg, river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, bezier_scale: &BezierScaleArg, overwrite_nations: &OverwriteNationsArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
+    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer + CultureWithType>(random: &mut Random, culture_lookup: &EntityLookup<CultureSchema, Culture>, loaded_namers: &mut NamerSet, size_variance: &SizeVarianceArg, nation_count: &NationCountArg, river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, nation_expansion: &NationExpansionArg, bezier_scale: &BezierScaleArg, overwrite_nations: &OverwriteNationsArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
         target.with_transaction(|transaction| {
     
-            Create::run_with_parameters(random, culture_lookup, loaded_namers, size_variance, overwrite_nations, transaction, progress)?;
+            Create::run_with_parameters(random, culture_lookup, loaded_namers, size_variance, nation_count, overwrite_nations, transaction, progress)?;
     
-            Expand::run_with_parameters(river_threshold, limit_factor, transaction, progress)?;
+            Expand::run_with_parameters(river_threshold, limit_factor, nation_expansion, transaction, progress)?;
     
             Normalize::run_with_parameters(transaction, progress)?;
     
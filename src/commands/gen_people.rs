@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::Args;
 use clap::Subcommand;
 use rand::Rng;
@@ -12,9 +14,14 @@ use crate::algorithms::population::generate_populations;
 use crate::algorithms::cultures::generate_cultures;
 use crate::algorithms::cultures::expand_cultures;
 use crate::algorithms::culture_sets::CultureSet;
+use crate::algorithms::culture_sets::CultureAssignments;
+use crate::algorithms::cultures::import_cultures;
 use crate::algorithms::naming::NamerSet;
 use crate::algorithms::tiles::dissolve_tiles_by_theme;
-use crate::utils::random::random_number_generator;
+use crate::utils::random::random_seed;
+use crate::utils::random::stage_random_number_generator;
+use crate::utils::random::NAMERS_STAGE;
+use crate::utils::random::PEOPLE_STAGE;
 use crate::algorithms::tiles::CultureTheme;
 use crate::algorithms::curves::curvify_layer_by_theme;
 use crate::world_map::WorldMapTransaction;
@@ -25,7 +32,10 @@ use crate::commands::BezierScaleArg;
 use crate::commands::NamerArg;
 use crate::commands::SizeVarianceArg;
 use crate::commands::RiverThresholdArg;
+use crate::commands::DebugHabitabilityArg;
 use crate::commands::ExpansionFactorArg;
+use crate::commands::CultureWaterCostArg;
+use crate::commands::MaxExpansionCostArg;
 use crate::commands::CulturesGenArg;
 
 subcommand_def!{
@@ -38,6 +48,9 @@ subcommand_def!{
 
         #[clap(flatten)]
         pub river_threshold_arg: RiverThresholdArg,
+
+        #[clap(flatten)]
+        pub debug_habitability: DebugHabitabilityArg,
         
     }
 }
@@ -51,7 +64,7 @@ impl Task for Population {
 
         target.with_transaction(|transaction| {
 
-            Self::run_with_parameters(&self.river_threshold_arg, transaction, progress)
+            Self::run_with_parameters(&self.river_threshold_arg, &self.debug_habitability, transaction, progress)
         })?;
 
         target.save(progress)
@@ -60,9 +73,9 @@ impl Task for Population {
 }
 
 impl Population {
-    fn run_with_parameters<Progress: ProgressObserver>(estuary_threshold: &RiverThresholdArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(estuary_threshold: &RiverThresholdArg, debug_habitability: &DebugHabitabilityArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Generating population");
-        generate_populations(target, estuary_threshold, progress)
+        generate_populations(target, estuary_threshold, debug_habitability.debug_habitability, progress)
     }
     
 }
@@ -102,9 +115,10 @@ impl Task for CreateCultures {
 
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
-        let mut random = random_number_generator(&self.random_seed);
+        let seed = random_seed(&self.random_seed);
+        let mut random = stage_random_number_generator(seed, PEOPLE_STAGE);
 
-        let mut loaded_namers = NamerSet::load_from(self.namer, &mut random, progress)?;
+        let mut loaded_namers = NamerSet::load_from(self.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
         let mut target = WorldMap::edit(&self.target.target)?;
 
@@ -123,11 +137,79 @@ impl CreateCultures {
         progress.announce("Generating cultures");
         let cultures = CultureSet::from_files(&cultures_arg.cultures,random,namers)?;
 
-        generate_cultures(target, random, &cultures, namers, cultures_arg.culture_count, size_variance, river_threshold, overwrite_cultures, progress)
+        namers.weight_fallback_by(&cultures, progress);
+
+        namers.report_fallbacks(progress);
+
+        generate_cultures(target, random, &cultures, namers, cultures_arg.culture_count, cultures_arg.diversify_namers, size_variance, river_threshold, overwrite_cultures, progress)
     }
     
 }
 
+subcommand_def!{
+    /// Assigns cultures to tiles from a file instead of generating them
+    #[command(hide=true)]
+    pub struct ImportCultures {
+
+        #[clap(flatten)]
+        pub target: TargetArg,
+
+        #[arg(long)]
+        /// A CSV file (.csv) of 'fid,culture' lines, or a GeoJSON file (.geojson or .json) with 'fid' and 'culture' properties, assigning cultures to land tiles
+        pub source: PathBuf,
+
+        #[clap(flatten)]
+        pub cultures: CulturesGenArg,
+
+        #[clap(flatten)]
+        pub namer: NamerArg,
+
+        #[clap(flatten)]
+        pub random_seed: RandomSeedArg,
+
+        #[clap(flatten)]
+        pub overwrite_cultures: OverwriteCulturesArg,
+
+    }
+}
+
+impl Task for ImportCultures {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let seed = random_seed(&self.random_seed);
+        let mut random = stage_random_number_generator(seed, PEOPLE_STAGE);
+
+        let mut loaded_namers = NamerSet::load_from(self.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
+
+        let assignments = CultureAssignments::from_file(&self.source)?;
+
+        let mut target = WorldMap::edit(&self.target.target)?;
+
+        target.with_transaction(|transaction| {
+            Self::run_with_parameters(&mut random, &self.cultures, &mut loaded_namers, &assignments, &self.overwrite_cultures, transaction, progress)
+        })?;
+
+        target.save(progress)
+
+    }
+}
+
+impl ImportCultures {
+    fn run_with_parameters<Random: Rng, Progress: ProgressObserver>(random: &mut Random, cultures_arg: &CulturesGenArg, namers: &mut NamerSet, assignments: &CultureAssignments, overwrite_cultures: &OverwriteCulturesArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+
+        progress.announce("Importing cultures");
+        let cultures = CultureSet::from_files(&cultures_arg.cultures,random,namers)?;
+
+        namers.weight_fallback_by(&cultures, progress);
+
+        namers.report_fallbacks(progress);
+
+        import_cultures(target, random, &cultures, namers, assignments, cultures_arg.diversify_namers, overwrite_cultures, progress)
+    }
+
+}
+
 subcommand_def!{
     /// Generates background population of tiles
     #[command(hide=true)]
@@ -142,6 +224,12 @@ subcommand_def!{
         #[clap(flatten)]
         pub expansion_factor: ExpansionFactorArg,
 
+        #[clap(flatten)]
+        pub max_expansion_cost: MaxExpansionCostArg,
+
+        #[clap(flatten)]
+        pub culture_water_cost: CultureWaterCostArg,
+
     }
 }
 
@@ -152,7 +240,7 @@ impl Task for ExpandCultures {
 
         let mut target = WorldMap::edit(&self.target.target)?;
         target.with_transaction(|transaction| {
-            Self::run_with_parameters(&self.river_threshold, &self.expansion_factor, transaction, progress)
+            Self::run_with_parameters(&self.river_threshold, &self.expansion_factor, &self.max_expansion_cost, &self.culture_water_cost, transaction, progress)
         })?;
 
         target.save(progress)
@@ -161,10 +249,10 @@ impl Task for ExpandCultures {
 }
 
 impl ExpandCultures {
-    fn run_with_parameters<Progress: ProgressObserver>(river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(river_threshold: &RiverThresholdArg, limit_factor: &ExpansionFactorArg, max_cost: &MaxExpansionCostArg, water_cost: &CultureWaterCostArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Applying cultures to tiles");
     
-        expand_cultures(target, river_threshold, limit_factor, progress)
+        expand_cultures(target, river_threshold, limit_factor, max_cost, water_cost, progress)
     }
     
 }
@@ -255,6 +343,7 @@ command_def!{
     pub PeopleCommand {
         Population,
         CreateCultures,
+        ImportCultures,
         ExpandCultures,
         DissolveCultures,
         CurvifyCultures
@@ -271,12 +360,25 @@ pub struct DefaultArgs {
     #[clap(flatten)]
     pub cultures: CulturesGenArg,
 
+    #[arg(long)]
+    /// A CSV file (.csv) of 'fid,culture' lines, or a GeoJSON file (.geojson or .json) with 'fid' and 'culture' properties. If given, cultures are assigned to land tiles from this file instead of being placed and expanded randomly. Cultures not found in the culture sets are created with the default namer.
+    pub import_cultures: Option<PathBuf>,
+
     #[clap(flatten)]
     pub river_threshold: RiverThresholdArg,
 
+    #[clap(flatten)]
+    pub debug_habitability: DebugHabitabilityArg,
+
     #[clap(flatten)]
     pub expansion_factor: ExpansionFactorArg,
 
+    #[clap(flatten)]
+    pub max_expansion_cost: MaxExpansionCostArg,
+
+    #[clap(flatten)]
+    pub culture_water_cost: CultureWaterCostArg,
+
     #[clap(flatten)]
     pub namer: NamerArg,
 
@@ -315,19 +417,26 @@ impl Task for GenPeople {
 
         if let Some(default_args) = self.default_args {
 
-            let mut random = random_number_generator(&default_args.random_seed);
+            let seed = random_seed(&default_args.random_seed);
+            let mut random = stage_random_number_generator(seed, PEOPLE_STAGE);
+
+            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
-            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut random, progress)?;
+            let import_cultures = default_args.import_cultures.map(CultureAssignments::from_file).transpose()?;
     
             let mut target = WorldMap::edit(&default_args.target.target)?;
     
             Self::run_default(
                 &default_args.river_threshold, 
+                &default_args.debug_habitability, 
                 &default_args.cultures, 
+                import_cultures.as_ref(),
                 &mut loaded_namers, 
                 &default_args.size_variance, 
                 &default_args.overwrite_cultures, 
                 &default_args.expansion_factor, 
+                &default_args.max_expansion_cost, 
+                &default_args.culture_water_cost, 
                 &default_args.bezier_scale, 
                 &mut target, 
                 &mut random, 
@@ -345,13 +454,17 @@ impl Task for GenPeople {
 }
 
 impl GenPeople {
-    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(river_threshold: &RiverThresholdArg, cultures: &CulturesGenArg, namers: &mut NamerSet, size_variance: &SizeVarianceArg, overwrite_cultures: &OverwriteCulturesArg, limit_factor: &ExpansionFactorArg, bezier_scale: &BezierScaleArg, target: &mut WorldMap, random: &mut Random, progress: &mut Progress) -> Result<(), CommandError> {
+    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(river_threshold: &RiverThresholdArg, debug_habitability: &DebugHabitabilityArg, cultures: &CulturesGenArg, import_cultures: Option<&CultureAssignments>, namers: &mut NamerSet, size_variance: &SizeVarianceArg, overwrite_cultures: &OverwriteCulturesArg, limit_factor: &ExpansionFactorArg, max_cost: &MaxExpansionCostArg, water_cost: &CultureWaterCostArg, bezier_scale: &BezierScaleArg, target: &mut WorldMap, random: &mut Random, progress: &mut Progress) -> Result<(), CommandError> {
         target.with_transaction(|transaction| {
-            Population::run_with_parameters(river_threshold, transaction, progress)?;
+            Population::run_with_parameters(river_threshold, debug_habitability, transaction, progress)?;
     
-            CreateCultures::run_with_parameters(random, cultures, namers, size_variance, river_threshold, overwrite_cultures, transaction, progress)?;
+            if let Some(assignments) = import_cultures {
+                ImportCultures::run_with_parameters(random, cultures, namers, assignments, overwrite_cultures, transaction, progress)?;
+            } else {
+                CreateCultures::run_with_parameters(random, cultures, namers, size_variance, river_threshold, overwrite_cultures, transaction, progress)?;
     
-            ExpandCultures::run_with_parameters(river_threshold, limit_factor, transaction, progress)?;
+                ExpandCultures::run_with_parameters(river_threshold, limit_factor, max_cost, water_cost, transaction, progress)?;
+            }
     
             DissolveCultures::run_with_parameters(transaction, progress)?;
     
@@ -0,0 +1,53 @@
+use clap::Args;
+
+use crate::algorithms::routes::RouteDetail;
+use crate::algorithms::routes::generate_routes;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::commands::OverwriteRoutesArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Connects towns with least-cost roads over land, and ports with sea routes. Roads prefer easy biomes and avoid hills and mountains.
+    pub struct GenRoutes {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        #[arg(long,default_value="towns")]
+        /// Which pairs of towns are connected with routes
+        pub route_detail: RouteDetail,
+
+        #[arg(long,default_value="2")]
+        /// With 'capitals' or 'towns' detail, the number of nearest towns each town is connected to by road, and each port is connected to by sea
+        pub route_connections: usize,
+
+        #[arg(long,default_value="10")]
+        /// With 'within' detail, the greatest straight-line distance in degrees between towns which are connected
+        pub route_distance: f64,
+
+        #[clap(flatten)]
+        pub overwrite_routes_arg: OverwriteRoutesArg,
+
+    }
+}
+
+impl Task for GenRoutes {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        target.with_transaction(|transaction| {
+            progress.announce("Generating routes");
+
+            generate_routes(transaction, &self.route_detail, self.route_connections, self.route_distance, &self.overwrite_routes_arg, progress)
+        })?;
+
+        target.save(progress)
+
+    }
+}
@@ -11,6 +11,10 @@ use crate::progress::ProgressObserver;
 use crate::world_map::culture_layer::CultureForNations;
 use crate::world_map::culture_layer::CultureSchema;
 use crate::utils::random::random_number_generator;
+use crate::utils::random::random_seed;
+use crate::utils::random::stage_random_number_generator;
+use crate::utils::random::NAMERS_STAGE;
+use crate::utils::random::SUBNATIONS_STAGE;
 use crate::algorithms::subnations::generate_subnations;
 use crate::algorithms::subnations::expand_subnations;
 use crate::algorithms::subnations::fill_empty_subnations;
@@ -63,11 +67,12 @@ impl Task for Create {
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
 
-        let mut random = random_number_generator(&self.random_seed);
+        let seed = random_seed(&self.random_seed);
+        let mut random = stage_random_number_generator(seed, SUBNATIONS_STAGE);
 
         let mut target = WorldMap::edit(&self.target.target)?;
 
-        let mut loaded_namers = NamerSet::load_from(self.namer, &mut random, progress)?;
+        let mut loaded_namers = NamerSet::load_from(self.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
         let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?;
 
@@ -116,7 +121,7 @@ impl Task for Expand {
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
 
-        let mut random = random_number_generator(&self.random_seed);
+        let mut random = random_number_generator(&self.random_seed, SUBNATIONS_STAGE);
 
         let mut target = WorldMap::edit(&self.target.target)?;
         
@@ -167,11 +172,12 @@ impl Task for FillEmpty {
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
 
-        let mut random = random_number_generator(&self.random_seed);
+        let seed = random_seed(&self.random_seed);
+        let mut random = stage_random_number_generator(seed, SUBNATIONS_STAGE);
 
         let mut target = WorldMap::edit(&self.target.target)?;
         
-        let mut loaded_namers = NamerSet::load_from(self.namer, &mut random, progress)?;
+        let mut loaded_namers = NamerSet::load_from(self.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
         let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?;
 
@@ -255,7 +261,7 @@ impl Task for AssignColors {
 
         let mut target = WorldMap::edit(&self.target_arg.target)?;
 
-        let mut random = random_number_generator(&self.random_seed_arg);
+        let mut random = random_number_generator(&self.random_seed_arg, SUBNATIONS_STAGE);
 
         target.with_transaction(|transaction| {
             Self::run_with_parameters(transaction, &mut random, progress)
@@ -414,11 +420,12 @@ impl Task for GenSubnations {
 
         if let Some(default_args) = self.default_args {
 
-            let mut random = random_number_generator(&default_args.random_seed);
+            let seed = random_seed(&default_args.random_seed);
+            let mut random = stage_random_number_generator(seed, SUBNATIONS_STAGE);
 
             let mut target = WorldMap::edit(&default_args.target.target)?;
 
-            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut random, progress)?;
+            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
             let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForNations>(progress)?;
     
@@ -8,7 +8,10 @@ use crate::algorithms::towns::populate_towns;
 use crate::algorithms::towns::generate_towns;
 use crate::world_map::culture_layer::CultureForTowns;
 use crate::world_map::WorldMap;
-use crate::utils::random::random_number_generator;
+use crate::utils::random::random_seed;
+use crate::utils::random::stage_random_number_generator;
+use crate::utils::random::NAMERS_STAGE;
+use crate::utils::random::TOWNS_STAGE;
 use crate::errors::CommandError;
 use crate::subcommand_def;
 use crate::progress::ProgressObserver;
@@ -53,11 +56,12 @@ impl Task for Create {
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
 
-        let mut random = random_number_generator(&self.random_seed);
+        let seed = random_seed(&self.random_seed);
+        let mut random = stage_random_number_generator(seed, TOWNS_STAGE);
 
         let mut target = WorldMap::edit(&self.target.target)?;
 
-        let mut loaded_namers = NamerSet::load_from(self.namer, &mut random, progress)?;
+        let mut loaded_namers = NamerSet::load_from(self.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
         let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForTowns>(progress)?;
 
@@ -172,11 +176,12 @@ impl Task for GenTowns {
 
         if let Some(default_args) = self.default_args {
         
-            let mut random = random_number_generator(&default_args.random_seed);
+            let seed = random_seed(&default_args.random_seed);
+            let mut random = stage_random_number_generator(seed, TOWNS_STAGE);
 
             let mut target = WorldMap::edit(&default_args.target.target)?;
     
-            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut random, progress)?;
+            let mut loaded_namers = NamerSet::load_from(default_args.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
 
             let culture_lookup = target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForTowns>(progress)?;
     
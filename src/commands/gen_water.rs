@@ -1,5 +1,8 @@
+use std::collections::HashMap;
+
 use clap::Args;
 use clap::Subcommand;
+use rand::Rng;
 
 use crate::commands::Task;
 use crate::errors::CommandError;
@@ -10,9 +13,25 @@ use crate::algorithms::water_flow::generate_water_flow;
 use crate::algorithms::water_fill::generate_water_fill;
 use crate::algorithms::water_flow::WaterFlowResult;
 use crate::algorithms::rivers::generate_water_rivers;
+use crate::algorithms::rivers::calculate_river_lengths;
 use crate::algorithms::water_distance::generate_water_distance;
+use crate::algorithms::basins::generate_water_basins;
 use crate::algorithms::grouping::calculate_grouping;
+use crate::algorithms::grouping::calculate_shelf;
+use crate::algorithms::grouping::release_isolated_basins;
 use crate::algorithms::tiles::calculate_coastline;
+use crate::algorithms::lakes::name_lakes;
+use crate::algorithms::naming::NamerSet;
+use crate::typed_map::entities::EntityLookup;
+use crate::typed_map::entities::NamedEntity;
+use crate::world_map::culture_layer::CultureForTowns;
+use crate::world_map::culture_layer::CultureSchema;
+use crate::typed_map::schema::Schema;
+use crate::world_map::culture_layer::CultureWithNamer;
+use crate::utils::random::random_seed;
+use crate::utils::random::stage_random_number_generator;
+use crate::utils::random::NAMERS_STAGE;
+use crate::utils::random::LAKE_NAMES_STAGE;
 use crate::progress::ProgressObserver;
 use crate::world_map::WorldMapTransaction;
 use crate::commands::TargetArg;
@@ -23,7 +42,18 @@ use crate::commands::OverwriteRiversArg;
 use crate::commands::OverwriteAllOceanArg;
 use crate::commands::OverwriteAllWaterArg;
 use crate::commands::BezierScaleArg;
+use crate::commands::CoastDensifyArg;
 use crate::commands::LakeBufferScaleArg;
+use crate::commands::FlowConcentrationArg;
+use crate::commands::FlowScaleArg;
+use crate::commands::MinLakeAccumulationArg;
+use crate::commands::EdgeDrainageArg;
+use crate::commands::DeltaBranchingArg;
+use crate::commands::ShelfDepthArg;
+use crate::commands::IsolatedBasinsArg;
+use crate::commands::NamerArg;
+use crate::commands::RandomSeedArg;
+use crate::commands::MinNamedLakeSizeArg;
 
 
 subcommand_def!{
@@ -37,6 +67,12 @@ subcommand_def!{
         #[clap(flatten)]
         pub bezier_scale: BezierScaleArg,
 
+        #[clap(flatten)]
+        pub coast_densify: CoastDensifyArg,
+
+        #[clap(flatten)]
+        pub isolated_basins: IsolatedBasinsArg,
+
         #[clap(flatten)]
         pub overwrite_all_ocean: OverwriteAllOceanArg,
 
@@ -52,7 +88,7 @@ impl Task for Coastline {
 
         target.with_transaction(|transaction| {
 
-            Self::run_with_parameters(&self.bezier_scale, &self.overwrite_all_ocean.overwrite_coastline(), &self.overwrite_all_ocean.overwrite_ocean(), transaction, progress)
+            Self::run_with_parameters(&self.bezier_scale, &self.coast_densify, &self.isolated_basins, &self.overwrite_all_ocean.overwrite_coastline(), &self.overwrite_all_ocean.overwrite_ocean(), transaction, progress)
         })?;
 
         target.save(progress)
@@ -64,10 +100,15 @@ impl Task for Coastline {
 impl Coastline {
 
 
-    fn run_with_parameters<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, coast_densify: &CoastDensifyArg, isolated_basins: &IsolatedBasinsArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+        if isolated_basins.isolated_basins_as_lakes {
+            progress.announce("Releasing isolated basins");
+            release_isolated_basins(target, progress)?;
+        }
+
         progress.announce("Creating coastline");
 
-        calculate_coastline(target, bezier_scale, overwrite_coastline, overwrite_ocean, progress)
+        calculate_coastline(target, bezier_scale, coast_densify, overwrite_coastline, overwrite_ocean, progress)
     }
 }
 
@@ -79,6 +120,18 @@ subcommand_def!{
         #[clap(flatten)]
         pub target_arg: TargetArg,
 
+        #[clap(flatten)]
+        pub concentration: FlowConcentrationArg,
+
+        #[clap(flatten)]
+        pub scale: FlowScaleArg,
+
+        #[clap(flatten)]
+        pub min_lake_accumulation: MinLakeAccumulationArg,
+
+        #[clap(flatten)]
+        pub edge_drainage: EdgeDrainageArg,
+
     }
 }
 
@@ -90,7 +143,7 @@ impl Task for Flow {
         let mut target = WorldMap::edit(&self.target_arg.target)?;
 
         _ = target.with_transaction(|transaction| {
-            Self::run_with_parameters(transaction, progress)
+            Self::run_with_parameters(&self.concentration, &self.scale, &self.min_lake_accumulation, &self.edge_drainage, transaction, progress)
         })?;
 
         target.save(progress)
@@ -99,9 +152,9 @@ impl Task for Flow {
 }
 
 impl Flow {
-    fn run_with_parameters<Progress: ProgressObserver>(target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<WaterFlowResult,CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(flow_concentration: &FlowConcentrationArg, flow_scale: &FlowScaleArg, min_lake_accumulation: &MinLakeAccumulationArg, edge_drainage: &EdgeDrainageArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<WaterFlowResult,CommandError> {
         progress.announce("Calculating water flow");
-        generate_water_flow(target, progress)
+        generate_water_flow(target, flow_concentration, flow_scale, min_lake_accumulation, edge_drainage, progress)
     }
     
 }
@@ -124,6 +177,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub buffer_scale: LakeBufferScaleArg,
 
+        #[clap(flatten)]
+        pub edge_drainage: EdgeDrainageArg,
+
 
 
     }
@@ -139,7 +195,7 @@ impl Task for Lakes {
         let water_flow_result = target.tiles_layer()?.get_index_and_queue_for_water_fill(progress)?;
 
         target.with_transaction(|transaction| {
-            Self::run_with_parameters(water_flow_result, &self.bezier_scale, &self.buffer_scale, &self.overwrite_lakes, transaction, progress)
+            Self::run_with_parameters(water_flow_result, &self.bezier_scale, &self.buffer_scale, &self.overwrite_lakes, &self.edge_drainage, transaction, progress)
 
         })?;
 
@@ -148,9 +204,9 @@ impl Task for Lakes {
 }
 
 impl Lakes {
-    fn run_with_parameters<Progress: ProgressObserver>(water_flow_result: WaterFlowResult, lake_bezier_scale: &BezierScaleArg, lake_buffer_scale: &LakeBufferScaleArg, overwrite_layer: &OverwriteLakesArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(water_flow_result: WaterFlowResult, lake_bezier_scale: &BezierScaleArg, lake_buffer_scale: &LakeBufferScaleArg, overwrite_layer: &OverwriteLakesArg, edge_drainage: &EdgeDrainageArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Filling lakes");
-        generate_water_fill(target, water_flow_result, lake_bezier_scale, lake_buffer_scale, overwrite_layer, progress)
+        generate_water_fill(target, water_flow_result, lake_bezier_scale, lake_buffer_scale, overwrite_layer, edge_drainage, progress)
     }
 }
 
@@ -169,6 +225,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub bezier_scale: BezierScaleArg,
 
+        #[clap(flatten)]
+        pub delta_branching: DeltaBranchingArg,
+
     }
 }
 
@@ -180,7 +239,7 @@ impl Task for Rivers {
         let mut target = WorldMap::edit(&self.target.target)?;
 
         target.with_transaction(|transaction| {
-            Self::run_with_parameters(&self.bezier_scale, &self.overwrite_rivers, progress, transaction)
+            Self::run_with_parameters(&self.bezier_scale, &self.delta_branching, &self.overwrite_rivers, progress, transaction)
         })?;
 
         target.save(progress)
@@ -189,15 +248,86 @@ impl Task for Rivers {
 }
 
 impl Rivers {
-    fn run_with_parameters<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, overwrite_layer: &OverwriteRiversArg, progress: &mut Progress, target: &mut WorldMapTransaction<'_>) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, delta_branching: &DeltaBranchingArg, overwrite_layer: &OverwriteRiversArg, progress: &mut Progress, target: &mut WorldMapTransaction<'_>) -> Result<(), CommandError> {
 
         progress.announce("Generating rivers");
-        generate_water_rivers(target, bezier_scale, overwrite_layer, progress)
+        generate_water_rivers(target, bezier_scale, delta_branching, overwrite_layer, progress)
 
     }
 }
 
 
+subcommand_def!{
+    /// Recalculates the length of existing river segments, without regenerating the rivers
+    pub struct RiverLengths {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+    }
+}
+
+impl Task for RiverLengths {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        target.with_transaction(|transaction| {
+            Self::run_with_parameters(transaction, progress)
+        })?;
+
+        target.save(progress)
+
+    }
+}
+
+impl RiverLengths {
+
+    fn run_with_parameters<Progress: ProgressObserver>(target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+        progress.announce("Measuring rivers");
+        calculate_river_lengths(target, progress)
+    }
+
+}
+
+subcommand_def!{
+    /// Labels each land tile with the tile where its water ultimately ends up, identifying drainage basins
+    #[command(hide=true)]
+    pub struct Basins {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+    }
+}
+
+impl Task for Basins {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        target.with_transaction(|transaction| {
+            Self::run_with_parameters(transaction, progress)
+        })?;
+
+        target.save(progress)
+
+    }
+}
+
+impl Basins {
+
+    fn run_with_parameters<Progress: ProgressObserver>(target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+        progress.announce("Finding drainage basins");
+        generate_water_basins(target, progress)
+    }
+
+}
+
 subcommand_def!{
     /// Calculates shortest distance to shoreline and some other water information for every tile, in count of tiles
     #[command(hide=true)]
@@ -242,6 +372,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub target_arg: TargetArg,
 
+        #[clap(flatten)]
+        pub shelf_depth: ShelfDepthArg,
+
     }
 }
 
@@ -253,7 +386,7 @@ impl Task for Grouping {
         let mut target = WorldMap::edit(&self.target_arg.target)?;
 
         target.with_transaction(|transaction| {
-            Self::run_with_parameters(transaction, progress)
+            Self::run_with_parameters(&self.shelf_depth, transaction, progress)
         })?;
 
         target.save(progress)
@@ -262,14 +395,70 @@ impl Task for Grouping {
 }
 
 impl Grouping {
-    fn run_with_parameters<Progress: ProgressObserver>(target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+    fn run_with_parameters<Progress: ProgressObserver>(shelf_depth: &ShelfDepthArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
         progress.announce("Delineating land and water bodies");
-        calculate_grouping(target, progress)
+        calculate_grouping(target, progress)?;
+        progress.announce("Finding continental shelf");
+        calculate_shelf(target, shelf_depth, progress)
     }
     
 }
 
 
+subcommand_def!{
+    /// Names the lakes after the culture living around them, or with the default namer if there are no cultures nearby
+    pub struct NameLakes {
+
+        #[clap(flatten)]
+        pub target: TargetArg,
+
+        #[clap(flatten)]
+        pub namer: NamerArg,
+
+        #[clap(flatten)]
+        pub random_seed: RandomSeedArg,
+
+        #[clap(flatten)]
+        pub min_named_lake_size: MinNamedLakeSizeArg,
+
+    }
+}
+
+impl Task for NameLakes {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+
+        let seed = random_seed(&self.random_seed);
+        let mut random = stage_random_number_generator(seed, LAKE_NAMES_STAGE);
+
+        let mut target = WorldMap::edit(&self.target.target)?;
+
+        let mut loaded_namers = NamerSet::load_from(self.namer, &mut stage_random_number_generator(seed, NAMERS_STAGE), progress)?;
+
+        // lakes can be named before there are any cultures, in which case the default namer is used for all of them.
+        let culture_lookup = if target.has_layer(CultureSchema::LAYER_NAME) {
+            target.cultures_layer()?.read_features().into_named_entities_index::<_,CultureForTowns>(progress)?
+        } else {
+            EntityLookup::from(HashMap::new())
+        };
+
+        target.with_transaction(|transaction| {
+            Self::run_with_parameters(&mut random, &culture_lookup, &mut loaded_namers, &self.min_named_lake_size, transaction, progress)
+        })?;
+
+        target.save(progress)
+
+    }
+}
+
+impl NameLakes {
+    pub(crate) fn run_with_parameters<Random: Rng, Progress: ProgressObserver, Culture: NamedEntity<CultureSchema> + CultureWithNamer>(random: &mut Random, culture_lookup: &EntityLookup<CultureSchema, Culture>, loaded_namers: &mut NamerSet, min_named_lake_size: &MinNamedLakeSizeArg, target: &mut WorldMapTransaction<'_>, progress: &mut Progress) -> Result<(), CommandError> {
+        progress.announce("Naming lakes");
+        name_lakes(target, random, culture_lookup, loaded_namers, min_named_lake_size, progress)
+    }
+}
+
 subcommand_def!{
     /// generates all water data
     pub struct All {
@@ -280,9 +469,33 @@ subcommand_def!{
         #[clap(flatten)]
         pub bezier_scale: BezierScaleArg,
     
+        #[clap(flatten)]
+        pub coast_densify: CoastDensifyArg,
+    
+        #[clap(flatten)]
+        pub isolated_basins: IsolatedBasinsArg,
+    
         #[clap(flatten)]
         pub buffer_scale: LakeBufferScaleArg,
     
+        #[clap(flatten)]
+        pub flow_concentration: FlowConcentrationArg,
+    
+        #[clap(flatten)]
+        pub flow_scale: FlowScaleArg,
+    
+        #[clap(flatten)]
+        pub min_lake_accumulation: MinLakeAccumulationArg,
+    
+        #[clap(flatten)]
+        pub edge_drainage: EdgeDrainageArg,
+    
+        #[clap(flatten)]
+        pub delta_branching: DeltaBranchingArg,
+    
+        #[clap(flatten)]
+        pub shelf_depth: ShelfDepthArg,
+    
         #[clap(flatten)]
         pub overwrite_all_water: OverwriteAllWaterArg,
     
@@ -297,7 +510,7 @@ impl Task for All {
         let mut target = WorldMap::edit(&self.target.target)?;
 
         target.with_transaction(|transaction| {
-            Self::run_with_parameters(&self.bezier_scale,&self.buffer_scale,&self.overwrite_all_water.overwrite_coastline(),&self.overwrite_all_water.overwrite_ocean(),&self.overwrite_all_water.overwrite_lakes(),&self.overwrite_all_water.overwrite_rivers(),transaction,progress)
+            Self::run_with_parameters(&self.bezier_scale,&self.coast_densify,&self.isolated_basins,&self.buffer_scale,&self.flow_concentration,&self.flow_scale,&self.min_lake_accumulation,&self.edge_drainage,&self.delta_branching,&self.shelf_depth,&self.overwrite_all_water.overwrite_coastline(),&self.overwrite_all_water.overwrite_ocean(),&self.overwrite_all_water.overwrite_lakes(),&self.overwrite_all_water.overwrite_rivers(),transaction,progress)
         })?;
 
         target.save(progress)
@@ -306,18 +519,20 @@ impl Task for All {
 }
 
 impl All {
-    fn run_with_parameters<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, lake_buffer_scale: &LakeBufferScaleArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, overwrite_lakes: &OverwriteLakesArg, overwrite_rivers: &OverwriteRiversArg, transaction: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(), CommandError> {
-        Coastline::run_with_parameters(bezier_scale, overwrite_coastline, overwrite_ocean, transaction, progress)?;
+    fn run_with_parameters<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, coast_densify: &CoastDensifyArg, isolated_basins: &IsolatedBasinsArg, lake_buffer_scale: &LakeBufferScaleArg, flow_concentration: &FlowConcentrationArg, flow_scale: &FlowScaleArg, min_lake_accumulation: &MinLakeAccumulationArg, edge_drainage: &EdgeDrainageArg, delta_branching: &DeltaBranchingArg, shelf_depth: &ShelfDepthArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, overwrite_lakes: &OverwriteLakesArg, overwrite_rivers: &OverwriteRiversArg, transaction: &mut WorldMapTransaction, progress: &mut Progress) -> Result<(), CommandError> {
+        Coastline::run_with_parameters(bezier_scale, coast_densify, isolated_basins, overwrite_coastline, overwrite_ocean, transaction, progress)?;
+
+        let water_flow_result = Flow::run_with_parameters(flow_concentration, flow_scale, min_lake_accumulation, edge_drainage, transaction, progress)?;
 
-        let water_flow_result = Flow::run_with_parameters(transaction, progress)?;
+        Lakes::run_with_parameters(water_flow_result, bezier_scale, lake_buffer_scale, overwrite_lakes, edge_drainage, transaction, progress)?;
 
-        Lakes::run_with_parameters(water_flow_result, bezier_scale, lake_buffer_scale, overwrite_lakes, transaction, progress)?;
+        Rivers::run_with_parameters(bezier_scale, delta_branching, overwrite_rivers, progress, transaction)?;
 
-        Rivers::run_with_parameters(bezier_scale, overwrite_rivers, progress, transaction)?;
+        Basins::run_with_parameters(transaction, progress)?;
 
         ShoreDistance::run_with_parameters(transaction, progress)?;
 
-        Grouping::run_with_parameters(transaction, progress)
+        Grouping::run_with_parameters(shelf_depth, transaction, progress)
     
     }
     
@@ -331,8 +546,11 @@ command_def!{
         Flow,
         Lakes,
         Rivers,
+        RiverLengths,
+        Basins,
         ShoreDistance,
-        Grouping
+        Grouping,
+        NameLakes
     }
 }
 
@@ -359,10 +577,10 @@ impl Task for GenWater {
 }
 
 impl GenWater {
-    pub(crate) fn run_default<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, lake_buffer_scale: &LakeBufferScaleArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, overwrite_lakes: &OverwriteLakesArg, overwrite_rivers: &OverwriteRiversArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
+    pub(crate) fn run_default<Progress: ProgressObserver>(bezier_scale: &BezierScaleArg, coast_densify: &CoastDensifyArg, isolated_basins: &IsolatedBasinsArg, lake_buffer_scale: &LakeBufferScaleArg, flow_concentration: &FlowConcentrationArg, flow_scale: &FlowScaleArg, min_lake_accumulation: &MinLakeAccumulationArg, edge_drainage: &EdgeDrainageArg, delta_branching: &DeltaBranchingArg, shelf_depth: &ShelfDepthArg, overwrite_coastline: &OverwriteCoastlineArg, overwrite_ocean: &OverwriteOceanArg, overwrite_lakes: &OverwriteLakesArg, overwrite_rivers: &OverwriteRiversArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
         target.with_transaction(|transaction| {
 
-            All::run_with_parameters(bezier_scale, lake_buffer_scale, overwrite_coastline, overwrite_ocean, overwrite_lakes, overwrite_rivers, transaction, progress)
+            All::run_with_parameters(bezier_scale, coast_densify, isolated_basins, lake_buffer_scale, flow_concentration, flow_scale, min_lake_accumulation, edge_drainage, delta_branching, shelf_depth, overwrite_coastline, overwrite_ocean, overwrite_lakes, overwrite_rivers, transaction, progress)
         
         
         })?;
@@ -0,0 +1,48 @@
+use clap::Args;
+
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Prints every layer in the world map with its geometry type and feature count, which shows which generation steps have been run
+    pub struct Info {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+    }
+}
+
+impl Task for Info {
+
+    fn run<Progress: ProgressObserver>(self, _: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let layers = target.layer_info();
+
+        let name_width = layers.iter().map(|layer| layer.name().len()).max().unwrap_or_default();
+        let type_width = layers.iter().map(|layer| layer.geometry_type().len()).max().unwrap_or_default();
+
+        for layer in &layers {
+            let unknown = if layer.known() {
+                ""
+            } else {
+                " (unknown layer)"
+            };
+            println!("{:name_width$}  {:type_width$}  {:>10}{unknown}",layer.name(),layer.geometry_type(),layer.feature_count());
+        }
+
+        let missing = WorldMap::known_layer_names().into_iter().filter(|name| !layers.iter().any(|layer| layer.name() == *name)).collect::<Vec<_>>();
+        if !missing.is_empty() {
+            println!("Not generated: {}",missing.join(", "));
+        }
+
+        Ok(())
+
+    }
+}
@@ -0,0 +1,45 @@
+use clap::Args;
+
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::commands::CompactArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Adds any fields that are missing from the layers of an existing world map, such as those added in newer versions. The fields will be empty until the commands which calculate them are run again.
+    pub struct Migrate {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        #[clap(flatten)]
+        pub compact_arg: CompactArg,
+
+    }
+}
+
+impl Task for Migrate {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        let added_count = target.migrate(progress)?;
+
+        if added_count == 0 {
+            progress.message(|| "World map is up to date.");
+        }
+
+        target.save(progress)?;
+
+        if self.compact_arg.compact {
+            target.compact(progress)?;
+        }
+
+        Ok(())
+
+    }
+}
@@ -15,6 +15,15 @@ use schemars::JsonSchema;
 use crate::errors::CommandError;
 use crate::progress::ProgressObserver;
 use crate::utils::world_shape::WorldShape;
+use crate::utils::extent::Extent;
+use crate::utils::random::random_seed;
+use crate::utils::random::derive_stage_seed;
+use crate::algorithms::random_points::BoundaryPoints;
+use crate::algorithms::random_points::PointDistribution;
+use crate::algorithms::water_flow::EdgeDrainage;
+use crate::algorithms::climate::WindBands;
+use crate::raster::ResampleMethod;
+use crate::world_map::fields::LakeType;
 
 
 mod gdal_dev; // called gdal_dev to avoid ambiguity with external crate
@@ -30,6 +39,20 @@ mod gen_towns;
 mod gen_nations;
 mod gen_subnations;
 mod big_bang;
+mod migrate;
+mod preview;
+mod gen_routes;
+mod export_style;
+mod fingerprint;
+mod info;
+mod check_neighbors;
+mod export_graph;
+mod export_rivers;
+mod export;
+mod reproject;
+mod set_tile_attr;
+mod render;
+pub(crate) mod config;
 
 use gdal_dev::Gdal;
 use dev::Dev;
@@ -38,6 +61,7 @@ use create::Create;
 use create::CreateCalcNeighbors;
 use create::CreateTiles;
 use terrain::Terrain;
+use terrain::ListTerrainRecipes;
 use gen_climate::GenClimate;
 use gen_water::GenWater;
 use gen_biome::GenBiome;
@@ -46,7 +70,21 @@ use gen_towns::GenTowns;
 use gen_nations::GenNations;
 use gen_subnations::GenSubnations;
 use big_bang::BigBang;
+use migrate::Migrate;
+use preview::Preview;
+use gen_routes::GenRoutes;
+use export_style::ExportStyle;
+use fingerprint::Fingerprint;
+use info::Info;
+use check_neighbors::CheckNeighbors;
+use export_graph::ExportGraph;
+use export_rivers::ExportRivers;
+use export::Export;
+use reproject::Reproject;
+use set_tile_attr::SetTileAttr;
+use render::Render;
 use crate::utils::arg_range::ArgRange;
+use crate::utils::tile_filter::TileFilter;
 
 
 pub(crate) trait Task {
@@ -65,8 +103,9 @@ macro_rules! command_def {
         $(#[$attr])*
         $visibility enum $struct_name {
             $(
+                // boxed, so the enum isn't as large as the command with the most arguments.
                 $(#[$command_attr])*
-                $command_name($command_name)
+                $command_name(Box<$command_name>)
             ),*
         }
 
@@ -101,6 +140,8 @@ command_def!{
         CreateTiles,
         /// Runs a terrain process on the world to manipulate elevations or ocean status
         Terrain,
+        /// Lists and validates the recipes in a terrain recipe set file
+        ListTerrainRecipes,
         /// Generates climate data for a world
         GenClimate,
         /// Generates water features for a world
@@ -115,8 +156,34 @@ command_def!{
         GenNations,
         /// Generates subnations (provinces and other administrative divisions) for a world
         GenSubnations,
+        /// Generates roads and sea routes connecting the towns of a world
+        GenRoutes,
         /// Creates a world map, generates natural features, and populates it with nations and subnations
-        BigBang
+        BigBang,
+        /// Adds fields which are missing from an older world map
+        Migrate,
+        /// Renders the tiles of a world map as a small PNG image
+        Preview,
+        /// Writes a QGIS style or JSON legend for the colors stored in a layer
+        ExportStyle,
+        /// Prints a hash of the tiles, for checking that two worlds are the same
+        Fingerprint,
+        /// Lists the layers in a world map with their geometry types and feature counts
+        Info,
+        /// Checks that every tile's neighbors exist and list the tile back
+        CheckNeighbors,
+        /// Writes the tile neighbors as an edge list for use with graph tools
+        ExportGraph,
+        /// Writes the rivers as GeoJSON with style hints for web maps
+        ExportRivers,
+        /// Writes world map layers into other formats, such as GeoJSON
+        Export,
+        /// Writes a copy of world map layers transformed to another coordinate system
+        Reproject,
+        /// Attaches a custom attribute to a tile
+        SetTileAttr,
+        /// Renders a layer of a world map over its oceans and lakes as a PNG image
+        Render
     }
 }
 
@@ -157,6 +224,15 @@ pub struct ElevationSourceArg {
 
 }
 
+#[derive(Args,Serialize,Deserialize,JsonSchema)]
+pub struct ResampleArg {
+    #[arg(long,default_value="nearest")]
+    #[serde(default)]
+    /// How to calculate values from raster pixels near each tile site
+    pub resample: ResampleMethod,
+
+}
+
 #[derive(Args,Serialize,Deserialize,JsonSchema)]
 pub struct OceanSourceArg {
     /// The path to the heightmap containing the ocean data
@@ -183,6 +259,90 @@ pub struct TileCountArg {
     /// The rough number of tiles to generate for the image
     pub tile_count: usize,
 
+    #[arg(long)]
+    /// If specified, the tiles are generated in rows this many degrees apart, and the tile count is ignored. On a spherical world, the tiles along each row are spread further apart towards the poles.
+    pub latitude_spacing: Option<f64>,
+
+}
+
+#[derive(Args)]
+pub struct ContinentCountArg {
+    #[arg(long)]
+    /// If specified, the terrain processes are repeated with new random numbers until they produce this many continent-sized landmasses, keeping the closest result. Landmasses are separated by ocean, so the processes should include one that creates ocean, such as fill-ocean or flood-ocean
+    pub continent_count: Option<usize>,
+
+    #[arg(long,default_value="20")]
+    /// The number of times the terrain processes may be run while trying to reach the continent count, if one is specified
+    pub continent_attempts: usize,
+
+}
+
+#[derive(Args)]
+pub struct BoundaryPointsArg {
+    #[arg(long,default_value="corners")]
+    /// The points added outside of the extent to shape the tiles along the edges
+    pub boundary_points: BoundaryPoints,
+
+}
+
+#[derive(Args)]
+pub struct PointDistributionArg {
+    #[arg(long,default_value="grid")]
+    /// How the points which become tiles are spread across the world
+    pub point_distribution: PointDistribution,
+
+}
+
+#[derive(Args)]
+pub struct TileRegionArg {
+    #[arg(long,value_delimiter=',',allow_hyphen_values=true,value_name="WEST,SOUTH,EAST,NORTH")]
+    /// Experimental: only generate the tiles inside this part of the world, so a large world can be generated in separate regions and combined later
+    pub tile_region: Option<Vec<f64>>,
+
+}
+
+/// Checks a rectangle given on the command line as west, south, east and north.
+pub(crate) fn parse_region(bounds: &[f64]) -> Result<Extent,CommandError> {
+    let &[west,south,east,north] = bounds else {
+        return Err(CommandError::InvalidTileRegion(format!("expected four numbers, found {}",bounds.len())))
+    };
+    if (west >= east) || (south >= north) {
+        return Err(CommandError::InvalidTileRegion("west and south must be less than east and north".to_owned()))
+    }
+    Ok(Extent::from_bounds(west, south, east, north))
+}
+
+impl TileRegionArg {
+
+    pub(crate) fn region(&self, extent: &Extent) -> Result<Option<Extent>,CommandError> {
+        let Some(bounds) = &self.tile_region else {
+            return Ok(None)
+        };
+        let region = parse_region(bounds)?;
+        if (region.west() < extent.west()) || (region.south() < extent.south()) || (region.east() > extent.east()) || (region.north() > extent.north()) {
+            return Err(CommandError::InvalidTileRegion(format!("region must be inside the world extent ({},{},{},{})",extent.west(),extent.south(),extent.east(),extent.north())))
+        }
+        Ok(Some(region))
+    }
+}
+
+#[derive(Args,Clone,Default,Deserialize,Serialize,JsonSchema)]
+pub struct TileFilterArg {
+    #[arg(long,allow_hyphen_values=true)]
+    /// Only tiles with a latitude in this range are changed, such as `30..60`, `..=-45` or `60..`
+    pub latitudes: Option<ArgRange<f64>>,
+
+    #[arg(long,value_delimiter=',',allow_hyphen_values=true,value_name="WEST,SOUTH,EAST,NORTH")]
+    /// Only tiles inside this rectangle are changed
+    pub region: Option<Vec<f64>>,
+}
+
+impl TileFilterArg {
+
+    pub(crate) fn filter(&self) -> Result<TileFilter,CommandError> {
+        let region = self.region.as_deref().map(parse_region).transpose()?;
+        Ok(TileFilter::new(self.latitudes.clone(), region))
+    }
 }
 
 #[derive(Args)]
@@ -194,13 +354,36 @@ pub struct WorldShapeArg {
 }
 
 
+#[derive(Args)]
+pub struct IntegerIdsArg {
+    #[arg(long)]
+    /// Store references to other features as integer fields instead of strings, for tools which expect integer foreign keys. This can only be chosen when the tiles are created.
+    pub integer_ids: bool,
+
+}
+
 #[derive(Args)]
 pub struct RandomSeedArg {
     #[arg(long)]
-    /// Seed for the random number generator, note that this might not reproduce the same over different versions and configurations of nfmt.
+    /// Seed for the random number generator. Note that this might not reproduce the same over different versions and configurations of cosmopoeia. Differences in the system math library and in GDAL can change results on different machines.
     pub seed: Option<u64>,
 }
 
+impl RandomSeedArg {
+
+    /// The seed for one stage of generation, derived from the seed given and the label of the stage. Running the stage on its own with the same seed gives the same results as running it in `big-bang`. If no seed was given, a random one is chosen on each call.
+    pub(crate) fn derive(&self, stage: &str) -> u64 {
+        derive_stage_seed(random_seed(self), stage)
+    }
+}
+
+#[derive(Args)]
+pub struct PeopleSeedArg {
+    #[arg(long)]
+    /// Seed for the random number generators used for cultures, towns, nations and subnations, kept separate from the one used for the physical geography. Change this to reroll the people of a world without changing its terrain. If not specified, the main seed is used. Running `gen-people` and the later commands with this as their seed generates the same people.
+    pub people_seed: Option<u64>,
+}
+
 #[derive(Args)]
 pub struct BezierScaleArg {
     #[arg(long,default_value="100")]
@@ -209,6 +392,14 @@ pub struct BezierScaleArg {
 
 }
 
+#[derive(Args)]
+pub struct CoastDensifyArg {
+    #[arg(long,default_value="0")]
+    /// The number of points to insert between each pair of coastline vertices before making them curvy. Higher numbers give smoother shores but larger files.
+    pub coast_densify: usize,
+
+}
+
 #[derive(Args)]
 pub struct TemperatureRangeArg {
         /// The rough temperature (in celsius) at the equator
@@ -230,7 +421,7 @@ fn parse_wind_range(value: &str) -> Result<(Range<OrderedFloat<f64>>, u16), &'st
         let direction = direction.parse().map_err(|_| HELP_MESSAGE)?;
         let range = match range {
             ArgRange::Exclusive(min, max) => OrderedFloat(min)..OrderedFloat(max),
-            ArgRange::Inclusive(_,_) | ArgRange::Single(_) => return Err(HELP_MESSAGE)
+            ArgRange::Inclusive(_,_) | ArgRange::Single(_) | ArgRange::From(_) | ArgRange::To(_) | ArgRange::ToInclusive(_) => return Err(HELP_MESSAGE)
         };
         Ok((range,direction))
     
@@ -268,27 +459,29 @@ pub struct WindsArg {
 
     #[arg(long,allow_hyphen_values=true,value_parser(parse_wind_range))]
     /// Specify a range of latitudes and a wind direction (S lat..N lat:Direction), later mappings will override earlier.
-    pub wind_range: Vec<(Range<OrderedFloat<f64>>, u16)>
+    pub wind_range: Vec<(Range<OrderedFloat<f64>>, u16)>,
 
+    #[arg(long)]
+    /// A raster of wind directions (clockwise degrees from north), which overrides the latitude bands wherever it has data
+    pub wind_raster: Option<PathBuf>,
 
 }
 
 impl WindsArg {
 
-    pub(crate) fn to_range_map(&self) -> RangeMap<OrderedFloat<f64>, u16> {
+    pub(crate) fn to_wind_bands(&self) -> WindBands {
         let mut result = RangeMap::new();
         result.insert(OrderedFloat(-90.0)..OrderedFloat(-60.0),self.south_polar_wind);
         result.insert(OrderedFloat(-60.0)..OrderedFloat(-30.0),self.south_middle_wind);
         result.insert(OrderedFloat(-30.0)..OrderedFloat(0.0),self.south_tropical_wind);
         result.insert(OrderedFloat(0.0)..OrderedFloat(30.0),self.north_tropical_wind);
         result.insert(OrderedFloat(30.0)..OrderedFloat(60.0),self.north_middle_wind);
-        // note that the last one is set at 90.1 since the range map is not inclusive
-        result.insert(OrderedFloat(60.0)..OrderedFloat(90.1),self.north_polar_wind);
+        result.insert(OrderedFloat(60.0)..OrderedFloat(90.0),self.north_polar_wind);
 
         for range in &self.wind_range {
             result.insert(range.0.clone(),range.1)
         }
-        result
+        WindBands::new(result)
 
     }
 }
@@ -297,22 +490,67 @@ impl WindsArg {
 pub struct PrecipitationArg {
 
     #[arg(long,default_value="1")]
-    /// Amount of global moisture on a scale of roughly 0-5, but there is no limit
+    /// Amount of global moisture on a scale of 0-5
     pub precipitation_factor: f64,
 
+    #[arg(long)]
+    /// Allow a precipitation factor greater than 5, for experimentation
+    pub allow_extreme_precipitation: bool,
+
+    #[arg(long)]
+    /// Trace precipitation on several threads. This is faster on large maps, and the results don't depend on the number of threads, but they are slightly different from tracing on one thread.
+    pub parallel: bool,
+
+}
+
+impl PrecipitationArg {
+
+    pub(crate) fn validated_factor(&self) -> Result<f64,CommandError> {
+        if !self.precipitation_factor.is_finite() || (self.precipitation_factor < 0.0) {
+            Err(CommandError::InvalidPrecipitationFactor(self.precipitation_factor))
+        } else if (self.precipitation_factor > 5.0) && !self.allow_extreme_precipitation {
+            Err(CommandError::ExtremePrecipitationFactor(self.precipitation_factor))
+        } else {
+            Ok(self.precipitation_factor)
+        }
+    }
+}
+
+fn parse_current_strength(value: &str) -> Result<f64, &'static str> {
+    const HELP_MESSAGE: &str = "Current strength must be a number from 0 to 1.";
+    let value: f64 = value.parse().map_err(|_| HELP_MESSAGE)?;
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(HELP_MESSAGE)
+    }
+}
+
+#[derive(Args)]
+pub struct CurrentStrengthArg {
+    #[arg(long,default_value="0",value_parser(parse_current_strength))]
+    /// How far temperatures carried downwind over the ocean, as a rough stand-in for ocean currents, pull the temperatures of the tiles they reach. At 0 this is skipped, at 1 a tile takes the temperature carried to it. Requires winds to have been generated.
+    pub current_strength: f64
 }
 
 #[derive(Args)]
 pub struct NamerArg {
 
     #[arg(long,required=true)]
-    /// Files to load name generators from, more than one may be specified to load multiple languages. Later language names will override previous ones.
+    /// Files to load name generators from, more than one may be specified to load multiple languages. Later language names will override previous ones. A directory of gendered name lists may also be given.
     pub namers: Vec<PathBuf>,
 
     #[arg(long)]
     /// The name generator to use for naming towns in tiles without a culture, or one will be randomly chosen
     pub default_namer: Option<String>,
 
+    #[arg(long)]
+    /// If no default namer is specified, choose the namer for each town in a tile without a culture at random, weighted by the probabilities of the loaded culture sets
+    pub weight_default_namer: bool,
+
+    #[arg(long)]
+    /// Report how often each markov namer couldn't build a valid name and fell back to one of its seed words, which can help improve the seed data
+    pub report_name_fallbacks: bool,
 
 }
 
@@ -339,11 +577,32 @@ pub struct OverrideBiomeCriteriaArg {
 
     #[arg(long)]
     /// A number at and above which waterflow that wetlands are applied, default is 400.
-    pub min_wetland_flow: Option<f64>
+    pub min_wetland_flow: Option<f64>,
+
+    #[arg(long)]
+    /// Add a barren biome for high and cold land, which is applied before the moisture and temperature matrix.
+    pub barren_biome: bool,
+
+    #[arg(long,allow_negative_numbers=true)]
+    /// An elevation at and above which barren biomes are applied, if they are included, default is 3000.
+    pub min_barren_elevation: Option<f64>,
+
+    #[arg(long,allow_negative_numbers=true)]
+    /// A temperature at and below which barren biomes are applied, if they are included, default is 0.
+    pub max_barren_temp: Option<f64>,
 
 
 }
 
+#[derive(Args)]
+pub struct IceCapArg {
+
+    #[arg(long,allow_negative_numbers=true)]
+    /// If specified, all tiles at and below this temperature, including ocean, are covered by the glacier biome, forming polar ice caps.
+    pub ice_cap_temperature: Option<f64>,
+
+}
+
 #[derive(Args)]
 pub struct SizeVarianceArg {
 
@@ -354,6 +613,15 @@ pub struct SizeVarianceArg {
 
 }
 
+#[derive(Args)]
+pub struct DebugHabitabilityArg {
+
+    #[arg(long)]
+    /// Writes the parts that make up each tile's habitability to extra 'debug_habitability_' fields in the tiles layer. Generating population without this removes them again.
+    pub debug_habitability: bool,
+
+}
+
 #[derive(Args)]
 pub struct RiverThresholdArg {
 
@@ -364,12 +632,52 @@ pub struct RiverThresholdArg {
 
 }
 
+#[derive(Args)]
+pub struct CompactArg {
+
+    #[arg(long)]
+    /// When done, rebuild the file to reclaim the space left behind by overwritten layers. This can take a while on large maps.
+    pub compact: bool
+
+}
+
 #[derive(Args)]
 pub struct ExpansionFactorArg {
 
     #[arg(long,default_value("1"))]
     /// A number, usually ranging from 0.1 to 2.0, which limits how far cultures and nations will expand. The higher the number, the fewer neutral lands.
-    pub expansion_factor: f64
+    pub expansion_factor: f64,
+
+}
+
+#[derive(Args)]
+pub struct MaxExpansionCostArg {
+
+    #[arg(long,default_value("inf"))]
+    /// The greatest total movement cost a culture can pay to reach a tile from its center, no matter how far the expansion factor would let it go. Lower values keep cultures from spreading across expensive biomes, such as glaciers, to reach the land beyond.
+    pub max_expansion_cost: f64,
+
+}
+
+#[derive(Args)]
+pub struct CultureWaterCostArg {
+
+    #[arg(long,default_value("6"))]
+    /// The cost for cultures to expand across a water tile, for each unit of the tile's area. Nomadic cultures pay several times as much. This doesn't apply to naval cultures, or lake cultures crossing lakes, which cross water cheaply. Use 'inf' to keep them from crossing water at all.
+    pub culture_water_cost: f64,
+
+}
+
+#[derive(Args)]
+pub struct NationExpansionArg {
+
+    #[arg(long,default_value("1000"))]
+    /// The cost for nations to expand across a water tile, which is the same for every tile no matter its size. This doesn't apply to naval nations, or lake nations crossing lakes, which cross water cheaply. Use 'inf' to keep them from crossing water at all.
+    pub nation_water_cost: f64,
+
+    #[arg(long)]
+    /// If true, nations pay a large cost to cross onto a river from land, so their borders tend to follow rivers. Moving along a river is not affected.
+    pub rivers_as_borders: bool
 
 }
 
@@ -384,6 +692,10 @@ pub struct CulturesGenArg {
     /// The number of cultures to generate
     pub culture_count: usize,
 
+    #[arg(long)]
+    /// If more than one culture uses the same namer, give the others namers which aren't used by any culture yet, so different cultures sound different. The namers are chosen in alphabetical order, so the same cultures always get the same namers.
+    pub diversify_namers: bool,
+
 
 }
 
@@ -412,12 +724,156 @@ pub struct TownCountsArg {
 
 
 #[derive(Args)]
+pub struct NationCountArg {
+    #[arg(long)]
+    /// The number of nations to create. If not specified, a nation is created for every capital. Extra capitals are demoted to towns, keeping the most populous ones. If there aren't enough capitals, only that many nations are created.
+    pub nation_count: Option<usize>,
+}
+
+
+#[derive(Args)]
+#[allow(clippy::struct_field_names,reason="The lake types are the only thing that differs between these.")]
 pub struct LakeBufferScaleArg {
     #[arg(long,default_value="2")]
     /// This number is used for determining a buffer between the lake and the tile. The higher the number, the smaller and simpler the lakes.
-    pub lake_buffer_scale: f64
+    pub lake_buffer_scale: f64,
+
+    #[arg(long)]
+    /// Overrides the lake buffer scale for fresh lakes.
+    pub fresh_buffer_scale: Option<f64>,
+
+    #[arg(long)]
+    /// Overrides the lake buffer scale for salt lakes.
+    pub salt_buffer_scale: Option<f64>,
+
+    #[arg(long)]
+    /// Overrides the lake buffer scale for frozen lakes.
+    pub frozen_buffer_scale: Option<f64>,
+
+    #[arg(long)]
+    /// Overrides the lake buffer scale for pluvial lakes.
+    pub pluvial_buffer_scale: Option<f64>,
+
+    #[arg(long)]
+    /// Overrides the lake buffer scale for dry lakes.
+    pub dry_buffer_scale: Option<f64>,
+
+    #[arg(long)]
+    /// Overrides the lake buffer scale for marshes.
+    pub marsh_buffer_scale: Option<f64>,
+
+}
+
+impl LakeBufferScaleArg {
+
+    pub(crate) fn buffer_scale(&self, lake_type: &LakeType) -> f64 {
+        match lake_type {
+            LakeType::Fresh => self.fresh_buffer_scale,
+            LakeType::Salt => self.salt_buffer_scale,
+            LakeType::Frozen => self.frozen_buffer_scale,
+            LakeType::Pluvial => self.pluvial_buffer_scale,
+            LakeType::Dry => self.dry_buffer_scale,
+            LakeType::Marsh => self.marsh_buffer_scale,
+        }.unwrap_or(self.lake_buffer_scale)
+    }
+}
 
+fn parse_flow_concentration(value: &str) -> Result<f64, &'static str> {
+    const HELP_MESSAGE: &str = "Flow concentration must be a number from 0 to 1.";
+    let value: f64 = value.parse().map_err(|_| HELP_MESSAGE)?;
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(HELP_MESSAGE)
+    }
+}
+
+#[derive(Args)]
+pub struct FlowConcentrationArg {
+    #[arg(long,default_value="0",value_parser(parse_flow_concentration))]
+    /// When several neighbors tie for lowest, this controls how water flow is split between them. At 0 the flow is split equally, at 1 all of it goes to a single neighbor, values in between send the extra portion to that neighbor. That neighbor is the one with the steepest drop, or the one with the lowest id if they are exactly tied.
+    pub flow_concentration: f64
+}
+
+fn parse_flow_scale(value: &str) -> Result<f64, &'static str> {
+    const HELP_MESSAGE: &str = "Flow scale must be a number greater than 0.";
+    let value: f64 = value.parse().map_err(|_| HELP_MESSAGE)?;
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(HELP_MESSAGE)
+    }
+}
+
+#[derive(Args)]
+pub struct FlowScaleArg {
+    #[arg(long,default_value="1",value_parser(parse_flow_scale))]
+    /// Multiplies the water each tile receives from precipitation, making rivers more or less prominent. Since flow accumulates downstream, the water flow of every tile changes by the same proportion.
+    pub flow_scale: f64,
+
+    #[arg(long,default_value="10000",value_parser(clap::value_parser!(u64).range(1..)))]
+    /// The tile count at which precipitation isn't scaled by map size. Precipitation is divided by the fourth root of the tile count over this number, so maps with more, smaller tiles don't get larger rivers just from having more tiles upstream.
+    pub flow_reference_tiles: u64,
+}
+
+fn parse_min_lake_accumulation(value: &str) -> Result<f64, &'static str> {
+    const HELP_MESSAGE: &str = "Minimum lake accumulation must be a number of at least 0.";
+    let value: f64 = value.parse().map_err(|_| HELP_MESSAGE)?;
+    if value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(HELP_MESSAGE)
+    }
+}
 
+#[derive(Args)]
+pub struct MinLakeAccumulationArg {
+    #[arg(long,default_value="0",value_parser(parse_min_lake_accumulation))]
+    /// A low point in the land only starts a lake if at least this much water collects there. At 0, every low point is handed to the lake fill, even a dry one.
+    pub min_lake_accumulation: f64
+}
+
+#[derive(Args)]
+pub struct MinNamedLakeSizeArg {
+    #[arg(long,default_value="2")]
+    /// Lakes covering fewer tiles than this are left without a name
+    pub min_named_lake_size: i32
+}
+
+#[derive(Args)]
+pub struct EdgeDrainageArg {
+    #[arg(long,default_value="ocean")]
+    /// What happens to water which reaches the edge of the map
+    pub edge_drainage: EdgeDrainage
+}
+
+#[derive(Args)]
+pub struct DeltaBranchingArg {
+    #[arg(long)]
+    /// If true, high-flow rivers reaching the ocean on a low coast will split into several distributaries at the mouth.
+    pub delta_branching: bool,
+
+    #[arg(long,default_value="500")]
+    /// The minimum water flow for a river mouth to split into a delta.
+    pub min_delta_flow: f64,
+
+    #[arg(long,default_value="20")]
+    /// The maximum elevation of the tile where a river mouth can split into a delta.
+    pub max_delta_elevation: f64,
+}
+
+#[derive(Args)]
+pub struct ShelfDepthArg {
+    #[arg(long,default_value="200")]
+    /// Ocean tiles connected to land which are no deeper than this depth, in meters, are marked as continental shelf.
+    pub shelf_depth: f64,
+}
+
+#[derive(Args)]
+pub struct IsolatedBasinsArg {
+    #[arg(long)]
+    /// Turn bodies of ocean which don't reach the edge of the map, other than the largest, back into land before creating the coastline, so depressions below sea level fill as lakes instead.
+    pub isolated_basins_as_lakes: bool,
 }
 
 macro_rules! overwrite_arg {
@@ -446,6 +902,7 @@ overwrite_arg!(cultures);
 overwrite_arg!(towns);
 overwrite_arg!(nations);
 overwrite_arg!(subnations);
+overwrite_arg!(routes);
 
 
 #[derive(Args)]
@@ -643,7 +1100,19 @@ impl OverwriteAllOceanArg {
 pub struct Cosmopoeia {
 
     #[command(subcommand)]
-    pub command: MainCommand
+    pub command: MainCommand,
+
+    #[arg(long,global=true)]
+    /// Prints how long each phase of the command took once it is done
+    pub timing: bool,
+
+    #[arg(long,global=true)]
+    /// A JSON file of option values, keyed by the long option name, which are used when the option isn't given on the command line. Positional arguments can't be set this way.
+    pub config: Option<PathBuf>,
+
+    #[arg(long,global=true)]
+    /// Prints every option of the command with its value, including defaults and any loaded with '--config', as a JSON config file, then exits without running the command. A random seed is chosen if the command takes one and none was given.
+    pub print_config: bool
 
 }
 
@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::algorithms::colors::ColorRamp;
+use crate::algorithms::preview::PreviewAttribute;
+use crate::algorithms::preview::generate_preview;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::commands::parse_region;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Renders the tiles of a world map into a small equirectangular PNG image, colored by the chosen attribute. This is meant for quickly checking the results of a command without opening the world in a GIS.
+    pub struct Preview {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        #[arg(long)]
+        /// The tile data which determines the color of each tile
+        pub attribute: PreviewAttribute,
+
+        #[arg(long)]
+        /// The path to write the PNG image to
+        pub output: PathBuf,
+
+        #[arg(long)]
+        /// A JSON file containing a list of stops, each with a 'value' and a hex 'color', to color elevation or temperature with instead of the default colors
+        pub color_ramp: Option<PathBuf>,
+
+        #[arg(long,default_value="720")]
+        /// The width of the image in pixels. The height is calculated from the extent of the world, or the region if one is given.
+        pub width: usize,
+
+        #[arg(long,value_delimiter=',',allow_hyphen_values=true,value_name="WEST,SOUTH,EAST,NORTH")]
+        /// Only render this part of the world, at the full width of the image, for looking at details of a large world
+        pub region: Option<Vec<f64>>,
+
+    }
+}
+
+impl Task for Preview {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let color_ramp = self.color_ramp.map(ColorRamp::from_file).transpose()?;
+
+        let region = self.region.as_deref().map(parse_region).transpose()?;
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        let image = target.with_transaction(|transaction| {
+            generate_preview(transaction, &self.attribute, color_ramp, self.width, region, progress)
+        })?;
+
+        progress.message(|| format!("Writing {}x{} preview image.",image.width(),image.height()));
+
+        image.save(&self.output)
+
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::algorithms::preview::RenderLayer;
+use crate::algorithms::preview::generate_render;
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Renders the polygons of a layer, colored by their color field, over the oceans and lakes into an equirectangular PNG image. Unlike `preview`, this draws the finished shapes instead of the tiles, for a recognizable thumbnail of the map.
+    pub struct Render {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        /// The path to write the PNG image to
+        pub output: PathBuf,
+
+        #[arg(long,default_value="biomes")]
+        /// The layer to draw over the water
+        pub layer: RenderLayer,
+
+        #[arg(long,default_value="720")]
+        /// The width of the image in pixels. The height is calculated from the extent of the world.
+        pub width: usize,
+
+    }
+}
+
+impl Task for Render {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        let image = target.with_transaction(|transaction| {
+            generate_render(transaction, &self.layer, self.width, progress)
+        })?;
+
+        progress.message(|| format!("Writing {}x{} image.",image.width(),image.height()));
+
+        image.save(&self.output)
+
+    }
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Writes a copy of layers from the world map in another coordinate system, such as an equal-area projection, for tools which measure areas or distances on the geometries.
+    pub struct Reproject {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        /// The path of the GeoPackage to write the copy to. An existing file is replaced.
+        pub output: PathBuf,
+
+        #[arg(long,default_value="6933")]
+        /// The EPSG code of the coordinate system to transform to. The default is the global cylindrical equal-area projection used for EASE-Grid 2.0.
+        pub epsg: u32,
+
+        #[arg(long,value_delimiter=',',default_value="tiles")]
+        /// The names of the layers to copy, separated by commas, as listed by the `info` command
+        pub layers: Vec<String>,
+
+    }
+}
+
+impl Task for Reproject {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let target = WorldMap::edit(&self.target_arg.target)?;
+
+        let count = target.write_reprojected_layers(&self.layers, &self.output, self.epsg, progress)?;
+
+        progress.message(|| format!("Wrote {count} features to EPSG:{}.",self.epsg));
+
+        Ok(())
+
+    }
+}
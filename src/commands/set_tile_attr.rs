@@ -0,0 +1,49 @@
+use clap::Args;
+
+use crate::commands::Task;
+use crate::commands::TargetArg;
+use crate::errors::CommandError;
+use crate::subcommand_def;
+use crate::typed_map::fields::IdRef;
+use crate::world_map::WorldMap;
+use crate::progress::ProgressObserver;
+
+subcommand_def!{
+    /// Attaches a custom named value to a tile, such as a danger level or a resource, which is kept in a separate layer so it isn't lost when the generation commands are run again
+    pub struct SetTileAttr {
+
+        #[clap(flatten)]
+        pub target_arg: TargetArg,
+
+        /// The fid of the tile to set the attribute on
+        pub fid: u64,
+
+        /// The name of the attribute. Setting an attribute the tile already has replaces its value.
+        pub name: String,
+
+        /// The value of the attribute, which is stored as text
+        pub value: String,
+
+    }
+}
+
+impl Task for SetTileAttr {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        let mut target = WorldMap::edit(&self.target_arg.target)?;
+
+        let tile_id = IdRef::new(self.fid);
+
+        // make sure the tile exists, so attributes aren't attached to nothing.
+        _ = target.tiles_layer()?.try_feature_by_id(&tile_id)?;
+
+        target.with_transaction(|transaction| {
+            _ = transaction.edit_or_create_tile_attributes_layer()?.set_attribute(&tile_id, &self.name, &self.value)?;
+            Ok(())
+        })?;
+
+        target.save(progress)
+
+    }
+}
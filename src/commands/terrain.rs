@@ -1,6 +1,8 @@
 use std::path::PathBuf;
+use std::path::Path;
 use std::fs::File;
 use std::io::BufReader;
+use std::io::Read;
 
 use clap::Args;
 use clap::Subcommand;
@@ -10,6 +12,7 @@ use serde::Serialize;
 use serde::Deserialize;
 use serde_json::from_reader as from_json_reader;
 use serde_json::to_string_pretty as to_json_string_pretty;
+use serde_json::to_string as to_json_string;
 use schemars::JsonSchema;
 use indexmap::IndexMap;
 
@@ -19,6 +22,7 @@ use crate::errors::CommandError;
 use crate::subcommand_def;
 use crate::algorithms::terrain::TerrainTask;
 use crate::utils::random::random_number_generator;
+use crate::utils::random::TERRAIN_STAGE;
 use crate::progress::ProgressObserver;
 use crate::algorithms::terrain::LoadTerrainTask;
 use crate::utils::random::RandomNth;
@@ -29,7 +33,10 @@ use crate::algorithms::terrain::SampleOceanMaskedLoaded;
 use crate::algorithms::terrain::SampleElevationLoaded;
 use crate::commands::TargetArg;
 use crate::commands::ElevationSourceArg;
+use crate::commands::TileFilterArg;
 use crate::commands::OceanSourceArg;
+use crate::commands::ContinentCountArg;
+use crate::commands::ResampleArg;
 use crate::commands::RandomSeedArg;
 
 
@@ -65,6 +72,18 @@ impl LoadTerrainTask for Recipe {
 }
 
 
+fn read_recipe_set<Reader: Read>(reader: Reader) -> Result<IndexMap<String,Vec<Command>>,CommandError> {
+    let mut tasks: IndexMap<String,Vec<Command>> = from_json_reader(reader).map_err(|e| CommandError::RecipeFileRead(format!("{e}")))?;
+    // Need to reproduce randomness
+    tasks.sort_keys();
+    Ok(tasks)
+}
+
+fn load_recipe_set(source: &Path) -> Result<IndexMap<String,Vec<Command>>,CommandError> {
+    let recipe_data = File::open(source).map_err(|e| CommandError::RecipeFileRead(format!("{e}")))?;
+    read_recipe_set(BufReader::new(recipe_data))
+}
+
 subcommand_def!{
 
     /// Randomly chooses a recipe from a set of named recipes and follows it
@@ -86,11 +105,7 @@ impl LoadTerrainTask for RecipeSet {
 
     fn load_terrain_task<Random: Rng, Progress: ProgressObserver>(self, random: &mut Random, progress: &mut Progress) -> Result<Vec<TerrainTask>,CommandError> {
         progress.start_unknown_endpoint(|| "Loading recipe set.");
-        let recipe_data = File::open(&self.source).map_err(|e| CommandError::RecipeFileRead(format!("{e}")))?;
-        let reader = BufReader::new(recipe_data);
-        let mut tasks: IndexMap<String,Vec<Command>> = from_json_reader(reader).map_err(|e| CommandError::RecipeFileRead(format!("{e}")))?;
-        // Need to reproduce randomness
-        tasks.sort_keys();
+        let mut tasks = load_recipe_set(&self.source)?;
         progress.finish(|| "Recipe set loaded.");
         if tasks.is_empty() {
             Err(CommandError::RecipeFileRead("Recipe set is empty.".to_owned()))
@@ -129,6 +144,7 @@ impl LoadTerrainTask for Clear {
     fn load_terrain_task<Random: Rng, Progress: ProgressObserver>(self, _: &mut Random, _: &mut Progress) -> Result<Vec<TerrainTask>,CommandError> {
         Ok(vec![TerrainTask::Multiply(Multiply { 
             height_filter: None, 
+            filter: TileFilterArg::default(),
             height_factor: 0.0
         })])
     }
@@ -145,6 +161,9 @@ subcommand_def!{
     pub struct Multiply {
         #[arg(long)]
         pub height_filter: Option<ArgRange<i8>>, 
+        #[clap(flatten)]
+        #[serde(flatten)]
+        pub filter: TileFilterArg,
         #[arg(long)]
         pub height_factor: f64 // this doesn't have to be i8 because it's a multiplication, will still work no matter what the scale.
     }
@@ -162,6 +181,32 @@ impl LoadTerrainTask for Multiply {
 }
 
 
+subcommand_def!{
+
+    /// Rescales elevations so they span the elevation limits of the world, or the specified range. Sea level stays fixed, with elevations below and above it scaled separately, so no tile moves into or out of the ocean and groupings are not changed.
+    #[derive(Deserialize,Serialize,JsonSchema)]
+    pub struct Normalize {
+        #[arg(long,allow_negative_numbers=true)]
+        /// The new lowest elevation, if not the minimum elevation of the world
+        pub min_elevation: Option<f64>,
+        #[arg(long,allow_negative_numbers=true)]
+        /// The new highest elevation, if not the maximum elevation of the world
+        pub max_elevation: Option<f64>
+    }
+
+}
+
+
+impl LoadTerrainTask for Normalize {
+
+    fn load_terrain_task<Random: Rng, Progress: ProgressObserver>(self, _: &mut Random, _: &mut Progress) -> Result<Vec<TerrainTask>,CommandError> {
+        Ok(vec![TerrainTask::Normalize(self)])
+    }
+
+
+}
+
+
 subcommand_def!{
 
     /// Marks all tiles below sea level as ocean (SeedOcean and FloodOcean might be better)
@@ -189,6 +234,9 @@ subcommand_def!{
 
         #[arg(long)]
         pub height_filter: Option<ArgRange<i8>>, 
+        #[clap(flatten)]
+        #[serde(flatten)]
+        pub filter: TileFilterArg,
         #[arg(long)]
         pub height_delta: ArgRange<i8>
     }
@@ -338,6 +386,63 @@ impl LoadTerrainTask for Invert {
 }
 
 
+subcommand_def!{
+
+    /// Mirrors elevations and groupings from west to east
+    #[derive(Deserialize,Serialize,JsonSchema)]
+    pub struct FlipHorizontal{}
+    
+}
+
+
+impl LoadTerrainTask for FlipHorizontal {
+
+    fn load_terrain_task<Random: Rng, Progress: ProgressObserver>(self, _: &mut Random, _: &mut Progress) -> Result<Vec<TerrainTask>,CommandError> {
+        Ok(vec![TerrainTask::FlipHorizontal(self)])
+    }
+
+
+}
+
+
+subcommand_def!{
+
+    /// Mirrors elevations and groupings from north to south
+    #[derive(Deserialize,Serialize,JsonSchema)]
+    pub struct FlipVertical{}
+    
+}
+
+
+impl LoadTerrainTask for FlipVertical {
+
+    fn load_terrain_task<Random: Rng, Progress: ProgressObserver>(self, _: &mut Random, _: &mut Progress) -> Result<Vec<TerrainTask>,CommandError> {
+        Ok(vec![TerrainTask::FlipVertical(self)])
+    }
+
+
+}
+
+
+subcommand_def!{
+
+    /// Turns elevations upside down, so the highest elevation of the world becomes the lowest, and vice versa. Groupings are not changed.
+    #[derive(Deserialize,Serialize,JsonSchema)]
+    pub struct InvertElevations{}
+    
+}
+
+
+impl LoadTerrainTask for InvertElevations {
+
+    fn load_terrain_task<Random: Rng, Progress: ProgressObserver>(self, _: &mut Random, _: &mut Progress) -> Result<Vec<TerrainTask>,CommandError> {
+        Ok(vec![TerrainTask::InvertElevations(self)])
+    }
+
+
+}
+
+
 subcommand_def!{
 
     /// Inverts the heights across the entier map
@@ -345,6 +450,9 @@ subcommand_def!{
     pub struct Add {
         #[arg(long)]
         pub height_filter: Option<ArgRange<i8>>, 
+        #[clap(flatten)]
+        #[serde(flatten)]
+        pub filter: TileFilterArg,
         #[arg(long)]
         pub height_delta: i8
     }
@@ -481,7 +589,17 @@ subcommand_def!{
 
         /// The elevation to compare to
         #[arg(long,allow_negative_numbers=true)]
-        pub elevation: f64
+        pub elevation: f64,
+
+        /// Tiles exactly at the elevation become ocean
+        #[arg(long,conflicts_with="coast_is_land")]
+        #[serde(default)]
+        pub coast_is_ocean: bool,
+
+        /// Tiles exactly at the elevation remain land. This is the default.
+        #[arg(long)]
+        #[serde(skip)]
+        pub coast_is_land: bool,
     }
 }
 
@@ -492,7 +610,7 @@ impl LoadTerrainTask for SampleOceanBelow {
         progress.start_unknown_endpoint(|| "Loading ocean raster.");
         let raster = RasterMap::open(&self.ocean_arg.source)?;
         progress.finish(|| "Ocean raster loaded.");
-        Ok(vec![TerrainTask::SampleOceanBelow(SampleOceanBelowLoaded::new(raster,self.elevation))])
+        Ok(vec![TerrainTask::SampleOceanBelow(SampleOceanBelowLoaded::new(raster,self.elevation,self.coast_is_ocean))])
     }
 }
 
@@ -531,6 +649,15 @@ subcommand_def!{
         #[clap(flatten)]
         #[serde(flatten)]
         pub heightmap_arg: ElevationSourceArg,
+
+        #[arg(long="overlay")]
+        #[serde(default)]
+        /// Additional heightmaps to sample over the main one, in order. Wherever one has data, it replaces the elevations from the heightmaps before it.
+        pub overlays: Vec<PathBuf>,
+
+        #[clap(flatten)]
+        #[serde(flatten)]
+        pub resample_arg: ResampleArg,
     }
 }
 
@@ -539,8 +666,9 @@ impl LoadTerrainTask for SampleElevation {
     fn load_terrain_task<Random: Rng, Progress: ProgressObserver>(self, _: &mut Random, progress: &mut Progress) -> Result<Vec<TerrainTask>,CommandError> {
         progress.start_unknown_endpoint(|| "Loading elevation raster.");
         let raster = RasterMap::open(self.heightmap_arg.source)?;
+        let overlays = self.overlays.into_iter().map(RasterMap::open).collect::<Result<Vec<_>,_>>()?;
         progress.finish(|| "Elevation raster loaded.");
-        Ok(vec![TerrainTask::SampleElevation(SampleElevationLoaded::new(raster))])
+        Ok(vec![TerrainTask::SampleElevation(SampleElevationLoaded::new(raster,overlays,self.resample_arg.resample))])
     }
 }
 
@@ -563,8 +691,12 @@ mod command {
         AddStrait(AddStrait),
         Mask(Mask),
         Invert(Invert),
+        FlipHorizontal(FlipHorizontal),
+        FlipVertical(FlipVertical),
+        InvertElevations(InvertElevations),
         Add(Add),
         Multiply(Multiply),
+        Normalize(Normalize),
         Smooth(Smooth),
         Erode(Erode),
         SeedOcean(SeedOcean),
@@ -596,8 +728,12 @@ impl Command {
             Self::AddStrait(params) => params.load_terrain_task(random,progress),
             Self::Mask(params) => params.load_terrain_task(random,progress),
             Self::Invert(params) => params.load_terrain_task(random,progress),
+            Self::FlipHorizontal(params) => params.load_terrain_task(random,progress),
+            Self::FlipVertical(params) => params.load_terrain_task(random,progress),
+            Self::InvertElevations(params) => params.load_terrain_task(random,progress),
             Self::Add(params) => params.load_terrain_task(random,progress),
             Self::Multiply(params) => params.load_terrain_task(random,progress),
+            Self::Normalize(params) => params.load_terrain_task(random,progress),
             Self::Smooth(params) => params.load_terrain_task(random,progress),
             Self::Erode(params) => params.load_terrain_task(random,progress),
             Self::SeedOcean(params) => params.load_terrain_task(random,progress),
@@ -612,6 +748,42 @@ impl Command {
 }
 
 
+subcommand_def!{
+    /// Lists the recipes in a recipe set file, and the tasks in each. This also validates the file, reporting any tasks or parameters that could not be read.
+    pub struct ListTerrainRecipes {
+
+        #[arg(long)]
+        /// JSON file containing a map of recipes
+        pub source: PathBuf,
+
+    }
+}
+
+impl Task for ListTerrainRecipes {
+
+    fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
+
+        progress.start_unknown_endpoint(|| "Loading recipe set.");
+        let recipes = load_recipe_set(&self.source)?;
+        progress.finish(|| "Recipe set loaded.");
+
+        if recipes.is_empty() {
+            progress.warning(|| "Recipe set is empty.");
+        }
+
+        for (name,tasks) in recipes {
+            println!("{name}:");
+            for task in tasks {
+                println!("  {}",to_json_string(&task).map_err(|e| CommandError::TerrainProcessWrite(format!("{e}")))?);
+            }
+        }
+
+        Ok(())
+
+    }
+}
+
+
 subcommand_def!{
     /// Calculates neighbors for tiles
     pub struct Terrain {
@@ -625,6 +797,9 @@ subcommand_def!{
         #[clap(flatten)]
         pub random_seed_arg: RandomSeedArg,
 
+        #[clap(flatten)]
+        pub continent_count_arg: ContinentCountArg,
+
         #[arg(long)]
         /// Instead of processing, display the serialized value for inclusion in a recipe file.
         pub serialize: bool
@@ -636,7 +811,7 @@ impl Task for Terrain {
 
     fn run<Progress: ProgressObserver>(self, progress: &mut Progress) -> Result<(),CommandError> {
 
-        let mut random = random_number_generator(&self.random_seed_arg);
+        let mut random = random_number_generator(&self.random_seed_arg, TERRAIN_STAGE);
 
         let mut target = WorldMap::edit(&self.target_arg.target)?;
 
@@ -644,7 +819,7 @@ impl Task for Terrain {
             println!("{}",self.command.to_json()?);
             Ok(())
         } else {
-            Self::run_default(&mut random, self.command, &mut target, progress)
+            Self::run_default(&mut random, self.command, &self.continent_count_arg, &mut target, progress)
         }
 
 
@@ -652,17 +827,71 @@ impl Task for Terrain {
 }
 
 impl Terrain {
-    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(random: &mut Random, terrain_command: Command, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
+    pub(crate) fn run_default<Random: Rng, Progress: ProgressObserver>(random: &mut Random, terrain_command: Command, continent_count: &ContinentCountArg, target: &mut WorldMap, progress: &mut Progress) -> Result<(), CommandError> {
         target.with_transaction(|transaction| {
 
             progress.announce("Loading terrain processes.");
 
             let processes = terrain_command.load_terrain_task(random, progress)?;
 
-            TerrainTask::process_terrain(&processes,random,transaction,progress)
+            let changes = TerrainTask::process_terrain(&processes,continent_count,random,transaction,progress)?;
+
+            progress.message(|| format!("{} tiles changed elevation, {} tiles changed grouping.",changes.elevation(),changes.grouping()));
+
+            Ok(())
 
         })?;
 
         target.save(progress)
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::read_recipe_set;
+    use super::load_recipe_set;
+
+    #[test]
+    fn test_read_recipe_set() {
+        let recipes = read_recipe_set(r#"{
+            "volcano": [
+                { "task": "AddHill", "count": 1, "height_delta": "90..=100", "x_filter": "44..=56", "y_filter": "40..=60" },
+                { "task": "Multiply", "height_filter": "50..=100", "height_factor": 0.8 }
+            ],
+            "atoll": [
+                { "task": "ClearOcean" }
+            ]
+        }"#.as_bytes()).unwrap();
+
+        // keys are sorted
+        assert_eq!(recipes.keys().collect::<Vec<_>>(),vec!["atoll","volcano"]);
+        assert_eq!(recipes["volcano"].len(),2);
+    }
+
+    #[test]
+    fn test_read_malformed_recipe_set() {
+        let Err(error) = read_recipe_set(r#"{
+            "volcano": [
+                { "task": "AddHil", "count": 1, "height_delta": "90..=100", "x_filter": "44..=56", "y_filter": "40..=60" }
+            ]
+        }"#.as_bytes()) else {
+            panic!("Malformed recipe set should not have been read.")
+        };
+
+        let message = error.to_string();
+        assert!(message.starts_with("Error reading recipe file:"),"{message}");
+        assert!(message.contains("unknown variant `AddHil`"),"{message}");
+        assert!(message.contains("line 3"),"{message}");
+    }
+
+    #[test]
+    fn test_shipped_recipe_set() {
+        let source = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("share").join("terrain_recipes").join("afmg_recipes.json");
+        let recipes = load_recipe_set(&source).unwrap();
+        assert!(recipes.contains_key("continents"));
+    }
+
+}
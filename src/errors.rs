@@ -30,6 +30,7 @@ pub enum CommandError {
     InvalidValueForSegmentTo(String,String),
     InvalidBiomeMatrixValue(String,String),
     InvalidValueForLakeType(String,String),
+    InvalidValueForRouteType(String,String),
     InvalidValueForGroupingType(String,String),
     InvalidValueForCultureType(String,String),
     MissingGlacierBiome,
@@ -37,9 +38,11 @@ pub enum CommandError {
     MissingOceanBiome,
     MissingBiomeMatrixSlot(usize,usize),
     UnknownLookup(&'static str,String),
+    UnknownLayer(String),
     UnknownNamer(String),
     DuplicateBiomeMatrixSlot(usize,usize),
     DuplicateGlacierBiome,
+    DuplicateBarrenBiome,
     DuplicateWetlandBiome,
     DuplicateOceanBiome,
     NamerSourceRead(String),
@@ -57,7 +60,23 @@ pub enum CommandError {
     InvalidPropertyValue(String,String,String),
     PropertyNotSet(String),
     InvalidRangeArgument(String,String),
+    InvalidTileRegion(String),
+    InvalidLatitudeSpacing(f64),
+    FibonacciRequiresSphere,
+    ColorRampRead(String),
+    CultureImportRead(String),
+    CultureAssignedToWater(String,IdRef),
+    InvalidPrecipitationFactor(f64),
+    ExtremePrecipitationFactor(f64),
+    SkippedStageRequired(String,String),
+    OutputLayerSkipped(String,String),
+    OutputLayerNotGenerated(String),
+    RetryRequiresOverwriteAll,
+    InconsistentNeighbors(usize),
+    ConfigRead(String),
+    InvalidConfigValue(String,String),
     CantFindTileNearPoint,
+    CantCalculateCentroid,
     EmptyNamerInput(String),
     TilePreferenceMultiplyMissingData,
     TilePreferenceDivideMissingData,
@@ -115,6 +134,7 @@ impl Display for CommandError {
             Self::InvalidValueForSegmentTo(a,message) => write!(f,"Invalid value ('{a}') found for river to_type field. ('{message}')"),
             Self::InvalidBiomeMatrixValue(a,message) => write!(f,"Invalid value ('{a}') for biome matrix field. ('{message}')"),
             Self::InvalidValueForLakeType(a,message) => write!(f,"Invalid value ('{a}') for lake type field. ('{message}')"),
+            Self::InvalidValueForRouteType(a,message) => write!(f,"Invalid value ('{a}') for route type field. ('{message}')"),
             Self::InvalidValueForGroupingType(a,message) => write!(f,"Invalid value ('{a}') for grouping type field. ('{message}')"),
             Self::InvalidValueForCultureType(a,message) => write!(f,"Invalid value ('{a}') for culture type field. ('{message}')"),
             Self::InvalidValueForColor(a,message) => write!(f,"Invalid value ('{a}') for color field. ('{message}')"),
@@ -124,10 +144,12 @@ impl Display for CommandError {
             Self::MissingOceanBiome => write!(f,"Ocean biome is not specified as criteria in biomes table."),
             Self::MissingBiomeMatrixSlot(a, b) => write!(f,"Matrix criteria at ({a},{b}) not specified in biome table."),
             Self::DuplicateGlacierBiome => write!(f,"Glacier biome is specified twice in biomes table."),
+            Self::DuplicateBarrenBiome => write!(f,"Barren biome is specified twice in biomes table."),
             Self::DuplicateWetlandBiome => write!(f,"Wetland biome is specified twice in biomes table."),
             Self::DuplicateOceanBiome => write!(f,"Ocean biome is specified twice in biomes table."),
             Self::DuplicateBiomeMatrixSlot(a, b) => write!(f,"Matrix criteria at ({a},{b}) specified twice in biome table."),
             Self::UnknownLookup(a,b) => write!(f,"Layer '{a}' has no feature with the name '{b}'."),
+            Self::UnknownLayer(a) => write!(f,"The world map has no layer named '{a}'."),
             Self::UnknownNamer(a) => write!(f,"Namer '{a}' not found in supplied name generators."),
             Self::NamerSourceRead(a) => write!(f,"Error reading namer source: {a}"),
             Self::NamerSourceWrite(a) => write!(f,"Error writing namer source: {a}"),
@@ -153,7 +175,23 @@ impl Display for CommandError {
             Self::InvalidPropertyValue(a,b,message) => write!(f,"Invalid value for property {a} :'{b}'. ('{message}')"),
             Self::PropertyNotSet(a) => write!(f,"Property {a} has not been set."),
             Self::InvalidRangeArgument(a,message) => write!(f,"Invalid range expression '{a}' in terrain processing parameters. ('{message}')"),
+            Self::InvalidTileRegion(message) => write!(f,"Invalid tile region: {message}"),
+            Self::InvalidLatitudeSpacing(a) => write!(f,"Latitude spacing must be a positive number of degrees, found {a}."),
+            Self::FibonacciRequiresSphere => write!(f,"The fibonacci point distribution spreads points evenly across a globe, so it requires '--world-shape sphere'."),
+            Self::ColorRampRead(a) => write!(f,"Error reading color ramp: {a}"),
+            Self::CultureImportRead(a) => write!(f,"Error reading culture assignments: {a}"),
+            Self::CultureAssignedToWater(culture, id) => write!(f,"Culture '{culture}' is assigned to tile '{id}', which is water."),
+            Self::InvalidPrecipitationFactor(a) => write!(f,"Precipitation factor ({a}) must be a number no less than 0."),
+            Self::ExtremePrecipitationFactor(a) => write!(f,"Precipitation factor ({a}) is greater than 5. Use --allow-extreme-precipitation if this was intended."),
+            Self::SkippedStageRequired(a, b) => write!(f,"The {a} stage can't be skipped, because the {b} stage needs it."),
+            Self::OutputLayerSkipped(a, b) => write!(f,"The {a} layer can't be kept, because the {b} stage which generates it is skipped."),
+            Self::OutputLayerNotGenerated(a) => write!(f,"The {a} layer can't be kept, because big-bang doesn't generate it."),
+            Self::RetryRequiresOverwriteAll => write!(f,"Retrying generation requires '--overwrite-all', since a failed attempt leaves its layers behind."),
+            Self::InconsistentNeighbors(count) => write!(f,"Found {count} inconsistent tile neighbors."),
+            Self::ConfigRead(message) => write!(f,"Error reading config file: {message}"),
+            Self::InvalidConfigValue(name,value) => write!(f,"The config file value {value} is not valid for option '{name}'."),
             Self::CantFindTileNearPoint => write!(f,"No tile was found close to a supplied point, even at max expansion."),
+            Self::CantCalculateCentroid => write!(f,"Can't calculate the centroid of an empty polygon."),
             Self::EmptyNamerInput(a) => write!(f,"Namer '{a}' data did not contain any words."),
             Self::TilePreferenceMultiplyMissingData => write!(f,"Tile preference multiplication in culture set needs at least one term"),
             Self::TilePreferenceDivideMissingData => write!(f,"Tile preference division in culture set needs at least one term"),
@@ -205,6 +243,29 @@ impl Display for CommandError {
     }
 }
 
+impl CommandError {
+
+    /// True for errors that come from the shape of the generated data, such as geometry operations on unusual tiles, rather than from the user's input or a bug. A different seed may avoid these.
+    pub(crate) const fn is_data_dependent(&self) -> bool {
+        matches!(self,
+            Self::VoronoiExpectsTriangles(_) |
+            Self::PointFinderOutOfBounds(..) |
+            Self::CantFindMiddlePoint(..) |
+            Self::CantFindMiddlePointOnEdge(..) |
+            Self::CantFindTileNearPoint |
+            Self::CantCalculateCentroid |
+            Self::GdalUnionFailed |
+            Self::GdalIntersectionFailed |
+            Self::GdalDifferenceFailed |
+            Self::LakeDissolveMadeAMultiPolygon |
+            Self::CantConvertMultiPolygonToPolygon |
+            Self::EmptyLinearRing |
+            Self::UnclosedLinearRing |
+            Self::InvalidTileEdge(..)
+        )
+    }
+}
+
 impl From<GdalError> for CommandError {
 
     fn from(value: GdalError) -> Self {
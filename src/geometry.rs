@@ -3,7 +3,9 @@ use gdal::vector::OGRwkbGeometryType;
 
 use crate::errors::CommandError;
 use geo::ChamberlainDuquetteArea;
+use geo::Centroid;
 use crate::algorithms::beziers::bezierify_points;
+use crate::algorithms::beziers::densify_points;
 use crate::utils::coordinates::Coordinates;
 use crate::utils::extent::Extent;
 use crate::utils::world_shape::WorldShape;
@@ -442,6 +444,13 @@ impl Polygon {
         }
     }
 
+    /// The planar center of mass of the polygon. For tiles, this is not the same as the site, especially where tiles have been clipped at the edge of the map.
+    pub(crate) fn centroid(&self) -> Result<Coordinates,CommandError> {
+        // the gdal crate doesn't expose OGR_G_Centroid, so go through geo instead.
+        let centroid = self.to_geo_type()?.centroid().ok_or(CommandError::CantCalculateCentroid)?;
+        Ok(Coordinates::try_from((centroid.x(),centroid.y()))?)
+    }
+
     // Yes, this should be defined for MultiPolygon, but it's not used for that anywhere. If I ever need to define it, it will be exactly like this.
     pub(crate) fn spherical_area(&self) -> Result<f64,CommandError> {
         Ok(self.to_geo_type()?.chamberlain_duquette_area_in_degrees())
@@ -470,6 +479,21 @@ impl Polygon {
         self.inner.make_valid(&validate_options_structure()?)?.try_into()
     }
 
+    /// Inserts extra vertices along each edge of the rings, so that a later bezierify follows the original shape more closely.
+    pub(crate) fn densify(self, count: usize) -> Result<Self,CommandError> {
+        let mut rings = Vec::new();
+        for ring in self {
+            let mut points = Vec::new();
+            for point in ring? {
+                points.push(point.try_into()?);
+            }
+
+            let line = densify_points(&points, count);
+            rings.push(LinearRing::from_vertices(line.iter().map(Coordinates::to_tuple))?);
+        }
+        Self::from_rings(rings)
+    }
+
     // NOTE: Theres a small chance that bezierifying will create invalid geometries. These are automatically
     // made valid, which could turn them into a multi-polygon.
     pub(crate) fn bezierify(self, scale: f64) -> Result<VariantArealGeometry,CommandError> {
@@ -20,9 +20,15 @@ pub(crate) mod algorithms;
 #[cfg(test)] mod test;
 
 use errors::ProgramError;
+use errors::CommandError;
 
 use commands::Cosmopoeia;
+use commands::config::expand_config_args;
+use commands::config::resolved_config;
+use commands::config::warn_ignored_options;
 use progress::ConsoleProgressBar;
+use progress::TimingObserver;
+use progress::DiagnosticsObserver;
 use std::process;
 use std::env;
 use std::ffi::OsString;
@@ -35,9 +41,33 @@ where
     Arg: Clone + Into<OsString>, 
     Args: IntoIterator<Item = Arg> 
 {
-    let mut progress = ConsoleProgressBar::new();
-    let command = Cosmopoeia::try_parse_from(args)?;
-    command.run(&mut progress)?;
+    let (args,ignored_options) = expand_config_args(args)?;
+    let command = Cosmopoeia::try_parse_from(&args)?;
+    if command.print_config {
+        warn_ignored_options(&ignored_options, &ConsoleProgressBar::new());
+        let config = serde_json::to_string_pretty(&resolved_config(args)?).map_err(|e| CommandError::SerdeJSONError(format!("{e}")))?;
+        println!("{config}");
+        return Ok(())
+    }
+    if command.timing {
+        let mut progress = DiagnosticsObserver::new(TimingObserver::new(ConsoleProgressBar::new()));
+        warn_ignored_options(&ignored_options, &progress);
+        let result = command.run(&mut progress);
+        // print the timing and warnings even if the command failed, they might help find out why.
+        eprintln!("{}",progress.inner_mut().report());
+        if let Some(report) = progress.report() {
+            eprintln!("{report}");
+        }
+        result?;
+    } else {
+        let mut progress = DiagnosticsObserver::new(ConsoleProgressBar::new());
+        warn_ignored_options(&ignored_options, &progress);
+        let result = command.run(&mut progress);
+        if let Some(report) = progress.report() {
+            eprintln!("{report}");
+        }
+        result?;
+    }
     Ok(())
 }
 
@@ -1,6 +1,11 @@
 use core::time::Duration;
+use core::cell::RefCell;
+#[cfg(test)] use core::cell::Ref;
+use std::time::Instant;
 use core::iter::Enumerate;
 use std::collections::VecDeque;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
 
 use indicatif::ProgressBar;
 use indicatif::ProgressStyle;
@@ -30,6 +35,41 @@ pub(crate) trait ProgressObserver {
 
     fn announce(&self, message: &str);    
 
+    /// Returns a handle which can report progress on the current bar from several threads at once. Its count starts at zero.
+    fn sync_progress(&self) -> SyncProgress;
+
+}
+
+/// Reports progress from parallel loops, where the observer itself can't be shared because starting and finishing need a mutable reference. Every thread shares one counter, and the bar moves forward by one for each step completed, regardless of which thread completed it.
+pub(crate) struct SyncProgress {
+    // indicatif's bars are already shared handles, with their own locking.
+    bar: Option<ProgressBar>,
+    count: AtomicUsize
+}
+
+impl SyncProgress {
+
+    const fn new(bar: Option<ProgressBar>) -> Self {
+        Self {
+            bar,
+            count: AtomicUsize::new(0)
+        }
+    }
+
+    /// Records that one more step is complete.
+    pub(crate) fn increment(&self) {
+        _ = self.count.fetch_add(1, Ordering::Relaxed);
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+
+    /// The number of steps completed so far, from all threads.
+    #[cfg(test)]
+    pub(crate) fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
 }
 
 
@@ -63,6 +103,10 @@ impl ProgressObserver for () {
 
     fn announce(&self, _: &str) {
     }
+
+    fn sync_progress(&self) -> SyncProgress {
+        SyncProgress::new(None)
+    }
 }
 
 
@@ -203,6 +247,265 @@ impl ProgressObserver for ConsoleProgressBar {
         }
     }
 
+    fn sync_progress(&self) -> SyncProgress {
+        if let Some(bar) = &self.bar {
+            bar.set_position(0);
+        }
+        SyncProgress::new(self.bar.clone())
+    }
+
+}
+
+struct PhaseTiming {
+    name: String,
+    started: Instant,
+    ended: Option<Instant>,
+    slowest_step: Option<(String,Duration)>
+}
+
+impl PhaseTiming {
+
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            started: Instant::now(),
+            ended: None,
+            slowest_step: None
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        self.ended.unwrap_or_else(Instant::now).duration_since(self.started)
+    }
+
+    fn record_step(&mut self, name: String, duration: Duration) {
+        if self.slowest_step.as_ref().is_none_or(|(_,slowest)| duration > *slowest) {
+            self.slowest_step = Some((name,duration))
+        }
+    }
+
+}
+
+/// Wraps another progress observer, recording the wall-clock time spent between each announcement and each start and finish. Used for the `--timing` option.
+pub(crate) struct TimingObserver<Inner: ProgressObserver> {
+    inner: Inner,
+    // announce only gets a shared reference, so the phases need interior mutability.
+    phases: RefCell<Vec<PhaseTiming>>,
+    step: Option<(String,Instant)>
+}
+
+impl<Inner: ProgressObserver> TimingObserver<Inner> {
+
+    pub(crate) fn new(inner: Inner) -> Self {
+        Self {
+            inner,
+            // the time before anything is announced, such as loading the inputs, still needs to be accounted for.
+            phases: RefCell::new(vec![PhaseTiming::new("(unannounced)".to_owned())]),
+            step: None
+        }
+    }
+
+    fn start_step(&mut self, message: &str) {
+        // progress bars are sometimes restarted without finishing, so that counts as the end of the previous step.
+        self.end_step();
+        self.step = Some((message.to_owned(),Instant::now()));
+    }
+
+    fn end_step(&mut self) {
+        if let Some((name,started)) = self.step.take() {
+            if let Some(phase) = self.phases.borrow_mut().last_mut() {
+                phase.record_step(name, started.elapsed())
+            }
+        }
+    }
+
+    /// Returns a table of the phases which were announced, how long they took, and the slowest step in each.
+    pub(crate) fn report(&mut self) -> String {
+        self.end_step();
+        let phases = self.phases.borrow();
+        let mut total = Duration::ZERO;
+        let mut lines = vec!["Timing:".to_owned()];
+        for phase in phases.iter() {
+            let duration = phase.duration();
+            total += duration;
+            let slowest = phase.slowest_step.as_ref().map(|(name,step_duration)| format!(" (slowest step: {name} {:.3}s)",step_duration.as_secs_f64())).unwrap_or_default();
+            lines.push(format!("{:>12.3}s  {}{slowest}",duration.as_secs_f64(),phase.name));
+        }
+        lines.push(format!("{:>12.3}s  Total",total.as_secs_f64()));
+        lines.join("\n")
+    }
+
+}
+
+impl<Inner: ProgressObserver> ProgressObserver for TimingObserver<Inner> {
+
+    fn start_known_endpoint<Message: AsRef<str>, Callback: FnOnce() -> (Message,usize)>(&mut self, callback: Callback) {
+        let (message,step_count) = callback();
+        self.start_step(message.as_ref());
+        self.inner.start_known_endpoint(|| (message,step_count))
+    }
+
+    fn start_unknown_endpoint<Message: AsRef<str>, Callback: FnOnce() -> Message>(&mut self, callback: Callback) {
+        let message = callback();
+        self.start_step(message.as_ref());
+        self.inner.start_unknown_endpoint(|| message)
+    }
+
+    fn start<Message: AsRef<str>, Callback: FnOnce() -> (Message,Option<usize>)>(&mut self, callback: Callback) {
+        let (message,step_count) = callback();
+        self.start_step(message.as_ref());
+        self.inner.start(|| (message,step_count))
+    }
+
+    fn update<Callback: FnOnce() -> usize>(&self, callback: Callback) {
+        self.inner.update(callback)
+    }
+
+    fn update_step_length<Callback: FnOnce() -> usize>(&self, callback: Callback) {
+        self.inner.update_step_length(callback)
+    }
+
+    fn message<Message: AsRef<str>, Callback: FnOnce() -> Message>(&self, callback: Callback) {
+        self.inner.message(callback)
+    }
+
+    fn warning<Message: AsRef<str>, Callback: FnOnce() -> Message>(&self, callback: Callback) {
+        self.inner.warning(callback)
+    }
+
+    fn finish<Message: AsRef<str>, Callback: FnOnce() -> Message>(&mut self, callback: Callback) {
+        self.end_step();
+        self.inner.finish(callback)
+    }
+
+    fn announce(&self, message: &str) {
+        {
+            let mut phases = self.phases.borrow_mut();
+            if let Some(phase) = phases.last_mut() {
+                phase.ended = Some(Instant::now());
+            }
+            phases.push(PhaseTiming::new(message.to_owned()));
+        }
+        self.inner.announce(message)
+    }
+
+    fn sync_progress(&self) -> SyncProgress {
+        self.inner.sync_progress()
+    }
+
+}
+
+/// A warning collected by the `DiagnosticsObserver`, along with the phase that was announced when it occurred.
+pub(crate) struct Diagnostic {
+    phase: Option<String>,
+    message: String
+}
+
+impl Diagnostic {
+
+    pub(crate) fn phase(&self) -> Option<&str> {
+        self.phase.as_deref()
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Wraps another progress observer, collecting every warning so they can be summarized once the command is done, where they won't get lost among the progress bars.
+pub(crate) struct DiagnosticsObserver<Inner: ProgressObserver> {
+    inner: Inner,
+    // warning and announce only get a shared reference, so these need interior mutability.
+    phase: RefCell<Option<String>>,
+    warnings: RefCell<Vec<Diagnostic>>
+}
+
+impl<Inner: ProgressObserver> DiagnosticsObserver<Inner> {
+
+    pub(crate) const fn new(inner: Inner) -> Self {
+        Self {
+            inner,
+            phase: RefCell::new(None),
+            warnings: RefCell::new(Vec::new())
+        }
+    }
+
+    pub(crate) const fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.inner
+    }
+
+    #[cfg(test)]
+    pub(crate) fn warnings(&self) -> Ref<'_, Vec<Diagnostic>> {
+        self.warnings.borrow()
+    }
+
+    /// Returns a list of the warnings which occurred, or None if there weren't any.
+    pub(crate) fn report(&self) -> Option<String> {
+        let warnings = self.warnings.borrow();
+        if warnings.is_empty() {
+            None
+        } else {
+            let mut lines = vec![format!("{} warning(s):",warnings.len())];
+            for warning in warnings.iter() {
+                match warning.phase() {
+                    Some(phase) => lines.push(format!("  [{phase}] {}",warning.message())),
+                    None => lines.push(format!("  {}",warning.message()))
+                }
+            }
+            Some(lines.join("\n"))
+        }
+    }
+
+}
+
+impl<Inner: ProgressObserver> ProgressObserver for DiagnosticsObserver<Inner> {
+
+    fn start_known_endpoint<Message: AsRef<str>, Callback: FnOnce() -> (Message,usize)>(&mut self, callback: Callback) {
+        self.inner.start_known_endpoint(callback)
+    }
+
+    fn start_unknown_endpoint<Message: AsRef<str>, Callback: FnOnce() -> Message>(&mut self, callback: Callback) {
+        self.inner.start_unknown_endpoint(callback)
+    }
+
+    fn start<Message: AsRef<str>, Callback: FnOnce() -> (Message,Option<usize>)>(&mut self, callback: Callback) {
+        self.inner.start(callback)
+    }
+
+    fn update<Callback: FnOnce() -> usize>(&self, callback: Callback) {
+        self.inner.update(callback)
+    }
+
+    fn update_step_length<Callback: FnOnce() -> usize>(&self, callback: Callback) {
+        self.inner.update_step_length(callback)
+    }
+
+    fn message<Message: AsRef<str>, Callback: FnOnce() -> Message>(&self, callback: Callback) {
+        self.inner.message(callback)
+    }
+
+    fn warning<Message: AsRef<str>, Callback: FnOnce() -> Message>(&self, callback: Callback) {
+        let message = callback().as_ref().to_owned();
+        self.warnings.borrow_mut().push(Diagnostic {
+            phase: self.phase.borrow().clone(),
+            message: message.clone()
+        });
+        self.inner.warning(|| message)
+    }
+
+    fn finish<Message: AsRef<str>, Callback: FnOnce() -> Message>(&mut self, callback: Callback) {
+        self.inner.finish(callback)
+    }
+
+    fn announce(&self, message: &str) {
+        _ = self.phase.borrow_mut().replace(message.to_owned());
+        self.inner.announce(message)
+    }
+
+    fn sync_progress(&self) -> SyncProgress {
+        self.inner.sync_progress()
+    }
+
 }
 
 pub(crate) struct IteratorWatcher<'progress,Message: AsRef<str>, Progress: ProgressObserver, IteratorType> {
@@ -418,3 +721,101 @@ impl<ItemType: Hash + Eq, PriorityType: Ord> WatchablePriorityQueue<ItemType,Pri
 }
 
 
+
+
+#[cfg(test)]
+mod test {
+
+    use std::thread;
+
+    use indicatif::ProgressBar;
+
+    use super::ProgressObserver;
+    use super::TimingObserver;
+    use super::DiagnosticsObserver;
+    use super::ConsoleProgressBar;
+
+    #[test]
+    fn test_timing_report() {
+        let mut progress = TimingObserver::new(());
+        progress.start_unknown_endpoint(|| "Loading.");
+        progress.finish(|| "Loaded.");
+        progress.announce("Calculating water flow");
+        progress.start_known_endpoint(|| ("Calculating flow.",10));
+        progress.finish(|| "Flow calculated.");
+        progress.announce("Generating rivers");
+        progress.start_unknown_endpoint(|| "Finding segments.");
+        let report = progress.report();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(),5);
+        assert!(lines[1].contains("(unannounced) (slowest step: Loading."));
+        assert!(lines[2].contains("Calculating water flow (slowest step: Calculating flow."));
+        assert!(lines[3].contains("Generating rivers (slowest step: Finding segments."));
+        assert!(lines[4].ends_with("Total"));
+    }
+
+    #[test]
+    fn test_timing_before_announcement() {
+        // time spent before the first announcement is reported, even if no steps were started.
+        let mut progress = TimingObserver::new(());
+        progress.announce("Calculating water flow");
+        let report = progress.report();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(),4);
+        assert!(lines[1].ends_with("(unannounced)"));
+        assert!(lines[2].ends_with("Calculating water flow"));
+    }
+
+    #[test]
+    fn test_diagnostics_collected() {
+        let progress = DiagnosticsObserver::new(());
+        assert!(progress.report().is_none());
+
+        progress.warning(|| "Before anything was announced.");
+        progress.announce("Sampling elevations");
+        progress.warning(|| format!("Tile {} was skipped.",42));
+
+        let warnings = progress.warnings();
+        assert_eq!(warnings.len(),2);
+        assert_eq!(warnings[0].phase(),None);
+        assert_eq!(warnings[1].phase(),Some("Sampling elevations"));
+        assert!(warnings[1].message().contains("42"));
+        drop(warnings);
+
+        let report = progress.report().expect("There should be a report.");
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines,vec![
+            "2 warning(s):",
+            "  Before anything was announced.",
+            "  [Sampling elevations] Tile 42 was skipped."
+        ]);
+    }
+
+    #[test]
+    fn test_sync_progress_from_threads() {
+        let progress = ConsoleProgressBar {
+            bar: Some(ProgressBar::hidden())
+        };
+        progress.update_step_length(|| 1000);
+
+        let sync = progress.sync_progress();
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                _ = scope.spawn(|| {
+                    for _ in 0..250 {
+                        sync.increment();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(sync.count(),1000);
+        assert_eq!(progress.bar.as_ref().map(ProgressBar::position),Some(1000));
+
+        // the observers which don't show anything still count.
+        let quiet = ().sync_progress();
+        quiet.increment();
+        assert_eq!(quiet.count(),1);
+    }
+
+}
@@ -3,6 +3,10 @@ use std::path::Path;
 use gdal::Dataset;
 use gdal::raster::Buffer;
 use gdal::raster::GdalType;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde::Deserialize;
+use schemars::JsonSchema;
 
 use crate::errors::CommandError;
 use crate::utils::extent::Extent;
@@ -73,26 +77,134 @@ impl<DataType: GdalType> RasterBandBuffer<DataType> {
 
     pub(crate) fn get_value(&self, x: f64, y: f64) -> Option<&DataType> {
         if y.is_sign_positive() && x.is_sign_positive() {
-            let idx = ((y.floor() as usize) * self.width) + (x.floor() as usize);
-            let data = self.buffer.data();
-            if idx < data.len() {
-                Some(&data[idx])
-            } else {
-                None
-            }
+            // checking the column separately, otherwise points east of the raster would wrap around to the next row.
+            self.get_pixel(x.floor() as usize, y.floor() as usize)
         } else {
             None
         }
 
     }
 
-    pub(crate) const fn no_data_value(&self) -> &Option<f64> {
-        &self.no_data
+    /// Returns the value at the specified pixel column and row, or None if that pixel is outside of the buffer.
+    pub(crate) fn get_pixel(&self, column: usize, row: usize) -> Option<&DataType> {
+        let (columns,rows) = self.buffer.shape();
+        if (column < columns) && (row < rows) {
+            self.buffer.data().get((row * self.width) + column)
+        } else {
+            None
+        }
     }
-    
+
 } 
 
 
+#[derive(Clone,Copy,Default,Deserialize,Serialize,ValueEnum,JsonSchema)]
+/// How to choose a value from the raster pixels around a sampled point.
+pub enum ResampleMethod {
+    #[default]
+    /// Use the value of the pixel containing the point
+    Nearest,
+    /// Interpolate between the centers of the four pixels surrounding the point, for smoother results on coarse rasters
+    Bilinear
+}
+
+/// A raster band which has been read into memory along with the bounds needed to sample it at geographic coordinates.
+pub(crate) struct RasterSampler {
+    bounds: RasterBounds,
+    band: RasterBandBuffer<f64>
+}
+
+impl RasterSampler {
+
+    #[cfg(test)]
+    /// Creates a sampler with pixels of the specified size in degrees, with the southwest corner at the specified coordinates. Rows of data are listed from north to south.
+    pub(crate) fn new(west: f64, south: f64, pixel_size: f64, width: usize, height: usize, data: Vec<f64>, no_data: Option<f64>) -> Self {
+        Self {
+            bounds: RasterBounds {
+                coord_min_x: west,
+                transform_x_factor: pixel_size,
+                coord_min_y: south,
+                transform_y_factor: pixel_size,
+                pixel_width: width,
+                pixel_height: height,
+            },
+            band: RasterBandBuffer {
+                width,
+                buffer: Buffer::new((width,height), data),
+                no_data
+            }
+        }
+    }
+
+    pub(crate) fn is_no_data(&self, value: f64) -> bool {
+        match self.band.no_data {
+            Some(no_data_value) if no_data_value.is_nan() => value.is_nan(),
+            Some(no_data_value) => (value - no_data_value).abs() < f64::EPSILON,
+            None => false,
+        }
+    }
+
+    /// Returns the value of the pixels under the coordinates, or None if the coordinates are outside of the raster. The value may be the no-data value.
+    pub(crate) fn sample(&self, lon: f64, lat: f64, method: ResampleMethod) -> Option<f64> {
+        let (x,y) = self.bounds.coords_to_pixels(lon, lat);
+        let nearest = self.band.get_value(x, y).copied()?;
+        match method {
+            ResampleMethod::Nearest => Some(nearest),
+            ResampleMethod::Bilinear => Some(self.interpolate(x, y).unwrap_or(nearest)),
+        }
+    }
+
+    /// Interpolates between the four pixel centers surrounding the pixel coordinates. Pixels past the edge of the raster are treated as copies of the edge pixels. Returns None if any of the pixels have no data.
+    fn interpolate(&self, x: f64, y: f64) -> Option<f64> {
+        let (columns,rows) = self.band.buffer.shape();
+        // pixel centers are at the half-pixel, so shift so they land on whole numbers.
+        let x = (x - 0.5).clamp(0.0,(columns - 1) as f64);
+        let y = (y - 0.5).clamp(0.0,(rows - 1) as f64);
+        let west = x.floor() as usize;
+        let north = y.floor() as usize;
+        let east = (west + 1).min(columns - 1);
+        let south = (north + 1).min(rows - 1);
+        let x_weight = x - west as f64;
+        let y_weight = y - north as f64;
+
+        let pixel = |column,row| self.band.get_pixel(column, row).copied().filter(|value| !self.is_no_data(*value));
+        let north_west = pixel(west,north)?;
+        let north_east = pixel(east,north)?;
+        let south_west = pixel(west,south)?;
+        let south_east = pixel(east,south)?;
+
+        let north_value = (north_east - north_west).mul_add(x_weight, north_west);
+        let south_value = (south_east - south_west).mul_add(x_weight, south_west);
+        Some((south_value - north_value).mul_add(y_weight, north_value))
+    }
+
+    /// Like `sample`, but also returns None if the pixel has no data.
+    pub(crate) fn sample_data(&self, lon: f64, lat: f64, method: ResampleMethod) -> Option<f64> {
+        self.sample(lon, lat, method).filter(|value| !self.is_no_data(*value))
+    }
+
+    /// Returns true if the pixel under the coordinates has data.
+    pub(crate) fn has_data(&self, lon: f64, lat: f64) -> bool {
+        self.sample_data(lon, lat, ResampleMethod::Nearest).is_some()
+    }
+
+    /// Returns true if the pixel under the coordinates has data, and that value is less than the specified elevation. If `coast_is_ocean` is true, a value equal to the elevation also counts.
+    pub(crate) fn is_below(&self, lon: f64, lat: f64, elevation: f64, coast_is_ocean: bool) -> bool {
+        self.sample_data(lon, lat, ResampleMethod::Nearest).is_some_and(|value| if coast_is_ocean {
+            value <= elevation
+        } else {
+            value < elevation
+        })
+    }
+
+    /// Samples the base raster at the coordinates, unless one of the overlays has data there. Later overlays take precedence over earlier ones.
+    pub(crate) fn sample_with_overlays(base: &Self, overlays: &[Self], lon: f64, lat: f64, method: ResampleMethod) -> Option<f64> {
+        overlays.iter().rev().find_map(|overlay| overlay.sample_data(lon, lat, method)).or_else(|| base.sample(lon, lat, method))
+    }
+
+}
+
+
 
 pub(crate) struct RasterMap {
     dataset: Dataset
@@ -127,6 +239,13 @@ impl RasterMap {
         })
     }
 
+    pub(crate) fn read_sampler(&self, index: usize) -> Result<RasterSampler,CommandError> {
+        Ok(RasterSampler {
+            bounds: self.bounds()?,
+            band: self.read_band(index)?
+        })
+    }
+
     pub(crate) fn bounds(&self) -> Result<RasterBounds,CommandError> {
         let [coord_left,transform_x_factor,_,coord_top,_,transform_y_factor] = self.dataset.geo_transform()?;
         let (pixel_width,pixel_height) = self.dataset.raster_size();
@@ -160,3 +279,150 @@ impl RasterMap {
 
 }
 
+
+#[cfg(test)]
+mod test {
+    use super::RasterSampler;
+    use super::ResampleMethod;
+
+    /// Creates a sampler with one pixel per degree, with the southwest corner at the specified coordinates. Rows of data are listed from north to south.
+    fn create_sampler(west: f64, south: f64, width: usize, height: usize, data: Vec<f64>, no_data: Option<f64>) -> RasterSampler {
+        create_sampler_with_resolution(west, south, 1.0, width, height, data, no_data)
+    }
+
+    /// Creates a sampler with pixels of the specified size in degrees.
+    fn create_sampler_with_resolution(west: f64, south: f64, pixel_size: f64, width: usize, height: usize, data: Vec<f64>, no_data: Option<f64>) -> RasterSampler {
+        RasterSampler::new(west, south, pixel_size, width, height, data, no_data)
+    }
+
+    #[test]
+    fn test_sample_with_overlays() {
+        let base = create_sampler(0.0, 0.0, 4, 4, vec![
+            1.0, 1.0, 1.0, 1.0,
+            1.0, 1.0, 1.0, 1.0,
+            1.0, 1.0, 1.0, 1.0,
+            1.0, 1.0, 1.0, 1.0,
+        ], None);
+        // the patch only covers the northeast quarter, and has one missing pixel
+        let patch = create_sampler(2.0, 2.0, 2, 2, vec![
+            5.0, -9999.0,
+            5.0, 5.0,
+        ], Some(-9999.0));
+
+        let overlays = [patch];
+        let sample = |lon,lat| RasterSampler::sample_with_overlays(&base, &overlays, lon, lat, ResampleMethod::Nearest);
+
+        // outside the patch
+        assert_eq!(sample(0.5,0.5),Some(1.0));
+        assert_eq!(sample(3.5,1.5),Some(1.0));
+        // inside the patch
+        assert_eq!(sample(2.5,3.5),Some(5.0));
+        assert_eq!(sample(3.5,2.5),Some(5.0));
+        // the no-data pixel in the patch falls back to the base
+        assert_eq!(sample(3.5,3.5),Some(1.0));
+        // off the map entirely
+        assert_eq!(sample(-0.5,0.5),None);
+    }
+
+    #[test]
+    fn test_later_overlays_take_precedence() {
+        let base = create_sampler(0.0, 0.0, 2, 1, vec![1.0, 1.0], None);
+        let first = create_sampler(0.0, 0.0, 2, 1, vec![2.0, 2.0], None);
+        let second = create_sampler(1.0, 0.0, 1, 1, vec![3.0], None);
+        let overlays = [first,second];
+
+        assert_eq!(RasterSampler::sample_with_overlays(&base, &overlays, 0.5, 0.5, ResampleMethod::Nearest),Some(2.0));
+        assert_eq!(RasterSampler::sample_with_overlays(&base, &overlays, 1.5, 0.5, ResampleMethod::Nearest),Some(3.0));
+    }
+
+    #[test]
+    fn test_bilinear_sampling() {
+        // increases by 10 with each column to the east
+        let gradient = create_sampler(0.0, 0.0, 3, 2, vec![
+            0.0, 10.0, 20.0,
+            0.0, 10.0, 20.0,
+        ], None);
+
+        // pixel centers give the pixel values either way
+        assert_eq!(gradient.sample(0.5, 0.5, ResampleMethod::Bilinear),Some(0.0));
+        assert_eq!(gradient.sample(1.5, 1.5, ResampleMethod::Bilinear),Some(10.0));
+
+        // between pixel centers, nearest gives a step while bilinear gives intermediate values
+        assert_eq!(gradient.sample(0.75, 0.5, ResampleMethod::Nearest),Some(0.0));
+        assert_eq!(gradient.sample(1.25, 0.5, ResampleMethod::Nearest),Some(10.0));
+        assert_eq!(gradient.sample(0.75, 0.5, ResampleMethod::Bilinear),Some(2.5));
+        assert_eq!(gradient.sample(1.25, 0.5, ResampleMethod::Bilinear),Some(7.5));
+        assert_eq!(gradient.sample(2.0, 1.0, ResampleMethod::Bilinear),Some(15.0));
+
+        // past the outer pixel centers, the edge values are used
+        assert_eq!(gradient.sample(2.9, 0.1, ResampleMethod::Bilinear),Some(20.0));
+        // off the map entirely
+        assert_eq!(gradient.sample(3.5, 0.5, ResampleMethod::Bilinear),None);
+    }
+
+    #[test]
+    fn test_bilinear_sampling_no_data() {
+        let raster = create_sampler(0.0, 0.0, 2, 1, vec![0.0, -9999.0], Some(-9999.0));
+
+        // falls back to the nearest pixel when one of the surrounding pixels has no data
+        assert_eq!(raster.sample(0.75, 0.5, ResampleMethod::Bilinear),Some(0.0));
+        assert_eq!(raster.sample_data(1.25, 0.5, ResampleMethod::Bilinear),None);
+    }
+
+    #[test]
+    fn test_ocean_mask_with_different_resolution() {
+        // detailed elevations over 4 degrees square
+        let elevations = create_sampler_with_resolution(0.0, 0.0, 0.5, 8, 8, vec![
+            10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0,
+            20.0, 21.0, 22.0, 23.0, 24.0, 25.0, 26.0, 27.0,
+            30.0, 31.0, 32.0, 33.0, 34.0, 35.0, 36.0, 37.0,
+            40.0, 41.0, 42.0, 43.0, 44.0, 45.0, 46.0, 47.0,
+            50.0, 51.0, 52.0, 53.0, 54.0, 55.0, 56.0, 57.0,
+            60.0, 61.0, 62.0, 63.0, 64.0, 65.0, 66.0, 67.0,
+            70.0, 71.0, 72.0, 73.0, 74.0, 75.0, 76.0, 77.0,
+            80.0, 81.0, 82.0, 83.0, 84.0, 85.0, 86.0, 87.0,
+        ], None);
+        // a coarse mask over the same area, with ocean along the western half.
+        let mask = create_sampler_with_resolution(0.0, 0.0, 2.0, 2, 2, vec![
+            1.0, -9999.0,
+            1.0, -9999.0,
+        ], Some(-9999.0));
+        // a coarse elevation for comparison, offset to start further south and west, so its ocean doesn't line up with the mask.
+        let below = create_sampler_with_resolution(-1.0, -1.0, 2.5, 2, 2, vec![
+            -5.0, 5.0,
+            -5.0, 5.0,
+        ], None);
+
+        let sites = [(0.25,3.75),(1.75,0.25),(2.25,3.75),(3.75,0.25)];
+        let sampled: Vec<_> = sites.iter().map(|(lon,lat)| (
+            elevations.sample(*lon, *lat, ResampleMethod::Nearest),
+            mask.has_data(*lon, *lat),
+            below.is_below(*lon, *lat, 0.0, false)
+        )).collect();
+
+        assert_eq!(sampled,vec![
+            (Some(10.0),true,true),
+            (Some(83.0),true,false),
+            (Some(14.0),false,false),
+            (Some(87.0),false,false),
+        ]);
+
+        // a site just east of a raster must not wrap around to the start of the next row.
+        assert_eq!(below.sample(4.25, 1.75, ResampleMethod::Nearest),None);
+    }
+
+    #[test]
+    fn test_coast_handling() {
+        let raster = create_sampler(0.0, 0.0, 3, 1, vec![-1.0, 0.0, 1.0], None);
+
+        // by default, tiles exactly at the elevation are land
+        assert!(raster.is_below(0.5, 0.5, 0.0, false));
+        assert!(!raster.is_below(1.5, 0.5, 0.0, false));
+        assert!(!raster.is_below(2.5, 0.5, 0.0, false));
+
+        assert!(raster.is_below(0.5, 0.5, 0.0, true));
+        assert!(raster.is_below(1.5, 0.5, 0.0, true));
+        assert!(!raster.is_below(2.5, 0.5, 0.0, true));
+    }
+
+}
@@ -65,6 +65,43 @@ fn test_run_command() {
 }
 
 
+/// Runs big-bang with the culture set, namers and seed used by most of these tests, on a blank world shaped by the 'continents' recipe. The extra arguments are passed before the source.
+fn run_big_bang(test_file: &std::path::Path, extra_args: &[&str]) {
+    run_big_bang_with_terrain(test_file, extra_args, &["recipe-set".into(), "--source".into(), "share/terrain_recipes/afmg_recipes.json".into(), "--recipe".into(), "continents".into()]);
+}
+
+/// Like `run_big_bang`, but the blank world is shaped by the specified terrain command instead.
+fn run_big_bang_with_terrain(test_file: &std::path::Path, extra_args: &[&str], terrain: &[std::ffi::OsString]) {
+    use std::ffi::OsString;
+
+    let mut args: Vec<OsString> = vec![
+        "".into(),
+        "big-bang".into(),
+        test_file.into(),
+        "--overwrite-all".into(),
+        "--cultures".into(),
+        "share/culture_sets/afmg_culture_antique.json".into(),
+        "--namers".into(),
+        "share/namers/afmg_namers.json".into(),
+        "--default-namer".into(),
+        "English".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+    ];
+    args.extend(extra_args.iter().map(OsString::from));
+    args.extend([
+        "--tile-count".into(),
+        "2000".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]);
+    args.extend(terrain.iter().cloned());
+    crate::run(&args).expect("Command should have run.");
+}
+
 #[test]
 #[should_panic(expected="create should not return an an error here, but it does for now: OgrError { err: 6, method_name: \"OGR_L_CreateFeature\" }")]
 fn test_database_lock_issue() {
@@ -150,6 +187,323 @@ fn test_database_lock_issue() {
 
 }
 
+#[test]
+fn test_migrate() {
+    use std::path::PathBuf;
+    use gdal::Dataset;
+    use gdal::DriverManager;
+    use gdal::vector::LayerOptions;
+    use gdal::vector::LayerAccess;
+    use gdal::vector::Feature;
+    use gdal::spatial_ref::SpatialRef;
+    use crate::typed_map::schema::Schema;
+    use crate::world_map::WorldMap;
+    use crate::world_map::tile_layer::TileSchema;
+    use crate::geometry::GDALGeometryWrapper;
+    use std::fs::remove_file;
+
+    let test_file: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("tmp").join("test_migrate.gpkg");
+    _ = remove_file(test_file.clone()); // ignore error
+
+    { // create a tiles layer as an older version would have, without the depth field.
+        let driver = DriverManager::get_driver_by_name("GPKG").expect("get driver");
+        let mut dataset = driver.create_vector_only(&test_file).expect("create dataset");
+        let tiles = dataset.create_layer(LayerOptions {
+            name: TileSchema::LAYER_NAME,
+            ty: <TileSchema as Schema>::Geometry::INTERNAL_TYPE,
+            srs: Some(&SpatialRef::from_epsg(4326).expect("srs")),
+            options: None
+        }).expect("create layer");
+        let old_fields: Vec<_> = TileSchema::get_field_defs().iter().filter(|(name,_)| *name != TileSchema::FIELD_DEPTH).copied().collect();
+        tiles.create_defn_fields(&old_fields).expect("define fields");
+        let feature = Feature::new(tiles.defn()).expect("new feature");
+        feature.create(&tiles).expect("create feature");
+    }
+
+    let mut target = WorldMap::edit(&test_file).expect("open world map");
+    assert_eq!(target.migrate(&mut ()).expect("migrate"),1);
+    // running it again should find nothing to do
+    assert_eq!(target.migrate(&mut ()).expect("migrate again"),0);
+    target.save(&mut ()).expect("save");
+
+    let dataset = Dataset::open(&test_file).expect("open dataset");
+    let mut tiles = dataset.layer_by_name(TileSchema::LAYER_NAME).expect("tiles layer");
+    assert!(tiles.defn().fields().any(|field| field.name() == TileSchema::FIELD_DEPTH));
+    let feature = tiles.features().next().expect("feature");
+    assert_eq!(feature.field_as_double_by_name(TileSchema::FIELD_DEPTH).expect("read depth"),None);
+
+}
+
+#[test]
+fn test_people_seed() {
+    use std::path::PathBuf;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_people_seed.gpkg");
+
+    let generate = |people_seed: &str| {
+        run_big_bang(&test_file, &["--people-seed", people_seed]);
+
+        let target = WorldMap::edit(&test_file).expect("open world map");
+        let elevations: Vec<u64> = target.tiles_layer().expect("tiles layer").read_features().map(|tile| tile.elevation().expect("elevation").to_bits()).collect();
+        let cultures: Vec<(String,u64)> = target.cultures_layer().expect("cultures layer").read_features().map(|culture| (culture.name().expect("name"),culture.center_tile_id().expect("center").to_inner())).collect();
+        (elevations,cultures)
+    };
+
+    let (first_elevations,first_cultures) = generate("1");
+    let (second_elevations,second_cultures) = generate("2");
+
+    assert_eq!(first_elevations,second_elevations,"changing the people seed should not change the terrain");
+    assert_ne!(first_cultures,second_cultures,"changing the people seed should change the cultures");
+
+}
+
+#[test]
+fn test_stage_seeds_match_big_bang() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let big_bang_file = cargo_dir.join("target").join("tmp").join("test_stage_seeds_big_bang.gpkg");
+    let separate_file = cargo_dir.join("target").join("tmp").join("test_stage_seeds_separate.gpkg");
+    let seed = "9543572450198918714";
+
+    let source: [OsString; 9] = ["blank".into(), "180".into(), "360".into(), "-90".into(), "-180".into(), "recipe-set".into(), "--source".into(), "share/terrain_recipes/afmg_recipes.json".into(), "--recipe".into()];
+
+    run_big_bang(&big_bang_file, &[]);
+
+    // the separate commands write to a new file, so nothing needs to be overwritten.
+    _ = std::fs::remove_file(&separate_file);
+    let mut create: Vec<OsString> = vec!["".into(), "create".into(), separate_file.clone().into(), "--seed".into(), seed.into(), "--tile-count".into(), "2000".into()];
+    create.extend(source.iter().cloned());
+    create.push("continents".into());
+    crate::run(&create).expect("Command should have run.");
+
+    for command in ["gen-climate", "gen-water", "gen-biome"] {
+        crate::run(&[OsString::from(""), command.into(), "all".into(), separate_file.clone().into()]).expect("Command should have run.");
+    }
+    crate::run(&[OsString::from(""), "gen-people".into(), separate_file.clone().into(), "--cultures".into(), "share/culture_sets/afmg_culture_antique.json".into(), "--namers".into(), "share/namers/afmg_namers.json".into(), "--default-namer".into(), "English".into(), "--seed".into(), seed.into()]).expect("Command should have run.");
+    for command in ["gen-towns", "gen-nations", "gen-subnations"] {
+        crate::run(&[OsString::from(""), command.into(), separate_file.clone().into(), "--namers".into(), "share/namers/afmg_namers.json".into(), "--default-namer".into(), "English".into(), "--seed".into(), seed.into()]).expect("Command should have run.");
+    }
+
+    let read_people = |file: &PathBuf| {
+        let target = WorldMap::edit(file).expect("open world map");
+        let cultures: Vec<(String,u64)> = target.cultures_layer().expect("cultures layer").read_features().map(|culture| (culture.name().expect("name"),culture.center_tile_id().expect("center").to_inner())).collect();
+        let towns: Vec<String> = target.edit_towns_layer().expect("towns layer").read_features().map(|town| town.name().expect("name")).collect();
+        let nations: Vec<String> = target.edit_nations_layer().expect("nations layer").read_features().map(|nation| nation.name().expect("name")).collect();
+        (cultures,towns,nations)
+    };
+
+    let (big_bang_cultures,big_bang_towns,big_bang_nations) = read_people(&big_bang_file);
+    let (separate_cultures,separate_towns,separate_nations) = read_people(&separate_file);
+
+    assert!(!big_bang_towns.is_empty());
+    assert_eq!(big_bang_cultures,separate_cultures,"separate commands should generate the same cultures as big-bang");
+    assert_eq!(big_bang_towns,separate_towns,"separate commands should generate the same towns as big-bang");
+    assert_eq!(big_bang_nations,separate_nations,"separate commands should generate the same nations as big-bang");
+
+}
+
+#[test]
+fn test_big_bang_output_layers() {
+    use std::path::PathBuf;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_big_bang_output_layers.gpkg");
+
+    run_big_bang(&test_file, &["--output-layers", "tiles,biomes,rivers"]);
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    let mut layers: Vec<String> = target.layer_info().iter().map(|layer| layer.name().to_owned()).collect();
+    layers.sort();
+    assert_eq!(layers,vec!["biomes","properties","rivers","tiles"]);
+
+}
+
+#[test]
+fn test_big_bang_names_lakes() {
+    use std::path::PathBuf;
+    use crate::world_map::WorldMap;
+    use crate::world_map::fields::LakeType;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_big_bang_names_lakes.gpkg");
+
+    run_big_bang(&test_file, &["--min-named-lake-size", "2"]);
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    let lakes: Vec<(i32,LakeType,Option<String>)> = target.edit_lakes_layer().expect("lakes layer").read_features().map(|lake| (lake.size().expect("size"),lake.type_().expect("type"),lake.name().expect("name"))).collect();
+
+    for (size,type_,name) in lakes {
+        assert_eq!(name.is_some(),size >= 2,"only lakes at least the minimum size should be named");
+        if let (Some(name),LakeType::Fresh) = (name,type_) {
+            assert!(name.starts_with("Lake "),"fresh lakes should be named 'Lake X', not '{name}'");
+        }
+    }
+
+}
+
+#[test]
+fn test_biome_apply_is_repeatable() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::world_map::WorldMap;
+    use crate::typed_map::features::TypedFeature;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_biome_apply.gpkg");
+
+    run_big_bang(&test_file, &[]);
+
+    let read_biomes = || {
+        let target = WorldMap::edit(&test_file).expect("open world map");
+        target.tiles_layer().expect("tiles layer").read_features().map(|tile| (tile.fid().expect("fid").to_inner(),tile.biome().expect("biome").into_bytes())).collect::<Vec<_>>()
+    };
+
+    let apply_biomes = || {
+        crate::run(&[
+            OsString::from(""),
+            "gen-biome".into(),
+            "apply".into(),
+            test_file.clone().into(),
+        ]).expect("Command should have run.");
+    };
+
+    let original = read_biomes();
+    apply_biomes();
+    let first = read_biomes();
+    apply_biomes();
+    let second = read_biomes();
+
+    assert_eq!(original,first,"reapplying biomes should not change them");
+    assert_eq!(first,second,"reapplying biomes should always write the same values");
+
+}
+
+#[test]
+fn test_compact() {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_compact.gpkg");
+
+    let big_bang = || {
+        run_big_bang(&test_file, &[]);
+    };
+
+    // generating twice overwrites every layer, leaving the space used by the first run behind.
+    big_bang();
+    big_bang();
+    let bloated_size = fs::metadata(&test_file).expect("file size").len();
+
+    crate::run(&[
+        OsString::from(""),
+        "migrate".into(),
+        test_file.clone().into(),
+        "--compact".into(),
+    ]).expect("Command should have run.");
+    let compacted_size = fs::metadata(&test_file).expect("file size").len();
+
+    assert!(compacted_size < bloated_size,"compacting should have made the file smaller ({compacted_size} >= {bloated_size})");
+
+}
+
+#[test]
+fn test_tile_centroid() {
+    use core::f64::consts::FRAC_PI_3;
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::geometry::GDALGeometryWrapper;
+    use crate::geometry::LinearRing;
+    use crate::geometry::Polygon;
+    use crate::utils::coordinates::Coordinates;
+    use crate::world_map::WorldMap;
+    use crate::typed_map::features::TypedFeature;
+
+    let site: Coordinates = (0.0,0.0).try_into().expect("site");
+
+    // a regular hexagon around the site, as an interior tile would be.
+    let hexagon: Vec<(f64,f64)> = (0..=6).map(|i| {
+        let angle = f64::from(i % 6) * FRAC_PI_3;
+        (angle.cos(),angle.sin())
+    }).collect();
+    let interior = Polygon::from_rings([LinearRing::from_vertices(hexagon).expect("ring")]).expect("polygon");
+    let centroid = interior.centroid().expect("centroid");
+    assert!(centroid.distance(&site) < 1e-9,"interior centroid {centroid:?} should be at the site");
+
+    // the same hexagon, clipped by a map edge at x = 0.5, with the site still at the center of the original.
+    let clipped = Polygon::from_rings([LinearRing::from_vertices([
+        (0.5,-0.866_025_403_784_438_6),(0.5,0.866_025_403_784_438_6),(-0.5,0.866_025_403_784_438_6),(-1.0,0.0),(-0.5,-0.866_025_403_784_438_6),(0.5,-0.866_025_403_784_438_6)
+    ]).expect("ring")]).expect("polygon");
+    let edge_centroid = clipped.centroid().expect("centroid");
+    assert!(edge_centroid.distance(&site) > 0.1,"edge centroid {edge_centroid:?} should be pulled away from the site");
+    assert!(edge_centroid.x().into_inner() < 0.0);
+
+    // the centroids of generated tiles lie inside them, even where the tiles are clipped by the edge of the world.
+    let test_file = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("tmp").join("test_tile_centroid.gpkg");
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "200".into(),
+        "blank".into(),
+        "30".into(),
+        "60".into(),
+        "0".into(),
+        "0".into(),
+    ]).expect("Command should have run.");
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    let mut tiles = target.tiles_layer().expect("tiles layer");
+    let mut moved_edge_tiles = 0;
+    for tile in tiles.read_features() {
+        let centroid = tile.centroid().expect("centroid");
+        let envelope = tile.geometry().expect("geometry").get_envelope();
+        assert!(envelope.contains(&centroid),"centroid {centroid:?} should be inside the tile {envelope:?}");
+        if tile.edge().expect("edge").is_some() && (centroid.distance(&tile.site().expect("site")) > 0.0) {
+            moved_edge_tiles += 1;
+        }
+    }
+    assert!(moved_edge_tiles > 0);
+
+}
+
+#[test]
+fn test_preview_image() {
+    use std::path::PathBuf;
+    use gdal::Dataset;
+    use prisma::Rgb;
+    use crate::algorithms::preview::PreviewImage;
+    use crate::utils::extent::Extent;
+
+    let test_file: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target").join("tmp").join("test_preview_image.png");
+
+    let mut image = PreviewImage::new(Extent::from_bounds(-180.0, -90.0, 180.0, 90.0), 120);
+    image.fill_polygon(&[vec![(-90.0,45.0),(90.0,45.0),(90.0,-45.0),(-90.0,-45.0),(-90.0,45.0)]], Rgb::new(0,128,0));
+    image.save(&test_file).expect("save preview");
+
+    let dataset = Dataset::open(&test_file).expect("open preview");
+    assert_eq!(dataset.raster_size(),(120,60));
+    assert_eq!(dataset.raster_count(),4);
+    let size = dataset.raster_size();
+    let green = dataset.rasterband(2).expect("green band").read_as::<u8>((0,0), size, size, None).expect("read green band");
+    let alpha = dataset.rasterband(4).expect("alpha band").read_as::<u8>((0,0), size, size, None).expect("read alpha band");
+    // half of the width and half of the height are covered by the tile, the rest should be transparent.
+    assert_eq!(alpha.data().iter().filter(|value| **value > 0).count(),60*30);
+    assert!(green.data().contains(&128));
+
+}
+
 #[test]
 fn split_and_remove_chars_should_be_the_same() {
     use crate::utils::remove_n_chars_from_end;
@@ -166,4 +520,944 @@ fn split_and_remove_chars_should_be_the_same() {
     let str1 = split_string_from_end(str1, test_count).0;
     assert_eq!(str2,str1);
 
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_nation_count() {
+    use std::path::PathBuf;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_nation_count.gpkg");
+
+    run_big_bang(&test_file, &["--nation-count", "3"]);
+
+    let mut target = WorldMap::edit(&test_file).expect("open world map");
+    let (nations,capitals) = target.with_transaction(|transaction| {
+        let nations = transaction.edit_nations_layer()?.feature_count();
+        let capitals = transaction.edit_towns_layer()?.read_features().filter(|town| town.is_capital().expect("is_capital")).count();
+        Ok((nations,capitals))
+    }).expect("read layers");
+
+    assert_eq!(nations,3,"there should be exactly the requested number of nations");
+    assert_eq!(capitals,3,"extra capitals should have been demoted");
+
+}
+
+#[test]
+fn test_big_bang_skip() {
+    use std::path::PathBuf;
+    use std::fs;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_big_bang_skip.gpkg");
+    // big-bang edits an existing file, so get rid of any cultures layer left over from a previous run.
+    _ = fs::remove_file(&test_file);
+
+    run_big_bang(&test_file, &["--skip", "people,towns,nations,subnations"]);
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    assert!(target.biomes_layer().is_ok(),"biomes should have been generated");
+    assert!(target.cultures_layer().is_err(),"cultures should not have been generated");
+
+}
+
+#[test]
+fn test_stored_tile_area() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::world_map::WorldMap;
+    use crate::utils::world_shape::WorldShape;
+    use crate::typed_map::features::TypedFeature;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_stored_tile_area.gpkg");
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--world-shape".into(),
+        "sphere".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    let mut tiles = target.tiles_layer().expect("tiles layer");
+    let mut count = 0;
+    for tile in tiles.read_features() {
+        let stored = tile.area().expect("area");
+        let calculated = tile.geometry().expect("geometry").shaped_area(&WorldShape::Sphere).expect("shaped area");
+        assert!((stored - calculated).abs() < 1e-9,"stored area {stored} should match the calculated area {calculated}");
+        count += 1;
+    }
+    assert!(count > 0);
+
+}
+
+#[test]
+fn test_fingerprint_same_seed() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::world_map::WorldMap;
+    use crate::algorithms::fingerprint::fingerprint_tiles;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    let generate = |name: &str, seed: &str| {
+        let test_file = cargo_dir.join("target").join("tmp").join(name);
+        crate::run(&[
+            OsString::from(""),
+            "create".into(),
+            test_file.clone().into(),
+            "--overwrite-tiles".into(),
+            "--seed".into(),
+            seed.into(),
+            "--tile-count".into(),
+            "500".into(),
+            "blank".into(),
+            "180".into(),
+            "360".into(),
+            "-90".into(),
+            "-180".into(),
+        ]).expect("Command should have run.");
+
+        let target = WorldMap::edit(&test_file).expect("open world map");
+        fingerprint_tiles(&mut target.tiles_layer().expect("tiles layer"), &mut ()).expect("fingerprint")
+    };
+
+    let first = generate("test_fingerprint_1.gpkg","9543572450198918714");
+    let second = generate("test_fingerprint_2.gpkg","9543572450198918714");
+    let different = generate("test_fingerprint_3.gpkg","1");
+
+    assert_eq!(first,second,"worlds generated with the same seed should have the same fingerprint");
+    assert_ne!(first,different,"worlds generated with different seeds should have different fingerprints");
+
+}
+
+#[test]
+fn test_info_layers() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::world_map::WorldMap;
+    use crate::world_map::LayerInfo;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_info_layers.gpkg");
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    let tile_count = target.tiles_layer().expect("tiles layer").read_features().count() as u64;
+
+    let layers = target.layer_info();
+    let tiles = layers.iter().find(|layer| layer.name() == "tiles").expect("tiles should be listed");
+    assert!(tiles.known());
+    assert_eq!(tiles.geometry_type(),"Polygon");
+    assert_eq!(tiles.feature_count(),tile_count);
+    assert!(layers.iter().all(LayerInfo::known),"only known layers should be in a new map");
+    assert!(!layers.iter().any(|layer| layer.name() == "nations"),"nations haven't been generated yet");
+
+}
+
+#[test]
+fn test_integer_ids() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use gdal::vector::LayerAccess;
+    use gdal::vector::OGRFieldType;
+    use crate::world_map::WorldMap;
+    use crate::world_map::tile_layer::TileSchema;
+    use crate::typed_map::fields::IdRef;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_integer_ids.gpkg");
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--integer-ids".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    let mut target = WorldMap::edit(&test_file).expect("open world map");
+
+    {
+        let tiles = target.tiles_layer().expect("tiles layer");
+        let field_type = tiles.layer().defn().fields().find(|field| field.name() == TileSchema::FIELD_LAKE_ID).expect("lake_id field").field_type();
+        assert_eq!(field_type,OGRFieldType::OFTInteger64,"id references should be stored as integers");
+    }
+
+    target.with_transaction(|transaction| {
+        let tiles = transaction.edit_tile_layer()?;
+        let mut feature = tiles.try_feature_by_id(&IdRef::new(1))?;
+        feature.set_lake_id(&Some(IdRef::new(42)))?;
+        tiles.update_feature(feature)
+    }).expect("write lake id");
+
+    let tiles = target.tiles_layer().expect("tiles layer");
+    let feature = tiles.try_feature_by_id(&IdRef::new(1)).expect("tile 1");
+    assert_eq!(feature.lake_id().expect("read lake id"),Some(IdRef::new(42)));
+
+}
+
+#[test]
+fn test_preview_region() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::world_map::WorldMap;
+    use crate::algorithms::preview::generate_preview;
+    use crate::algorithms::preview::PreviewAttribute;
+    use crate::utils::extent::Extent;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_preview_region.gpkg");
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    let mut target = WorldMap::edit(&test_file).expect("open world map");
+    let image = target.with_transaction(|transaction| {
+        generate_preview(transaction, &PreviewAttribute::Elevation, None, 100, Some(Extent::from_bounds(10.0, 20.0, 60.0, 45.0)), &mut ())
+    }).expect("generate preview");
+
+    assert_eq!((image.extent().west(),image.extent().south(),image.extent().east(),image.extent().north()),(10.0,20.0,60.0,45.0));
+    assert_eq!((image.width(),image.height()),(100,50));
+    // the tiles cover the whole world, so the ones found by the filter should cover the whole region.
+    assert!((0..image.height()).all(|row| (0..image.width()).all(|column| image.pixel(column, row).is_some())),"every pixel in the region should be drawn");
+
+}
+
+#[test]
+fn test_triangulate_deduplicated_points() {
+    use crate::algorithms::random_points::DuplicatePointFilter;
+    use crate::algorithms::triangles::DelaunayGenerator;
+    use crate::geometry::Collection;
+    use crate::geometry::Point;
+    use crate::utils::world_shape::WorldShape;
+
+    // a grid of points, with every point along the edge given twice, as clamped jitter might do.
+    let mut offsets = Vec::new();
+    for x in 0..5 {
+        for y in 0..5 {
+            offsets.push((f64::from(x),f64::from(y)));
+            if (x == 0) || (y == 0) {
+                offsets.push((f64::from(x),f64::from(y)));
+            }
+        }
+    }
+
+    let mut filter = DuplicatePointFilter::new(0.001);
+    let mut points = Collection::new().expect("collection");
+    for (x,y) in offsets {
+        if !filter.is_duplicate(x, y) {
+            points.push_item(Point::new(x, y).expect("point")).expect("push point");
+        }
+    }
+    assert_eq!(filter.removed(),9);
+
+    let triangles = DelaunayGenerator::new(points, WorldShape::Cylinder).collect::<Result<Vec<_>,_>>().expect("triangulation");
+    // a 4x4 grid of squares, each split into two triangles.
+    assert_eq!(triangles.len(),32);
+    assert!(triangles.iter().all(|triangle| triangle.area() > 0.0),"no triangle should be degenerate");
+
+}
+
+#[test]
+fn test_export_graph() {
+    use std::collections::HashSet;
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::world_map::WorldMap;
+    use crate::world_map::fields::Neighbor;
+    use crate::world_map::fields::NeighborAndDirection;
+    use crate::world_map::tile_layer::TileForNeighborCheck;
+    use crate::world_map::tile_layer::TileWithNeighbors;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_export_graph.gpkg");
+    let graph_file = cargo_dir.join("target").join("tmp").join("test_export_graph.json");
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    crate::run(&[
+        OsString::from(""),
+        "export-graph".into(),
+        test_file.clone().into(),
+        "--format".into(),
+        "json".into(),
+        "--edge-lengths".into(),
+        "--output".into(),
+        graph_file.clone().into(),
+    ]).expect("Command should have run.");
+
+    let graph: serde_json::Value = serde_json::from_str(&fs::read_to_string(&graph_file).expect("graph should be written")).expect("graph should be JSON");
+    let edges = graph.as_array().expect("graph should be a list of edges");
+    let exported: HashSet<(u64,u64)> = edges.iter().map(|edge| (edge["from"].as_u64().expect("from"),edge["to"].as_u64().expect("to"))).collect();
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    let tile_map = target.tiles_layer().expect("tiles layer").read_features().into_entities_index::<_,TileForNeighborCheck>(&mut ()).expect("read tiles");
+    let mut stored = HashSet::new();
+    for (fid,tile) in tile_map.iter() {
+        for NeighborAndDirection(neighbor,_) in tile.neighbors() {
+            if let Neighbor::Tile(neighbor_id) | Neighbor::CrossMap(neighbor_id,_) = neighbor {
+                _ = stored.insert((fid.to_inner(),neighbor_id.to_inner()));
+            }
+        }
+    }
+
+    assert_eq!(exported,stored);
+    assert!(exported.iter().all(|(from,to)| exported.contains(&(*to,*from))),"edges should be symmetric");
+    assert!(edges.iter().filter(|edge| edge["cross_map"] == false).all(|edge| edge["shared_edge_length"].as_f64().expect("length") > 0.0),"neighbors should share a border");
+
+}
+
+#[test]
+fn test_export_geojson() {
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::PathBuf;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_export_geojson.gpkg");
+    let tiles_file = cargo_dir.join("target").join("tmp").join("test_export_geojson_tiles.geojson");
+    let properties_file = cargo_dir.join("target").join("tmp").join("test_export_geojson_properties.json");
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    crate::run(&[
+        OsString::from(""),
+        "export".into(),
+        "geojson".into(),
+        test_file.clone().into(),
+        tiles_file.clone().into(),
+    ]).expect("Command should have run.");
+
+    let tiles: serde_json::Value = serde_json::from_str(&fs::read_to_string(&tiles_file).expect("tiles should be written")).expect("tiles should be JSON");
+    assert_eq!(tiles["type"],"FeatureCollection");
+    let features = tiles["features"].as_array().expect("features should be a list");
+    assert!(!features.is_empty());
+    assert!(features.iter().all(|feature| feature["geometry"]["type"] == "Polygon"),"tiles should be polygons");
+    assert!(features.iter().all(|feature| feature["properties"]["site_x"].is_f64()),"tiles should have their fields");
+    assert!(features.iter().all(|feature| feature["properties"].get("neighbors_compass").is_none()),"compass points should only be added when asked for");
+
+    crate::run(&[
+        OsString::from(""),
+        "export".into(),
+        "geojson".into(),
+        test_file.clone().into(),
+        tiles_file.clone().into(),
+        "--compass".into(),
+    ]).expect("Command should have run.");
+
+    let tiles: serde_json::Value = serde_json::from_str(&fs::read_to_string(&tiles_file).expect("tiles should be written")).expect("tiles should be JSON");
+    let features = tiles["features"].as_array().expect("features should be a list");
+    assert!(features.iter().all(|feature| feature["properties"]["neighbors_compass"].as_array().is_some_and(|neighbors| !neighbors.is_empty() && neighbors.iter().all(|neighbor| neighbor.as_str().and_then(|neighbor| neighbor.rsplit_once(':')).is_some_and(|(_,point)| ["N","NE","E","SE","S","SW","W","NW"].contains(&point))))),"tiles should have their neighbor directions as compass points");
+
+    crate::run(&[
+        OsString::from(""),
+        "export".into(),
+        "geojson".into(),
+        test_file.clone().into(),
+        properties_file.clone().into(),
+        "--layer".into(),
+        "properties".into(),
+    ]).expect("Command should have run.");
+
+    let properties: serde_json::Value = serde_json::from_str(&fs::read_to_string(&properties_file).expect("properties should be written")).expect("properties should be JSON");
+    assert!(properties.as_array().is_some_and(|properties| !properties.is_empty()),"properties should be a plain list");
+
+    // a layer which doesn't exist is an error, not an empty file.
+    _ = crate::run(&[
+        OsString::from(""),
+        "export".into(),
+        "geojson".into(),
+        test_file.into(),
+        properties_file.into(),
+        "--layer".into(),
+        "no_such_layer".into(),
+    ]).expect_err("Unknown layer should fail.");
+
+}
+
+#[test]
+fn test_all_land_world() {
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::world_map::WorldMap;
+    use crate::world_map::fields::CultureType;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_all_land_world.gpkg");
+    let coastlines_file = cargo_dir.join("target").join("tmp").join("test_all_land_world_coastlines.geojson");
+    let oceans_file = cargo_dir.join("target").join("tmp").join("test_all_land_world_oceans.geojson");
+
+    // without any ocean tasks in the terrain, every tile stays land.
+    run_big_bang_with_terrain(&test_file, &[], &["random-uniform".into(), "--height-delta".into(), "10..=60".into()]);
+
+    let target = WorldMap::edit(&test_file).expect("open world map");
+    let mut tiles = target.tiles_layer().expect("tiles layer");
+    for tile in tiles.read_features() {
+        let grouping = tile.grouping().expect("grouping");
+        assert!(!grouping.is_ocean(),"no tile should be ocean");
+        let shore_distance = tile.shore_distance().expect("shore distance");
+        assert!(if grouping.is_water() { shore_distance < 0 } else { shore_distance > 0 },"shore distance {shore_distance} should be measured for every tile");
+    }
+
+    let cultures = target.cultures_layer().expect("cultures layer").read_features().map(|culture| culture.type_().expect("type")).collect::<Vec<_>>();
+    assert!(!cultures.is_empty());
+    assert!(!cultures.iter().any(|culture| matches!(culture,CultureType::Naval)),"no culture should be naval without an ocean");
+
+    for (layer,file) in [("coastlines",&coastlines_file),("oceans",&oceans_file)] {
+        crate::run(&[
+            OsString::from(""),
+            "export".into(),
+            "geojson".into(),
+            test_file.clone().into(),
+            file.into(),
+            "--layer".into(),
+            layer.into(),
+        ]).expect("Command should have run.");
+
+        let exported: serde_json::Value = serde_json::from_str(&fs::read_to_string(file).expect("layer should be written")).expect("layer should be JSON");
+        assert!(exported["features"].as_array().is_some_and(Vec::is_empty),"{layer} should be empty");
+    }
+
+}
+
+#[test]
+fn test_reproject_equal_area() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use gdal::Dataset;
+    use gdal::vector::LayerAccess;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_reproject_equal_area.gpkg");
+    let output_file = cargo_dir.join("target").join("tmp").join("test_reproject_equal_area_6933.gpkg");
+
+    // the spiral gives the tiles about the same area on the globe at every latitude
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--world-shape".into(),
+        "sphere".into(),
+        "--point-distribution".into(),
+        "fibonacci".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "2000".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    crate::run(&[
+        OsString::from(""),
+        "reproject".into(),
+        test_file.clone().into(),
+        output_file.clone().into(),
+        "--epsg".into(),
+        "6933".into(),
+    ]).expect("Command should have run.");
+
+    // the average area of the high latitude tiles relative to the average area of the equatorial tiles
+    let high_latitude_ratio = |file: &PathBuf| {
+        let dataset = Dataset::open(file).expect("open dataset");
+        let mut tiles = dataset.layer_by_name("tiles").expect("tiles layer");
+        let mut high = (0.0,0_u32);
+        let mut equatorial = (0.0,0_u32);
+        for tile in tiles.features() {
+            let latitude = tile.field_as_double_by_name("site_y").expect("site_y").expect("site_y value").abs();
+            let area = tile.geometry().expect("geometry").area();
+            if (60.0..75.0).contains(&latitude) {
+                high = (high.0 + area, high.1 + 1);
+            } else if latitude < 15.0 {
+                equatorial = (equatorial.0 + area, equatorial.1 + 1);
+            }
+        }
+        assert!(high.1 > 0 && equatorial.1 > 0,"the sample should have tiles at both latitudes");
+        (high.0 / f64::from(high.1)) / (equatorial.0 / f64::from(equatorial.1))
+    };
+
+    let original = high_latitude_ratio(&test_file);
+    let reprojected = high_latitude_ratio(&output_file);
+
+    // in degrees, the high latitude tiles are stretched to about three times the area of the others
+    assert!(original > 2.0,"{original} should show the distortion of EPSG:4326");
+    assert!((reprojected - 1.0).abs() < (original - 1.0).abs(),"{reprojected} should be more uniform than {original}");
+    assert!((0.8..1.25).contains(&reprojected),"{reprojected} should be close to equal area");
+
+}
+
+#[test]
+fn test_river_lengths() {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_river_lengths.gpkg");
+
+    run_big_bang(&test_file, &["--skip", "biomes,people,towns,nations,subnations"]);
+
+    let read_lengths = || {
+        let target = WorldMap::edit(&test_file).expect("open world map");
+        let mut rivers = target.rivers_layer().expect("rivers layer");
+        rivers.read_features().map(|river| river.length().expect("length")).collect::<Vec<_>>()
+    };
+
+    let generated = read_lengths();
+    assert!(!generated.is_empty());
+    assert!(generated.iter().any(|length| *length > 0.0),"segments should have lengths");
+
+    // recalculating from the stored segments gives the same lengths.
+    crate::run(&[
+        OsString::from(""),
+        "gen-water".into(),
+        "river-lengths".into(),
+        test_file.clone().into(),
+    ]).expect("Command should have run.");
+
+    let recalculated = read_lengths();
+    assert_eq!(generated.len(),recalculated.len());
+    for (generated,recalculated) in generated.iter().zip(&recalculated) {
+        assert!((generated - recalculated).abs() < 1e-9,"recalculated length {recalculated} should match generated length {generated}");
+    }
+
+}
+
+#[test]
+fn test_ocean_world() {
+    use std::ffi::OsString;
+    use std::fs;
+    use std::path::PathBuf;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let tmp_dir = cargo_dir.join("target").join("tmp");
+    let test_file = tmp_dir.join("test_ocean_world.gpkg");
+    let recipe_file = tmp_dir.join("test_ocean_world_recipe.json");
+    let layer_file = tmp_dir.join("test_ocean_world_layer.geojson");
+
+    let big_bang = |tasks: &str| {
+        fs::write(&recipe_file, format!("[{{\"task\": \"Add\", \"height_delta\": -50}},{tasks}{{\"task\": \"FillOcean\"}}]")).expect("recipe should be written");
+        run_big_bang_with_terrain(&test_file, &[], &["recipe".into(), "--source".into(), recipe_file.clone().into()]);
+    };
+
+    let feature_count = |layer: &str| {
+        crate::run(&[
+            OsString::from(""),
+            "export".into(),
+            "geojson".into(),
+            test_file.clone().into(),
+            layer_file.clone().into(),
+            "--layer".into(),
+            layer.into(),
+        ]).expect("Command should have run.");
+        let exported: serde_json::Value = serde_json::from_str(&fs::read_to_string(&layer_file).expect("layer should be written")).expect("layer should be JSON");
+        exported["features"].as_array().expect("features should be a list").len()
+    };
+
+    // with no land at all, there is nobody to live there.
+    big_bang("");
+    assert_eq!(feature_count("coastlines"),0);
+    for layer in ["cultures","towns","nations","subnations"] {
+        assert_eq!(feature_count(layer),0,"{layer} should be empty");
+    }
+
+    // a single small island may have more room for capitals than towns.
+    big_bang("{\"task\": \"AddHill\", \"count\": 1, \"height_delta\": \"55..=60\", \"x_filter\": \"48..=52\", \"y_filter\": \"48..=52\"},");
+    assert!(feature_count("coastlines") > 0,"the island should have a coastline");
+
+}
+
+#[test]
+fn test_tile_attributes() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use std::fs;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_tile_attributes.gpkg");
+    // the attributes aren't replaced when the tiles are recreated, so start from scratch.
+    _ = fs::remove_file(&test_file);
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "blank".into(),
+        "180".into(),
+        "360".into(),
+        "-90".into(),
+        "-180".into(),
+    ]).expect("Command should have run.");
+
+    let set_attribute = |fid: &str, name: &str, value: &str| {
+        crate::run(&[
+            OsString::from(""),
+            "set-tile-attr".into(),
+            test_file.clone().into(),
+            fid.into(),
+            name.into(),
+            value.into(),
+        ])
+    };
+
+    set_attribute("5", "mana_level", "3").expect("Command should have run.");
+    set_attribute("5", "mana_level", "7").expect("Command should have run.");
+    set_attribute("6", "danger", "high").expect("Command should have run.");
+    _ = set_attribute("100000", "danger", "none").expect_err("Tiles that don't exist should not get attributes.");
+
+    crate::run(&[
+        OsString::from(""),
+        "gen-climate".into(),
+        "all".into(),
+        test_file.clone().into(),
+    ]).expect("Command should have run.");
+
+    let mut target = WorldMap::edit(&test_file).expect("open world map");
+    let mut attributes = target.with_transaction(|transaction| {
+        Ok(transaction.edit_or_create_tile_attributes_layer()?.read_features().map(|feature| (feature.tile_id().expect("tile_id").to_inner(),feature.name().expect("name"),feature.value().expect("value"))).collect::<Vec<_>>())
+    }).expect("read attributes");
+    attributes.sort();
+
+    assert_eq!(attributes,vec![(5,"mana_level".to_owned(),"7".to_owned()),(6,"danger".to_owned(),"high".to_owned())]);
+
+}
+
+#[test]
+fn test_render() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use gdal::Dataset;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let test_file = cargo_dir.join("target").join("tmp").join("test_render.gpkg");
+    let image_file = cargo_dir.join("target").join("tmp").join("test_render.png");
+
+    run_big_bang(&test_file, &["--skip", "people,towns,nations,subnations"]);
+
+    crate::run(&[
+        OsString::from(""),
+        "render".into(),
+        test_file.into(),
+        image_file.clone().into(),
+        "--layer".into(),
+        "biomes".into(),
+        "--width".into(),
+        "120".into(),
+    ]).expect("Command should have run.");
+
+    let dataset = Dataset::open(&image_file).expect("open render");
+    assert_eq!(dataset.raster_size(),(120,60));
+    assert_eq!(dataset.raster_count(),4);
+    let size = dataset.raster_size();
+    let alpha = dataset.rasterband(4).expect("alpha band").read_as::<u8>((0,0), size, size, None).expect("read alpha band");
+    assert!(alpha.data().iter().any(|value| *value > 0),"something should have been drawn");
+
+}
+
+#[test]
+fn test_sampled_depth() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use gdal::DriverManager;
+    use gdal::raster::Buffer;
+    use crate::world_map::WorldMap;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let raster_file = cargo_dir.join("target").join("tmp").join("test_sampled_depth.tif");
+    let test_file = cargo_dir.join("target").join("tmp").join("test_sampled_depth.gpkg");
+
+    {
+        // deep ocean in the western half and land in the eastern half, with a pixel every ten degrees
+        let driver = DriverManager::get_driver_by_name("GTiff").expect("get driver");
+        let mut dataset = driver.create_with_band_type::<f64,_>(&raster_file, 36, 18, 1).expect("create raster");
+        dataset.set_geo_transform(&[-180.0, 10.0, 0.0, 90.0, 0.0, -10.0]).expect("set geo transform");
+        let data = (0..18).flat_map(|_| (0..36).map(|column| if column < 18 { -5000.0 } else { 1000.0 })).collect();
+        let mut buffer = Buffer::new((36,18), data);
+        dataset.rasterband(1).expect("get band").write((0,0), (36,18), &mut buffer).expect("write raster");
+    }
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "from-heightmap".into(),
+        raster_file.into(),
+    ]).expect("Command should have run.");
+
+    let read_tiles = || {
+        let target = WorldMap::edit(&test_file).expect("open world map");
+        let mut tiles = target.tiles_layer().expect("open tiles");
+        let mut values = Vec::new();
+        for feature in tiles.read_features() {
+            values.push((feature.elevation().expect("read elevation"),feature.depth().expect("read depth")));
+        }
+        values
+    };
+
+    let sampled = read_tiles();
+    assert!(sampled.iter().any(|(_,depth)| depth.is_some()),"some tiles should have been sampled below sea level");
+    for (elevation,depth) in &sampled {
+        if elevation < &0.0 {
+            assert_eq!(depth.map(f64::to_bits),Some(elevation.to_bits()));
+        } else {
+            assert!(depth.is_none());
+        }
+    }
+
+    crate::run(&[
+        OsString::from(""),
+        "terrain".into(),
+        test_file.clone().into(),
+        "multiply".into(),
+        "--height-factor".into(),
+        "0.5".into(),
+    ]).expect("Command should have run.");
+
+    // the elevations changed, but the depth from the heightmap is kept
+    let processed = read_tiles();
+    assert_eq!(processed.len(),sampled.len());
+    for ((old_elevation,old_depth),(elevation,depth)) in sampled.iter().zip(&processed) {
+        assert_eq!(elevation.to_bits(),(old_elevation * 0.5).to_bits());
+        assert_eq!(depth.map(f64::to_bits),old_depth.map(f64::to_bits));
+    }
+
+}
+
+#[test]
+fn test_ocean_above_sea_level_warning() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use gdal::DriverManager;
+    use gdal::raster::Buffer;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::world_map::WorldMap;
+    use crate::algorithms::terrain::TerrainTask;
+    use crate::commands::ContinentCountArg;
+    use crate::commands::TileFilterArg;
+    use crate::commands::terrain::Multiply;
+    use crate::progress::DiagnosticsObserver;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let raster_file = cargo_dir.join("target").join("tmp").join("test_ocean_above_sea_level_warning.tif");
+    let test_file = cargo_dir.join("target").join("tmp").join("test_ocean_above_sea_level_warning.gpkg");
+
+    {
+        // ocean in the western half and land in the eastern half, with a pixel every ten degrees
+        let driver = DriverManager::get_driver_by_name("GTiff").expect("get driver");
+        let mut dataset = driver.create_with_band_type::<f64,_>(&raster_file, 36, 18, 1).expect("create raster");
+        dataset.set_geo_transform(&[-180.0, 10.0, 0.0, 90.0, 0.0, -10.0]).expect("set geo transform");
+        let data = (0..18).flat_map(|_| (0..36).map(|column| if column < 18 { -1000.0 } else { 1000.0 })).collect();
+        let mut buffer = Buffer::new((36,18), data);
+        dataset.rasterband(1).expect("get band").write((0,0), (36,18), &mut buffer).expect("write raster");
+    }
+
+    crate::run(&[
+        OsString::from(""),
+        "create".into(),
+        test_file.clone().into(),
+        "--overwrite-tiles".into(),
+        "--seed".into(),
+        "9543572450198918714".into(),
+        "--tile-count".into(),
+        "500".into(),
+        "from-heightmap".into(),
+        raster_file.into(),
+    ]).expect("Command should have run.");
+
+    crate::run(&[
+        OsString::from(""),
+        "terrain".into(),
+        test_file.clone().into(),
+        "fill-ocean".into(),
+    ]).expect("Command should have run.");
+
+    // flipping the elevations raises the ocean above sea level without changing the grouping
+    let flip = TerrainTask::Multiply(Multiply {
+        height_filter: None,
+        filter: TileFilterArg {
+            latitudes: None,
+            region: None
+        },
+        height_factor: -1.0
+    });
+
+    let mut progress = DiagnosticsObserver::new(());
+    let mut random = StdRng::seed_from_u64(9543572450198918714);
+    let continent_count = ContinentCountArg {
+        continent_count: None,
+        continent_attempts: 20
+    };
+    let mut target = WorldMap::edit(&test_file).expect("open world map");
+    _ = target.with_transaction(|transaction| {
+        TerrainTask::process_terrain(&[flip], &continent_count, &mut random, transaction, &mut progress)
+    }).expect("processes should have run");
+
+    let warnings = progress.warnings();
+    assert_eq!(warnings.len(),1);
+    assert_eq!(warnings[0].phase(),Some("Multiplying some elevations by -1."));
+    assert!(warnings[0].message().starts_with("At least one ocean tile was found with an elevation above 0"));
+
+}
+
+#[test]
+fn test_boundary_points() {
+    use std::path::PathBuf;
+    use std::ffi::OsString;
+    use crate::world_map::WorldMap;
+    use crate::geometry::GDALGeometryWrapper;
+    use crate::typed_map::features::TypedFeature;
+
+    let cargo_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+    // returns the count, mean aspect ratio of the envelopes, and the spread of the areas (as a coefficient of variation) of the edge tiles.
+    let edge_tile_shapes = |boundary_points: &str| {
+        let test_file = cargo_dir.join("target").join("tmp").join(format!("test_boundary_points_{boundary_points}.gpkg"));
+
+        crate::run(&[
+            OsString::from(""),
+            "create".into(),
+            test_file.clone().into(),
+            "--overwrite-tiles".into(),
+            "--seed".into(),
+            "9543572450198918714".into(),
+            "--tile-count".into(),
+            "300".into(),
+            "--boundary-points".into(),
+            boundary_points.into(),
+            "blank".into(),
+            "30".into(),
+            "60".into(),
+            "0".into(),
+            "0".into(),
+        ]).expect("Command should have run.");
+
+        let target = WorldMap::edit(&test_file).expect("open world map");
+        let mut tiles = target.tiles_layer().expect("tiles layer");
+        let mut aspect_ratios = Vec::new();
+        let mut areas = Vec::new();
+        for tile in tiles.read_features() {
+            let geometry = tile.geometry().expect("geometry");
+            assert!(geometry.is_valid(),"tile geometries should be valid with --boundary-points {boundary_points}");
+            let envelope = geometry.get_envelope();
+            assert!((envelope.west() >= -1e-9) && (envelope.south() >= -1e-9) && (envelope.east() <= 60.0 + 1e-9) && (envelope.north() <= 30.0 + 1e-9),"tile {envelope:?} should be inside the extent with --boundary-points {boundary_points}");
+            if tile.edge().expect("edge").is_some() {
+                let (long,short) = if envelope.width() > envelope.height() {
+                    (envelope.width(),envelope.height())
+                } else {
+                    (envelope.height(),envelope.width())
+                };
+                aspect_ratios.push(long / short);
+                areas.push(geometry.area());
+            }
+        }
+        let count = areas.len() as f64;
+        let mean_area = areas.iter().sum::<f64>() / count;
+        let area_spread = (areas.iter().map(|area| (area - mean_area).powi(2)).sum::<f64>() / count).sqrt() / mean_area;
+        (areas.len(),aspect_ratios.iter().sum::<f64>() / count,area_spread)
+    };
+
+    // without boundary points the edges are ragged, but the tiles must still be valid.
+    _ = edge_tile_shapes("none");
+    let (corners_count,corners_aspect,corners_spread) = edge_tile_shapes("corners");
+    let (ring_count,ring_aspect,ring_spread) = edge_tile_shapes("ring");
+    assert!((corners_count > 0) && (ring_count > 0));
+    assert!((ring_aspect < corners_aspect) || (ring_spread < corners_spread),"edge tiles should be closer to regular with the ring (aspect ratio {ring_aspect}, area spread {ring_spread}) than with corners (aspect ratio {corners_aspect}, area spread {corners_spread})");
+
+}
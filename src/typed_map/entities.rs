@@ -59,6 +59,61 @@ impl<'impl_life, SchemaType: Schema, Feature: TypedFeature<'impl_life, SchemaTyp
     }
 }
 
+/// The extremes and average of a numeric field over a set of entities.
+#[derive(Debug,PartialEq)]
+pub(crate) struct FieldStats<Value> {
+    min: Value,
+    max: Value,
+    mean: f64,
+    count: usize
+}
+
+impl<Value: Copy + PartialOrd + Into<f64>> FieldStats<Value> {
+
+    /// Returns None if there are no values, since there would be nothing to report.
+    pub(crate) fn from_values<Values: IntoIterator<Item = Value>>(values: Values) -> Option<Self> {
+        let mut values = values.into_iter();
+        let first = values.next()?;
+        let mut result = Self {
+            min: first,
+            max: first,
+            mean: 0.0,
+            count: 1
+        };
+        let mut total: f64 = first.into();
+        for value in values {
+            if value < result.min {
+                result.min = value;
+            }
+            if value > result.max {
+                result.max = value;
+            }
+            total += value.into();
+            result.count += 1;
+        }
+        result.mean = total / result.count as f64;
+        Some(result)
+    }
+
+    pub(crate) const fn min(&self) -> Value {
+        self.min
+    }
+
+    pub(crate) const fn max(&self) -> Value {
+        self.max
+    }
+
+    #[cfg(test)]
+    pub(crate) const fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    #[cfg(test)]
+    pub(crate) const fn count(&self) -> usize {
+        self.count
+    }
+}
+
 pub(crate) struct EntityIndex<SchemaType: Schema, EntityType: Entity<SchemaType>> {
     // I use an IndexMap instead of HashMap as it ensures that the map maintains an order when iterating.
     // This helps me get reproducible results with the same random seed.
@@ -120,6 +175,11 @@ impl<SchemaType: Schema, EntityType: Entity<SchemaType>> EntityIndex<SchemaType,
         self.inner.pop()
     }
 
+    /// Calculates the minimum, maximum and mean of a numeric field, or None if the index is empty.
+    pub(crate) fn reduce_field<Value: Copy + PartialOrd + Into<f64>, Accessor: Fn(&EntityType) -> Value>(&self, accessor: Accessor) -> Option<FieldStats<Value>> {
+        FieldStats::from_values(self.inner.values().map(accessor))
+    }
+
     pub(crate) fn watch_queue<StartMessage: AsRef<str>, FinishMessage: AsRef<str>, Progress: ProgressObserver>(self, progress: &mut Progress, start: StartMessage, finish: FinishMessage) -> EntityIndexQueueWatcher<FinishMessage, Progress, SchemaType, EntityType> {
         progress.start(|| (start,Some(self.len())));
         EntityIndexQueueWatcher { 
@@ -133,6 +193,16 @@ impl<SchemaType: Schema, EntityType: Entity<SchemaType>> EntityIndex<SchemaType,
 
 }
 
+impl<SchemaType: Schema, EntityType: Entity<SchemaType> + Clone> Clone for EntityIndex<SchemaType,EntityType> {
+
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _phantom: PhantomData
+        }
+    }
+}
+
 impl<SchemaType: Schema, EntityType: Entity<SchemaType>> IntoIterator for EntityIndex<SchemaType,EntityType> {
     type Item = (IdRef,EntityType);
 
@@ -265,10 +335,10 @@ macro_rules! entity_field_def {
 #[macro_export]
 /// Used by `entity!` to generate the type for an entity field
 macro_rules! entity_get_field_fn {
-    (false $field: ident -> $type: ty  $([$function: expr])?) => {
+    (false [$(#[$get_attr: meta])*] $field: ident -> $type: ty  $([$function: expr])?) => {
     };
-    ($field: ident -> $type: ty  $([$function: expr])?) => {
-        pub(crate) const fn $field(&self) -> &$crate::entity_field_def!($type $([$function])?) { 
+    ([$(#[$get_attr: meta])*] $field: ident -> $type: ty  $([$function: expr])?) => {
+        $(#[$get_attr])* pub(crate) const fn $field(&self) -> &$crate::entity_field_def!($type $([$function])?) { 
             &self.$field
         }
     };
@@ -315,6 +385,7 @@ Creates an entity struct that contains the specified fields. (See Entity trait)
 
 The body of the entity is a set of braces with a comma-separated list items describing the fields of the entity.
 
+* `$get_attr` is an attribute that will be placed on the getter for the field, such as an `allow` for a lint.
 * `$field` is the identifier of the struct field
 * `$type` is the type of the field generated
 * `$function` is the assignment function, an optional closure used to initialize the value of the field. 
@@ -323,7 +394,7 @@ The assignment function closure takes an TypedFeature value and returns a result
 
 */ 
 macro_rules! entity {
-    ($(#[$struct_attr: meta])* $name: ident: $layer: ident {$( $(#[get = $get: ident])? $(#[set = $set: ident])? $(#[mut = $mut: ident])? $field: ident: $type: ty $(= $function: expr)?),*$(,)?}) => {
+    ($(#[$struct_attr: meta])* $name: ident: $layer: ident {$( $(#[get = $get: ident])? $(#[get($get_attr: meta)])* $(#[set = $set: ident])? $(#[mut = $mut: ident])? $field: ident: $type: ty $(= $function: expr)?),*$(,)?}) => {
         #[derive(Clone)]
         $(#[$struct_attr])* 
         pub(crate) struct $name {
@@ -334,7 +405,7 @@ macro_rules! entity {
 
         impl $name {
             $(
-                $crate::entity_get_field_fn!($($get)? $field -> $type $([$function])?);
+                $crate::entity_get_field_fn!($($get)? [$(#[$get_attr])*] $field -> $type $([$function])?);
                 $crate::entity_get_mut_field_fn!($($mut)? $field -> $type $([$function])?);
                 $crate::entity_set_field_fn!($($set)? $field -> $type $([$function])?);
             )*
@@ -363,3 +434,33 @@ macro_rules! entity {
 
     };
 }
+
+#[cfg(test)]
+mod test {
+    use core::iter::empty;
+
+    use super::EntityIndex;
+    use super::FieldStats;
+    use crate::typed_map::fields::IdRef;
+    use crate::world_map::tile_layer::TileSchema;
+    use crate::world_map::tile_layer::TileForEdgeWrap;
+
+    #[test]
+    fn test_reduce_field() {
+        let tile_map: EntityIndex<TileSchema,TileForEdgeWrap> = [-10.0,30.0,5.0,15.0].into_iter().enumerate().map(|(fid,site_y)| (IdRef::new(fid as u64 + 1),TileForEdgeWrap::new(site_y, Vec::new()))).collect();
+
+        let stats = tile_map.reduce_field(|tile| *tile.site_y()).expect("stats for a non-empty index");
+        assert_eq!(stats.min().to_bits(),(-10.0f64).to_bits());
+        assert_eq!(stats.max().to_bits(),30.0f64.to_bits());
+        assert_eq!(stats.mean().to_bits(),10.0f64.to_bits());
+        assert_eq!(stats.count(),4);
+
+        // integer fields keep their type for the extremes
+        let int_stats = FieldStats::from_values([3_i32,-7,10]).expect("stats for some values");
+        assert_eq!((int_stats.min(),int_stats.max(),int_stats.count()),(-7,10,3));
+        assert_eq!(int_stats.mean().to_bits(),2.0f64.to_bits());
+
+        let empty: EntityIndex<TileSchema,TileForEdgeWrap> = empty().collect();
+        assert_eq!(empty.reduce_field(|tile| *tile.site_y()),None);
+    }
+}
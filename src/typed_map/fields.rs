@@ -67,6 +67,9 @@ pub(crate) trait TypedField: Sized + DocumentedFieldType {
 
     const STORAGE_TYPE: OGRFieldType::Type;
 
+    /// The storage type used for maps created with integer ids. Only id references are stored differently.
+    const INTEGER_ID_STORAGE_TYPE: OGRFieldType::Type = Self::STORAGE_TYPE;
+
     fn get_required<FieldType>(value: Option<FieldType>, field_id: &'static str) -> Result<FieldType,CommandError> {
         value.ok_or_else(|| CommandError::MissingField(field_id))
     }
@@ -174,6 +177,15 @@ impl IdRef {
         self.0
     }
 
+    /// Id references might be stored as strings or integers, depending on how the map was created.
+    fn from_field_value(value: FieldValue) -> Result<Self,CommandError> {
+        match value {
+            FieldValue::Integer64Value(value) => Ok(Self(value.try_into().map_err(|_| CommandError::InvalidValueForIdRef(value.to_string(),"negative id".to_owned()))?)),
+            FieldValue::IntegerValue(value) => Ok(Self(value.try_into().map_err(|_| CommandError::InvalidValueForIdRef(value.to_string(),"negative id".to_owned()))?)),
+            value => Deserialize::read_from_str(&value.into_string().unwrap_or_default())
+        }
+    }
+
 }
 
 impl Display for IdRef {
@@ -201,12 +213,15 @@ impl TypedField for IdRef {
 
     const STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTString;
 
+    const INTEGER_ID_STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTInteger64;
+
     fn get_field(feature: &Feature, field_name: &str, field_id: &'static str) -> Result<Self,CommandError> {
-        Deserialize::read_from_str(&Self::get_required(feature.field_as_string_by_name(field_name)?, field_id)?)
+        Self::from_field_value(Self::get_required(feature.field(field_name)?, field_id)?)
     }
 
 
     fn set_field(&self, feature: &mut Feature, field_name: &str) -> Result<(),CommandError> {
+        // GDAL converts the string if the field is an integer.
         Ok(feature.set_field_string(field_name, &self.write_to_string())?)
     }
 
@@ -220,8 +235,10 @@ impl TypedField for Option<IdRef> {
 
     const STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTString;
 
+    const INTEGER_ID_STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTInteger64;
+
     fn get_field(feature: &Feature, field_name: &str, _: &'static str) -> Result<Self,CommandError> {
-        feature.field_as_string_by_name(field_name)?.map(|a| Deserialize::read_from_str(&a)).transpose()
+        feature.field(field_name)?.map(IdRef::from_field_value).transpose()
     }
 
     fn set_field(&self, feature: &mut Feature, field_name: &str) -> Result<(),CommandError> {
@@ -242,11 +259,29 @@ impl TypedField for Option<IdRef> {
 
 }
 
+impl TypedField for Vec<IdRef> {
+
+    const STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTString;
+
+    fn get_field(feature: &Feature, field_name: &str, field_id: &'static str) -> Result<Self,CommandError> {
+        Deserialize::read_from_str(&Self::get_required(feature.field_as_string_by_name(field_name)?, field_id)?)
+    }
+
+    fn set_field(&self, feature: &mut Feature, field_name: &str) -> Result<(),CommandError> {
+        Ok(feature.set_field_string(field_name, &self.write_to_string())?)
+    }
+
+    fn to_field_value(&self) -> Result<Option<FieldValue>,CommandError> {
+        Ok(Some(FieldValue::StringValue(self.write_to_string())))
+    }
+
+}
+
 impl DocumentedFieldType for IdRef {
     fn get_field_type_documentation() -> FieldTypeDocumentation {
         FieldTypeDocumentation {
             name: "ID Reference".to_owned(),
-            description: "A reference to the 'fid' field in another table. This is stored as a String field because an unsigned integer field is not available.".to_owned(),
+            description: "A reference to the 'fid' field in another table. This is stored as a String field because an unsigned integer field is not available, unless the map was created with integer ids, in which case it is an Integer64.".to_owned(),
             storage_type: field_type_to_name(Self::STORAGE_TYPE), 
             syntax: "<integer>".to_owned(),
             sub_types: Vec::new()
@@ -408,6 +443,33 @@ impl TypedField for f64 {
 
 }
 
+impl TypedField for Option<f64> {
+
+    const STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTReal;
+
+
+    fn get_field(feature: &Feature, field_name: &str, _: &'static str) -> Result<Self,CommandError> {
+        Ok(feature.field_as_double_by_name(field_name)?)
+    }
+
+    fn set_field(&self, feature: &mut Feature, field_name: &str) -> Result<(),CommandError> {
+        if let Some(value) = self {
+            value.set_field(feature, field_name)
+        } else {
+            Ok(feature.set_field_null(field_name)?)
+        }
+    }
+
+    fn to_field_value(&self) -> Result<Option<FieldValue>,CommandError> {
+        if let Some(value) = self {
+            value.to_field_value()
+        } else {
+            Ok(None)
+        }
+    }
+
+}
+
 impl DocumentedFieldType for f64 {
     fn get_field_type_documentation() -> FieldTypeDocumentation {
         FieldTypeDocumentation { 
@@ -508,3 +570,18 @@ impl FieldDocumentation {
         &self.field_type
     }
 }
+
+#[cfg(test)]
+mod test {
+    use gdal::vector::FieldValue;
+
+    use super::IdRef;
+
+    #[test]
+    fn test_id_ref_from_field_value() {
+        assert_eq!(IdRef::from_field_value(FieldValue::StringValue("42".to_owned())).unwrap(),IdRef::new(42));
+        assert_eq!(IdRef::from_field_value(FieldValue::Integer64Value(42)).unwrap(),IdRef::new(42));
+        assert_eq!(IdRef::from_field_value(FieldValue::IntegerValue(42)).unwrap(),IdRef::new(42));
+        _ = IdRef::from_field_value(FieldValue::Integer64Value(-1)).unwrap_err();
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use gdal::Dataset;
 use gdal::vector::LayerOptions;
 use gdal::spatial_ref::SpatialRef;
@@ -5,6 +7,7 @@ use gdal::vector::FieldValue;
 use gdal::vector::Layer;
 use gdal::vector::LayerAccess;
 use gdal::vector::OGRwkbGeometryType;
+use gdal::vector::OGRFieldType;
 use gdal::vector::Feature as GdalFeature;
 
 use crate::errors::CommandError;
@@ -13,8 +16,15 @@ use crate::typed_map::fields::IdRef;
 use crate::typed_map::features::TypedFeature;
 use crate::typed_map::fields::FieldDocumentation;
 use crate::typed_map::schema::Schema;
+use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
 use core::marker::PhantomData;
 
+/// Returns true if the dataset contains a layer with the specified name.
+pub(crate) fn has_layer(dataset: &Dataset, layer_name: &str) -> bool {
+    dataset.layers().any(|layer| layer.name() == layer_name)
+}
+
 pub(crate) struct LayerDocumentation {
     name: String,
     description: String,
@@ -117,6 +127,11 @@ macro_rules! layer {
                     $((paste::paste!{Self::[<FIELD_ $prop:snake:upper>]},<$prop_type as $crate::typed_map::fields::TypedField>::STORAGE_TYPE)),*
                 ];
 
+                // field definitions for maps created with integer ids
+                const INTEGER_ID_FIELD_DEFS: [(&'static str,gdal::vector::OGRFieldType::Type); $crate::count_ids!($($prop),*)] = [
+                    $((paste::paste!{Self::[<FIELD_ $prop:snake:upper>]},<$prop_type as $crate::typed_map::fields::TypedField>::INTEGER_ID_STORAGE_TYPE)),*
+                ];
+
 
             }
         }
@@ -133,6 +148,10 @@ macro_rules! layer {
                     &Self::FIELD_DEFS
                 }
 
+                fn get_integer_id_field_defs() -> &'static [(&'static str,gdal::vector::OGRFieldType::Type)] {
+                    &Self::INTEGER_ID_FIELD_DEFS
+                }
+
 
             }
         }
@@ -271,7 +290,15 @@ pub(crate) struct MapLayer<'layer, 'feature, SchemaType: Schema, Feature: TypedF
 impl<'layer, 'feature, SchemaType: Schema, Feature: TypedFeature<'feature, SchemaType>> MapLayer<'layer,'feature,SchemaType,Feature> {
 
 
-    pub(crate) fn create_from_dataset(dataset: &'layer mut Dataset, overwrite: bool) -> Result<Self,CommandError> {
+    fn field_defs(integer_ids: bool) -> &'static [(&'static str,OGRFieldType::Type)] {
+        if integer_ids {
+            SchemaType::get_integer_id_field_defs()
+        } else {
+            SchemaType::get_field_defs()
+        }
+    }
+
+    pub(crate) fn create_from_dataset(dataset: &'layer mut Dataset, overwrite: bool, integer_ids: bool) -> Result<Self,CommandError> {
 
         // 4326 is WGS 84, although this is a fictional world and isn't necessarily shaped like Earth.
         // That coordinate system just seems "safe" as far as other tools are expecting an Earth-shape.
@@ -292,7 +319,7 @@ impl<'layer, 'feature, SchemaType: Schema, Feature: TypedFeature<'feature, Schem
                 None
             }
         })?;
-        layer.create_defn_fields(SchemaType::get_field_defs())?;
+        layer.create_defn_fields(Self::field_defs(integer_ids))?;
         
         Ok(Self {
             layer,
@@ -301,6 +328,10 @@ impl<'layer, 'feature, SchemaType: Schema, Feature: TypedFeature<'feature, Schem
         })
     }
 
+    pub(crate) const fn layer_name() -> &'static str {
+        SchemaType::LAYER_NAME
+    }
+
     pub(crate) fn open_from_dataset(dataset: &'layer Dataset) -> Result<Self,CommandError> {
         
         let layer = dataset.layer_by_name(SchemaType::LAYER_NAME)?;
@@ -312,6 +343,25 @@ impl<'layer, 'feature, SchemaType: Schema, Feature: TypedFeature<'feature, Schem
 
     }
 
+    /// Adds any fields in the schema which are missing from the layer, returning their names. Existing features will have null values for those fields. If the layer doesn't exist, nothing is done.
+    pub(crate) fn add_missing_fields(dataset: &Dataset, integer_ids: bool) -> Result<Vec<&'static str>,CommandError> {
+        if !has_layer(dataset, SchemaType::LAYER_NAME) {
+            return Ok(Vec::new())
+        }
+        let layer = dataset.layer_by_name(SchemaType::LAYER_NAME)?;
+        let existing: Vec<String> = layer.defn().fields().map(|field| field.name()).collect();
+        let missing: Vec<_> = Self::field_defs(integer_ids).iter().filter(|(name,_)| !existing.iter().any(|field| field == name)).copied().collect();
+        layer.create_defn_fields(&missing)?;
+        Ok(missing.into_iter().map(|(name,_)| name).collect())
+    }
+
+    /// Adds real number fields which aren't part of the schema, such as temporary debugging output. Fields which already exist are left alone.
+    pub(crate) fn add_extra_fields(&self, fields: &[&str]) -> Result<(),CommandError> {
+        let existing: Vec<String> = self.layer.defn().fields().map(|field| field.name()).collect();
+        let missing: Vec<_> = fields.iter().filter(|name| !existing.iter().any(|field| field == *name)).map(|name| (*name,OGRFieldType::OFTReal)).collect();
+        Ok(self.layer.create_defn_fields(&missing)?)
+    }
+
     pub(crate) fn try_feature_by_id(&'feature self, fid: &IdRef) -> Result<Feature,CommandError> {
         self.layer.feature(fid.to_inner()).ok_or_else(|| CommandError::MissingFeature(SchemaType::LAYER_NAME,fid.clone())).map(Feature::from)
     }
@@ -321,6 +371,16 @@ impl<'layer, 'feature, SchemaType: Schema, Feature: TypedFeature<'feature, Schem
         Ok(self.layer.set_feature(feature.into_feature())?)
     }
 
+    /// Updates many features at once. Keying the updates by fid means they are always written in fid order, no matter what order they were calculated in.
+    pub(crate) fn update_features_by_id<Value, Progress: ProgressObserver, Callback: FnMut(&mut Feature, Value) -> Result<(),CommandError>>(&'feature self, updates: BTreeMap<IdRef,Value>, progress: &mut Progress, mut callback: Callback) -> Result<(),CommandError> {
+        for (fid,value) in updates.into_iter().watch(progress,format!("Writing {}.",SchemaType::LAYER_NAME),format!("{} written.",SchemaType::LAYER_NAME)) {
+            let mut feature = self.try_feature_by_id(&fid)?;
+            callback(&mut feature,value)?;
+            self.update_feature(feature)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn feature_count(&self) -> usize {
         self.layer.feature_count() as usize
     }
@@ -10,4 +10,6 @@ pub(crate) trait Schema {
 
     fn get_field_defs() -> &'static [(&'static str,OGRFieldType::Type)];
 
+    fn get_integer_id_field_defs() -> &'static [(&'static str,OGRFieldType::Type)];
+
 }
@@ -18,12 +18,16 @@ use crate::errors::CommandError;
 use core::fmt;
 
 
-#[derive(Clone)]
+#[derive(Clone,Debug)]
 pub enum ArgRange<NumberType> {
     // While I could use a real Range<> and RangeInclusive<>, I'd have to copy it every time I want to generate a number from it anyway, and
     Inclusive(NumberType,NumberType),
     Exclusive(NumberType,NumberType),
-    Single(NumberType)
+    Single(NumberType),
+    // These are open-ended, and can be used for filters, but not for choosing a number.
+    From(NumberType),
+    To(NumberType),
+    ToInclusive(NumberType)
 }
 
 pub trait TruncOrSelf {
@@ -79,13 +83,14 @@ impl_trunc_or_self_int!(u64);
 
 impl_trunc_or_self_int!(u128);
 
-impl<NumberType: SampleUniform + PartialOrd + Copy + TruncOrSelf> ArgRange<NumberType> {
+impl<NumberType: SampleUniform + PartialOrd + Copy + TruncOrSelf + FromStr + Display> ArgRange<NumberType> {
 
-    pub(crate) fn choose<Random: Rng>(&self, rng: &mut Random) -> NumberType {
+    pub(crate) fn choose<Random: Rng>(&self, rng: &mut Random) -> Result<NumberType,CommandError> {
         match self  {
-            Self::Inclusive(min,max) => rng.gen_range(*min..=*max),
-            Self::Exclusive(min,max) => rng.gen_range(*min..*max),
-            Self::Single(value) => *value,
+            Self::Inclusive(min,max) => Ok(rng.gen_range(*min..=*max)),
+            Self::Exclusive(min,max) => Ok(rng.gen_range(*min..*max)),
+            Self::Single(value) => Ok(*value),
+            Self::From(_) | Self::To(_) | Self::ToInclusive(_) => Err(CommandError::InvalidRangeArgument(self.to_string(),"A number can only be chosen from a range with a start and an end.".to_owned()))
         }
     }
 
@@ -93,7 +98,10 @@ impl<NumberType: SampleUniform + PartialOrd + Copy + TruncOrSelf> ArgRange<Numbe
         match self {
             Self::Inclusive(min, max) => (value >= min) && (value <= max),
             Self::Exclusive(min, max) => (value >= min) && (value < max),
-            Self::Single(single) => single.trunc_or_self() == single.trunc_or_self(),
+            Self::Single(single) => single.trunc_or_self() == value.trunc_or_self(),
+            Self::From(min) => value >= min,
+            Self::To(max) => value < max,
+            Self::ToInclusive(max) => value <= max,
         }
     }
 }
@@ -144,17 +152,30 @@ where <NumberType as FromStr>::Err: Display {
                 false
             };
 
-            let first = first.parse().map_err(|e| CommandError::InvalidRangeArgument(s.to_owned(),format!("{e}")))?;
-            let last = last.parse().map_err(|e| CommandError::InvalidRangeArgument(s.to_owned(),format!("{e}")))?;
-            if first > last {
-                return Err(CommandError::InvalidRangeArgument(s.to_owned(),"First number must be less than last.".to_owned()))
+            let parse = |number: &str| number.parse().map_err(|e| CommandError::InvalidRangeArgument(s.to_owned(),format!("{e}")));
+
+            match (first.is_empty(),last.is_empty()) {
+                (true,true) => Err(CommandError::InvalidRangeArgument(s.to_owned(),"Range must have a first or last number.".to_owned())),
+                (false,true) if !include_last => Ok(Self::From(parse(first)?)),
+                (true,false) => Ok(if include_last {
+                    Self::ToInclusive(parse(last)?)
+                } else {
+                    Self::To(parse(last)?)
+                }),
+                (false,_) => {
+                    let first = parse(first)?;
+                    let last = parse(last)?;
+                    if first > last {
+                        return Err(CommandError::InvalidRangeArgument(s.to_owned(),"First number must be less than last.".to_owned()))
+                    }
+
+                    Ok(if include_last {
+                        Self::Inclusive(first,last)
+                    } else {
+                        Self::Exclusive(first,last)
+                    })
+                }
             }
-
-            Ok(if include_last {
-                Self::Inclusive(first,last)
-            } else {
-                Self::Exclusive(first,last)
-            })
         } else {
             let number = s.parse().map_err(|e| CommandError::InvalidRangeArgument(s.to_owned(),format!("{e}")))?;
             Ok(Self::Single(number))
@@ -169,6 +190,9 @@ impl<NumberType: FromStr + Display> Display for ArgRange<NumberType> {
             Self::Inclusive(min,max) => write!(f,"{min}..={max}"),
             Self::Exclusive(min,max) => write!(f,"{min}..{max}"),
             Self::Single(single) => write!(f,"{single}"),
+            Self::From(min) => write!(f,"{min}.."),
+            Self::To(max) => write!(f,"..{max}"),
+            Self::ToInclusive(max) => write!(f,"..={max}"),
         }
     }
 }
@@ -244,7 +268,7 @@ impl<NumberType: JsonSchema + NumberPattern> JsonSchema for ArgRange<NumberType>
 
     fn json_schema(_: &mut SchemaGenerator) -> Schema {
         let number_pattern = NumberType::pattern();
-        let pattern = Some(format!("{number_pattern}(\\.\\.=?{number_pattern})?"));
+        let pattern = Some(format!("{number_pattern}(\\.\\.(=?{number_pattern})?)?|\\.\\.=?{number_pattern}"));
     
         Schema::Object(SchemaObject {
             instance_type: Some(InstanceType::String.into()),
@@ -261,4 +285,61 @@ impl<NumberType: JsonSchema + NumberPattern> JsonSchema for ArgRange<NumberType>
             ..Default::default()
         })
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use rand::thread_rng;
+
+    use super::ArgRange;
+
+    fn parse(value: &str) -> ArgRange<f64> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_open_ended_ranges() {
+        let from = parse("30..");
+        assert!(matches!(from,ArgRange::From(min) if min.to_bits() == 30.0f64.to_bits()));
+        assert!(from.includes(&30.0));
+        assert!(from.includes(&90.0));
+        assert!(!from.includes(&29.0));
+
+        let to = parse("..-10");
+        assert!(matches!(to,ArgRange::To(max) if max.to_bits() == (-10.0f64).to_bits()));
+        assert!(to.includes(&-20.0));
+        assert!(!to.includes(&-10.0));
+
+        let to_inclusive = parse("..=-10");
+        assert!(matches!(to_inclusive,ArgRange::ToInclusive(_)));
+        assert!(to_inclusive.includes(&-10.0));
+
+        // the strings should round trip
+        for value in ["30..","..-10","..=-10","30..60","30..=60","45"] {
+            assert_eq!(parse(value).to_string(),value);
+        }
+
+        _ = "..".parse::<ArgRange<f64>>().unwrap_err();
+        _ = "30..=".parse::<ArgRange<f64>>().unwrap_err();
+        _ = "60..30".parse::<ArgRange<f64>>().unwrap_err();
+    }
+
+    #[test]
+    fn test_choose_requires_bounds() {
+        let mut rng = thread_rng();
+        _ = parse("30..").choose(&mut rng).unwrap_err();
+        let chosen = parse("30..60").choose(&mut rng).unwrap();
+        assert!((30.0..60.0).contains(&chosen));
+    }
+
+    #[test]
+    fn test_single_includes() {
+        let single: ArgRange<i8> = "5".parse().unwrap();
+        assert!(single.includes(&5));
+        assert!(!single.includes(&6));
+
+        let latitude = parse("30");
+        assert!(latitude.includes(&30.4));
+        assert!(!latitude.includes(&45.0));
+    }
+}
@@ -0,0 +1,70 @@
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::fmt::Result as FormatResult;
+
+use angular_units::Deg;
+
+/// The eight points of the compass, used to label angular directions so they are easier to read.
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub(crate) enum Compass {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest
+}
+
+impl Compass {
+
+    const POINTS: [Self; 8] = [Self::North, Self::NorthEast, Self::East, Self::SouthEast, Self::South, Self::SouthWest, Self::West, Self::NorthWest];
+
+    pub(crate) const fn abbreviation(self) -> &'static str {
+        match self {
+            Self::North => "N",
+            Self::NorthEast => "NE",
+            Self::East => "E",
+            Self::SouthEast => "SE",
+            Self::South => "S",
+            Self::SouthWest => "SW",
+            Self::West => "W",
+            Self::NorthWest => "NW",
+        }
+    }
+}
+
+impl From<Deg<f64>> for Compass {
+
+    /// Angles are measured clockwise from north, as they are for neighbor directions and winds. Each point covers 45 degrees centered on its direction.
+    fn from(value: Deg<f64>) -> Self {
+        let index = ((value.0.rem_euclid(360.0) + 22.5) / 45.0).floor() as usize % 8;
+        Self::POINTS[index]
+    }
+}
+
+impl Display for Compass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        write!(f,"{}",self.abbreviation())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use angular_units::Deg;
+
+    use super::Compass;
+
+    #[test]
+    fn test_compass_from_angle() {
+        assert_eq!(Compass::from(Deg(0.0)),Compass::North);
+        assert_eq!(Compass::from(Deg(90.0)),Compass::East);
+        assert_eq!(Compass::from(Deg(225.0)),Compass::SouthWest);
+        assert_eq!(Compass::from(Deg(350.0)),Compass::North);
+        assert_eq!(Compass::from(Deg(-90.0)),Compass::West);
+        assert_eq!(Compass::from(Deg(22.4)),Compass::North);
+        assert_eq!(Compass::from(Deg(22.6)),Compass::NorthEast);
+        assert_eq!(Compass::from(Deg(225.0)).to_string(),"SW");
+    }
+}
@@ -52,13 +52,53 @@ impl Extent {
     }
 
     pub(crate) fn contains(&self,point: &Coordinates) -> bool {
-        let x = point.x().into_inner();
+        self.contains_tuple(point.x().into_inner(), point.y().into_inner())
+    }
+
+    pub(crate) fn contains_tuple(&self, x: f64, y: f64) -> bool {
+        (x >= self.west) &&
+           (x <= (self.west + self.width)) &&
+           (y >= self.south) &&
+           (y <= (self.south + self.height))
+
+    }
+
+    /// Like `contains`, but the longitude of the point is first wrapped around the globe so it is as close to the extent as possible. A point at 179.9 will be found inside an extent which begins at -180.0 and has been expanded slightly past the antimeridian.
+    #[cfg(test)]
+    pub(crate) fn contains_wrapped(&self, point: &Coordinates) -> bool {
+        let x = self.wrap_longitude(point.x().into_inner());
         let y = point.y().into_inner();
         (x >= self.west) &&
            (x <= (self.west + self.width)) &&
            (y >= self.south) &&
            (y <= (self.south + self.height))
+    }
 
+    /// Shifts a longitude by whole revolutions of 360 degrees so that it is as close as possible to the center of the extent.
+    #[cfg(test)]
+    pub(crate) fn wrap_longitude(&self, longitude: f64) -> f64 {
+        let center = self.width.mul_add(0.5, self.west);
+        let revolutions = ((longitude - center) / 360.0).round();
+        revolutions.mul_add(-360.0, longitude)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn intersects(&self, other: &Self) -> bool {
+        (self.west <= other.east()) &&
+           (other.west <= self.east()) &&
+           (self.south <= other.north()) &&
+           (other.south <= self.north())
+    }
+
+    /// Returns a new extent which has been grown by the specified amount in all four directions. A negative amount will shrink the extent.
+    #[cfg(test)]
+    pub(crate) fn expanded_by(&self, amount: f64) -> Self {
+        Self::from_height_width_south_west(
+            amount.mul_add(2.0, self.height), 
+            amount.mul_add(2.0, self.width), 
+            self.south - amount, 
+            self.west - amount
+        )
     }
 
     pub(crate) fn is_extent_on_edge(&self, extent: &Self) -> Result<Option<Edge>,CommandError> {
@@ -107,6 +147,16 @@ impl Extent {
         }
     }
 
+    /// Returns the side of the extent which is closest to the point, or which the point is furthest beyond if it lies outside.
+    pub(crate) fn nearest_edge_to_tuple(&self, x: f64, y: f64) -> Edge {
+        [
+            (x - self.west, Edge::West),
+            (self.east() - x, Edge::East),
+            (y - self.south, Edge::South),
+            (self.north() - y, Edge::North),
+        ].into_iter().min_by(|(a,_),(b,_)| a.total_cmp(b)).map_or(Edge::North, |(_,edge)| edge)
+    }
+
     pub(crate) fn is_off_edge(&self, point: &Coordinates) -> Option<Edge> {
         let (x,y) = point.to_tuple();
         self.is_tuple_on_edge(x, y)
@@ -215,3 +265,91 @@ impl Extent {
 
 }
 
+
+#[cfg(test)]
+mod test {
+
+    use super::Extent;
+    use crate::utils::coordinates::Coordinates;
+    use crate::utils::edge::Edge;
+
+    fn assert_close(found: f64, expected: f64) {
+        assert!((found - expected).abs() < 1e-9,"Expected {expected}, found {found}");
+    }
+
+    #[test]
+    fn test_nearest_edge_to_tuple() {
+        let extent = Extent::from_bounds(0.0, 0.0, 10.0, 4.0);
+        assert_eq!(extent.nearest_edge_to_tuple(1.0, 2.0), Edge::West);
+        assert_eq!(extent.nearest_edge_to_tuple(9.0, 2.0), Edge::East);
+        assert_eq!(extent.nearest_edge_to_tuple(5.0, 0.5), Edge::South);
+        assert_eq!(extent.nearest_edge_to_tuple(5.0, 3.5), Edge::North);
+        // a point beyond the extent is nearest the side it lies past.
+        assert_eq!(extent.nearest_edge_to_tuple(12.0, 2.0), Edge::East);
+        // a tile envelope larger than the extent touches opposite sides, which can't be combined into one edge.
+        assert!(extent.is_extent_on_edge(&Extent::from_bounds(-1.0, 1.0, 11.0, 3.0)).is_err());
+    }
+
+    #[test]
+    fn test_contains() {
+        let extent = Extent::from_bounds(-10.0, -5.0, 10.0, 5.0);
+        assert!(extent.contains(&Coordinates::try_from((0.0,0.0)).unwrap()));
+        // the boundary is inclusive
+        assert!(extent.contains(&Coordinates::try_from((10.0,5.0)).unwrap()));
+        assert!(extent.contains(&Coordinates::try_from((-10.0,-5.0)).unwrap()));
+        assert!(!extent.contains(&Coordinates::try_from((10.1,0.0)).unwrap()));
+        assert!(!extent.contains(&Coordinates::try_from((0.0,-5.1)).unwrap()));
+    }
+
+    #[test]
+    fn test_intersects() {
+        let extent = Extent::from_bounds(-10.0, -5.0, 10.0, 5.0);
+        assert!(extent.intersects(&Extent::from_bounds(5.0, 0.0, 15.0, 10.0)));
+        assert!(extent.intersects(&Extent::from_bounds(-1.0, -1.0, 1.0, 1.0)));
+        // touching edges count as intersecting
+        assert!(extent.intersects(&Extent::from_bounds(10.0, -5.0, 20.0, 5.0)));
+        assert!(!extent.intersects(&Extent::from_bounds(10.1, -5.0, 20.0, 5.0)));
+        assert!(!extent.intersects(&Extent::from_bounds(-10.0, 6.0, 10.0, 8.0)));
+    }
+
+    #[test]
+    fn test_expanded_by() {
+        let extent = Extent::from_bounds(-10.0, -5.0, 10.0, 5.0).expanded_by(1.5);
+        assert_close(extent.west(),-11.5);
+        assert_close(extent.south(),-6.5);
+        assert_close(extent.east(),11.5);
+        assert_close(extent.north(),6.5);
+        assert_close(extent.width(),23.0);
+        assert_close(extent.height(),13.0);
+
+        let shrunk = extent.expanded_by(-1.5);
+        assert_close(shrunk.west(),-10.0);
+        assert_close(shrunk.north(),5.0);
+    }
+
+    #[test]
+    fn test_wrap_longitude() {
+        let world = Extent::from_bounds(-180.0, -90.0, 180.0, 90.0);
+        assert_close(world.wrap_longitude(179.9),179.9);
+        assert_close(world.wrap_longitude(-179.9),-179.9);
+        assert_close(world.wrap_longitude(190.0),-170.0);
+        assert_close(world.wrap_longitude(-370.0),-10.0);
+
+        let western = Extent::from_bounds(-180.0, -90.0, -170.0, 90.0);
+        assert_close(western.wrap_longitude(179.9),-180.1);
+        let eastern = Extent::from_bounds(170.0, -90.0, 180.0, 90.0);
+        assert_close(eastern.wrap_longitude(-179.9),180.1);
+    }
+
+    #[test]
+    fn test_contains_wrapped() {
+        let near_antimeridian = Extent::from_bounds(-180.0, -10.0, -170.0, 10.0).expanded_by(0.5);
+        let point = Coordinates::try_from((179.9,0.0)).unwrap();
+        assert!(!near_antimeridian.contains(&point));
+        assert!(near_antimeridian.contains_wrapped(&point));
+
+        let far_away = Coordinates::try_from((90.0,0.0)).unwrap();
+        assert!(!near_antimeridian.contains_wrapped(&far_away));
+    }
+
+}
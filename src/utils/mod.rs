@@ -8,6 +8,8 @@ pub(crate) mod point_finder;
 pub(crate) mod arg_range;
 pub(crate) mod simple_serde;
 pub(crate) mod world_shape;
+pub(crate) mod tile_filter;
+pub(crate) mod compass;
 
 /// Splits a string given an index from the end of the string.
 pub(crate) fn split_string_from_end(string: &str, char_index_from_end: usize) -> (&str, &str) {
@@ -109,8 +109,8 @@ impl TileFinder {
                 {
                     let west = point.x() - spacing;
                     let south = point.y() - spacing;
-                    let north = point.x() + spacing;
-                    let east = point.y() + spacing;
+                    let north = point.y() + spacing;
+                    let east = point.x() + spacing;
                     Boundary::between_points((west.into(),south.into()),(east.into(),north.into()))
                 }
             };
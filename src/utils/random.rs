@@ -3,17 +3,85 @@ use rand::SeedableRng;
 use rand::Rng;
 
 use crate::commands::RandomSeedArg;
-
-pub(crate) fn random_number_generator(arg: &RandomSeedArg) -> StdRng {
-    let seed = if let Some(seed) = arg.seed {
+use crate::commands::PeopleSeedArg;
+use crate::errors::CommandError;
+use crate::progress::ProgressObserver;
+
+// The labels of the stages of generation which use random numbers. Each stage gets its own seed derived from the one given, so the stages can be run as separate commands and still generate the same world as `big-bang`.
+pub(crate) const CREATE_STAGE: &str = "create";
+pub(crate) const TERRAIN_STAGE: &str = "terrain";
+pub(crate) const NAMERS_STAGE: &str = "namers";
+pub(crate) const PEOPLE_STAGE: &str = "gen_people";
+pub(crate) const TOWNS_STAGE: &str = "gen_towns";
+pub(crate) const NATIONS_STAGE: &str = "gen_nations";
+pub(crate) const SUBNATIONS_STAGE: &str = "gen_subnations";
+pub(crate) const LAKE_NAMES_STAGE: &str = "name_lakes";
+pub(crate) const DEV_STAGE: &str = "dev";
+
+pub(crate) fn random_seed(arg: &RandomSeedArg) -> u64 {
+    if let Some(seed) = arg.seed {
         seed
     } else {
         let mut seeder = StdRng::from_entropy();
         let seed = seeder.gen::<u64>();
         println!("Using random seed {seed}");
         seed
-    };
-    StdRng::seed_from_u64(seed)
+    }
+}
+
+/// The generator for a stage of generation, seeded with the seed derived for that stage.
+pub(crate) fn random_number_generator(arg: &RandomSeedArg, stage: &str) -> StdRng {
+    StdRng::seed_from_u64(arg.derive(stage))
+}
+
+/// The generator for a stage of generation, for when the seed has already been chosen. Commands which need more than one generator choose the seed once, so a random seed that was printed reproduces all of them.
+pub(crate) fn stage_random_number_generator(seed: u64, stage: &str) -> StdRng {
+    StdRng::seed_from_u64(derive_stage_seed(seed, stage))
+}
+
+/// Derives the seed for one stage of generation from a seed and the label of the stage. The same seed and label always derive the same seed.
+pub(crate) fn derive_stage_seed(seed: u64, stage: &str) -> u64 {
+    // FNV-1a, which unlike the standard library hashers is guaranteed not to change between versions of rust.
+    let hash = stage.bytes().fold(seed ^ 0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    // similar labels hash to similar numbers, so the result is scrambled before it's used.
+    StdRng::seed_from_u64(hash).gen::<u64>()
+}
+
+/// The seed to try after generating with `seed` failed. This is deterministic, so the same original seed always retries with the same seeds.
+pub(crate) fn derive_retry_seed(seed: u64) -> u64 {
+    StdRng::seed_from_u64(seed).gen::<u64>()
+}
+
+/// Calls `attempt` with the seed for retry number `replay_retry` of `seed`, which is `seed` itself for 0, and again with the seeds for the following retries up to `retries` more times as long as it fails with a data-dependent error. Any other error is returned immediately, and panics are not caught. Returns the number of the retry that succeeded.
+///
+/// Anything loaded with the original seed before the attempts is not derived again, so a retry can only be reproduced by replaying it from the original seed, not by passing the derived seed as a new seed.
+pub(crate) fn retry_with_derived_seeds<Progress: ProgressObserver, Attempt: FnMut(u64, usize, &mut Progress) -> Result<(),CommandError>>(seed: u64, replay_retry: usize, retries: usize, progress: &mut Progress, mut attempt: Attempt) -> Result<usize,CommandError> {
+    let mut attempt_seed = (0..replay_retry).fold(seed, |attempt_seed, _| derive_retry_seed(attempt_seed));
+    let mut retry = replay_retry;
+    loop {
+        match attempt(attempt_seed, retry, progress) {
+            Ok(()) => {
+                if retry > replay_retry {
+                    progress.message(|| format!("Generation succeeded on retry {retry} of seed {seed}. Run with '--seed {seed} --replay-retry {retry}' to generate it again."));
+                }
+                return Ok(retry)
+            },
+            Err(err) if ((retry - replay_retry) < retries) && err.is_data_dependent() => {
+                progress.warning(|| format!("Generation on retry {retry} of seed {seed} failed: {err} Trying again with retry {}.",retry + 1));
+                attempt_seed = derive_retry_seed(attempt_seed);
+                retry += 1;
+            },
+            Err(err) => return Err(err)
+        }
+    }
+}
+
+/// The seed the stages for the human layers are derived from, so they can be rerolled without changing the physical geography, which is derived from the seed passed here.
+pub(crate) const fn people_seed(arg: &PeopleSeedArg, seed: u64) -> u64 {
+    match arg.people_seed {
+        Some(people_seed) => people_seed,
+        None => seed
+    }
 }
 
 pub(crate) trait RandomNth<ItemType> {
@@ -99,3 +167,75 @@ impl<ItemType, const N: usize> RandomIndex<ItemType> for [ItemType; N] {
     }
 
 }
+
+
+#[cfg(test)]
+mod test {
+    use super::derive_retry_seed;
+    use super::retry_with_derived_seeds;
+    use super::derive_stage_seed;
+    use super::PEOPLE_STAGE;
+    use super::TOWNS_STAGE;
+    use crate::errors::CommandError;
+    use crate::commands::RandomSeedArg;
+
+    #[test]
+    fn test_retry_with_derived_seeds() {
+        let first_retry = derive_retry_seed(42);
+        let second_retry = derive_retry_seed(first_retry);
+        assert_ne!(first_retry, 42);
+
+        // the original and the first derived seed "fail" on their geometry, the second derived seed works
+        let mut attempted = Vec::new();
+        let succeeded = retry_with_derived_seeds(42, 0, 3, &mut (), |seed, _, ()| {
+            attempted.push(seed);
+            if seed == second_retry {
+                Ok(())
+            } else {
+                Err(CommandError::CantCalculateCentroid)
+            }
+        }).expect("Retry should have succeeded.");
+        assert_eq!(succeeded, 2);
+        assert_eq!(attempted, vec![42, first_retry, second_retry]);
+
+        // replaying the retry that succeeded starts with its seed
+        let mut replayed = Vec::new();
+        let replayed_retry = retry_with_derived_seeds(42, succeeded, 0, &mut (), |seed, retry, ()| {
+            replayed.push((seed,retry));
+            Ok(())
+        }).expect("Replay should have succeeded.");
+        assert_eq!(replayed_retry, 2);
+        assert_eq!(replayed, vec![(second_retry,2)]);
+
+        // retries are limited
+        let mut limited_attempts = Vec::new();
+        _ = retry_with_derived_seeds(42, 0, 1, &mut (), |seed, _, ()| {
+            limited_attempts.push(seed);
+            Err(CommandError::CantCalculateCentroid)
+        }).unwrap_err();
+        assert_eq!(limited_attempts, vec![42, first_retry]);
+
+        // errors that aren't caused by the data are not retried
+        let mut unretried_attempts = Vec::new();
+        match retry_with_derived_seeds(42, 0, 3, &mut (), |seed, _, ()| {
+            unretried_attempts.push(seed);
+            Err(CommandError::UnknownNamer("missing".to_owned()))
+        }) {
+            Err(CommandError::UnknownNamer(_)) => (),
+            _ => panic!("Non-data-dependent error should have been returned.")
+        }
+        assert_eq!(unretried_attempts, vec![42]);
+    }
+
+    #[test]
+    fn test_derive_stage_seed() {
+        // the same seed and stage always derive the same seed, which is how separate commands match big-bang
+        assert_eq!(derive_stage_seed(42, PEOPLE_STAGE), derive_stage_seed(42, PEOPLE_STAGE));
+        assert_eq!(RandomSeedArg { seed: Some(42) }.derive(PEOPLE_STAGE), derive_stage_seed(42, PEOPLE_STAGE));
+
+        // different stages and different seeds get different seeds
+        assert_ne!(derive_stage_seed(42, PEOPLE_STAGE), derive_stage_seed(42, TOWNS_STAGE));
+        assert_ne!(derive_stage_seed(42, PEOPLE_STAGE), derive_stage_seed(43, PEOPLE_STAGE));
+        assert_ne!(derive_stage_seed(42, PEOPLE_STAGE), 42);
+    }
+}
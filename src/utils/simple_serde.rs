@@ -348,6 +348,17 @@ impl Deserializer for Peekable<Tokenizer<'_>> {
 
 }
 
+/// Formats a float for serialization. Rust's own formatting is used, rather than anything from the platform, and it always writes the shortest decimal
+/// which parses back to the same value, without exponents. So the output only depends on the value. The one exception is negative zero, which
+/// is written the same as zero, since it can come out of floating point math on some platforms and not others.
+fn format_float(number: f64) -> String {
+    if number == 0.0 {
+        "0".to_owned()
+    } else {
+        number.to_string()
+    }
+}
+
 pub(crate) trait Serializer: Sized {
 
     fn write_token(&mut self, token: Token);
@@ -366,7 +377,7 @@ impl Serializer for String {
             Token::Colon => self.push(':'),
             Token::Comma => self.push(','),
             Token::Whitespace => self.push(' '),
-            Token::Float(number) => self.push_str(&number.to_string()),
+            Token::Float(number) => self.push_str(&format_float(number)),
             Token::Integer(number) => self.push_str(&number.to_string()),
             Token::SignedInteger(number) => self.push_str(&number.to_string()),
             Token::String(string) => {
@@ -494,6 +505,8 @@ impl_simple_serde_tuple!(Item1);
 
 impl_simple_serde_tuple!(Item1,Item2);
 
+impl_simple_serde_tuple!(Item1,Item2,Item3);
+
 impl Serialize for f64 {
     fn write_value<Target: Serializer>(&self, serializer: &mut Target) {
         serializer.write_token(Token::Float(*self))
@@ -738,6 +751,8 @@ mod test {
     #[test]
     fn test_serde_biome_criteria() {
         test_serializing(&BiomeCriteria::Glacier(-4.0), "Glacier(-4)");
+        test_serializing(&BiomeCriteria::Barren(3000.0,-2.5), "Barren(3000,-2.5)");
+        test_serializing(&BiomeCriteria::OceanZone(200.0,20.0,40.0), "OceanZone(200,20,40)");
         test_serializing(&BiomeCriteria::Matrix(vec![(23,24),(12,20),(13,4)]), "Matrix([(23,24),(12,20),(13,4)])")
     }
 
@@ -747,4 +762,16 @@ mod test {
     }
 
 
+    #[test]
+    fn test_serde_float() {
+        test_serializing(&0.1, "0.1");
+        test_serializing(&45.6, "45.6");
+        test_serializing(&-4.0, "-4");
+        test_serializing(&0.0, "0");
+        test_serializing(&-0.0, "0");
+        test_serializing(&1.0e-7, "0.0000001");
+        test_serializing(&123_456.789, "123456.789");
+        test_serializing(&(1.0/3.0), "0.3333333333333333");
+    }
+
 }
@@ -0,0 +1,46 @@
+use crate::utils::arg_range::ArgRange;
+use crate::utils::coordinates::Coordinates;
+use crate::utils::extent::Extent;
+
+/// Limits a process to the tiles whose sites are within a band of latitudes and a region. Without either, every tile is included.
+#[derive(Clone,Default)]
+pub(crate) struct TileFilter {
+    latitudes: Option<ArgRange<f64>>,
+    region: Option<Extent>
+}
+
+impl TileFilter {
+
+    pub(crate) const fn new(latitudes: Option<ArgRange<f64>>, region: Option<Extent>) -> Self {
+        Self {
+            latitudes,
+            region
+        }
+    }
+
+    pub(crate) fn includes(&self, site: &Coordinates) -> bool {
+        self.latitudes.as_ref().is_none_or(|latitudes| latitudes.includes(&site.y())) &&
+        self.region.as_ref().is_none_or(|region| region.contains(site))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TileFilter;
+    use crate::utils::coordinates::Coordinates;
+    use crate::utils::extent::Extent;
+
+    #[test]
+    fn test_tile_filter() {
+        let site = |x: f64, y: f64| -> Coordinates { (x,y).try_into().unwrap() };
+
+        assert!(TileFilter::default().includes(&site(-170.0,-80.0)));
+
+        let filter = TileFilter::new(Some("-23.5..=23.5".parse().unwrap()), Some(Extent::from_bounds(-20.0, -40.0, 50.0, 40.0)));
+        assert!(filter.includes(&site(10.0,0.0)));
+        // inside the region, outside the latitudes
+        assert!(!filter.includes(&site(10.0,30.0)));
+        // inside the latitudes, outside the region
+        assert!(!filter.includes(&site(60.0,0.0)));
+    }
+}
@@ -30,8 +30,10 @@ pub(crate) struct BiomeDefault {
 pub(crate) struct BiomeMatrix {
     matrix: [[String; 26]; 5],
     ocean: String,
+    ocean_zones: Vec<(String,f64,f64,f64)>,
     glacier: (String,f64),
-    wetland: (String,f64)
+    wetland: (String,f64),
+    barren: Option<(String,f64,f64)>
 }
 
 impl BiomeMatrix {
@@ -39,6 +41,10 @@ impl BiomeMatrix {
         &self.ocean
     }
     
+    pub(crate) fn ocean_zones(&self) -> &[(String,f64,f64,f64)] {
+        &self.ocean_zones
+    }
+
     pub(crate) const fn glacier(&self) -> &(String,f64) {
         &self.glacier
     }
@@ -46,6 +52,10 @@ impl BiomeMatrix {
     pub(crate) const fn wetland(&self) -> &(String,f64) {
         &self.wetland
     }
+
+    pub(crate) const fn barren(&self) -> Option<&(String,f64,f64)> {
+        self.barren.as_ref()
+    }
     
     pub(crate) const fn matrix(&self) -> &[[String; 26]; 5] {
         &self.matrix
@@ -105,6 +115,7 @@ impl BiomeSchema {
     pub(crate) const TUNDRA: &'static str = "Tundra";
     pub(crate) const GLACIER: &'static str = "Glacier";
     pub(crate) const WETLAND: &'static str = "Wetland";
+    pub(crate) const BARREN: &'static str = "Barren";
 
     pub(crate) const DEFAULT_BIOMES: [BiomeDefault; 13] = [ // name, index, habitability, supports_nomadic, supports_hunting
         BiomeDefault { name: Self::OCEAN, habitability: 0, criteria: BiomeCriteria::Ocean, movement_cost: 10, supports_nomadic: false, supports_hunting: false, color: (0x1F, 0x78, 0xB4)},
@@ -122,6 +133,9 @@ impl BiomeSchema {
         BiomeDefault { name: Self::WETLAND, habitability: 12, criteria: BiomeCriteria::Wetland(400.0), movement_cost: 150, supports_nomadic: false, supports_hunting: true, color: (0x0B, 0x91, 0x31)},
     ];
 
+    // This one is only included if requested.
+    pub(crate) const BARREN_DEFAULT: BiomeDefault = BiomeDefault { name: Self::BARREN, habitability: 0, criteria: BiomeCriteria::Barren(3000.0,0.0), movement_cost: 1000, supports_nomadic: false, supports_hunting: false, color: (0x9E, 0x9A, 0x93)};
+
     //these constants make the default matrix easier to read.
     pub(crate) const HDT: &'static str = Self::HOT_DESERT;
     pub(crate) const CDT: &'static str = Self::COLD_DESERT;
@@ -159,7 +173,8 @@ impl BiomeSchema {
         }
 
         // now insert the matrix numbers into the output biomes criteria fields and return the biome entities.
-        Self::DEFAULT_BIOMES.iter().map(|default| {
+        let barren = override_criteria.barren_biome.then_some(&Self::BARREN_DEFAULT);
+        Self::DEFAULT_BIOMES.iter().chain(barren).map(|default| {
             let criteria = match default.criteria {
                 BiomeCriteria::Matrix(_) => {
                     BiomeCriteria::Matrix(matrix_criteria.get(&default.name).expect("Someone messed up the default biome constants.").clone())
@@ -178,6 +193,9 @@ impl BiomeSchema {
                         default.criteria.clone()
                     }
                 },
+                BiomeCriteria::Barren(elevation,temperature) => {
+                    BiomeCriteria::Barren(override_criteria.min_barren_elevation.unwrap_or(elevation),override_criteria.max_barren_temp.unwrap_or(temperature))
+                },
                 _ => default.criteria.clone(),
             };
             NewBiome {
@@ -202,6 +220,8 @@ impl BiomeSchema {
         let mut wetland = None;
         let mut glacier = None;
         let mut ocean = None;
+        let mut barren = None;
+        let mut ocean_zones = Vec::new();
         for biome in biomes {
             match &biome.criteria {
                 BiomeCriteria::Matrix(list) => {
@@ -225,11 +245,17 @@ impl BiomeSchema {
                 } else {
                     glacier = Some((biome.name.clone(),*temperature))
                 },
+                BiomeCriteria::Barren(elevation,temperature) => if barren.is_some() {
+                    return Err(CommandError::DuplicateBarrenBiome)
+                } else {
+                    barren = Some((biome.name.clone(),*elevation,*temperature))
+                },
                 BiomeCriteria::Ocean => if ocean.is_some() {
                     return Err(CommandError::DuplicateOceanBiome)
                 } else {
                     ocean = Some(biome.name.clone())
-                }
+                },
+                BiomeCriteria::OceanZone(max_depth,min_temperature,max_temperature) => ocean_zones.push((biome.name.clone(),*max_depth,*min_temperature,*max_temperature))
             }
 
         }
@@ -247,8 +273,10 @@ impl BiomeSchema {
         Ok(BiomeMatrix { 
             matrix, 
             ocean, 
+            ocean_zones,
             glacier, 
-            wetland 
+            wetland,
+            barren
         })
     }
 
@@ -282,6 +310,17 @@ entity!(BiomeForCultureExpand: Biome {
     movement_cost: i32
 });
 
+impl BiomeForCultureExpand {
+
+    #[cfg(test)]
+    pub(crate) const fn new(name: String, movement_cost: i32) -> Self {
+        Self {
+            name,
+            movement_cost
+        }
+    }
+}
+
 impl NamedEntity<BiomeSchema> for BiomeForCultureExpand {
     fn name(&self) -> &String {
         &self.name
@@ -299,6 +338,28 @@ impl NamedEntity<BiomeSchema> for BiomeForNationExpand {
     }
 }
 
+entity!(BiomeForRoutes: Biome {
+    #[get=false] name: String,
+    movement_cost: i32
+});
+
+impl BiomeForRoutes {
+
+    #[cfg(test)]
+    pub(crate) const fn new(name: String, movement_cost: i32) -> Self {
+        Self {
+            name,
+            movement_cost
+        }
+    }
+}
+
+impl NamedEntity<BiomeSchema> for BiomeForRoutes {
+    fn name(&self) -> &String {
+        &self.name
+    }
+}
+
 entity!(BiomeForDissolve: Biome {
     fid: IdRef,
     #[get=false] name: String
@@ -310,6 +371,17 @@ impl NamedEntity<BiomeSchema> for BiomeForDissolve {
     }
 }
 
+entity!(BiomeForPreview: Biome {
+    #[get=false] name: String,
+    color: Rgb<u8>
+});
+
+impl NamedEntity<BiomeSchema> for BiomeForPreview {
+    fn name(&self) -> &String {
+        &self.name
+    }
+}
+
 impl BiomeLayer<'_,'_> {
 
     pub(crate) fn add_biome(&mut self, biome: &NewBiome) -> Result<IdRef,CommandError> {
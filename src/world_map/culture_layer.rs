@@ -23,7 +23,7 @@ layer!(Culture["cultures"]: MultiPolygon {
     #[set(allow(dead_code))] type_: CultureType,
     #[set(allow(dead_code))] expansionism: f64,
     #[set(allow(dead_code))] center_tile_id: IdRef,
-    #[get(allow(dead_code))] #[set(allow(dead_code))] color: Rgb<u8>,
+    #[set(allow(dead_code))] color: Rgb<u8>,
 });
 
 impl<'feature> NamedFeature<'feature,CultureSchema> for CultureFeature<'feature> {
@@ -60,6 +60,19 @@ entity!(#[derive(Hash,Eq,PartialEq)] CultureForPlacement: Culture {
     expansionism: OrderedFloat<f64> = |feature: &CultureFeature| Ok::<_,CommandError>(OrderedFloat::from(feature.expansionism()?))
 });
 
+impl CultureForPlacement {
+
+    #[cfg(test)]
+    pub(crate) const fn new(name: String, center_tile_id: IdRef, type_: CultureType, expansionism: OrderedFloat<f64>) -> Self {
+        Self {
+            name,
+            center_tile_id,
+            type_,
+            expansionism
+        }
+    }
+}
+
 entity!(CultureForTowns: Culture {
     #[get=false] name: String,
     #[get=false] namer: String
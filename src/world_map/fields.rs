@@ -9,6 +9,7 @@ use crate::errors::CommandError;
 use crate::impl_documentation_for_tagged_enum;
 use crate::impl_simple_serde_tagged_enum;
 use crate::utils::edge::Edge;
+use crate::utils::compass::Compass;
 use crate::utils::simple_serde::Deserialize;
 use crate::utils::simple_serde::Deserializer;
 use crate::utils::simple_serde::Serialize;
@@ -203,6 +204,14 @@ impl Deserialize for Neighbor {
 #[derive(Clone,PartialEq,Debug)]
 pub(crate) struct NeighborAndDirection(pub Neighbor,pub Deg<f64>);
 
+impl NeighborAndDirection {
+
+    /// The direction as a compass point, for readable output.
+    pub(crate) fn compass(&self) -> Compass {
+        self.1.into()
+    }
+}
+
 impl TypedField for Vec<NeighborAndDirection> {
 
     const STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTString;
@@ -586,6 +595,62 @@ impl From<&RiverSegmentTo> for String {
     }
 }
 
+#[derive(Clone,PartialEq,Debug)]
+pub(crate) enum RouteType {
+    Land,
+    Sea,
+}
+
+impl TypedField for RouteType {
+
+    const STORAGE_TYPE: OGRFieldType::Type = OGRFieldType::OFTString;
+
+    fn get_field(feature: &Feature, field_name: &str, field_id: &'static str) -> Result<Self,CommandError> {
+        Deserialize::read_from_str(&Self::get_required(feature.field_as_string_by_name(field_name)?, field_id)?)
+    }
+
+    fn set_field(&self, feature: &mut Feature, field_name: &str) -> Result<(),CommandError> {
+        Ok(feature.set_field_string(field_name, &self.write_to_string())?)
+    }
+
+    fn to_field_value(&self) -> Result<Option<FieldValue>,CommandError> {
+        Ok(Some(FieldValue::StringValue(self.write_to_string())))
+    }
+
+}
+
+impl_simple_serde_tagged_enum!{
+    RouteType {
+        Land,
+        Sea,
+    }
+}
+
+impl_documentation_for_tagged_enum!{
+    /// A name for how a route travels between towns
+    RouteType {
+        /// The route is a road over land
+        Land,
+        /// The route is a shipping lane between ports
+        Sea,
+    }
+}
+
+impl TryFrom<String> for RouteType {
+    type Error = CommandError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Deserialize::read_from_str(&value).map_err(|e| CommandError::InvalidValueForRouteType(value,format!("{e}")))
+    }
+}
+
+impl From<&RouteType> for String {
+
+    fn from(value: &RouteType) -> Self {
+        value.write_to_string()
+    }
+}
+
 #[derive(Clone,PartialEq,Debug)]
 pub(crate) enum LakeType {
     Fresh,
@@ -668,7 +733,9 @@ pub(crate) enum BiomeCriteria {
     Matrix(Vec<(usize,usize)>), // moisture band, temperature band
     Wetland(f64), // waterflow above which climate is considered wetland
     Glacier(f64), // temperature below which climate is considered glacier
-    Ocean
+    Barren(f64,f64), // elevation at and above which, and temperature at and below which, land is considered barren
+    Ocean,
+    OceanZone(f64,f64,f64) // depth at and above which, and temperatures between which, ocean gets this biome instead of the ocean biome
 }
 
 impl DocumentedFieldType for (usize,usize) {
@@ -689,10 +756,14 @@ impl_documentation_for_tagged_enum!{
     BiomeCriteria {
         /// This biome should be used for glacier -- only one is allowed
         Glacier(temp: f64),
+        /// This biome should be used for land at or above the elevation and at or below the temperature, before the matrix is checked -- only one is allowed
+        Barren(elevation: f64, temp: f64),
         /// The biome should be placed in the following locations in the moisture and temperature matrix -- coordinates must not be used for another biome
         Matrix(list: Vec<(usize,usize)>),
         /// The biome should be used for ocean -- only one is allowed
         Ocean,
+        /// The biome should be used for ocean no deeper than the depth, with a temperature from the minimum to the maximum, instead of the ocean biome -- more than one is allowed, the first that matches is used
+        OceanZone(max_depth: f64, min_temp: f64, max_temp: f64),
         /// The biome should be used for wetland -- only one is allowed
         Wetland(water_flow: f64),
     }
@@ -721,8 +792,10 @@ impl TypedField for BiomeCriteria {
 impl_simple_serde_tagged_enum!{
     BiomeCriteria {
         Glacier(temp: f64),
+        Barren(elevation: f64, temp: f64),
         Matrix(list: Vec<(usize,usize)>),
         Ocean,
+        OceanZone(max_depth: f64, min_temp: f64, max_temp: f64),
         Wetland(water_flow: f64),
     }
 }
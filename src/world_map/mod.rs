@@ -1,3 +1,6 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -6,6 +9,20 @@ use gdal::DatasetOptions;
 use gdal::DriverManager;
 use gdal::GdalOpenFlags;
 use gdal::vector::Transaction;
+use gdal::vector::LayerAccess;
+use gdal::vector::geometry_type_to_name;
+use gdal::vector::sql::Dialect;
+use gdal::vector::FieldValue;
+use gdal::vector::Feature;
+use gdal::vector::LayerOptions;
+use gdal::vector::OGRFieldType;
+use gdal::vector::OGRwkbGeometryType;
+use gdal::spatial_ref::SpatialRef;
+use gdal::spatial_ref::CoordTransform;
+use gdal::spatial_ref::AxisMappingStrategy;
+use serde_json::Map;
+use serde_json::Value;
+use angular_units::Deg;
 
 use crate::commands::OverwriteBiomesArg;
 use crate::commands::OverwriteCoastlineArg;
@@ -14,11 +31,19 @@ use crate::commands::OverwriteLakesArg;
 use crate::commands::OverwriteNationsArg;
 use crate::commands::OverwriteOceanArg;
 use crate::commands::OverwriteRiversArg;
+use crate::commands::OverwriteRoutesArg;
 use crate::commands::OverwriteSubnationsArg;
 use crate::commands::OverwriteTilesArg;
 use crate::commands::OverwriteTownsArg;
 use crate::errors::CommandError;
 use crate::progress::ProgressObserver;
+use crate::progress::WatchableIterator;
+use crate::typed_map::layers::has_layer;
+use crate::typed_map::schema::Schema;
+use crate::utils::compass::Compass;
+use crate::utils::simple_serde::Deserialize;
+use crate::utils::simple_serde::Serialize;
+use crate::world_map::fields::NeighborAndDirection;
 use crate::world_map::auxiliary_layers::PointLayer;
 use crate::world_map::auxiliary_layers::TriangleLayer;
 use crate::world_map::biome_layer::BiomeLayer;
@@ -26,7 +51,10 @@ use crate::world_map::culture_layer::CultureLayer;
 use crate::world_map::nation_layers::NationLayer;
 use crate::world_map::nation_layers::SubnationLayer;
 use crate::world_map::property_layer::PropertyLayer;
+use crate::world_map::tile_attribute_layer::TileAttributeLayer;
+use crate::world_map::route_layer::RouteLayer;
 use crate::world_map::tile_layer::TileLayer;
+use crate::world_map::tile_layer::TileSchema;
 use crate::world_map::town_layer::TownLayer;
 use crate::world_map::water_layers::CoastlineLayer;
 use crate::world_map::water_layers::LakeLayer;
@@ -56,6 +84,8 @@ pub(crate) mod culture_layer;
 pub(crate) mod town_layer;
 pub(crate) mod nation_layers;
 pub(crate) mod property_layer;
+pub(crate) mod route_layer;
+pub(crate) mod tile_attribute_layer;
 
 
 /*
@@ -76,6 +106,73 @@ impl LineLayer<'_,'_> {
 
 
 
+/// A summary of one layer in the world map file, as reported by the `info` command.
+pub(crate) struct LayerInfo {
+    name: String,
+    geometry_type: String,
+    feature_count: u64,
+    known: bool
+}
+
+impl LayerInfo {
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn geometry_type(&self) -> &str {
+        &self.geometry_type
+    }
+
+    pub(crate) const fn feature_count(&self) -> u64 {
+        self.feature_count
+    }
+
+    /// False if the layer isn't one that Cosmopoeia creates.
+    pub(crate) const fn known(&self) -> bool {
+        self.known
+    }
+}
+
+/// Labels the wind and neighbor directions of a tile feature with compass points, for reading in exports. The neighbors are a list of `<neighbor>:<compass point>` strings. Fields which haven't been generated yet are written as null.
+fn tile_compass_properties(feature: &Feature) -> Result<[(String,Value);2],CommandError> {
+    let wind = feature.field_as_double_by_name(TileSchema::FIELD_WIND)?.map(|wind| Compass::from(Deg(wind)).to_string());
+    let neighbors = feature.field_as_string_by_name(TileSchema::FIELD_NEIGHBORS)?.map(|neighbors| {
+        let neighbors: Vec<NeighborAndDirection> = Deserialize::read_from_str(&neighbors)?;
+        Ok::<_,CommandError>(neighbors.iter().map(|neighbor| format!("{}:{}",neighbor.0.write_to_string(),neighbor.compass())).collect::<Vec<_>>())
+    }).transpose()?;
+    Ok([
+        ("wind_compass".to_owned(),wind.map_or(Value::Null, Value::from)),
+        ("neighbors_compass".to_owned(),neighbors.map_or(Value::Null, Value::from))
+    ])
+}
+
+/// Converts a field value read from any layer into JSON. Dates are written as strings.
+fn field_value_to_json(value: FieldValue) -> Value {
+    match value {
+        FieldValue::IntegerValue(value) => value.into(),
+        FieldValue::IntegerListValue(values) => values.into(),
+        FieldValue::Integer64Value(value) => value.into(),
+        FieldValue::Integer64ListValue(values) => values.into(),
+        FieldValue::StringValue(value) => value.into(),
+        FieldValue::StringListValue(values) => values.into(),
+        // non-finite reals, which JSON can't represent, become null.
+        FieldValue::RealValue(value) => value.into(),
+        FieldValue::RealListValue(values) => values.into(),
+        FieldValue::DateValue(value) => value.to_string().into(),
+        FieldValue::DateTimeValue(value) => value.to_string().into(),
+    }
+}
+
+/// Maps can be created to store id references as integers. This is recorded in the properties, if they have been created yet.
+fn uses_integer_ids(dataset: &Dataset) -> Result<bool,CommandError> {
+    if has_layer(dataset, PropertyLayer::layer_name()) {
+        PropertyLayer::open_from_dataset(dataset)?.get_integer_ids()
+    } else {
+        Ok(false)
+    }
+}
+
 pub(crate) struct WorldMap {
     //path: PathBuf, Removed after reedit bug was fixed
     dataset: Dataset
@@ -144,6 +241,25 @@ impl WorldMap {
         Ok(())
     }
 
+    /// Rebuilds the file to reclaim the space left behind by overwritten and deleted layers. This can't be done during a transaction.
+    pub(crate) fn compact<Progress: ProgressObserver>(&self, progress: &mut Progress) -> Result<(),CommandError> {
+        progress.start_unknown_endpoint(|| "Compacting map."); 
+        // VACUUM doesn't return any rows, so there's no result set to look at.
+        _ = self.dataset.execute_sql("VACUUM", None, Dialect::DEFAULT)?;
+        progress.finish(|| "Map compacted."); 
+        Ok(())
+    }
+
+    /// Deletes every known layer which isn't in the list, leaving out any layers which cosmopoeia doesn't create. Returns the number of layers deleted.
+    pub(crate) fn delete_layers_except<Progress: ProgressObserver>(&self, keep: &[&str], progress: &mut Progress) -> Result<usize,CommandError> {
+        let deleting: Vec<String> = self.dataset.layers().map(|layer| layer.name()).filter(|name| Self::known_layer_names().contains(&name.as_str()) && !keep.contains(&name.as_str())).collect();
+        for layer_name in deleting.iter().watch(progress,"Deleting unwanted layers.","Unwanted layers deleted.") {
+            // The GeoPackage driver handles this itself, so the layer is also removed from the file's table of contents. It doesn't return any rows.
+            _ = self.dataset.execute_sql(format!("DROP TABLE \"{layer_name}\""), None, Dialect::DEFAULT)?;
+        }
+        Ok(deleting.len())
+    }
+
     pub(crate) fn points_layer(&self) -> Result<PointLayer,CommandError> {
         PointLayer::open_from_dataset(&self.dataset)
     }
@@ -160,6 +276,198 @@ impl WorldMap {
         CultureLayer::open_from_dataset(&self.dataset)
     }
 
+    pub(crate) fn rivers_layer(&self) -> Result<RiverLayer, CommandError> {
+        RiverLayer::open_from_dataset(&self.dataset)
+    }
+
+    /// Returns true if the map contains a layer with the specified name.
+    pub(crate) fn has_layer(&self, layer_name: &str) -> bool {
+        has_layer(&self.dataset, layer_name)
+    }
+
+    /// Adds fields to the layers in the map which were added to their schemas since the map was created. Layers which don't exist are skipped, and no data is calculated for the new fields.
+    pub(crate) fn migrate<Progress: ProgressObserver>(&self, progress: &mut Progress) -> Result<usize,CommandError> {
+
+        let integer_ids = uses_integer_ids(&self.dataset)?;
+
+        macro_rules! add_missing_fields {
+            ($($layer: ident),*) => {
+                [$(
+                    ($layer::layer_name(),$layer::add_missing_fields(&self.dataset,integer_ids)?)
+                ),*]
+            };
+        }
+
+        progress.start_unknown_endpoint(|| "Adding missing fields.");
+        let added = add_missing_fields!(PointLayer,TriangleLayer,TileLayer,RiverLayer,LakeLayer,BiomeLayer,CultureLayer,TownLayer,NationLayer,SubnationLayer,RouteLayer,CoastlineLayer,OceanLayer,PropertyLayer,TileAttributeLayer);
+        progress.finish(|| "Missing fields added.");
+
+        let mut added_count = 0;
+        for (layer_name,fields) in added {
+            if !fields.is_empty() {
+                progress.message(|| format!("Added fields to {layer_name}: {}",fields.join(", ")));
+                added_count += fields.len();
+            }
+        }
+        Ok(added_count)
+    }
+
+    /// The names of all of the layers Cosmopoeia knows about, whether or not they are in the file.
+    pub(crate) const fn known_layer_names() -> [&'static str; 15] {
+        [
+            PointLayer::layer_name(),
+            TriangleLayer::layer_name(),
+            TileLayer::layer_name(),
+            RiverLayer::layer_name(),
+            LakeLayer::layer_name(),
+            BiomeLayer::layer_name(),
+            CultureLayer::layer_name(),
+            TownLayer::layer_name(),
+            NationLayer::layer_name(),
+            SubnationLayer::layer_name(),
+            RouteLayer::layer_name(),
+            CoastlineLayer::layer_name(),
+            OceanLayer::layer_name(),
+            PropertyLayer::layer_name(),
+            TileAttributeLayer::layer_name()
+        ]
+    }
+
+    /// Lists every layer in the file, in the order they are stored, including any that weren't created by Cosmopoeia.
+    pub(crate) fn layer_info(&self) -> Vec<LayerInfo> {
+        let known_names = Self::known_layer_names();
+        self.dataset.layers().map(|layer| {
+            let name = layer.name();
+            // layers without geometry, such as properties, have no geometry fields at all.
+            let geometry_type = layer.defn().geom_fields().next().map_or_else(|| "None".to_owned(), |field| geometry_type_to_name(field.field_type()));
+            LayerInfo {
+                known: known_names.contains(&name.as_str()),
+                feature_count: layer.feature_count(),
+                geometry_type,
+                name
+            }
+        }).collect()
+    }
+
+    /// Writes any layer in the file to a GeoJSON file, one feature at a time. Layers without geometry, such as properties, are written as a plain array of objects instead of a feature collection. Returns the number of features written.
+    pub(crate) fn write_layer_geojson<Progress: ProgressObserver>(&self, layer_name: &str, output: &Path, compass: bool, progress: &mut Progress) -> Result<usize,CommandError> {
+        // checked before creating the file, so a mistyped layer name doesn't leave an empty file behind.
+        if !has_layer(&self.dataset, layer_name) {
+            return Err(CommandError::UnknownLayer(layer_name.to_owned()))
+        }
+        let mut layer = self.dataset.layer_by_name(layer_name)?;
+        let mut writer = BufWriter::new(File::create(output)?);
+        let has_geometry = layer.defn().geom_fields().next().is_some();
+        let feature_count = layer.feature_count() as usize;
+
+        if has_geometry {
+            writer.write_all(br#"{"type":"FeatureCollection","features":["#)?;
+        } else {
+            writer.write_all(b"[")?;
+        }
+
+        progress.start_known_endpoint(|| (format!("Writing {layer_name}."),feature_count));
+
+        let mut written = 0;
+        for feature in layer.features() {
+            if written > 0 {
+                writer.write_all(b",")?;
+            }
+
+            let mut properties = Map::new();
+            for (name,value) in feature.fields() {
+                _ = properties.insert(name, value.map_or(Value::Null, field_value_to_json));
+            }
+            if compass && (layer_name == TileSchema::LAYER_NAME) {
+                properties.extend(tile_compass_properties(&feature)?);
+            }
+
+            if has_geometry {
+                let geometry = feature.geometry().map(|geometry| Ok::<_,CommandError>(serde_json::from_str::<Value>(&geometry.json()?)?)).transpose()?;
+                serde_json::to_writer(&mut writer, &serde_json::json!({
+                    "type": "Feature",
+                    "id": feature.fid(),
+                    "geometry": geometry,
+                    "properties": properties
+                }))?;
+            } else {
+                serde_json::to_writer(&mut writer, &properties)?;
+            }
+
+            written += 1;
+            progress.update(|| written);
+        }
+
+        if has_geometry {
+            writer.write_all(b"]}")?;
+        } else {
+            writer.write_all(b"]")?;
+        }
+        writer.flush()?;
+
+        progress.finish(|| format!("{layer_name} written."));
+
+        Ok(written)
+    }
+
+    /// Writes a copy of the named layers to a new GeoPackage, with their geometries transformed to the coordinate system with the given EPSG code. An existing file at the output is replaced. Returns the number of features written.
+    pub(crate) fn write_reprojected_layers<Progress: ProgressObserver>(&self, layer_names: &[String], output: &Path, epsg: u32, progress: &mut Progress) -> Result<usize,CommandError> {
+        // checked before creating the file, so a mistyped layer name doesn't leave a partial copy behind.
+        if let Some(unknown) = layer_names.iter().find(|name| !has_layer(&self.dataset, name)) {
+            return Err(CommandError::UnknownLayer(unknown.clone()))
+        }
+
+        // the coordinates are always stored longitude first, even though the authority for 4326 says latitude comes first.
+        let mut target_srs = SpatialRef::from_epsg(epsg)?;
+        target_srs.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+
+        let driver = DriverManager::get_driver_by_name(Self::GDAL_DRIVER)?;
+        let mut output_dataset = driver.create_vector_only(output)?;
+        let mut transaction = output_dataset.start_transaction()?;
+
+        let mut written = 0;
+        for layer_name in layer_names {
+            let mut layer = self.dataset.layer_by_name(layer_name)?;
+            let geometry_type = layer.defn().geom_fields().next().map(|field| field.field_type());
+            let fields: Vec<(String,OGRFieldType::Type)> = layer.defn().fields().map(|field| (field.name(),field.field_type())).collect();
+
+            let transform = if geometry_type.is_some() {
+                let mut source_srs = layer.spatial_ref().map_or_else(|| SpatialRef::from_epsg(4326), Ok)?;
+                source_srs.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+                Some(CoordTransform::new(&source_srs, &target_srs)?)
+            } else {
+                // A few layers, such as properties, have no geometry to transform, and are just copied.
+                None
+            };
+
+            let output_layer = transaction.create_layer(LayerOptions {
+                name: layer_name,
+                ty: geometry_type.unwrap_or(OGRwkbGeometryType::wkbNone),
+                srs: transform.is_some().then_some(&target_srs),
+                options: None
+            })?;
+            output_layer.create_defn_fields(&fields.iter().map(|(name,field_type)| (name.as_str(),*field_type)).collect::<Vec<_>>())?;
+
+            for feature in layer.features().watch(progress,format!("Reprojecting {layer_name}."),format!("{layer_name} reprojected.")) {
+                let mut copy = Feature::new(output_layer.defn())?;
+                for (name,value) in feature.fields() {
+                    if let Some(value) = value {
+                        copy.set_field(&name, &value)?;
+                    }
+                }
+                if let (Some(transform),Some(geometry)) = (&transform,feature.geometry()) {
+                    copy.set_geometry(geometry.transform(transform)?)?;
+                }
+                copy.create(&output_layer)?;
+                written += 1;
+            }
+        }
+
+        transaction.commit()?;
+
+        Ok(written)
+    }
+
 
 
  
@@ -178,14 +486,23 @@ impl<'impl_life> WorldMapTransaction<'impl_life> {
         }
     }
 
-    pub(crate) fn create_points_layer(&mut self, overwrite: bool) -> Result<PointLayer,CommandError> {
-        PointLayer::create_from_dataset(&mut self.dataset, overwrite)       
+    fn integer_ids(&self) -> Result<bool,CommandError> {
+        uses_integer_ids(&self.dataset)
+    }
 
+    /// Returns true if the map contains a layer with the specified name.
+    pub(crate) fn has_layer(&self, layer_name: &str) -> bool {
+        has_layer(&self.dataset, layer_name)
     }
 
-    pub(crate) fn create_triangles_layer(&mut self, overwrite: bool) -> Result<TriangleLayer,CommandError> {
-        TriangleLayer::create_from_dataset(&mut self.dataset, overwrite)
+    pub(crate) fn create_points_layer(&mut self, overwrite: bool) -> Result<PointLayer,CommandError> {
+        let integer_ids = self.integer_ids()?;
+        PointLayer::create_from_dataset(&mut self.dataset, overwrite, integer_ids)
+    }
 
+    pub(crate) fn create_triangles_layer(&mut self, overwrite: bool) -> Result<TriangleLayer,CommandError> {
+        let integer_ids = self.integer_ids()?;
+        TriangleLayer::create_from_dataset(&mut self.dataset, overwrite, integer_ids)
     }
 
     pub(crate) fn edit_triangles_layer(&self) -> Result<TriangleLayer, CommandError> {
@@ -193,30 +510,50 @@ impl<'impl_life> WorldMapTransaction<'impl_life> {
     }
 
     pub(crate) fn create_tile_layer(&mut self, overwrite: &OverwriteTilesArg) -> Result<TileLayer,CommandError> {
-        TileLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_tiles)
-
+        let integer_ids = self.integer_ids()?;
+        TileLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_tiles, integer_ids)
     }
 
     pub(crate) fn create_rivers_layer(&mut self, overwrite: &OverwriteRiversArg) -> Result<RiverLayer,CommandError> {
-        RiverLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_rivers)
+        let integer_ids = self.integer_ids()?;
+        RiverLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_rivers, integer_ids)
+    }
 
+    pub(crate) fn edit_rivers_layer(&self) -> Result<RiverLayer,CommandError> {
+        RiverLayer::open_from_dataset(&self.dataset)
     }
 
     pub (crate) fn create_lakes_layer(&mut self, overwrite_layer: &OverwriteLakesArg) -> Result<LakeLayer,CommandError> {
-        LakeLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_lakes)
+        let integer_ids = self.integer_ids()?;
+        LakeLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_lakes, integer_ids)
     }
 
     pub (crate) fn edit_lakes_layer(&self) -> Result<LakeLayer,CommandError> {
         LakeLayer::open_from_dataset(&self.dataset)
     }
 
+    /// Drops fields which aren't part of a layer's schema, such as temporary debugging output. Returns the fields which were there to drop.
+    pub(crate) fn drop_extra_fields(&self, layer_name: &str, fields: &[&'static str]) -> Result<Vec<&'static str>,CommandError> {
+        if !has_layer(&self.dataset, layer_name) {
+            return Ok(Vec::new())
+        }
+        let existing: Vec<String> = self.dataset.layer_by_name(layer_name)?.defn().fields().map(|field| field.name()).collect();
+        let dropping: Vec<_> = fields.iter().filter(|name| existing.iter().any(|field| field == *name)).copied().collect();
+        for field in &dropping {
+            // OGR SQL passes this on to the driver's field deletion, and it doesn't return any rows.
+            _ = self.dataset.execute_sql(format!("ALTER TABLE \"{layer_name}\" DROP COLUMN \"{field}\""), None, Dialect::OGR)?;
+        }
+        Ok(dropping)
+    }
+
     pub(crate) fn edit_tile_layer(&self) -> Result<TileLayer,CommandError> {
         TileLayer::open_from_dataset(&self.dataset)
 
     }
 
     pub(crate) fn create_biomes_layer(&mut self, overwrite: &OverwriteBiomesArg) -> Result<BiomeLayer,CommandError> {
-        BiomeLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_biomes)
+        let integer_ids = self.integer_ids()?;
+        BiomeLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_biomes, integer_ids)
     }
 
     pub(crate) fn edit_biomes_layer(&self) -> Result<BiomeLayer,CommandError> {
@@ -225,7 +562,8 @@ impl<'impl_life> WorldMapTransaction<'impl_life> {
     }
 
     pub(crate) fn create_cultures_layer(&mut self, overwrite: &OverwriteCulturesArg) -> Result<CultureLayer,CommandError> {
-        CultureLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_cultures)
+        let integer_ids = self.integer_ids()?;
+        CultureLayer::create_from_dataset(&mut self.dataset, overwrite.overwrite_cultures, integer_ids)
     }
 
     pub(crate) fn edit_cultures_layer(&self) -> Result<CultureLayer,CommandError> {
@@ -234,7 +572,8 @@ impl<'impl_life> WorldMapTransaction<'impl_life> {
     }
 
     pub(crate) fn create_towns_layer(&mut self, overwrite_layer: &OverwriteTownsArg) -> Result<TownLayer,CommandError> {
-        TownLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_towns)
+        let integer_ids = self.integer_ids()?;
+        TownLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_towns, integer_ids)
     }
 
     pub(crate) fn edit_towns_layer(&self) -> Result<TownLayer,CommandError> {
@@ -243,7 +582,8 @@ impl<'impl_life> WorldMapTransaction<'impl_life> {
     }
 
     pub(crate) fn create_nations_layer(&mut self, overwrite_layer: &OverwriteNationsArg) -> Result<NationLayer,CommandError> {
-        NationLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_nations)
+        let integer_ids = self.integer_ids()?;
+        NationLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_nations, integer_ids)
     }
 
     pub(crate) fn edit_nations_layer(&self) -> Result<NationLayer,CommandError> {
@@ -251,33 +591,74 @@ impl<'impl_life> WorldMapTransaction<'impl_life> {
     }
 
     pub(crate) fn create_subnations_layer(&mut self, overwrite_layer: &OverwriteSubnationsArg) -> Result<SubnationLayer,CommandError> {
-        SubnationLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_subnations)
+        let integer_ids = self.integer_ids()?;
+        SubnationLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_subnations, integer_ids)
     }
 
     pub(crate) fn edit_subnations_layer(&self) -> Result<SubnationLayer,CommandError> {
         SubnationLayer::open_from_dataset(&self.dataset)
     }
 
+    pub(crate) fn create_routes_layer(&mut self, overwrite_layer: &OverwriteRoutesArg) -> Result<RouteLayer,CommandError> {
+        let integer_ids = self.integer_ids()?;
+        RouteLayer::create_from_dataset(&mut self.dataset, overwrite_layer.overwrite_routes, integer_ids)
+    }
+
     pub(crate) fn create_coastline_layer(&mut self, overwrite_coastline: &OverwriteCoastlineArg) -> Result<CoastlineLayer,CommandError> {
-        CoastlineLayer::create_from_dataset(&mut self.dataset, overwrite_coastline.overwrite_coastline)
+        let integer_ids = self.integer_ids()?;
+        CoastlineLayer::create_from_dataset(&mut self.dataset, overwrite_coastline.overwrite_coastline, integer_ids)
     }
 
     pub(crate) fn create_ocean_layer(&mut self, overwrite_ocean: &OverwriteOceanArg) -> Result<OceanLayer,CommandError> {
-        OceanLayer::create_from_dataset(&mut self.dataset, overwrite_ocean.overwrite_ocean)
+        let integer_ids = self.integer_ids()?;
+        OceanLayer::create_from_dataset(&mut self.dataset, overwrite_ocean.overwrite_ocean, integer_ids)
+    }
+
+    pub(crate) fn edit_ocean_layer(&self) -> Result<OceanLayer,CommandError> {
+        OceanLayer::open_from_dataset(&self.dataset)
     }
 
     /* Uncomment this to add a line layer for playing around with ideas.
      pub(crate) fn create_lines_layer(&mut self, overwrite: bool) -> Result<LineLayer,CommandError> {
-        Ok(LineLayer::create_from_dataset(&mut self.dataset, overwrite)?)
+        Ok(LineLayer::create_from_dataset(&mut self.dataset, overwrite, false)?)
     }
     */
 
     pub(crate) fn create_properties_layer(&mut self) -> Result<PropertyLayer,CommandError> {
-        PropertyLayer::create_from_dataset(&mut self.dataset,true)
+        // properties don't refer to anything, so they never need integer ids.
+        PropertyLayer::create_from_dataset(&mut self.dataset, true, false)
     }
 
     pub(crate) fn edit_properties_layer(&self) -> Result<PropertyLayer,CommandError> {
         PropertyLayer::open_from_dataset(&self.dataset)
     }
 
+    /// The tile attributes layer is only created when the first attribute is set, and is never overwritten, since the attributes aren't generated.
+    pub(crate) fn edit_or_create_tile_attributes_layer(&mut self) -> Result<TileAttributeLayer,CommandError> {
+        if has_layer(&self.dataset, TileAttributeLayer::layer_name()) {
+            TileAttributeLayer::open_from_dataset(&self.dataset)
+        } else {
+            let integer_ids = self.integer_ids()?;
+            TileAttributeLayer::create_from_dataset(&mut self.dataset, false, integer_ids)
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use gdal::vector::FieldValue;
+    use serde_json::json;
+
+    use super::field_value_to_json;
+
+    #[test]
+    fn test_field_value_to_json() {
+        assert_eq!(field_value_to_json(FieldValue::IntegerValue(3)),json!(3));
+        assert_eq!(field_value_to_json(FieldValue::Integer64ListValue(vec![1,2])),json!([1,2]));
+        assert_eq!(field_value_to_json(FieldValue::RealValue(1.5)),json!(1.5));
+        assert_eq!(field_value_to_json(FieldValue::RealValue(f64::NAN)),json!(null));
+        assert_eq!(field_value_to_json(FieldValue::StringValue("Tile:4".to_owned())),json!("Tile:4"));
+        assert_eq!(field_value_to_json(FieldValue::StringListValue(vec!["a".to_owned()])),json!(["a"]));
+    }
 }
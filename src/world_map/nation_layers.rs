@@ -51,6 +51,10 @@ entity!(NationForEmptySubnations: Nation {
     culture: Option<String>
 });
 
+entity!(NationForPreview: Nation {
+    color: Rgb<u8>
+});
+
 entity!(NationForSubnationColors: Nation {
     color: Rgb<u8>,
     #[mut=true] subnation_count: usize = |_| Ok::<_,CommandError>(0) // to be filled in by algorithm
@@ -73,7 +77,7 @@ layer!(Subnation["subnations"]: MultiPolygon {
     #[get(allow(dead_code))] #[set(allow(dead_code))] type_: CultureType,
     #[set(allow(dead_code))] seat_town_id: Option<IdRef>, 
     #[set(allow(dead_code))] nation_id: IdRef, 
-    #[get(allow(dead_code))] #[set(allow(dead_code))] color: Rgb<u8>,
+    #[set(allow(dead_code))] color: Rgb<u8>,
 });
 
 impl<'feature> NamedFeature<'feature,SubnationSchema> for SubnationFeature<'feature> {
@@ -18,6 +18,8 @@ layer!(#[hide_read(true)] Property["properties"]: NoGeometry {
 impl PropertySchema {
     pub(crate) const PROP_ELEVATION_LIMITS: &'static str = "elevation-limits";
     pub(crate) const PROP_WORLD_SHAPE: &'static str = "world-shape";
+    pub(crate) const PROP_INTEGER_IDS: &'static str = "integer-ids";
+    pub(crate) const PROP_FLOW_CONCENTRATION: &'static str = "flow-concentration";
 
 }
 
@@ -124,9 +126,35 @@ impl PropertyLayer<'_,'_> {
         self.get_property(PropertySchema::PROP_WORLD_SHAPE)?.try_into()
     }
 
+    /// Maps created before this property existed always stored ids as strings.
+    pub(crate) fn get_integer_ids(&mut self) -> Result<bool,CommandError> {
+        match self.get_property(PropertySchema::PROP_INTEGER_IDS) {
+            Ok(value) => value.parse().map_err(|e| CommandError::InvalidPropertyValue(PropertySchema::PROP_INTEGER_IDS.to_owned(),value.clone(),format!("{e}"))),
+            Err(CommandError::PropertyNotSet(_)) => Ok(false),
+            Err(err) => Err(err)
+        }
+    }
+
+    pub(crate) fn set_integer_ids(&mut self, value: bool) -> Result<IdRef,CommandError> {
+        self.set_property(PropertySchema::PROP_INTEGER_IDS, &value.to_string())
+    }
+
     pub(crate) fn set_world_shape(&mut self, value: &WorldShape) -> Result<IdRef,CommandError> {
         self.set_property(PropertySchema::PROP_WORLD_SHAPE, &Into::<String>::into(value))
     }
 
+    /// Maps whose water flow was generated before this property existed always split the flow evenly.
+    pub(crate) fn get_flow_concentration(&mut self) -> Result<f64,CommandError> {
+        match self.get_property(PropertySchema::PROP_FLOW_CONCENTRATION) {
+            Ok(value) => value.parse().map_err(|e| CommandError::InvalidPropertyValue(PropertySchema::PROP_FLOW_CONCENTRATION.to_owned(),value.clone(),format!("{e}"))),
+            Err(CommandError::PropertyNotSet(_)) => Ok(0.0),
+            Err(err) => Err(err)
+        }
+    }
+
+    pub(crate) fn set_flow_concentration(&mut self, value: f64) -> Result<IdRef,CommandError> {
+        self.set_property(PropertySchema::PROP_FLOW_CONCENTRATION, &value.to_string())
+    }
+
 
 }
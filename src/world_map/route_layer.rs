@@ -0,0 +1,31 @@
+use crate::errors::CommandError;
+use crate::geometry::LineString;
+use crate::geometry::MultiLineString;
+use crate::layer;
+use crate::utils::coordinates::Coordinates;
+use crate::typed_map::fields::IdRef;
+use crate::world_map::fields::RouteType;
+
+layer!(#[hide_read(true)] Route["routes"]: MultiLineString {
+    /// Whether the route is a road over land or a shipping lane between ports
+    #[get(allow(dead_code))] #[set(allow(dead_code))] type_: RouteType,
+    // clippy doesn't understand why I'm using 'from_*' here.
+    /// The town the route starts at
+    #[get(allow(clippy::wrong_self_convention))] #[get(allow(dead_code))] #[set(allow(dead_code))] from_town_id: IdRef,
+    /// The town the route ends at
+    #[get(allow(dead_code))] #[set(allow(dead_code))] to_town_id: IdRef,
+    /// The total travel cost along the route
+    #[get(allow(dead_code))] #[set(allow(dead_code))] cost: f64,
+});
+
+impl RouteLayer<'_,'_> {
+
+    pub(crate) fn add_route(&mut self, new_route: &NewRoute, lines: Vec<Vec<Coordinates>>) -> Result<IdRef,CommandError> {
+        let lines = lines.into_iter().map(|line| {
+            LineString::from_vertices(line.into_iter().map(|p| p.to_tuple()))
+        });
+        let geometry = MultiLineString::from_lines(lines)?;
+        self.add_struct(new_route, Some(geometry))
+    }
+
+}
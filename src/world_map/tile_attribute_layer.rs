@@ -0,0 +1,51 @@
+use gdal::vector::LayerAccess;
+
+use crate::errors::CommandError;
+use crate::geometry::NoGeometry;
+use crate::layer;
+use crate::typed_map::fields::IdRef;
+use crate::typed_map::features::TypedFeature;
+
+// Custom attributes attached to tiles with the `set-tile-attr` command, for information that Cosmopoeia doesn't generate. These are kept
+// out of the tiles layer, so they aren't lost when the generation commands are run again, but they won't match anymore if the tiles are recreated.
+layer!(TileAttribute["tile_attributes"]: NoGeometry {
+    /// The tile the attribute is attached to
+    #[set(allow(dead_code))] tile_id: IdRef,
+    /// The name of the attribute
+    #[set(allow(dead_code))] name: String,
+    /// The value of the attribute
+    value: String,
+});
+
+impl TileAttributeLayer<'_,'_> {
+
+    fn find_attribute(&mut self, tile_id: &IdRef, name: &str) -> Result<Option<IdRef>,CommandError> {
+        let mut found = None;
+        for feature in self.read_features() {
+            if (&feature.tile_id()? == tile_id) && (feature.name()? == name) {
+                found = Some(feature.fid()?);
+                break;
+            }
+        }
+        // as with properties, this is necessary if the loop isn't completed.
+        self.layer_mut().reset_feature_reading();
+        Ok(found)
+    }
+
+    /// Sets the value of an attribute on a tile, replacing the value if the tile already has that attribute.
+    pub(crate) fn set_attribute(&mut self, tile_id: &IdRef, name: &str, value: &str) -> Result<IdRef,CommandError> {
+        if let Some(found) = self.find_attribute(tile_id, name)? {
+            let mut feature = self.try_feature_by_id(&found)?;
+            feature.set_value(&value.to_owned())?;
+            self.update_feature(feature)?;
+            Ok(found)
+        } else {
+            self.add_struct(&NewTileAttribute {
+                tile_id: tile_id.clone(),
+                name: name.to_owned(),
+                value: value.to_owned()
+            }, None)
+        }
+    }
+
+}
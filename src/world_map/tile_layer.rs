@@ -4,6 +4,7 @@ use angular_units::Deg;
 use gdal::vector::LayerAccess;
 
 use crate::algorithms::water_flow::WaterFlowResult;
+use crate::algorithms::water_flow::FlowGraph;
 use crate::entity;
 use crate::errors::CommandError;
 use crate::geometry::Polygon;
@@ -33,16 +34,20 @@ layer!(#[hide_add(true)] #[hide_doc(false)] Tile["tiles"]: Polygon {
     #[set(allow(dead_code))] site_x: f64,
     /// latitude of the node point for the tile's voronoi
     #[set(allow(dead_code))] site_y: f64,
-    /// calculated area based on shape of world (this may not be the same as the area calculated by GDAL)
+    /// calculated area based on shape of world (this may not be the same as the area calculated by GDAL). This is calculated once when the tiles are created, so other algorithms don't have to.
     #[set(allow(dead_code))] area: f64,
     /// elevation in meters of the node point for the tile's voronoi
     elevation: f64,
+    /// for tiles sampled below sea level from a heightmap, the elevation in meters of the sea floor. Unlike elevation, this is not limited to the elevation limits of the world, and isn't changed by terrain processes other than sampling, so it can be used to render bathymetry for ocean tiles.
+    depth: Option<f64>,
     // NOTE: This field is used in various places which use algorithms ported from AFMG, which depend on a height from 0-100. 
     // If I ever get rid of those algorithms, this field can go away.
     /// elevation scaled into a value from 0 to 100, where 20 is sea-level.
     elevation_scaled: i32,
     /// Indicates whether the tile is part of the ocean, an island, a continent, a lake, and maybe others.
     grouping: Grouping,
+    /// true if this is an ocean tile on the continental shelf, shallow and connected to land through other shallow tiles.
+    #[get(allow(dead_code))] shelf: bool,
     /// A unique id for each grouping. These id's do not map to other tables, but will tell when tiles are in the same group. Use lake_id to link to the lake table.
     // NOTE: This isn't an IdRef, but let's store it that way anyway
     grouping_id: IdRef,
@@ -74,6 +79,10 @@ layer!(#[hide_add(true)] #[hide_doc(false)] Tile["tiles"]: Polygon {
     population: i32,
     /// The name of the culture assigned to this tile, unless wild
     culture: Option<String>,
+    /// The name of another culture which also reached this tile while cultures were expanding, along the border between them
+    #[get(allow(dead_code))] secondary_culture: Option<String>,
+    /// How strongly the secondary culture is blended into this tile, from 0 for not at all, to 0.5 where both cultures are equally strong
+    #[get(allow(dead_code))] culture_blend: f64,
     /// if the tile has a town, this is the id of the town in the towns layer
     town_id: Option<IdRef>, 
     /// if the tile is part of a nation, this is the id of the nation which controls it
@@ -82,6 +91,8 @@ layer!(#[hide_add(true)] #[hide_doc(false)] Tile["tiles"]: Polygon {
     subnation_id: Option<IdRef>,
     /// If this tile is an outlet from a lake, this is the neighbor from which the water is flowing.
     outlet_from: Option<Neighbor>,
+    /// The id of the tile where water flowing from this tile ultimately ends up, either in the ocean, off the edge of the map, or in a lake with no outlet. Tiles with the same id are in the same drainage basin.
+    #[get(allow(dead_code))] basin_id: Option<IdRef>,
     /// A list of all tile neighbors and their angular directions (tile_id:direction)
     neighbors: Vec<NeighborAndDirection>,
     /// A value indicating whether the tile is on the edge of the map
@@ -95,6 +106,12 @@ impl TileFeature<'_> {
         Ok(Coordinates::try_from((self.site_x()?,self.site_y()?))?)
     }
 
+    /// The centroid of the tile's polygon, which may be a better spot for placing things inside the tile than the site which generated it.
+    #[cfg(test)]
+    pub(crate) fn centroid(&self) -> Result<Coordinates,CommandError> {
+        self.geometry()?.centroid()
+    }
+
 }
 
 pub(crate) trait TileWithNeighbors: Entity<TileSchema> {
@@ -147,13 +164,29 @@ entity!(TileForTerrain: Tile {
     #[set=true] #[mut=true] elevation: f64,
     #[set=true] grouping: Grouping, 
     neighbors: Vec<NeighborAndDirection>,
+    #[set=true] depth: Option<f64>,
     // 'old' values so the algorithm can check if it's changed.
     #[get=false] old_elevation: f64 = TileFeature::elevation,
-    #[get=false] old_grouping: Grouping = TileFeature::grouping
+    #[get=false] old_grouping: Grouping = TileFeature::grouping,
+    #[get=false] old_depth: Option<f64> = TileFeature::depth
 });
 
 impl TileForTerrain {
 
+    #[cfg(test)]
+    pub(crate) fn new(site: Coordinates, elevation: f64, grouping: Grouping, neighbors: Vec<NeighborAndDirection>) -> Self {
+        Self {
+            site,
+            elevation,
+            old_elevation: elevation,
+            old_grouping: grouping.clone(),
+            grouping,
+            neighbors,
+            depth: None,
+            old_depth: None
+        }
+    }
+
     pub(crate) fn elevation_changed(&self) -> bool {
         (self.elevation - self.old_elevation).abs() > f64::EPSILON
     }
@@ -161,6 +194,10 @@ impl TileForTerrain {
     pub(crate) fn grouping_changed(&self) -> bool {
         self.grouping != self.old_grouping
     }
+
+    pub(crate) fn depth_changed(&self) -> bool {
+        self.depth != self.old_depth
+    }
 }
 
 entity!(TileForTemperatures: Tile {
@@ -172,20 +209,90 @@ entity!(TileForTemperatures: Tile {
 
 entity!(TileForWinds: Tile {
     fid: IdRef, 
-    site_y: f64
+    site: Coordinates
+});
+
+entity!(TileForEdgeWrap: Tile {
+    site_y: f64,
+    neighbors: Vec<NeighborAndDirection>
 });
 
+impl TileForEdgeWrap {
+
+    #[cfg(test)]
+    pub(crate) const fn new(site_y: f64, neighbors: Vec<NeighborAndDirection>) -> Self {
+        Self {
+            site_y,
+            neighbors
+        }
+    }
+}
+
+entity!(TileForNeighborCheck: Tile {
+    #[get=false] neighbors: Vec<NeighborAndDirection>
+});
+
+impl TileForNeighborCheck {
+
+    #[cfg(test)]
+    pub(crate) const fn new(neighbors: Vec<NeighborAndDirection>) -> Self {
+        Self {
+            neighbors
+        }
+    }
+}
+
+impl TileWithNeighbors for TileForNeighborCheck {
+    fn neighbors(&self) -> &Vec<NeighborAndDirection> {
+        &self.neighbors
+    }
+}
+
+entity!(TileForGraph: Tile {
+    neighbors: Vec<NeighborAndDirection>,
+    // the points of the outer ring, for finding the borders shared with neighbors.
+    outline: Vec<(f64,f64)> = |feature: &TileFeature| Ok::<_,CommandError>(feature.geometry()?.get_ring(0)?.into_iter().collect())
+});
+
+impl TileForGraph {
+
+    #[cfg(test)]
+    pub(crate) const fn new(neighbors: Vec<NeighborAndDirection>, outline: Vec<(f64,f64)>) -> Self {
+        Self {
+            neighbors,
+            outline
+        }
+    }
+}
+
 entity!(TileForWaterflow: Tile {
     elevation: f64, 
     #[set=true] flow_to: Vec<Neighbor> = |_| Ok::<_,CommandError>(Vec::new()),
     grouping: Grouping, 
-    neighbors: Vec<NeighborAndDirection>,
+    #[mut=true] neighbors: Vec<NeighborAndDirection>,
     precipitation: f64, // not in TileForWaterFill
     #[get=false] temperature: f64,
     #[mut=true] water_accumulation: f64 = |_| Ok::<_,CommandError>(0.0),
     #[set=true] #[mut=true] water_flow: f64 = |_| Ok::<_,CommandError>(0.0),
 });
 
+impl TileForWaterflow {
+
+    #[cfg(test)]
+    pub(crate) const fn new(elevation: f64, grouping: Grouping, neighbors: Vec<NeighborAndDirection>, precipitation: f64) -> Self {
+        Self {
+            elevation,
+            flow_to: Vec::new(),
+            grouping,
+            neighbors,
+            precipitation,
+            temperature: 20.0,
+            water_accumulation: 0.0,
+            water_flow: 0.0
+        }
+    }
+}
+
 // Basically the same struct as WaterFlow, except that the fields are initialized differently. I can't
 // just use a different function because it's based on a trait. I could take this one out
 // of the macro and figure something out, but this is easier.
@@ -194,13 +301,31 @@ entity!(TileForWaterFill: Tile {
     flow_to: Vec<Neighbor>, // Initialized to blank in TileForWaterFlow
     grouping: Grouping, 
     #[set=true] lake_id: Option<IdRef> = |_| Ok::<_,CommandError>(None), // Not in TileForWaterFlow
-    neighbors: Vec<NeighborAndDirection>,
+    #[mut=true] neighbors: Vec<NeighborAndDirection>,
     #[set=true] outlet_from: Option<Neighbor> = |_| Ok::<_,CommandError>(None), // Not in TileForWaterFlow
     temperature: f64,
     #[get=false] water_accumulation: f64,  // Initialized to blank in TileForWaterFlow
     #[mut=true] water_flow: f64,  // Initialized to blank in TileForWaterFlow
 });
 
+impl TileForWaterFill {
+
+    #[cfg(test)]
+    pub(crate) const fn new(elevation: f64, grouping: Grouping, neighbors: Vec<NeighborAndDirection>, temperature: f64) -> Self {
+        Self {
+            elevation,
+            flow_to: Vec::new(),
+            grouping,
+            lake_id: None,
+            neighbors,
+            outlet_from: None,
+            temperature,
+            water_accumulation: 0.0,
+            water_flow: 0.0
+        }
+    }
+}
+
 impl From<TileForWaterflow> for TileForWaterFill {
 
     fn from(value: TileForWaterflow) -> Self {
@@ -219,11 +344,77 @@ impl From<TileForWaterflow> for TileForWaterFill {
 }
 
 entity!(TileForRiverConnect: Tile {
+    elevation: f64,
+    grouping: Grouping,
+    neighbors: Vec<NeighborAndDirection>,
     water_flow: f64,
     flow_to: Vec<Neighbor>,
     outlet_from: Option<Neighbor>
 });
 
+impl TileForRiverConnect {
+
+    #[cfg(test)]
+    pub(crate) const fn new(elevation: f64, grouping: Grouping, neighbors: Vec<NeighborAndDirection>, water_flow: f64, flow_to: Vec<Neighbor>, outlet_from: Option<Neighbor>) -> Self {
+        Self {
+            elevation,
+            grouping,
+            neighbors,
+            water_flow,
+            flow_to,
+            outlet_from
+        }
+    }
+}
+
+// Unlike TileForWaterFill, this reads the stored results of the water commands instead of initializing them.
+entity!(TileForFlowGraph: Tile {
+    elevation: f64,
+    water_flow: f64,
+    water_accumulation: f64,
+    flow_to: Vec<Neighbor>,
+    lake_id: Option<IdRef>
+});
+
+impl TileForFlowGraph {
+
+    #[cfg(test)]
+    pub(crate) const fn new(elevation: f64, water_flow: f64, water_accumulation: f64, flow_to: Vec<Neighbor>, lake_id: Option<IdRef>) -> Self {
+        Self {
+            elevation,
+            water_flow,
+            water_accumulation,
+            flow_to,
+            lake_id
+        }
+    }
+}
+
+entity!(TileForRiverLength: Tile {
+    site: Coordinates,
+    lake_id: Option<IdRef>
+});
+
+entity!(TileForBasins: Tile {
+    grouping: Grouping,
+    flow_to: Vec<Neighbor>,
+    lake_id: Option<IdRef>,
+    outlet_from: Option<Neighbor>
+});
+
+impl TileForBasins {
+
+    #[cfg(test)]
+    pub(crate) const fn new(grouping: Grouping, flow_to: Vec<Neighbor>, lake_id: Option<IdRef>, outlet_from: Option<Neighbor>) -> Self {
+        Self {
+            grouping,
+            flow_to,
+            lake_id,
+            outlet_from
+        }
+    }
+}
+
 entity!(TileForWaterDistance: Tile {
     site: Coordinates,
     grouping: Grouping, 
@@ -239,6 +430,48 @@ entity!(TileForGroupingCalc: Tile {
     neighbors: Vec<NeighborAndDirection>
 });
 
+impl TileForGroupingCalc {
+
+    #[cfg(test)]
+    pub(crate) const fn new(grouping: Grouping, edge: Option<Edge>, neighbors: Vec<NeighborAndDirection>) -> Self {
+        Self {
+            grouping,
+            edge,
+            lake_id: None,
+            neighbors
+        }
+    }
+}
+
+entity!(TileForShelf: Tile {
+    grouping: Grouping,
+    elevation: f64,
+    depth: Option<f64>,
+    neighbors: Vec<NeighborAndDirection>
+});
+
+impl TileForShelf {
+
+    #[cfg(test)]
+    pub(crate) const fn new(grouping: Grouping, elevation: f64, depth: Option<f64>, neighbors: Vec<NeighborAndDirection>) -> Self {
+        Self {
+            grouping,
+            elevation,
+            depth,
+            neighbors
+        }
+    }
+
+    /// Files generated before depth was recorded will only have the elevation.
+    fn ocean_depth(&self) -> f64 {
+        self.depth().unwrap_or_else(|| self.elevation().min(0.0))
+    }
+
+    pub(crate) fn is_shallow_ocean(&self, shelf_depth: f64) -> bool {
+        self.grouping().is_ocean() && (self.ocean_depth() >= -shelf_depth)
+    }
+}
+
 entity!(TileForPopulation: Tile {
     water_flow: f64,
     elevation_scaled: i32,
@@ -265,7 +498,7 @@ entity!(TileForCultureGen: Tile {
     #[get=false] biome: String,
     #[get=false] water_count: Option<i32>,
     #[get=false] harbor_tile_id: Option<Neighbor>,
-    #[get=false] grouping: Grouping,
+    grouping: Grouping,
     #[get=false] water_flow: f64,
     #[get=false] temperature: f64
 
@@ -368,6 +601,7 @@ impl TileForCulturePrefSorting<'_> {
 }
 
 entity!(TileForCultureExpand: Tile {
+    habitability: f64,
     shore_distance: i32,
     elevation_scaled: i32,
     biome: String,
@@ -376,8 +610,79 @@ entity!(TileForCultureExpand: Tile {
     neighbors: Vec<NeighborAndDirection>,
     lake_id: Option<IdRef>,
     area: f64,
+    #[set=true] culture: Option<String> = |_| Ok::<_,CommandError>(None),
+    #[set=true] secondary_culture: Option<String> = |_| Ok::<_,CommandError>(None),
+    #[set=true] culture_blend: f64 = |_| Ok::<_,CommandError>(0.0)
+
+});
+
+impl TileForCultureExpand {
+
+    #[cfg(test)]
+    pub(crate) const fn new(biome: String, grouping: Grouping, neighbors: Vec<NeighborAndDirection>) -> Self {
+        Self {
+            habitability: if grouping.is_water() { 0.0 } else { 10.0 },
+            shore_distance: 5,
+            elevation_scaled: 30,
+            biome,
+            grouping,
+            water_flow: 0.0,
+            neighbors,
+            lake_id: None,
+            area: 1.0,
+            culture: None,
+            secondary_culture: None,
+            culture_blend: 0.0
+        }
+    }
+}
+
+entity!(TileForRoutes: Tile {
+    site: Coordinates,
+    elevation_scaled: i32,
+    biome: String,
+    grouping: Grouping,
+    neighbors: Vec<NeighborAndDirection>
+});
+
+impl TileForRoutes {
+
+    #[cfg(test)]
+    pub(crate) const fn new(site: Coordinates, elevation_scaled: i32, biome: String, grouping: Grouping, neighbors: Vec<NeighborAndDirection>) -> Self {
+        Self {
+            site,
+            elevation_scaled,
+            biome,
+            grouping,
+            neighbors
+        }
+    }
+}
+
+entity!(TileForCultureImport: Tile {
+    habitability: f64,
+    grouping: Grouping,
     #[set=true] culture: Option<String> = |_| Ok::<_,CommandError>(None)
+});
+
+impl TileForCultureImport {
 
+    #[cfg(test)]
+    pub(crate) const fn new(habitability: f64, grouping: Grouping) -> Self {
+        Self {
+            habitability,
+            grouping,
+            culture: None
+        }
+    }
+}
+
+entity!(TileForLakeNames: Tile {
+    area: f64,
+    grouping: Grouping,
+    lake_id: Option<IdRef>,
+    culture: Option<String>,
+    neighbors: Vec<NeighborAndDirection>
 });
 
 entity!(TileForTowns: Tile {
@@ -455,6 +760,26 @@ entity!(TileForNationExpand: Tile {
     area: f64,
 });
 
+impl TileForNationExpand {
+
+    #[cfg(test)]
+    pub(crate) const fn new(grouping: Grouping, water_flow: f64) -> Self {
+        Self {
+            habitability: 10.0,
+            shore_distance: 2,
+            elevation_scaled: 30,
+            biome: String::new(),
+            grouping,
+            water_flow,
+            neighbors: Vec::new(),
+            lake_id: None,
+            culture: None,
+            nation_id: None,
+            area: 1.0
+        }
+    }
+}
+
 entity!(TileForNationNormalize: Tile {
     grouping: Grouping,
     neighbors: Vec<NeighborAndDirection>,
@@ -657,6 +982,11 @@ impl TileLayer<'_,'_> {
 
     }
 
+    /// Limits the features read from the layer to those which intersect the extent.
+    pub(crate) fn set_spatial_filter(&mut self, extent: &Extent) {
+        self.layer_mut().set_spatial_filter_rect(extent.west(), extent.south(), extent.east(), extent.north())
+    }
+
     // This is for when you want to generate the water fill in a second step, so you can verify the flow first.
     // It's a function here because it's used in a command, which I want to be as simple as possible.
     pub(crate) fn get_index_and_queue_for_water_fill<Progress: ProgressObserver>(&mut self, progress: &mut Progress) -> Result<WaterFlowResult,CommandError> {
@@ -680,6 +1010,10 @@ impl TileLayer<'_,'_> {
 
     }
 
+    pub(crate) fn read_flow_graph<Progress: ProgressObserver>(&mut self, progress: &mut Progress) -> Result<FlowGraph,CommandError> {
+        Ok(FlowGraph::new(self.read_features().into_entities_index::<_,TileForFlowGraph>(progress)?))
+    }
+
 
 }
 
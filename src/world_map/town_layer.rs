@@ -12,7 +12,7 @@ use crate::typed_map::features::TypedFeatureIterator;
 layer!(Town["towns"]: Point {
     #[set(allow(dead_code))] name: String,
     #[set(allow(dead_code))] culture: Option<String>,
-    #[set(allow(dead_code))] is_capital: bool,
+    is_capital: bool,
     #[set(allow(dead_code))] tile_id: IdRef,
     #[get(allow(dead_code))] #[set(allow(dead_code))] grouping_id: IdRef, 
     #[get(allow(dead_code))] population: i32,
@@ -37,7 +37,29 @@ entity!(TownForNations: Town {
     fid: IdRef,
     is_capital: bool,
     culture: Option<String>,
-    tile_id: IdRef
+    tile_id: IdRef,
+    population: i32
+});
+
+impl TownForNations {
+
+    #[cfg(test)]
+    pub(crate) const fn new(fid: IdRef, tile_id: IdRef, population: i32) -> Self {
+        Self {
+            fid,
+            is_capital: true,
+            culture: None,
+            tile_id,
+            population
+        }
+    }
+}
+
+entity!(TownForRoutes: Town {
+    tile_id: IdRef,
+    grouping_id: IdRef,
+    is_capital: bool,
+    is_port: bool
 });
 
 entity!(TownForNationNormalize: Town {
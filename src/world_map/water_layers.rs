@@ -14,8 +14,9 @@ use crate::world_map::fields::Neighbor;
 use crate::world_map::fields::RiverSegmentFrom;
 use crate::world_map::fields::RiverSegmentTo;
 use crate::typed_map::features::TypedFeatureIterator;
+use crate::typed_map::features::TypedFeature;
 
-layer!(#[hide_read(true)] River["rivers"]: MultiLineString {
+layer!(River["rivers"]: MultiLineString {
     // clippy doesn't understand why I'm using 'from_*' here.
     #[get(allow(clippy::wrong_self_convention))] #[get(allow(dead_code))] #[set(allow(dead_code))] from_tile_id: IdRef,
     #[get(allow(clippy::wrong_self_convention))] #[get(allow(dead_code))] #[set(allow(dead_code))] from_type: RiverSegmentFrom,
@@ -23,6 +24,44 @@ layer!(#[hide_read(true)] River["rivers"]: MultiLineString {
     #[get(allow(dead_code))] #[set(allow(dead_code))] to_tile_id: Neighbor,
     #[get(allow(dead_code))] #[set(allow(dead_code))] to_type: RiverSegmentTo,
     #[get(allow(dead_code))] #[set(allow(dead_code))] to_flow: f64,
+    /// Distance from the source of the river to the end of the segment, following the longest path upstream
+    #[get(allow(dead_code))] length: f64,
+    /// Strahler stream order of the segment, which is 1 at the sources and increases where two rivers of the same order join
+    #[set(allow(dead_code))] strahler: i32,
+});
+
+entity!(RiverForExport: River {
+    #[get=false] from_flow: f64,
+    #[get=false] to_flow: f64,
+    strahler: i32,
+    lines: Vec<Vec<(f64,f64)>> = |feature: &RiverFeature| {
+        let geometry = feature.geometry()?;
+        (0..geometry.len()).map(|index| Ok(geometry.get_line(index)?.into_iter().collect())).collect::<Result<Vec<_>,CommandError>>()
+    }
+});
+
+impl RiverForExport {
+
+    #[cfg(test)]
+    pub(crate) const fn new(from_flow: f64, to_flow: f64, strahler: i32, lines: Vec<Vec<(f64,f64)>>) -> Self {
+        Self {
+            from_flow,
+            to_flow,
+            strahler,
+            lines
+        }
+    }
+
+    /// The most water the segment carries, at either end.
+    pub(crate) const fn peak_flow(&self) -> f64 {
+        self.from_flow.max(self.to_flow)
+    }
+}
+
+entity!(RiverForLength: River {
+    // clippy doesn't understand why I'm using 'from_*' here.
+    #[get(allow(clippy::wrong_self_convention))] from_tile_id: IdRef,
+    to_tile_id: Neighbor
 });
 
 impl RiverLayer<'_,'_> {
@@ -42,8 +81,16 @@ layer!(Lake["lakes"]: MultiPolygon {
     #[set(allow(dead_code))] type_: LakeType,
     #[get(allow(dead_code))] #[set(allow(dead_code))] flow: f64,
     #[set(allow(dead_code))] size: i32,
+    /// The mean temperature of the tiles covered by the lake
     #[get(allow(dead_code))] #[set(allow(dead_code))] temperature: f64,
+    /// An estimate of the water lost from the surface of the lake, based on its temperature, elevation and size, in the same units as the flow
     #[get(allow(dead_code))] #[set(allow(dead_code))] evaporation: f64,
+    /// The river segments which flow into the lake
+    #[get(allow(dead_code))] inlet_segment_ids: Vec<IdRef>,
+    /// The river segment which drains the lake, if any
+    #[get(allow(dead_code))] outlet_segment_id: Option<IdRef>,
+    /// The name of the lake, unless it's too small to be named
+    #[get(allow(dead_code))] name: Option<String>,
 });
 
 entity!(LakeForBiomes: Lake {
@@ -62,6 +109,11 @@ entity!(LakeForTownPopulation: Lake {
     size: i32
 });
 
+entity!(LakeForNames: Lake {
+    type_: LakeType,
+    size: i32
+});
+
 impl LakeLayer<'_,'_> {
 
     pub(crate) fn add_lake(&mut self, lake: &NewLake, geometry: MultiPolygon) -> Result<IdRef,CommandError> {
@@ -83,7 +135,7 @@ impl CoastlineLayer<'_,'_> {
 
 }
 
-layer!(#[hide_read(true)] Ocean["oceans"]: Polygon {
+layer!(Ocean["oceans"]: Polygon {
 });
 
 impl OceanLayer<'_,'_> {